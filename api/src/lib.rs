@@ -0,0 +1,103 @@
+//! Request and response types of the HTTP API of `fibonacci-server`.
+//!
+//! The server and `fibonacci-client` share them, so a change of the API fails to compile on both
+//! sides rather than at runtime. They only need serde and the bundle and error types of
+//! `fibonacci-circuit`, clients don't pull in the server, its prover or its web framework.
+use std::collections::HashMap;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use fibonacci_circuit::{BackendId, ErrorMessage, ErrorReport, FibonacciError, ProofBundle};
+use serde::{Deserialize, Serialize};
+
+/// Body of `POST /jobs`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct JobRequest {
+    /// Id of the circuit to prove, may be left out if the server hosts one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub circuit: Option<String>,
+    /// Circuit inputs, e.g. `{"out": ["55"]}`
+    pub input: HashMap<String, Vec<String>>,
+}
+
+/// Body of `POST /verify`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VerifyRequest {
+    /// Id of the circuit whose verifying key to use, may be left out if the server hosts one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub circuit: Option<String>,
+    pub bundle: EncodedBundle,
+}
+
+/// Entry of the response of `GET /circuits`, e.g. `{"id": "gemini", "backend": "gemini"}`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CircuitInfo {
+    pub id: String,
+    /// The backend's name, as in the key store manifest
+    #[serde(with = "fibonacci_circuit::bundle::backend_name")]
+    pub backend: BackendId,
+}
+
+/// Response of `POST /jobs`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct JobSubmitted {
+    pub id: String,
+}
+
+/// Proof bundle with the proof and public inputs as base64 strings
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EncodedBundle {
+    pub backend: Option<BackendId>,
+    pub proof: String,
+    pub public_inputs: String,
+}
+
+impl From<&ProofBundle> for EncodedBundle {
+    fn from(bundle: &ProofBundle) -> Self {
+        Self {
+            backend: bundle.backend,
+            proof: STANDARD.encode(&bundle.proof),
+            public_inputs: STANDARD.encode(&bundle.public_inputs),
+        }
+    }
+}
+
+impl EncodedBundle {
+    pub fn decode(&self) -> Result<ProofBundle, FibonacciError> {
+        let decode = |name: &str, value: &str| {
+            STANDARD.decode(value).map_err(|e| {
+                FibonacciError::Serialization(ErrorMessage::with_source(
+                    format!("`{}` is not valid base64: {}", name, e),
+                    e,
+                ))
+            })
+        };
+        Ok(ProofBundle {
+            backend: self.backend,
+            proof: decode("proof", &self.proof)?,
+            public_inputs: decode("public_inputs", &self.public_inputs)?,
+        })
+    }
+}
+
+/// Status reported by `GET /jobs/{id}`, in the order a job goes through them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Done,
+    Failed,
+}
+
+/// Response of `GET /jobs/{id}`, e.g. `{"id": "..", "status": "running"}`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JobResponse {
+    pub id: String,
+    pub status: JobStatus,
+    /// Proof of a job which is done
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bundle: Option<EncodedBundle>,
+    /// Error of a job which failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<ErrorReport>,
+}
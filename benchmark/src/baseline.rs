@@ -0,0 +1,338 @@
+//! Baselines of benchmark timings, to catch performance regressions of the backends.
+//!
+//! A `Baseline` holds the median keygen, prover and verifier time of every backend over several
+//! `compare_backends` runs and is stored as JSON, e.g. next to the commit it was measured on.
+//! Comparing a later run against it flags every backend and phase which got slower by more than
+//! its threshold. Timings are only comparable on the same machine and build profile.
+use std::{collections::HashMap, fmt::Write, fs, path::Path, time::Duration};
+
+use fibonacci_circuit::{BackendId, ErrorMessage, FibonacciError};
+use serde::{Deserialize, Serialize};
+
+use crate::{ComparisonReport, SchemeReport};
+
+/// Relative slowdown flagged when no threshold is set for a backend and phase, 10%
+pub const DEFAULT_THRESHOLD: f64 = 0.1;
+
+/// Measured step of a benchmark run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Phase {
+    Keygen,
+    Prove,
+    Verify,
+}
+
+impl Phase {
+    pub const ALL: [Phase; 3] = [Phase::Keygen, Phase::Prove, Phase::Verify];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Phase::Keygen => "keygen",
+            Phase::Prove => "prove",
+            Phase::Verify => "verify",
+        }
+    }
+
+    fn time(&self, report: &SchemeReport) -> Duration {
+        match self {
+            Phase::Keygen => report.keygen_time,
+            Phase::Prove => report.prover_time,
+            Phase::Verify => report.verifier_time,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Measurement {
+    pub backend: BackendId,
+    pub phase: Phase,
+    /// Median over the runs of the baseline
+    pub time: Duration,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Baseline {
+    /// Free-form label, e.g. the commit or machine it was measured on
+    pub name: String,
+    /// Runs the medians were taken over
+    pub runs: usize,
+    pub measurements: Vec<Measurement>,
+}
+
+impl Baseline {
+    /// Median time of every backend and phase over `reports`, in the order of the first report.
+    pub fn from_reports(name: &str, reports: &[ComparisonReport]) -> Self {
+        let mut measurements = Vec::new();
+        if let Some(first) = reports.first() {
+            for scheme in &first.schemes {
+                for phase in Phase::ALL {
+                    let mut times: Vec<Duration> = reports
+                        .iter()
+                        .flat_map(|report| &report.schemes)
+                        .filter(|report| report.backend == scheme.backend)
+                        .map(|report| phase.time(report))
+                        .collect();
+                    times.sort();
+                    measurements.push(Measurement {
+                        backend: scheme.backend,
+                        phase,
+                        time: times[times.len() / 2],
+                    });
+                }
+            }
+        }
+
+        Self {
+            name: name.to_string(),
+            runs: reports.len(),
+            measurements,
+        }
+    }
+
+    pub fn time(&self, backend: BackendId, phase: Phase) -> Option<Duration> {
+        self.measurements
+            .iter()
+            .find(|m| m.backend == backend && m.phase == phase)
+            .map(|m| m.time)
+    }
+
+    /// Parse a baseline, rejecting zero times, which no slowdown can be measured against.
+    pub fn from_json(json: &str) -> Result<Self, FibonacciError> {
+        let baseline: Self = serde_json::from_str(json).map_err(|e| {
+            FibonacciError::Serialization(ErrorMessage::with_source(
+                format!("Invalid baseline: {}", e),
+                e,
+            ))
+        })?;
+        if let Some(zero) = baseline.measurements.iter().find(|m| m.time.is_zero()) {
+            return Err(FibonacciError::InvalidInput(
+                format!(
+                    "Baseline '{}' has a zero {} time for {}",
+                    baseline.name,
+                    zero.phase.name(),
+                    zero.backend.name()
+                )
+                .into(),
+            ));
+        }
+        Ok(baseline)
+    }
+
+    pub fn to_json(&self) -> Result<String, FibonacciError> {
+        serde_json::to_string_pretty(self).map_err(|e| {
+            FibonacciError::Serialization(ErrorMessage::with_source(
+                format!("Failed to serialize baseline: {}", e),
+                e,
+            ))
+        })
+    }
+
+    pub fn load(path: &Path) -> Result<Self, FibonacciError> {
+        let json = fs::read_to_string(path).map_err(|e| {
+            FibonacciError::Io(ErrorMessage::with_source(
+                format!("Failed to read baseline {}: {}", path.display(), e),
+                e,
+            ))
+        })?;
+        Self::from_json(&json)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), FibonacciError> {
+        fs::write(path, self.to_json()?).map_err(|e| {
+            FibonacciError::Io(ErrorMessage::with_source(
+                format!("Failed to write baseline {}: {}", path.display(), e),
+                e,
+            ))
+        })
+    }
+}
+
+/// Relative slowdown tolerated per backend and phase, e.g. 0.1 for 10%
+#[derive(Debug, Clone, PartialEq)]
+pub struct Thresholds {
+    pub default: f64,
+    overrides: HashMap<(BackendId, Phase), f64>,
+}
+
+impl Default for Thresholds {
+    fn default() -> Self {
+        Self::new(DEFAULT_THRESHOLD)
+    }
+}
+
+impl Thresholds {
+    /// Parse a threshold argument, e.g. `0.1` for 10%.
+    pub fn parse(threshold: &str) -> Result<f64, FibonacciError> {
+        match threshold.parse::<f64>() {
+            Ok(threshold) if threshold.is_finite() && threshold >= 0.0 => Ok(threshold),
+            _ => Err(FibonacciError::InvalidInput(
+                format!(
+                    "Invalid threshold '{}', expected a non-negative number like 0.1",
+                    threshold
+                )
+                .into(),
+            )),
+        }
+    }
+
+    pub fn new(default: f64) -> Self {
+        Self {
+            default,
+            overrides: HashMap::new(),
+        }
+    }
+
+    /// Tolerate `threshold` for `phase` of `backend`, e.g. more for phases which are noisy.
+    pub fn with(mut self, backend: BackendId, phase: Phase, threshold: f64) -> Self {
+        self.overrides.insert((backend, phase), threshold);
+        self
+    }
+
+    pub fn get(&self, backend: BackendId, phase: Phase) -> f64 {
+        self.overrides
+            .get(&(backend, phase))
+            .copied()
+            .unwrap_or(self.default)
+    }
+}
+
+/// Timing of a backend and phase in the baseline and the current run
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PhaseComparison {
+    pub backend: BackendId,
+    pub phase: Phase,
+    pub baseline: Duration,
+    pub current: Duration,
+    pub threshold: f64,
+}
+
+impl PhaseComparison {
+    /// Relative change of the time, positive when the current run is slower.
+    pub fn change(&self) -> f64 {
+        self.current.as_secs_f64() / self.baseline.as_secs_f64() - 1.0
+    }
+
+    pub fn is_regression(&self) -> bool {
+        self.change() > self.threshold
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RegressionReport {
+    pub comparisons: Vec<PhaseComparison>,
+}
+
+impl RegressionReport {
+    pub fn regressions(&self) -> impl Iterator<Item = &PhaseComparison> {
+        self.comparisons.iter().filter(|c| c.is_regression())
+    }
+
+    pub fn has_regressions(&self) -> bool {
+        self.regressions().next().is_some()
+    }
+
+    /// Render the comparisons as a markdown table.
+    pub fn to_table(&self) -> String {
+        let mut table = String::from(
+            "| backend | phase | baseline | current | change | regression |\n\
+             |---|---|---|---|---|---|\n",
+        );
+        for comparison in &self.comparisons {
+            writeln!(
+                table,
+                "| {} | {} | {:?} | {:?} | {:+.1}% | {} |",
+                comparison.backend.name(),
+                comparison.phase.name(),
+                comparison.baseline,
+                comparison.current,
+                comparison.change() * 100.0,
+                if comparison.is_regression() {
+                    "yes"
+                } else {
+                    ""
+                }
+            )
+            .expect("writing to a String should not fail");
+        }
+        table
+    }
+}
+
+/// Compare the backends and phases measured in both `baseline` and `current`, those missing from
+/// either are skipped.
+pub fn compare(
+    baseline: &Baseline,
+    current: &Baseline,
+    thresholds: &Thresholds,
+) -> RegressionReport {
+    let comparisons = current
+        .measurements
+        .iter()
+        .filter_map(|measurement| {
+            let baseline = baseline.time(measurement.backend, measurement.phase)?;
+            Some(PhaseComparison {
+                backend: measurement.backend,
+                phase: measurement.phase,
+                baseline,
+                current: measurement.time,
+                threshold: thresholds.get(measurement.backend, measurement.phase),
+            })
+        })
+        .collect();
+
+    RegressionReport { comparisons }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn baseline(prover_time: Duration) -> Baseline {
+        Baseline {
+            name: "test".to_string(),
+            runs: 1,
+            measurements: vec![
+                Measurement {
+                    backend: BackendId::Gemini,
+                    phase: Phase::Prove,
+                    time: prover_time,
+                },
+                Measurement {
+                    backend: BackendId::Gemini,
+                    phase: Phase::Verify,
+                    time: Duration::from_millis(10),
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_compare_baselines() {
+        let stored = baseline(Duration::from_millis(100));
+        let stored = Baseline::from_json(&stored.to_json().unwrap()).unwrap();
+
+        let report = compare(
+            &stored,
+            &baseline(Duration::from_millis(105)),
+            &Thresholds::default(),
+        );
+        assert_eq!(report.comparisons.len(), 2);
+        assert!(!report.has_regressions());
+
+        let slower = baseline(Duration::from_millis(150));
+        let report = compare(&stored, &slower, &Thresholds::default());
+        let regressions: Vec<_> = report.regressions().collect();
+        assert_eq!(regressions.len(), 1);
+        assert_eq!(regressions[0].phase, Phase::Prove);
+        // Header and separator rows followed by a row per comparison
+        assert_eq!(report.to_table().lines().count(), 4);
+
+        let thresholds = Thresholds::default().with(BackendId::Gemini, Phase::Prove, 0.6);
+        assert!(!compare(&stored, &slower, &thresholds).has_regressions());
+
+        // Every change against a zero time would be infinite
+        let zero = baseline(Duration::ZERO);
+        assert!(Baseline::from_json(&zero.to_json().unwrap()).is_err());
+    }
+}
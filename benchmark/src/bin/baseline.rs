@@ -0,0 +1,101 @@
+use std::{collections::HashMap, env, path::Path, process};
+
+use fibonacci_benchmark::{
+    baseline::{compare, Baseline, Thresholds, DEFAULT_THRESHOLD},
+    compare_backends, Scheme,
+};
+use fibonacci_circuit::{artifact_dirs, BackendId};
+
+// Runs per baseline, the median of every phase is recorded
+const RUNS: usize = 5;
+
+const USAGE: &str = "Usage: baseline save <path> [name] | baseline compare <path> [threshold]";
+
+enum Command {
+    Save { name: String },
+    Compare { baseline: Baseline, threshold: f64 },
+}
+
+pub fn main() {
+    // The SRS files shipped in the backend crates of the workspace these binaries were built in
+    let workspace_root = artifact_dirs::workspace_dir();
+
+    // Everything the runs are checked against is read first, so a typo doesn't cost the runs
+    let args: Vec<String> = env::args().skip(1).collect();
+    let (command, path) = match args.as_slice() {
+        [command, path, ..] if args.len() <= 3 => (command.as_str(), Path::new(path)),
+        _ => usage(),
+    };
+    let command = match command {
+        // Name defaults to the host's architecture, like calibration profiles
+        "save" => {
+            let parent = path
+                .parent()
+                .filter(|parent| !parent.as_os_str().is_empty());
+            if parent.is_some_and(|parent| !parent.is_dir()) {
+                exit(&format!("No directory to save {} in", path.display()));
+            }
+            Command::Save {
+                name: args
+                    .get(2)
+                    .cloned()
+                    .unwrap_or_else(|| env::consts::ARCH.to_string()),
+            }
+        }
+        "compare" => Command::Compare {
+            baseline: Baseline::load(path).unwrap_or_else(|e| exit(&e.to_string())),
+            threshold: args.get(2).map_or(DEFAULT_THRESHOLD, |threshold| {
+                Thresholds::parse(threshold).unwrap_or_else(|e| exit(&e.to_string()))
+            }),
+        },
+        _ => usage(),
+    };
+
+    let schemes: Vec<Scheme> = BackendId::ALL
+        .into_iter()
+        .map(|backend| {
+            Scheme::from_workspace(&workspace_root, backend)
+                .unwrap_or_else(|e| exit(&format!("Failed to read {} SRS: {}", backend.name(), e)))
+        })
+        .collect();
+
+    let mut input = HashMap::new();
+    input.insert("out".to_string(), vec!["55".to_string()]);
+
+    let reports: Vec<_> = (0..RUNS)
+        .map(|_| {
+            compare_backends(&input, &schemes)
+                .unwrap_or_else(|e| exit(&format!("Comparing backends failed: {}", e)))
+        })
+        .collect();
+
+    match command {
+        Command::Save { name } => Baseline::from_reports(&name, &reports)
+            .save(path)
+            .unwrap_or_else(|e| exit(&e.to_string())),
+        Command::Compare {
+            baseline,
+            threshold,
+        } => {
+            let current = Baseline::from_reports(&baseline.name, &reports);
+
+            let report = compare(&baseline, &current, &Thresholds::new(threshold));
+            print!("{}", report.to_table());
+            // Fail the run, e.g. a CI job, when a phase got slower than the threshold
+            if report.has_regressions() {
+                process::exit(1);
+            }
+        }
+    }
+}
+
+fn usage() -> ! {
+    eprintln!("{}", USAGE);
+    process::exit(2)
+}
+
+// Exit code 1 is a regression, so errors exit like a usage error
+fn exit(message: &str) -> ! {
+    eprintln!("{}", message);
+    process::exit(2)
+}
@@ -0,0 +1,42 @@
+use std::env;
+
+use fibonacci_benchmark::{calibrate, CALIBRATION_KS};
+use fibonacci_circuit::BackendId;
+
+// Proofs per backend and domain size, the median is recorded
+const RUNS: usize = 5;
+
+pub fn main() {
+    // Profile name as argument, defaults to the host's architecture, followed by an optional
+    // `--k` with comma-separated domain sizes
+    let args: Vec<String> = env::args().skip(1).collect();
+    let mut name = env::consts::ARCH.to_string();
+    let mut ks = CALIBRATION_KS.to_vec();
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if arg == "--k" {
+            let list = args.next().unwrap_or_else(|| exit("--k needs a value"));
+            ks = list
+                .split(',')
+                .map(|k| {
+                    k.trim()
+                        .parse()
+                        .unwrap_or_else(|_| exit(&format!("Invalid domain size '{}'", k)))
+                })
+                .collect();
+        } else {
+            name = arg;
+        }
+    }
+
+    let profile = calibrate(&name, &BackendId::ALL, &ks, RUNS)
+        .unwrap_or_else(|e| exit(&format!("Calibration failed: {}", e)));
+
+    println!("{}", profile.to_json().expect("Profile should serialize"));
+}
+
+fn exit(message: &str) -> ! {
+    eprintln!("{}", message);
+    std::process::exit(1)
+}
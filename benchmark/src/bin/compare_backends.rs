@@ -0,0 +1,34 @@
+use std::{collections::HashMap, env};
+
+use fibonacci_benchmark::{compare_backends, Scheme};
+use fibonacci_circuit::{artifact_dirs, BackendId};
+
+pub fn main() {
+    // The SRS files shipped in the backend crates of the workspace these binaries were built in
+    let workspace_root = artifact_dirs::workspace_dir();
+
+    // Print the report as JSON with `--json`, as a markdown table otherwise
+    let json = env::args().any(|arg| arg == "--json");
+
+    let schemes: Vec<Scheme> = BackendId::ALL
+        .into_iter()
+        .map(|backend| {
+            Scheme::from_workspace(&workspace_root, backend)
+                .unwrap_or_else(|e| panic!("Failed to read {} SRS: {}", backend.name(), e))
+        })
+        .collect();
+
+    let mut input = HashMap::new();
+    input.insert("out".to_string(), vec!["55".to_string()]);
+
+    let report = compare_backends(&input, &schemes).expect("Comparing backends should not fail");
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&report).expect("Report should serialize")
+        );
+    } else {
+        print!("{}", report.to_table());
+    }
+}
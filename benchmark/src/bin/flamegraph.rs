@@ -0,0 +1,39 @@
+use std::{collections::HashMap, env, path::PathBuf};
+
+use fibonacci_benchmark::Scheme;
+use fibonacci_circuit::{artifact_dirs, profiling, BackendId};
+
+// Sampling frequency in Hz
+const FREQUENCY: i32 = 1000;
+
+pub fn main() {
+    // The SRS files shipped in the backend crates of the workspace these binaries were built in
+    let workspace_root = artifact_dirs::workspace_dir();
+
+    // Backend can be given as argument, defaults to gemini
+    let backend = env::args()
+        .nth(1)
+        .map(|name| BackendId::from_name(&name).expect("Unknown backend"))
+        .unwrap_or(BackendId::Gemini);
+    let scheme = Scheme::from_workspace(&workspace_root, backend).expect("Failed to read SRS");
+
+    let mut input = HashMap::new();
+    input.insert("out".to_string(), vec!["55".to_string()]);
+
+    let flamegraph = profiling::Flamegraph::start(FREQUENCY).expect("Failed to start profiler");
+
+    let (proving_key, _) =
+        fibonacci_mobile::keygen(backend, &scheme.srs_key).expect("Key generation failed");
+    fibonacci_mobile::prove(backend, &scheme.srs_key, &proving_key, input).expect("Proving failed");
+    profiling::finish_frame();
+
+    // Output path can be given as second argument, defaults to the current directory
+    let svg_path = env::args()
+        .nth(2)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(format!("flamegraph-{}.svg", backend.name())));
+    flamegraph
+        .write_svg(&svg_path)
+        .expect("Failed to write flamegraph");
+    println!("Flamegraph stored in {}", svg_path.display());
+}
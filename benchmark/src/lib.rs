@@ -0,0 +1,249 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt::Write,
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use fibonacci_circuit::{
+    artifact_dirs,
+    estimate::{measure_prover_time, CalibrationSample, HardwareProfile, MAX_K},
+    BackendId, CircuitShape, FibonacciError,
+};
+use gemini_fibonacci::GeminiScheme;
+use hyperplonk_fibonacci::HyperPlonkScheme;
+use serde::Serialize;
+use unihyperplonk_fibonacci::UniHyperPlonkScheme;
+
+pub mod baseline;
+
+/// Backend to compare together with the SRS it is set up with
+pub struct Scheme {
+    pub backend: BackendId,
+    pub srs_key: Vec<u8>,
+}
+
+impl Scheme {
+    /// Load the SRS shipped in the crate directory of `backend`.
+    pub fn from_workspace(workspace_root: &Path, backend: BackendId) -> std::io::Result<Self> {
+        Ok(Self {
+            backend,
+            srs_key: fs::read(default_srs_path(workspace_root, backend))?,
+        })
+    }
+}
+
+pub fn default_srs_path(workspace_root: &Path, backend: BackendId) -> PathBuf {
+    workspace_root.join(artifact_dirs::shipped_srs(backend))
+}
+
+/// Domain size of the default circuit with the SRS shipped for `backend`
+pub fn default_k(backend: BackendId) -> usize {
+    match backend {
+        BackendId::Plonk => plonk_fibonacci::MIN_K as usize,
+        _ => CircuitShape::default().k,
+    }
+}
+
+/// Sizes in bytes and timings of one backend for the same input
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SchemeReport {
+    pub backend: BackendId,
+    pub srs_size: usize,
+    pub proving_key_size: usize,
+    pub verifying_key_size: usize,
+    pub proof_size: usize,
+    pub public_inputs_size: usize,
+    pub keygen_time: Duration,
+    pub prover_time: Duration,
+    pub verifier_time: Duration,
+    pub verified: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ComparisonReport {
+    pub schemes: Vec<SchemeReport>,
+}
+
+impl ComparisonReport {
+    /// Render the report as a markdown table.
+    pub fn to_table(&self) -> String {
+        let mut table = String::from(
+            "| backend | SRS | pk | vk | proof | keygen | prove | verify | verified |\n\
+             |---|---|---|---|---|---|---|---|---|\n",
+        );
+        for scheme in &self.schemes {
+            writeln!(
+                table,
+                "| {} | {} B | {} B | {} B | {} B | {:?} | {:?} | {:?} | {} |",
+                scheme.backend.name(),
+                scheme.srs_size,
+                scheme.proving_key_size,
+                scheme.verifying_key_size,
+                scheme.proof_size,
+                scheme.keygen_time,
+                scheme.prover_time,
+                scheme.verifier_time,
+                scheme.verified
+            )
+            .expect("writing to a String should not fail");
+        }
+        table
+    }
+}
+
+/// Generate keys, prove and verify `input` with every scheme, measuring each step.
+pub fn compare_backends(
+    input: &HashMap<String, Vec<String>>,
+    schemes: &[Scheme],
+) -> Result<ComparisonReport, Box<dyn Error>> {
+    let mut reports = Vec::with_capacity(schemes.len());
+
+    for scheme in schemes {
+        let start = Instant::now();
+        let (proving_key, verifying_key) =
+            fibonacci_mobile::keygen(scheme.backend, &scheme.srs_key)?;
+        let keygen_time = start.elapsed();
+
+        let start = Instant::now();
+        let (proof, public_inputs) =
+            fibonacci_mobile::prove(scheme.backend, &scheme.srs_key, &proving_key, input.clone())?;
+        let prover_time = start.elapsed();
+
+        let (proof_size, public_inputs_size) = (proof.len(), public_inputs.len());
+
+        let start = Instant::now();
+        let verified = fibonacci_mobile::verify(
+            scheme.backend,
+            &scheme.srs_key,
+            &verifying_key,
+            &proof,
+            &public_inputs,
+        )?;
+        let verifier_time = start.elapsed();
+
+        reports.push(SchemeReport {
+            backend: scheme.backend,
+            srs_size: scheme.srs_key.len(),
+            proving_key_size: proving_key.len(),
+            verifying_key_size: verifying_key.len(),
+            proof_size,
+            public_inputs_size,
+            keygen_time,
+            prover_time,
+            verifier_time,
+            verified,
+        });
+    }
+
+    Ok(ComparisonReport { schemes: reports })
+}
+
+/// Domain sizes the `calibrate` binary measures by default. Estimates scale the closest sample,
+/// so the sizes apps actually prove at should be close to one of them.
+pub const CALIBRATION_KS: [usize; 2] = [10, 14];
+
+/// Measure the prover time of every backend at every domain size 2^k of `ks` on this machine for
+/// `estimate_proving_time`, taking the median of `runs` proofs each. All sizes are checked before
+/// anything is proven.
+pub fn calibrate(
+    name: &str,
+    backends: &[BackendId],
+    ks: &[usize],
+    runs: usize,
+) -> Result<HardwareProfile, Box<dyn Error>> {
+    if ks.is_empty() {
+        return Err(FibonacciError::InvalidInput("No domain sizes to calibrate".into()).into());
+    }
+    for &backend in backends {
+        for &k in ks {
+            if !(default_k(backend)..=MAX_K).contains(&k) {
+                return Err(FibonacciError::InvalidInput(
+                    format!(
+                        "{} can't be calibrated at k = {}, it supports {}..={}",
+                        backend.name(),
+                        k,
+                        default_k(backend),
+                        MAX_K
+                    )
+                    .into(),
+                )
+                .into());
+            }
+        }
+    }
+
+    let mut samples = Vec::with_capacity(backends.len() * ks.len());
+    for &backend in backends {
+        for &k in ks {
+            let prover_time = match backend {
+                BackendId::Plonk => plonk_fibonacci::measure_prover_time(k as u32, runs)?,
+                BackendId::HyperPlonk => measure_prover_time::<HyperPlonkScheme>(k, runs)?,
+                BackendId::Gemini => measure_prover_time::<GeminiScheme>(k, runs)?,
+                BackendId::UniHyperPlonk => measure_prover_time::<UniHyperPlonkScheme>(k, runs)?,
+            };
+            samples.push(CalibrationSample {
+                backend,
+                k,
+                prover_time,
+            });
+        }
+    }
+
+    Ok(HardwareProfile {
+        name: name.to_string(),
+        samples,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compare_backends() {
+        let workspace_root = Path::new(env!("CARGO_MANIFEST_DIR")).parent().unwrap();
+        let schemes: Vec<Scheme> = BackendId::ALL
+            .into_iter()
+            .map(|backend| Scheme::from_workspace(workspace_root, backend).unwrap())
+            .collect();
+
+        let mut input = HashMap::new();
+        input.insert("out".to_string(), vec!["55".to_string()]);
+
+        let report = compare_backends(&input, &schemes).unwrap();
+
+        assert_eq!(report.schemes.len(), BackendId::ALL.len());
+        for (scheme, backend) in report.schemes.iter().zip(BackendId::ALL) {
+            assert_eq!(scheme.backend, backend);
+            assert!(scheme.verified);
+            assert!(scheme.proof_size > 0);
+        }
+        // Header and separator rows followed by a row per backend
+        assert_eq!(report.to_table().lines().count(), BackendId::ALL.len() + 2);
+    }
+
+    #[test]
+    fn test_calibrate() {
+        let backends = [BackendId::Gemini, BackendId::Plonk];
+        let profile = calibrate("test", &backends, &[4, 5], 1).unwrap();
+        assert_eq!(profile.samples.len(), 4);
+
+        for sample in &profile.samples {
+            let estimate = fibonacci_circuit::estimate::estimate_proving_time(
+                sample.backend,
+                sample.k,
+                &profile,
+            )
+            .unwrap();
+            assert_eq!(estimate, sample.prover_time);
+        }
+
+        // Sizes are checked before proving
+        assert!(calibrate("test", &backends, &[], 1).is_err());
+        assert!(calibrate("test", &backends, &[4, MAX_K + 1], 1).is_err());
+        assert!(calibrate("test", &[BackendId::Plonk], &[2], 1).is_err());
+    }
+}
@@ -0,0 +1,217 @@
+//! Standard locations of SRS and key files.
+//!
+//! Artifacts live in `plonkish-fibonacci` under the platform cache directory (`$XDG_CACHE_HOME`
+//! or `~/.cache` on Linux, `~/Library/Caches` on macOS, `%LOCALAPPDATA%` on Windows), with SRS
+//! files in `srs/` and generated keys in `keys/`. Environment variables override each location,
+//! e.g. for CI runners or containers with a read-only home directory.
+use std::{
+    env,
+    ffi::OsString,
+    path::{Path, PathBuf},
+};
+
+use crate::{BackendId, FibonacciError};
+
+/// Overrides the base directory of all artifacts
+pub const ARTIFACT_DIR_ENV: &str = "PLONKISH_FIBONACCI_DIR";
+/// Overrides the directory SRS files are looked up in
+pub const SRS_DIR_ENV: &str = "PLONKISH_FIBONACCI_SRS_DIR";
+/// Overrides the directory keys are written to
+pub const KEYS_DIR_ENV: &str = "PLONKISH_FIBONACCI_KEYS_DIR";
+
+const APP_DIR: &str = "plonkish-fibonacci";
+
+/// SRS and key locations, resolved once by a binary and passed to the code reading and writing
+/// artifacts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArtifactDirs {
+    pub srs_dir: PathBuf,
+    pub keys_dir: PathBuf,
+    /// Searched for SRS files after `srs_dir`, the crate directory under `cargo run` where the
+    /// backend crates ship their SRS files
+    pub crate_dir: Option<PathBuf>,
+}
+
+impl ArtifactDirs {
+    /// Locations given by the environment variables of the process, see `from_vars`.
+    pub fn from_env() -> Result<Self, FibonacciError> {
+        Self::from_vars(|name| env::var_os(name))
+    }
+
+    /// Locations given by the variables `var` looks up: `$PLONKISH_FIBONACCI_SRS_DIR` and
+    /// `$PLONKISH_FIBONACCI_KEYS_DIR`, defaulting to `srs` and `keys` under the base directory,
+    /// and `$CARGO_MANIFEST_DIR`.
+    pub fn from_vars(var: impl Fn(&str) -> Option<OsString>) -> Result<Self, FibonacciError> {
+        Ok(Self {
+            srs_dir: dir_or_default(&var, SRS_DIR_ENV, "srs")?,
+            keys_dir: dir_or_default(&var, KEYS_DIR_ENV, "keys")?,
+            crate_dir: var("CARGO_MANIFEST_DIR").map(PathBuf::from),
+        })
+    }
+
+    /// Locate the SRS file `name`: the path itself if it exists, otherwise `name` in `srs_dir`,
+    /// then in `crate_dir`.
+    pub fn find_srs(&self, name: &str) -> Result<PathBuf, FibonacciError> {
+        let mut dirs = vec![self.srs_dir.clone()];
+        dirs.extend(self.crate_dir.clone());
+        search_srs(name, &dirs)
+    }
+}
+
+/// Base directory of the artifacts, `$PLONKISH_FIBONACCI_DIR` or `plonkish-fibonacci` under the
+/// platform cache directory.
+pub fn artifact_dir() -> Result<PathBuf, FibonacciError> {
+    base_dir(env::var_os(ARTIFACT_DIR_ENV))
+}
+
+/// Directory of the SRS files, `$PLONKISH_FIBONACCI_SRS_DIR` or `srs` under `artifact_dir()`.
+pub fn srs_dir() -> Result<PathBuf, FibonacciError> {
+    dir_or_default(&|name| env::var_os(name), SRS_DIR_ENV, "srs")
+}
+
+/// Directory of the generated keys, `$PLONKISH_FIBONACCI_KEYS_DIR` or `keys` under
+/// `artifact_dir()`.
+pub fn keys_dir() -> Result<PathBuf, FibonacciError> {
+    dir_or_default(&|name| env::var_os(name), KEYS_DIR_ENV, "keys")
+}
+
+/// Like `ArtifactDirs::find_srs` with the locations of the environment. Paths are found even
+/// without an SRS directory, e.g. on platforms without a cache directory.
+pub fn find_srs(name: &str) -> Result<PathBuf, FibonacciError> {
+    let mut dirs: Vec<PathBuf> = srs_dir().into_iter().collect();
+    dirs.extend(env::var_os("CARGO_MANIFEST_DIR").map(PathBuf::from));
+    search_srs(name, &dirs)
+}
+
+/// Root of the workspace these crates were built in, where the backend crates ship their SRS
+/// files. It's fixed at build time, so binaries find the shipped files however they are run.
+pub fn workspace_dir() -> PathBuf {
+    let circuit_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    circuit_dir.parent().unwrap_or(circuit_dir).to_path_buf()
+}
+
+/// SRS file shipped for `backend`, relative to the workspace root. Gemini and UniHyperPlonk
+/// share the univariate KZG SRS.
+pub fn shipped_srs(backend: BackendId) -> &'static Path {
+    Path::new(match backend {
+        BackendId::Plonk => "plonk/perpetual-powers-of-tau-raw-3",
+        BackendId::HyperPlonk => "hyperplonk/hyperplonk-srs-4",
+        BackendId::Gemini | BackendId::UniHyperPlonk => "gemini/unihyperplonk-srs-4",
+    })
+}
+
+/// Absolute path of the SRS file shipped for `backend`, which tests read wherever they run from
+pub fn shipped_srs_path(backend: BackendId) -> PathBuf {
+    workspace_dir().join(shipped_srs(backend))
+}
+
+fn dir_or_default(
+    var: &impl Fn(&str) -> Option<OsString>,
+    name: &str,
+    subdir: &str,
+) -> Result<PathBuf, FibonacciError> {
+    match var(name) {
+        Some(dir) => Ok(PathBuf::from(dir)),
+        None => Ok(base_dir(var(ARTIFACT_DIR_ENV))?.join(subdir)),
+    }
+}
+
+fn base_dir(var: Option<OsString>) -> Result<PathBuf, FibonacciError> {
+    if let Some(dir) = var {
+        return Ok(PathBuf::from(dir));
+    }
+    dirs::cache_dir()
+        .map(|dir| dir.join(APP_DIR))
+        .ok_or_else(|| {
+            FibonacciError::Io(
+                format!(
+                    "No cache directory on this platform, set {} to store artifacts",
+                    ARTIFACT_DIR_ENV
+                )
+                .into(),
+            )
+        })
+}
+
+fn search_srs(name: &str, dirs: &[PathBuf]) -> Result<PathBuf, FibonacciError> {
+    let mut candidates = vec![PathBuf::from(name)];
+    candidates.extend(dirs.iter().map(|dir| dir.join(name)));
+
+    candidates
+        .iter()
+        .find(|path| path.is_file())
+        .cloned()
+        .ok_or_else(|| {
+            let searched: Vec<_> = candidates
+                .iter()
+                .map(|path| path.display().to_string())
+                .collect();
+            FibonacciError::Io(
+                format!(
+                    "SRS file '{}' not found, searched {}",
+                    name,
+                    searched.join(", ")
+                )
+                .into(),
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn dirs_of(vars: &[(&str, &Path)]) -> ArtifactDirs {
+        let vars: HashMap<&str, OsString> = vars
+            .iter()
+            .map(|(name, dir)| (*name, dir.as_os_str().to_owned()))
+            .collect();
+        ArtifactDirs::from_vars(|name| vars.get(name).cloned()).unwrap()
+    }
+
+    #[test]
+    fn test_artifact_dirs() {
+        let base = std::env::temp_dir().join(format!("fibonacci-dirs-{}", std::process::id()));
+
+        let dirs = dirs_of(&[(ARTIFACT_DIR_ENV, &base)]);
+        assert_eq!(dirs.srs_dir, base.join("srs"));
+        assert_eq!(dirs.keys_dir, base.join("keys"));
+        assert_eq!(dirs.crate_dir, None);
+
+        std::fs::create_dir_all(base.join("srs")).unwrap();
+        std::fs::write(base.join("srs").join("test-srs"), b"srs").unwrap();
+        assert_eq!(
+            dirs.find_srs("test-srs").unwrap(),
+            base.join("srs").join("test-srs")
+        );
+        assert!(dirs.find_srs("missing-srs").is_err());
+
+        let keys = base.join("elsewhere");
+        let dirs = dirs_of(&[(ARTIFACT_DIR_ENV, &base), (KEYS_DIR_ENV, &keys)]);
+        assert_eq!(dirs.keys_dir, keys);
+        assert_eq!(dirs.srs_dir, base.join("srs"));
+
+        // Shipped SRS files are found in the crate directory
+        let dirs = dirs_of(&[
+            (SRS_DIR_ENV, &base.join("empty")),
+            (KEYS_DIR_ENV, &keys),
+            ("CARGO_MANIFEST_DIR", &base.join("srs")),
+        ]);
+        assert_eq!(
+            dirs.find_srs("test-srs").unwrap(),
+            base.join("srs").join("test-srs")
+        );
+
+        std::fs::remove_dir_all(base).unwrap();
+    }
+
+    #[test]
+    fn test_workspace_dir() {
+        assert!(workspace_dir().join("circuit").join("Cargo.toml").is_file());
+        for backend in BackendId::ALL {
+            assert!(workspace_dir().join(shipped_srs(backend)).is_file());
+        }
+    }
+}
@@ -0,0 +1,415 @@
+use std::error::Error;
+
+#[cfg(feature = "multibase")]
+pub use multibase::Base;
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "multibase")]
+use crate::ErrorMessage;
+use crate::{
+    circuit::{INSTANCE_A_ROW, INSTANCE_B_ROW, INSTANCE_OUT_ROW, NUM_PUBLIC_INPUTS},
+    serialisation::{canonical_public_inputs, Codec},
+    ErrorCode, ErrorReport, FibonacciError, GenerateProofResult,
+};
+
+/// Proving backends available in this workspace
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum BackendId {
+    Plonk,
+    HyperPlonk,
+    Gemini,
+    UniHyperPlonk,
+}
+
+impl BackendId {
+    pub const ALL: [BackendId; 4] = [
+        BackendId::Plonk,
+        BackendId::HyperPlonk,
+        BackendId::Gemini,
+        BackendId::UniHyperPlonk,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            BackendId::Plonk => "plonk",
+            BackendId::HyperPlonk => "hyperplonk",
+            BackendId::Gemini => "gemini",
+            BackendId::UniHyperPlonk => "unihyperplonk",
+        }
+    }
+
+    /// Whether the backend can blind the witness so that proofs are zero-knowledge, see `Hiding`
+    pub fn supports_hiding(&self) -> bool {
+        matches!(self, BackendId::Plonk)
+    }
+
+    /// Inverse of `name`
+    pub fn from_name(name: &str) -> Result<Self, FibonacciError> {
+        Self::ALL
+            .into_iter()
+            .find(|backend| backend.name() == name)
+            .ok_or_else(|| {
+                FibonacciError::InvalidInput(format!("Unknown backend '{}'", name).into())
+            })
+    }
+}
+
+/// Serde of a backend as its name, e.g. `"gemini"`, for `#[serde(with = "backend_name")]`
+/// fields of JSON APIs and files people read. `BackendId` itself serializes as the variant name.
+pub mod backend_name {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    use super::BackendId;
+
+    pub fn serialize<S: Serializer>(backend: &BackendId, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(backend.name())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<BackendId, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        BackendId::from_name(&name).map_err(D::Error::custom)
+    }
+}
+
+/// Whether proofs hide the witness.
+///
+/// With hiding on, halo2 reserves blinding rows and randomizes the witness polynomials, so proofs
+/// are zero-knowledge rather than only succinct. This costs a larger domain and prover time. Keys
+/// are specific to the setting they were generated with. The plonkish backends have no hiding,
+/// requesting it from them fails instead of silently producing proofs which leak the witness.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Hiding {
+    #[default]
+    Off,
+    On,
+}
+
+impl Hiding {
+    /// Fail if hiding is requested from a backend without it.
+    pub fn check_supported(&self, backend: BackendId) -> Result<(), FibonacciError> {
+        if *self == Hiding::On && !backend.supports_hiding() {
+            return Err(FibonacciError::InvalidInput(
+                format!(
+                    "The {} backend doesn't support zero-knowledge proofs",
+                    backend.name()
+                )
+                .into(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Proof together with its serialized public inputs, as returned by `prove`.
+///
+/// The proof and inputs are encoded as byte strings, so the CBOR form decodes to
+/// `{ "backend": .., "proof": h'..', "public_inputs": h'..' }` in any CBOR library.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProofBundle {
+    pub backend: Option<BackendId>,
+    #[serde(with = "serde_bytes")]
+    pub proof: Vec<u8>,
+    #[serde(with = "serde_bytes")]
+    pub public_inputs: Vec<u8>,
+}
+
+impl ProofBundle {
+    pub fn new(proof: Vec<u8>, public_inputs: Vec<u8>) -> Self {
+        Self {
+            backend: None,
+            proof,
+            public_inputs,
+        }
+    }
+
+    pub fn with_backend(mut self, backend: BackendId) -> Self {
+        self.backend = Some(backend);
+        self
+    }
+
+    pub fn encode(&self, codec: Codec) -> Result<Vec<u8>, FibonacciError> {
+        codec.encode(self)
+    }
+
+    pub fn decode(bytes: &[u8], codec: Codec) -> Result<Self, FibonacciError> {
+        codec.decode(bytes)
+    }
+
+    #[cfg(feature = "cbor")]
+    pub fn to_cbor(&self) -> Result<Vec<u8>, FibonacciError> {
+        self.encode(Codec::Cbor)
+    }
+
+    #[cfg(feature = "cbor")]
+    pub fn from_cbor(bytes: &[u8]) -> Result<Self, FibonacciError> {
+        Self::decode(bytes, Codec::Cbor)
+    }
+
+    /// Bincode encoding of the bundle as a base64url multibase string (prefix `u`), without
+    /// characters which need escaping in URLs, e.g. to pass a proof in a deep link.
+    #[cfg(feature = "multibase")]
+    pub fn to_multibase(&self) -> Result<String, FibonacciError> {
+        self.to_multibase_with(Base::Base64Url)
+    }
+
+    /// Like `to_multibase` in another base. `Base::Base32Upper` only uses characters of the QR
+    /// alphanumeric mode, whose codes hold more characters than byte mode ones, so it makes the
+    /// smallest QR codes even though its strings are longer.
+    #[cfg(feature = "multibase")]
+    pub fn to_multibase_with(&self, base: Base) -> Result<String, FibonacciError> {
+        Ok(multibase::encode(base, self.encode(Codec::Bincode)?))
+    }
+
+    /// Decode a bundle of `to_multibase` in any base, selected by the string's prefix. The
+    /// bundle isn't validated, see `validate`.
+    #[cfg(feature = "multibase")]
+    pub fn from_multibase(encoded: &str) -> Result<Self, FibonacciError> {
+        let (_, bytes) = multibase::decode(encoded.trim()).map_err(|e| {
+            FibonacciError::Serialization(ErrorMessage::with_source(
+                format!("Invalid multibase proof bundle: {}", e),
+                e,
+            ))
+        })?;
+        Self::decode(&bytes, Codec::Bincode)
+    }
+
+    /// Check the bundle is well-formed before verification is attempted: the proof is present and
+    /// the public inputs decode to the instance of the circuit, `a` and `b` at rows 0 and 1 and
+    /// `out` at row 2, each a canonical encoding of a field element.
+    pub fn validate(&self) -> Result<(), FibonacciError> {
+        let backend = self.backend.map_or("unknown", |backend| backend.name());
+        let invalid = |reason: String| {
+            FibonacciError::InvalidInput(
+                format!("Invalid {} proof bundle: {}", backend, reason).into(),
+            )
+        };
+
+        if self.proof.is_empty() {
+            return Err(invalid("the proof is empty".to_string()));
+        }
+
+        let malformed =
+            |e: &dyn std::fmt::Display| invalid(format!("malformed public inputs, {}", e));
+        let canonical = canonical_public_inputs(&self.public_inputs).map_err(|e| malformed(&e))?;
+        let inputs = match fibonacci_verifier_core::decode_public_inputs(&canonical) {
+            Ok(inputs) => inputs,
+            Err(fibonacci_verifier_core::Error::NonCanonical { index }) => {
+                let name = [
+                    (INSTANCE_A_ROW, "a"),
+                    (INSTANCE_B_ROW, "b"),
+                    (INSTANCE_OUT_ROW, "out"),
+                ]
+                .iter()
+                .find(|(row, _)| *row == index)
+                .map_or(String::new(), |(_, name)| format!("`{}` ", name));
+                return Err(invalid(format!(
+                    "public input {}at row {} isn't a canonical field element",
+                    name, index
+                )));
+            }
+            Err(e) => return Err(malformed(&e)),
+        };
+        if inputs.len() != NUM_PUBLIC_INPUTS {
+            return Err(invalid(format!(
+                "expected {} public inputs, got {}",
+                NUM_PUBLIC_INPUTS,
+                inputs.len()
+            )));
+        }
+
+        Ok(())
+    }
+
+    pub fn into_parts(self) -> GenerateProofResult {
+        (self.proof, self.public_inputs)
+    }
+}
+
+impl From<GenerateProofResult> for ProofBundle {
+    fn from((proof, public_inputs): GenerateProofResult) -> Self {
+        Self::new(proof, public_inputs)
+    }
+}
+
+/// Result of verifying a proof bundle, for CLI and server output, e.g.
+/// `{"backend": "Gemini", "valid": false, "error": {"code": "verification", ..}}`.
+///
+/// A proof the verifier rejects is invalid, any other error means it couldn't be checked.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VerificationOutcome {
+    pub backend: Option<BackendId>,
+    pub valid: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<ErrorReport>,
+}
+
+impl VerificationOutcome {
+    /// Outcome of a `verify` call. The plonkish backends report rejected proofs as
+    /// `FibonacciError::Verification` rather than `Ok(false)`, both are invalid proofs.
+    pub fn from_result(backend: Option<BackendId>, result: Result<bool, Box<dyn Error>>) -> Self {
+        match result {
+            Ok(valid) => Self {
+                backend,
+                valid,
+                error: None,
+            },
+            Err(e) => Self {
+                backend,
+                valid: false,
+                error: Some(ErrorReport::from_error(e.as_ref())),
+            },
+        }
+    }
+
+    /// Whether the proof was checked, and rejected
+    pub fn is_invalid(&self) -> bool {
+        !self.valid
+            && self
+                .error
+                .as_ref()
+                .map_or(true, |error| error.code == ErrorCode::Verification)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("Verification outcome should serialize")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::halo2curves::bn256::Fr;
+
+    use super::*;
+    use crate::{
+        io,
+        serialisation::{encode_public_inputs, InputsSerialisationWrapper},
+    };
+
+    #[test]
+    fn test_bundle_roundtrip() {
+        let bundle = ProofBundle::from((vec![1, 2, 3], vec![4, 5])).with_backend(BackendId::Gemini);
+
+        let encoded = bundle.encode(Codec::Bincode).unwrap();
+        assert_eq!(
+            ProofBundle::decode(&encoded, Codec::Bincode).unwrap(),
+            bundle
+        );
+
+        #[cfg(feature = "cbor")]
+        {
+            let cbor = bundle.to_cbor().unwrap();
+            // map(3)
+            assert_eq!(cbor[0], 0xa3);
+            assert_eq!(ProofBundle::from_cbor(&cbor).unwrap(), bundle);
+        }
+
+        #[cfg(feature = "multibase")]
+        {
+            let encoded = bundle.to_multibase().unwrap();
+            assert!(encoded.starts_with('u'));
+            assert_eq!(ProofBundle::from_multibase(&encoded).unwrap(), bundle);
+
+            let qr = bundle.to_multibase_with(Base::Base32Upper).unwrap();
+            assert!(qr
+                .chars()
+                .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit()));
+            assert_eq!(ProofBundle::from_multibase(&qr).unwrap(), bundle);
+            assert!(ProofBundle::from_multibase("not a bundle").is_err());
+        }
+
+        assert_eq!(bundle.into_parts(), (vec![1, 2, 3], vec![4, 5]));
+    }
+
+    #[test]
+    fn test_bundle_validate() {
+        let inputs = |inputs: Vec<Fr>| encode_public_inputs(&inputs);
+        let bundle = ProofBundle::new(
+            vec![1, 2, 3],
+            inputs(vec![Fr::from(1), Fr::from(1), Fr::from(55)]),
+        )
+        .with_backend(BackendId::HyperPlonk);
+        assert!(bundle.validate().is_ok());
+
+        // Inputs written as an artifact with a header are accepted too
+        let artifact = io::encode_artifact(
+            &InputsSerialisationWrapper(vec![Fr::from(1), Fr::from(1), Fr::from(55)]),
+            Codec::Bincode,
+        )
+        .unwrap();
+        assert!(ProofBundle::new(vec![1], artifact).validate().is_ok());
+
+        let empty_proof = ProofBundle::new(Vec::new(), bundle.public_inputs.clone());
+        assert!(empty_proof.validate().is_err());
+
+        let missing_out = ProofBundle::new(vec![1], inputs(vec![Fr::from(1), Fr::from(1)]));
+        assert!(missing_out.validate().is_err());
+
+        let truncated = ProofBundle::new(
+            vec![1],
+            bundle.public_inputs[..bundle.public_inputs.len() - 1].to_vec(),
+        );
+        assert!(truncated.validate().is_err());
+
+        // The field modulus itself is out of range
+        let mut non_canonical = bundle.clone();
+        let out_offset = non_canonical.public_inputs.len() - 32;
+        non_canonical.public_inputs[out_offset..].copy_from_slice(&(-Fr::from(1)).to_bytes());
+        non_canonical.public_inputs[out_offset] += 1;
+        let error = non_canonical.validate().unwrap_err();
+        assert!(error.to_string().contains("`out` at row 2"));
+    }
+
+    #[test]
+    fn test_verification_outcome() {
+        let valid = VerificationOutcome::from_result(Some(BackendId::Gemini), Ok(true));
+        assert!(valid.valid && !valid.is_invalid());
+        assert_eq!(valid.to_json(), r#"{"backend":"Gemini","valid":true}"#);
+
+        let rejected = VerificationOutcome::from_result(None, Ok(false));
+        assert!(rejected.is_invalid());
+
+        let error: Box<dyn Error> =
+            Box::new(FibonacciError::Verification("Verification failed".into()));
+        assert!(VerificationOutcome::from_result(None, Err(error)).is_invalid());
+
+        let error: Box<dyn Error> = Box::new(FibonacciError::Io("No such file".into()));
+        let unchecked = VerificationOutcome::from_result(None, Err(error));
+        assert!(!unchecked.valid && !unchecked.is_invalid());
+        assert_eq!(
+            serde_json::from_str::<VerificationOutcome>(&unchecked.to_json()).unwrap(),
+            unchecked
+        );
+    }
+
+    #[test]
+    fn test_backend_names() {
+        for backend in BackendId::ALL {
+            assert_eq!(BackendId::from_name(backend.name()).unwrap(), backend);
+        }
+        assert!(BackendId::from_name("groth16").is_err());
+
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Named {
+            #[serde(with = "backend_name")]
+            backend: BackendId,
+        }
+        let named = Named {
+            backend: BackendId::UniHyperPlonk,
+        };
+        let json = serde_json::to_string(&named).unwrap();
+        assert_eq!(json, r#"{"backend":"unihyperplonk"}"#);
+        assert_eq!(serde_json::from_str::<Named>(&json).unwrap(), named);
+        assert!(serde_json::from_str::<Named>(r#"{"backend":"UniHyperPlonk"}"#).is_err());
+    }
+
+    #[test]
+    fn test_hiding_supported() {
+        for backend in BackendId::ALL {
+            assert!(Hiding::Off.check_supported(backend).is_ok());
+            assert_eq!(
+                Hiding::On.check_supported(backend).is_ok(),
+                backend == BackendId::Plonk
+            );
+        }
+    }
+}
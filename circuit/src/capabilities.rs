@@ -0,0 +1,130 @@
+//! What a build supports, for hosts which pick the backend at runtime, e.g. to fill a backend
+//! menu or to reject requests for a backend they weren't built with up front.
+//!
+//! This crate doesn't know which backend crates are linked, so hosts build their `Capabilities`
+//! from the backends they dispatch to, see `fibonacci_mobile::capabilities`.
+use serde::Serialize;
+
+use crate::{BackendId, FibonacciError};
+
+/// Proof system components of a backend
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct BackendCapabilities {
+    pub backend: BackendId,
+    /// Polynomial commitment scheme
+    pub pcs: &'static str,
+    pub curve: &'static str,
+    /// Hash of the Fiat-Shamir transcript
+    pub transcript: &'static str,
+}
+
+impl BackendCapabilities {
+    pub fn of(backend: BackendId) -> Self {
+        let (pcs, transcript) = match backend {
+            BackendId::Plonk => ("KZG with SHPLONK multiopen", "Blake2b"),
+            BackendId::HyperPlonk => ("multilinear KZG", "Keccak256"),
+            BackendId::Gemini => ("Gemini over univariate KZG", "Keccak256"),
+            BackendId::UniHyperPlonk => ("univariate KZG", "Keccak256"),
+        };
+        Self {
+            backend,
+            pcs,
+            curve: "bn256",
+            transcript,
+        }
+    }
+}
+
+/// Backends, codecs and cargo features of a build
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Capabilities {
+    pub backends: Vec<BackendCapabilities>,
+    /// Codecs keys, inputs and bundles can be encoded with, see `serialisation::Codec`
+    pub codecs: Vec<&'static str>,
+    /// Enabled cargo features of `fibonacci-circuit`
+    pub features: Vec<&'static str>,
+}
+
+impl Capabilities {
+    /// Capabilities of a build dispatching to `backends`.
+    pub fn new(backends: impl IntoIterator<Item = BackendId>) -> Self {
+        let mut codecs = vec!["bincode"];
+        if cfg!(feature = "postcard") {
+            codecs.push("postcard");
+        }
+        if cfg!(feature = "cbor") {
+            codecs.push("cbor");
+        }
+
+        Self {
+            backends: backends.into_iter().map(BackendCapabilities::of).collect(),
+            codecs,
+            features: enabled_features(),
+        }
+    }
+
+    pub fn supports(&self, backend: BackendId) -> bool {
+        self.backends
+            .iter()
+            .any(|capabilities| capabilities.backend == backend)
+    }
+
+    /// Fail with `FibonacciError::InvalidInput` for backends this build doesn't support.
+    pub fn require(&self, backend: BackendId) -> Result<(), FibonacciError> {
+        if self.supports(backend) {
+            Ok(())
+        } else {
+            Err(FibonacciError::InvalidInput(
+                format!(
+                    "The {} backend isn't available in this build",
+                    backend.name()
+                )
+                .into(),
+            ))
+        }
+    }
+}
+
+/// Enabled cargo features of `fibonacci-circuit`
+pub fn enabled_features() -> Vec<&'static str> {
+    [
+        ("frontend-halo2", cfg!(feature = "frontend-halo2")),
+        // The feature has no effect on wasm32
+        (
+            "parallel",
+            cfg!(all(feature = "parallel", not(target_arch = "wasm32"))),
+        ),
+        ("simd", cfg!(feature = "simd")),
+        ("postcard", cfg!(feature = "postcard")),
+        ("cbor", cfg!(feature = "cbor")),
+        ("protobuf", cfg!(feature = "protobuf")),
+        ("encryption", cfg!(feature = "encryption")),
+        ("signatures", cfg!(feature = "signatures")),
+        ("profiling", cfg!(feature = "profiling")),
+        ("metrics", cfg!(feature = "metrics")),
+    ]
+    .into_iter()
+    .filter_map(|(feature, enabled)| enabled.then_some(feature))
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capabilities() {
+        let capabilities = Capabilities::new([BackendId::Gemini, BackendId::HyperPlonk]);
+        assert!(capabilities.supports(BackendId::Gemini));
+        assert!(capabilities.require(BackendId::HyperPlonk).is_ok());
+        assert!(matches!(
+            capabilities.require(BackendId::Plonk),
+            Err(FibonacciError::InvalidInput(_))
+        ));
+        assert_eq!(capabilities.codecs[0], "bincode");
+        assert_eq!(
+            capabilities.features.contains(&"parallel"),
+            cfg!(all(feature = "parallel", not(target_arch = "wasm32")))
+        );
+    }
+}
@@ -0,0 +1,189 @@
+//! Incremental proving of a sequence in links of a fixed number of terms.
+//!
+//! The state after the term F(k) is the pair (F(k), F(k+1)). A link proves that `steps` more
+//! terms lead from the state it starts in to the state it ends in, with two proofs starting from
+//! the same values: one of F(k + steps) and one of F(k + steps + 1). The proofs of a link are
+//! bound to a commitment of the state it extends, which hashes in the commitment of the state
+//! before, so a link only verifies at its place in the chain. Unlike folding the verifier still
+//! checks every link, the chain demonstrates how an IVC prover hands state from step to step.
+use std::{collections::HashMap, io::Cursor};
+
+use plonkish_backend::{
+    halo2_curves::bn256::Fr,
+    pcs::CommitmentChunk,
+    util::transcript::{Keccak256Transcript, TranscriptRead, TranscriptWrite},
+};
+use sha3::{Digest, Keccak256};
+
+use crate::{
+    circuit::{fibonacci, generate_halo2_proof_with_shape, verify_halo2_proof_with_vk},
+    preprocess_for_shape, CircuitShape, FibonacciError, PlonkishComponents, TranscriptBinding,
+};
+
+/// Label the proofs of chain links are bound to
+pub const CHAIN_LABEL: &[u8] = b"fibonacci-chain";
+
+/// Keccak-256 commitment to a chain state and all states before it
+pub type StateCommitment = [u8; 32];
+
+/// Two consecutive terms (F(k), F(k+1)) of a sequence
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChainState {
+    pub current: Fr,
+    pub next: Fr,
+}
+
+impl ChainState {
+    pub fn new(current: Fr, next: Fr) -> Self {
+        Self { current, next }
+    }
+
+    /// Commitment to this state following the state committed to by `previous`. The first state
+    /// of a chain follows the all zero commitment.
+    pub fn commitment(&self, previous: &StateCommitment) -> StateCommitment {
+        Keccak256::new()
+            .chain_update(previous)
+            .chain_update(self.current.to_bytes())
+            .chain_update(self.next.to_bytes())
+            .finalize()
+            .into()
+    }
+}
+
+/// Proofs that `steps` terms lead to the state `end` from the state before the link
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChainLink {
+    pub end: ChainState,
+    /// Proof of `end.current`, F(k + steps)
+    pub current_proof: Vec<u8>,
+    /// Proof of `end.next`, F(k + steps + 1)
+    pub next_proof: Vec<u8>,
+}
+
+/// Keys of the two circuits a link is proven with, of `steps` and `steps + 1` terms
+pub struct ChainKeys<PC: PlonkishComponents> {
+    pub steps: usize,
+    current: (PC::ProverParam, PC::VerifierParam),
+    next: (PC::ProverParam, PC::VerifierParam),
+}
+
+impl<PC: PlonkishComponents> ChainKeys<PC> {
+    /// Preprocess the circuits for links of `steps` terms, each in the smallest domain holding it.
+    pub fn preprocess(param: &PC::Param, steps: usize) -> Result<Self, FibonacciError> {
+        Ok(Self {
+            steps,
+            current: preprocess_for_shape::<PC>(param, CircuitShape::new(steps))?,
+            next: preprocess_for_shape::<PC>(param, CircuitShape::new(steps + 1))?,
+        })
+    }
+}
+
+fn link_inputs(start: &ChainState, out: Fr) -> HashMap<String, Vec<Fr>> {
+    HashMap::from([
+        ("a".to_string(), vec![start.current]),
+        ("b".to_string(), vec![start.next]),
+        ("out".to_string(), vec![out]),
+    ])
+}
+
+/// Prove `links` links of `keys.steps` terms each, starting from `start`.
+pub fn prove_chain<PC>(
+    param: &PC::Param,
+    keys: &ChainKeys<PC>,
+    start: ChainState,
+    links: usize,
+) -> Result<Vec<ChainLink>, FibonacciError>
+where
+    PC: PlonkishComponents,
+    Keccak256Transcript<Cursor<Vec<u8>>>: TranscriptWrite<CommitmentChunk<Fr, PC::Pcs>, Fr>,
+{
+    let mut state = start;
+    let mut commitment = state.commitment(&[0; 32]);
+    let mut chain = Vec::with_capacity(links);
+
+    for _ in 0..links {
+        let binding = TranscriptBinding {
+            label: CHAIN_LABEL,
+            context: &commitment,
+        };
+        let end = ChainState::new(
+            fibonacci(state.current, state.next, keys.steps),
+            fibonacci(state.current, state.next, keys.steps + 1),
+        );
+
+        let (current_proof, _) = generate_halo2_proof_with_shape::<PC>(
+            param,
+            &keys.current.0,
+            link_inputs(&state, end.current),
+            CircuitShape::new(keys.steps),
+            binding,
+        )?;
+        let (next_proof, _) = generate_halo2_proof_with_shape::<PC>(
+            param,
+            &keys.next.0,
+            link_inputs(&state, end.next),
+            CircuitShape::new(keys.steps + 1),
+            binding,
+        )?;
+
+        chain.push(ChainLink {
+            end,
+            current_proof,
+            next_proof,
+        });
+        commitment = end.commitment(&commitment);
+        state = end;
+    }
+
+    Ok(chain)
+}
+
+/// Verify every link of `chain` in order, starting from `start`, and return the final state.
+pub fn verify_chain<PC>(
+    keys: &ChainKeys<PC>,
+    start: ChainState,
+    chain: &[ChainLink],
+) -> Result<ChainState, FibonacciError>
+where
+    PC: PlonkishComponents,
+    Keccak256Transcript<Cursor<Vec<u8>>>: TranscriptRead<CommitmentChunk<Fr, PC::Pcs>, Fr>,
+{
+    let mut state = start;
+    let mut commitment = state.commitment(&[0; 32]);
+
+    for (index, link) in chain.iter().enumerate() {
+        let binding = TranscriptBinding {
+            label: CHAIN_LABEL,
+            context: &commitment,
+        };
+        // Invalid proofs fail verification with an error rather than `Ok(false)`
+        let verified = |verifier_param, proof: &[u8], out| {
+            matches!(
+                verify_halo2_proof_with_vk::<PC>(
+                    verifier_param,
+                    proof,
+                    vec![state.current, state.next, out],
+                    binding,
+                ),
+                Ok(true)
+            )
+        };
+
+        let valid = verified(&keys.current.1, &link.current_proof, link.end.current)
+            && verified(&keys.next.1, &link.next_proof, link.end.next);
+        if !valid {
+            return Err(FibonacciError::Verification(
+                format!(
+                    "Link {} of the chain doesn't extend the state before it",
+                    index
+                )
+                .into(),
+            ));
+        }
+
+        commitment = link.end.commitment(&commitment);
+        state = link.end;
+    }
+
+    Ok(state)
+}
@@ -8,17 +8,25 @@ use halo2_proofs::{
     poly::Rotation,
 };
 use plonkish_backend::{
-    backend::PlonkishBackend,
+    backend::{PlonkishBackend, PlonkishCircuit},
     frontend::halo2::{CircuitExt, Halo2Circuit},
     halo2_curves::bn256::Fr,
     pcs::{CommitmentChunk, PolynomialCommitmentScheme},
     util::{
         test::std_rng,
-        transcript::{InMemoryTranscript, Keccak256Transcript, TranscriptRead, TranscriptWrite},
+        transcript::{
+            FieldTranscript, FieldTranscriptRead, InMemoryTranscript, Keccak256Transcript,
+            Transcript, TranscriptRead, TranscriptWrite,
+        },
     },
 };
 
-use crate::{FibonacciError, PlonkishComponents};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    serialisation::{decimal, field_element, field_element_to_decimal},
+    BackendError, ErrorMessage, FibonacciError, PlonkishComponents, ProofTranscript,
+};
 
 /// Defines the configuration of all the columns, and all of the column definitions
 /// Will be incrementally populated and passed around
@@ -32,7 +40,7 @@ pub struct FibonacciConfig {
 }
 
 #[derive(Debug, Clone)]
-struct FibonacciChip<F: Field> {
+pub(crate) struct FibonacciChip<F: Field> {
     config: FibonacciConfig,
     _marker: PhantomData<F>,
     // In rust, when you have a struct that is generic over a type parameter (here F),
@@ -103,7 +111,7 @@ impl<F: Field> FibonacciChip<F> {
                 let a_cell = region.assign_advice_from_instance(
                     || "f(0)",
                     self.config.instance,
-                    0,
+                    INSTANCE_A_ROW,
                     self.config.col_a,
                     0,
                 )?;
@@ -111,7 +119,7 @@ impl<F: Field> FibonacciChip<F> {
                 let b_cell = region.assign_advice_from_instance(
                     || "f(1)",
                     self.config.instance,
-                    1,
+                    INSTANCE_B_ROW,
                     self.config.col_b,
                     0,
                 )?;
@@ -164,11 +172,211 @@ impl<F: Field> FibonacciChip<F> {
     ) -> Result<(), Error> {
         layouter.constrain_instance(cell.cell(), self.config.instance, row)
     }
+
+    /// Assign the rows of a sequence of `steps`, starting from the values in the instance column,
+    /// and return the cell of F(steps).
+    pub fn assign_sequence(
+        &self,
+        mut layouter: impl Layouter<F>,
+        steps: usize,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let (_, mut prev_b, mut prev_c) =
+            self.assign_first_row(layouter.namespace(|| "first row"))?;
+
+        for _i in MIN_STEPS..steps {
+            let c_cell = self.assign_row(layouter.namespace(|| "next row"), &prev_b, &prev_c)?;
+            prev_b = prev_c;
+            prev_c = c_cell;
+        }
+
+        Ok(prev_c)
+    }
 }
 
-#[derive(Clone, Default)]
+// Shared with verifiers built without std
+pub use fibonacci_verifier_core::{
+    INSTANCE_A_ROW, INSTANCE_B_ROW, INSTANCE_OUT_ROW, NUM_PUBLIC_INPUTS,
+};
+
+/// Sequence length of the default circuit, whose output F(9) is 55 for F(0) = F(1) = 1
+pub const DEFAULT_STEPS: usize = 9;
+/// Shortest sequence the circuit supports, a single row computing F(2)
+pub const MIN_STEPS: usize = 2;
+/// Rows at the end of the domain which can't hold the sequence, the halo2 blinding rows plus one
+const RESERVED_ROWS: usize = 6;
+
+/// Sequence length and domain size (2^k rows) a circuit is preprocessed for. Keys are only valid
+/// for the shape they were generated with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct CircuitShape {
+    /// Index n of the output F(n)
+    pub steps: usize,
+    pub k: usize,
+}
+
+impl Default for CircuitShape {
+    fn default() -> Self {
+        Self {
+            steps: DEFAULT_STEPS,
+            k: 4,
+        }
+    }
+}
+
+impl CircuitShape {
+    /// Shape of a sequence of `steps`, in the smallest domain which holds it.
+    pub fn new(steps: usize) -> Self {
+        Self {
+            steps,
+            k: Self::min_k(steps),
+        }
+    }
+
+    /// Smallest k whose domain holds a sequence of `steps`, one row per step after F(1).
+    pub fn min_k(steps: usize) -> usize {
+        let rows = steps.saturating_sub(1) + RESERVED_ROWS;
+        rows.next_power_of_two().trailing_zeros() as usize
+    }
+
+    /// Longest sequence a domain of 2^k rows holds.
+    pub fn max_steps(k: usize) -> usize {
+        (1usize << k).saturating_sub(RESERVED_ROWS) + 1
+    }
+
+    pub fn validate(&self) -> Result<(), FibonacciError> {
+        if self.steps < MIN_STEPS {
+            return Err(FibonacciError::InvalidInput(
+                format!(
+                    "Sequence length {} is shorter than {}",
+                    self.steps, MIN_STEPS
+                )
+                .into(),
+            ));
+        }
+        if self.k < Self::min_k(self.steps) {
+            return Err(FibonacciError::InvalidInput(
+                format!(
+                    "Sequence length {} doesn't fit in 2^{} rows, it needs k >= {}",
+                    self.steps,
+                    self.k,
+                    Self::min_k(self.steps)
+                )
+                .into(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Parameters a Fibonacci circuit is instantiated with, e.g. from a deployment manifest:
+///
+/// ```json
+/// {"steps": 9, "k": 4, "a": "1", "b": "1"}
+/// ```
+///
+/// The shape fixes the keys, proving with keys of another shape fails with
+/// `FibonacciError::CircuitMismatch`. The starting values are public inputs, the config fixes
+/// which ones the host proves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CircuitConfig {
+    #[serde(flatten)]
+    pub shape: CircuitShape,
+    /// F(0), 1 unless given
+    #[serde(default = "one", with = "decimal")]
+    pub a: Fr,
+    /// F(1), 1 unless given
+    #[serde(default = "one", with = "decimal")]
+    pub b: Fr,
+}
+
+fn one() -> Fr {
+    Fr::from(1)
+}
+
+impl Default for CircuitConfig {
+    fn default() -> Self {
+        Self::new(CircuitShape::default())
+    }
+}
+
+impl CircuitConfig {
+    /// Config of the sequence starting with 1 and 1 in `shape`.
+    pub fn new(shape: CircuitShape) -> Self {
+        Self {
+            shape,
+            a: one(),
+            b: one(),
+        }
+    }
+
+    /// F(steps) of the sequence starting with `a` and `b`, the output the circuit proves.
+    pub fn output(&self) -> Fr {
+        fibonacci(self.a, self.b, self.shape.steps)
+    }
+
+    /// Prover input map, as taken by `prove_from_bytes_with_shape`.
+    pub fn input(&self) -> HashMap<String, Vec<String>> {
+        HashMap::from([
+            ("steps".to_string(), vec![self.shape.steps.to_string()]),
+            ("a".to_string(), vec![field_element_to_decimal(&self.a)]),
+            ("b".to_string(), vec![field_element_to_decimal(&self.b)]),
+            (
+                "out".to_string(),
+                vec![field_element_to_decimal(&self.output())],
+            ),
+        ])
+    }
+
+    /// Circuit with the public inputs of the config, after checking its shape.
+    pub fn circuit(&self) -> Result<FibonacciCircuit<Fr>, FibonacciError> {
+        self.shape.validate()?;
+        Ok(FibonacciCircuit {
+            public_input: vec![vec![self.a, self.b, self.output()]],
+            steps: self.shape.steps,
+        })
+    }
+}
+
+#[derive(Clone)]
 pub struct FibonacciCircuit<F> {
     pub public_input: Vec<Vec<F>>,
+    /// Index n of the output F(n) exposed as the last public input
+    pub steps: usize,
+}
+
+impl FibonacciCircuit<Fr> {
+    /// Circuit proving `out` is F(`steps`) of the sequence starting with `a` and `b`, 1 and 1
+    /// unless given.
+    pub fn from_inputs(
+        inputs: &HashMap<String, Vec<Fr>>,
+        steps: usize,
+    ) -> Result<Self, FibonacciError> {
+        let a = initial_value(inputs, "a")?; // F[0]
+        let b = initial_value(inputs, "b")?; // F[1]
+
+        // `out` must be F(steps), 55 for the default circuit
+        let out =
+            *inputs
+                .get("out")
+                .and_then(|out| out.first())
+                .ok_or(FibonacciError::InvalidInput(
+                    "Failed to get `out` value".into(),
+                ))?;
+
+        Ok(Self {
+            public_input: vec![vec![a, b, out]],
+            steps,
+        })
+    }
+}
+
+impl<F> Default for FibonacciCircuit<F> {
+    fn default() -> Self {
+        Self {
+            public_input: Vec::new(),
+            steps: DEFAULT_STEPS,
+        }
+    }
 }
 
 // Our circuit will instantiate an instance based on the interface defined on the chip and floorplanner (layouter)
@@ -179,7 +387,10 @@ impl<F: Field> Circuit<F> for FibonacciCircuit<F> {
 
     // Circuit without witnesses, called only during key generation
     fn without_witnesses(&self) -> Self {
-        Self::default()
+        Self {
+            public_input: Vec::new(),
+            steps: self.steps,
+        }
     }
 
     // Has the arrangement of columns. Called only during keygen, and will just call chip config most of the time
@@ -197,16 +408,8 @@ impl<F: Field> Circuit<F> for FibonacciCircuit<F> {
     ) -> Result<(), Error> {
         let chip = FibonacciChip::construct(config);
 
-        let (_, mut prev_b, mut prev_c) =
-            chip.assign_first_row(layouter.namespace(|| "first row"))?;
-
-        for _i in 3..10 {
-            let c_cell = chip.assign_row(layouter.namespace(|| "next row"), &prev_b, &prev_c)?;
-            prev_b = prev_c;
-            prev_c = c_cell;
-        }
-
-        chip.expose_public(layouter.namespace(|| "out"), &prev_c, 2)?;
+        let out = chip.assign_sequence(layouter.namespace(|| "sequence"), self.steps)?;
+        chip.expose_public(layouter.namespace(|| "out"), &out, INSTANCE_OUT_ROW)?;
 
         Ok(())
     }
@@ -222,8 +425,62 @@ impl<F: Field> CircuitExt<F> for FibonacciCircuit<F> {
     }
 }
 
+/// Caller-supplied data bound into the proof transcript.
+///
+/// Verification only succeeds when the verifier supplies identical values, so proofs can't be
+/// replayed in another application or session without modifying the circuit.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TranscriptBinding<'a> {
+    /// Domain separation label, e.g. the application name
+    pub label: &'a [u8],
+    /// Per-proof context, e.g. a session id or chain id
+    pub context: &'a [u8],
+}
+
+impl<'a> TranscriptBinding<'a> {
+    pub fn label(label: &'a [u8]) -> Self {
+        Self {
+            label,
+            context: &[],
+        }
+    }
+
+    pub fn context(context: &'a [u8]) -> Self {
+        Self {
+            label: &[],
+            context,
+        }
+    }
+
+    /// Field elements absorbed into the transcript, empty when nothing is bound
+    pub fn to_field_elements(&self) -> Vec<Fr> {
+        fibonacci_verifier_core::binding_elements(self.label, self.context)
+            .iter()
+            .map(field_element)
+            .collect()
+    }
+}
+
+/// Absorb the binding into the transcript before proving or verifying. An empty binding leaves
+/// the transcript untouched.
+pub fn absorb_binding(
+    transcript: &mut impl FieldTranscript<Fr>,
+    binding: &TranscriptBinding,
+) -> Result<(), FibonacciError> {
+    let elements = binding.to_field_elements();
+    if elements.is_empty() {
+        return Ok(());
+    }
+    transcript.common_field_elements(&elements).map_err(|e| {
+        FibonacciError::Transcript(ErrorMessage::with_source(
+            format!("Failed to absorb transcript binding: {:?}", e),
+            BackendError(e),
+        ))
+    })
+}
+
 pub fn generate_halo2_proof<PC>(
-    _srs: &<PC::Pcs as PolynomialCommitmentScheme<Fr>>::Param,
+    srs: &<PC::Pcs as PolynomialCommitmentScheme<Fr>>::Param,
     prover_parameters: &PC::ProverParam,
     inputs: HashMap<String, Vec<Fr>>,
 ) -> Result<(Vec<u8>, Vec<Fr>), FibonacciError>
@@ -231,74 +488,315 @@ where
     PC: PlonkishComponents,
     Keccak256Transcript<Cursor<Vec<u8>>>: TranscriptWrite<CommitmentChunk<Fr, PC::Pcs>, Fr>,
 {
-    // Setup starting values of the Fibonacci sequence
-    let a = Fr::from(1); // F[0]
-    let b = Fr::from(1); // F[1]
-
-    let k = 4usize;
-
-    // `out` value right now must be 55, but will be replaced with the actual output value
-    let out: Fr = inputs
-        .get("out")
-        .ok_or(FibonacciError("Failed to get `out` value".to_string()))?
-        .get(0)
-        .ok_or(FibonacciError("Failed to get `out` value".to_string()))?
-        .clone();
-
-    let public_input = vec![a, b, out];
-    let circuit = FibonacciCircuit::<Fr> {
-        public_input: vec![public_input.clone()],
-    };
+    generate_halo2_proof_with_binding::<PC>(
+        srs,
+        prover_parameters,
+        inputs,
+        TranscriptBinding::default(),
+    )
+}
+
+/// Like `generate_halo2_proof`, binding the proof to the label and context of `binding`.
+pub fn generate_halo2_proof_with_binding<PC>(
+    srs: &<PC::Pcs as PolynomialCommitmentScheme<Fr>>::Param,
+    prover_parameters: &PC::ProverParam,
+    inputs: HashMap<String, Vec<Fr>>,
+    binding: TranscriptBinding,
+) -> Result<(Vec<u8>, Vec<Fr>), FibonacciError>
+where
+    PC: PlonkishComponents,
+    Keccak256Transcript<Cursor<Vec<u8>>>: TranscriptWrite<CommitmentChunk<Fr, PC::Pcs>, Fr>,
+{
+    generate_halo2_proof_with_shape::<PC>(
+        srs,
+        prover_parameters,
+        inputs,
+        CircuitShape::default(),
+        binding,
+    )
+}
+
+/// Like `generate_halo2_proof_with_binding`, for prover parameters preprocessed for `shape`
+/// instead of the default circuit. The circuit is laid out in 2^`shape.k` rows, which the SRS must
+/// hold.
+pub fn generate_halo2_proof_with_shape<PC>(
+    srs: &<PC::Pcs as PolynomialCommitmentScheme<Fr>>::Param,
+    prover_parameters: &PC::ProverParam,
+    inputs: HashMap<String, Vec<Fr>>,
+    shape: CircuitShape,
+    binding: TranscriptBinding,
+) -> Result<(Vec<u8>, Vec<Fr>), FibonacciError>
+where
+    PC: PlonkishComponents,
+    Keccak256Transcript<Cursor<Vec<u8>>>: TranscriptWrite<CommitmentChunk<Fr, PC::Pcs>, Fr>,
+{
+    crate::profile_scope!("generate_halo2_proof");
 
-    let halo2_circuit =
-        Halo2Circuit::<Fr, FibonacciCircuit<Fr>>::new::<PC::ProvingBackend>(k, circuit);
+    shape.validate()?;
+    crate::io::check_srs_size::<PC>(srs, shape.k)?;
 
-    let proof_transcript = {
-        let mut proof_transcript = Keccak256Transcript::new(());
+    let circuit = FibonacciCircuit::from_inputs(&inputs, shape.steps)?;
+    let (proof, mut instances) =
+        generate_halo2_proof_for_circuit::<PC, _>(prover_parameters, shape.k, circuit, binding)?;
 
-        PC::ProvingBackend::prove(
-            &prover_parameters,
-            &halo2_circuit,
-            &mut proof_transcript,
-            std_rng(),
-        )
-        .unwrap();
-        proof_transcript
+    Ok((proof, instances.remove(0)))
+}
+
+/// Prove any halo2 circuit laid out in 2^`k` rows with prover parameters preprocessed for it, see
+/// `preprocess_circuit`. Returns the proof and the public inputs of each instance column.
+pub fn generate_halo2_proof_for_circuit<PC, C>(
+    prover_parameters: &PC::ProverParam,
+    k: usize,
+    circuit: C,
+    binding: TranscriptBinding,
+) -> Result<(Vec<u8>, Vec<Vec<Fr>>), FibonacciError>
+where
+    PC: PlonkishComponents,
+    C: CircuitExt<Fr>,
+    Keccak256Transcript<Cursor<Vec<u8>>>: TranscriptWrite<CommitmentChunk<Fr, PC::Pcs>, Fr>,
+{
+    let instances = circuit.instances();
+    let halo2_circuit = {
+        crate::profile_scope!("synthesize");
+        build_halo2_circuit::<PC, C>(k, circuit)
     };
 
-    let proof = proof_transcript.into_proof();
+    let proof = prove_synthesized_circuit::<PC>(prover_parameters, &halo2_circuit, binding)?;
+    Ok((proof, instances))
+}
 
-    Ok((proof, public_input))
+/// Wrap a halo2 circuit laid out in 2^`k` rows for the backend of `PC`. Key generation takes the
+/// circuit info from the wrapped circuit and the prover synthesizes its witness, so one wrapped
+/// circuit can be inspected with `circuit_info()`, preprocessed and then proven with
+/// `prove_synthesized_circuit`.
+pub fn build_halo2_circuit<PC, C>(k: usize, circuit: C) -> Halo2Circuit<Fr, C>
+where
+    PC: PlonkishComponents,
+    C: CircuitExt<Fr>,
+{
+    Halo2Circuit::new::<PC::ProvingBackend>(k, circuit)
+}
+
+/// Prove a circuit already laid out for the backend, e.g. a `Halo2Circuit`.
+pub fn prove_synthesized_circuit<PC>(
+    prover_parameters: &PC::ProverParam,
+    circuit: &impl PlonkishCircuit<Fr>,
+    binding: TranscriptBinding,
+) -> Result<Vec<u8>, FibonacciError>
+where
+    PC: PlonkishComponents,
+    Keccak256Transcript<Cursor<Vec<u8>>>: TranscriptWrite<CommitmentChunk<Fr, PC::Pcs>, Fr>,
+{
+    crate::profile_scope!("prove");
+    let mut proof_transcript = Keccak256Transcript::new(());
+    absorb_binding(&mut proof_transcript, &binding)?;
+
+    PC::ProvingBackend::prove(prover_parameters, circuit, &mut proof_transcript, std_rng())
+        .map_err(|e| {
+            FibonacciError::Proving(ErrorMessage::with_source(
+                format!("Proving error: {:?}", e),
+                BackendError(e),
+            ))
+        })?;
+
+    Ok(proof_transcript.into_proof())
+}
+
+/// Optional starting value `name` (`a` or `b`) of the sequence, 1 without it.
+pub fn initial_value(inputs: &HashMap<String, Vec<Fr>>, name: &str) -> Result<Fr, FibonacciError> {
+    match inputs.get(name) {
+        Some(values) => values.first().copied().ok_or_else(|| {
+            FibonacciError::InvalidInput(format!("Failed to get `{}` value", name).into())
+        }),
+        None => Ok(Fr::from(1)),
+    }
+}
+
+/// F(steps) of the sequence starting with F(0) = `a` and F(1) = `b`, the `out` a proof of
+/// `steps` must expose.
+pub fn fibonacci<F: Field>(a: F, b: F, steps: usize) -> F {
+    if steps == 0 {
+        return a;
+    }
+    let (mut prev, mut cur) = (a, b);
+    for _ in 1..steps {
+        (prev, cur) = (cur, prev + cur);
+    }
+    cur
 }
 
 pub fn verify_halo2_proof<PC>(
+    srs: &<PC::Pcs as PolynomialCommitmentScheme<Fr>>::Param,
+    verifier_parameters: &PC::VerifierParam,
+    proof: &[u8],
+    inputs: Vec<Fr>,
+) -> Result<bool, FibonacciError>
+where
+    PC: PlonkishComponents,
+    Keccak256Transcript<Cursor<Vec<u8>>>: TranscriptRead<CommitmentChunk<Fr, PC::Pcs>, Fr>,
+{
+    verify_halo2_proof_with_binding::<PC>(
+        srs,
+        verifier_parameters,
+        proof,
+        inputs,
+        TranscriptBinding::default(),
+    )
+}
+
+/// Like `verify_halo2_proof`, only accepting proofs bound to the label and context of `binding`.
+pub fn verify_halo2_proof_with_binding<PC>(
     _srs: &<PC::Pcs as PolynomialCommitmentScheme<Fr>>::Param,
     verifier_parameters: &PC::VerifierParam,
-    proof: Vec<u8>,
+    proof: &[u8],
     inputs: Vec<Fr>,
+    binding: TranscriptBinding,
 ) -> Result<bool, FibonacciError>
 where
     PC: PlonkishComponents,
     Keccak256Transcript<Cursor<Vec<u8>>>: TranscriptRead<CommitmentChunk<Fr, PC::Pcs>, Fr>,
 {
-    let mut transcript;
-    let result: Result<(), plonkish_backend::Error> = {
-        transcript = Keccak256Transcript::from_proof((), proof.as_slice());
-        PC::ProvingBackend::verify(&verifier_parameters, &[inputs], &mut transcript, std_rng())
-    };
+    verify_halo2_proof_with_vk::<PC>(verifier_parameters, proof, inputs, binding)
+}
+
+/// Like `verify_halo2_proof_with_binding`, without the SRS. The verifier parameters already hold
+/// the PCS elements needed to verify, trimmed from the SRS during preprocessing.
+pub fn verify_halo2_proof_with_vk<PC>(
+    verifier_parameters: &PC::VerifierParam,
+    proof: &[u8],
+    inputs: Vec<Fr>,
+    binding: TranscriptBinding,
+) -> Result<bool, FibonacciError>
+where
+    PC: PlonkishComponents,
+    Keccak256Transcript<Cursor<Vec<u8>>>: TranscriptRead<CommitmentChunk<Fr, PC::Pcs>, Fr>,
+{
+    verify_halo2_proof_with_instances::<PC>(verifier_parameters, proof, &[inputs], binding)
+}
+
+/// Like `verify_halo2_proof_with_vk`, for circuits with several instance columns, given the
+/// public inputs of each column in order. Proofs with bytes after the end of the transcript are
+/// rejected, so each proof has a single encoding.
+pub fn verify_halo2_proof_with_instances<PC>(
+    verifier_parameters: &PC::VerifierParam,
+    proof: &[u8],
+    instances: &[Vec<Fr>],
+    binding: TranscriptBinding,
+) -> Result<bool, FibonacciError>
+where
+    PC: PlonkishComponents,
+    Keccak256Transcript<Cursor<Vec<u8>>>: TranscriptRead<CommitmentChunk<Fr, PC::Pcs>, Fr>,
+{
+    crate::profile_scope!("verify_halo2_proof");
+
+    let mut transcript = RecordingTranscript::from_proof(proof);
+    absorb_binding(&mut transcript, &binding)?;
+    PC::ProvingBackend::verify(verifier_parameters, instances, &mut transcript, std_rng())
+        .map_err(|e| {
+            FibonacciError::Verification(ErrorMessage::with_source(
+                format!("Verifying proof error: {:?}", e),
+                BackendError(e),
+            ))
+        })?;
+
+    if !transcript.read_whole::<CommitmentChunk<Fr, PC::Pcs>>(proof) {
+        return Err(FibonacciError::Verification(
+            "Proof has unread bytes after the end of the transcript".into(),
+        ));
+    }
+    Ok(true)
+}
 
-    result
-        .map(|_| true)
-        .map_err(|e| FibonacciError(format!("Verifying proof error: {:?}", e)))
+/// Item the verifier read from the proof, see `RecordingTranscript`
+#[derive(Debug, Clone, Copy)]
+enum ProofRead {
+    FieldElement,
+    Commitment,
+}
+
+/// Transcript reading a proof through `ProofTranscript`, recording the items the verifier reads.
+///
+/// The transcript keeps the position of its stream to itself, so the recorded reads tell how much
+/// of the proof the verifier read instead, see `read_whole`.
+struct RecordingTranscript {
+    transcript: ProofTranscript,
+    reads: Vec<ProofRead>,
+}
+
+impl RecordingTranscript {
+    fn from_proof(proof: &[u8]) -> Self {
+        Self {
+            transcript: ProofTranscript::from_proof((), proof),
+            reads: Vec::new(),
+        }
+    }
+
+    /// Whether the verifier read all of `proof`. The recorded reads are replayed on the proof
+    /// without its last byte, where they only fit if that byte was never read. Replaying decodes
+    /// the items again but skips the verifier's checks, so it costs a fraction of a verification.
+    fn read_whole<C>(&self, proof: &[u8]) -> bool
+    where
+        ProofTranscript: TranscriptRead<C, Fr>,
+    {
+        let Some((_, shorter)) = proof.split_last() else {
+            return true;
+        };
+        let mut transcript = ProofTranscript::from_proof((), shorter);
+        !self.reads.iter().all(|read| match read {
+            ProofRead::FieldElement => {
+                FieldTranscriptRead::<Fr>::read_field_element(&mut transcript).is_ok()
+            }
+            ProofRead::Commitment => {
+                TranscriptRead::<C, Fr>::read_commitment(&mut transcript).is_ok()
+            }
+        })
+    }
+}
+
+impl FieldTranscript<Fr> for RecordingTranscript {
+    fn squeeze_challenge(&mut self) -> Fr {
+        FieldTranscript::<Fr>::squeeze_challenge(&mut self.transcript)
+    }
+
+    fn common_field_element(&mut self, fe: &Fr) -> Result<(), plonkish_backend::Error> {
+        FieldTranscript::<Fr>::common_field_element(&mut self.transcript, fe)
+    }
+}
+
+impl FieldTranscriptRead<Fr> for RecordingTranscript {
+    fn read_field_element(&mut self) -> Result<Fr, plonkish_backend::Error> {
+        self.reads.push(ProofRead::FieldElement);
+        FieldTranscriptRead::<Fr>::read_field_element(&mut self.transcript)
+    }
+}
+
+impl<C> Transcript<C, Fr> for RecordingTranscript
+where
+    ProofTranscript: Transcript<C, Fr>,
+{
+    fn common_commitment(&mut self, comm: &C) -> Result<(), plonkish_backend::Error> {
+        Transcript::<C, Fr>::common_commitment(&mut self.transcript, comm)
+    }
+}
+
+impl<C> TranscriptRead<C, Fr> for RecordingTranscript
+where
+    ProofTranscript: TranscriptRead<C, Fr>,
+{
+    fn read_commitment(&mut self) -> Result<C, plonkish_backend::Error> {
+        self.reads.push(ProofRead::Commitment);
+        TranscriptRead::<C, Fr>::read_commitment(&mut self.transcript)
+    }
 }
 
 // Exporting Test
 pub mod test_utils {
     use std::collections::HashMap;
 
+    use halo2_proofs::{dev::MockProver, halo2curves::ff::Field};
     use plonkish_backend::{
         backend::{PlonkishBackend, PlonkishCircuit},
-        frontend::halo2::Halo2Circuit,
+        frontend::halo2::{CircuitExt, Halo2Circuit},
         halo2_curves::bn256::Fr,
         pcs::{CommitmentChunk, PolynomialCommitmentScheme},
         util::{
@@ -310,12 +808,127 @@ pub mod test_utils {
         Error::InvalidSumcheck,
     };
 
-    use super::FibonacciCircuit;
+    use rand::{Rng, RngCore};
+
+    use super::{
+        fibonacci, CircuitConfig, CircuitShape, FibonacciCircuit, DEFAULT_STEPS, MIN_STEPS,
+    };
     use crate::{
-        circuit::{generate_halo2_proof, verify_halo2_proof},
-        PlonkishComponents, ProofTranscript,
+        chain::{prove_chain, verify_chain, ChainKeys, ChainState},
+        circuit::{
+            build_halo2_circuit, generate_halo2_proof, generate_halo2_proof_with_binding,
+            generate_halo2_proof_with_shape, prove_synthesized_circuit, verify_halo2_proof,
+            verify_halo2_proof_with_binding, verify_halo2_proof_with_vk, TranscriptBinding,
+        },
+        circuit_hash, circuit_hash_for_shape,
+        container::ProofContainer,
+        fixed_start::{keygen_with_fixed_start, prove_with_fixed_start, verify_with_fixed_start},
+        hash_circuit_info, io, keygen_from_bytes,
+        lookup::{DynamicLookupCircuit, LOOKUP_K},
+        merkle::{
+            self, merkle_k, MerkleCircuit, MerkleInput, MerkleTree, DEFAULT_MERKLE_DEPTH, LEAF_ROW,
+            ROOT_ROW,
+        },
+        multi_instance::MultiInstanceCircuit,
+        nonce::{
+            keygen_with_nonce, nonce_circuit_hash, nonce_from_bytes, prove_with_nonce,
+            verify_with_nonce, INSTANCE_NONCE_ROW,
+        },
+        preprocess_circuit, prove_instances,
+        serialisation::{
+            decode_public_inputs, encode_public_inputs, Codec, InstanceColumnsSerialisationWrapper,
+        },
+        shuffle::{ShuffleCircuit, SHUFFLE_K},
+        step_count::{
+            keygen_with_step_count, prove_with_step_count, proven_steps, step_count_circuit_hash,
+            verify_with_step_count, INSTANCE_STEPS_ROW,
+        },
+        verify_container, verify_container_for_shape, verify_instances_with_vk,
+        witness::FibonacciWitness,
+        FibonacciError, PlonkishComponents, ProofBundle, ProofTranscript,
     };
 
+    /// `proof` with bit `bit` flipped, counting from the least significant bit of the first byte
+    pub fn flip_bit(proof: &[u8], bit: usize) -> Vec<u8> {
+        let mut proof = proof.to_vec();
+        proof[bit / 8] ^= 1 << (bit % 8);
+        proof
+    }
+
+    pub fn truncate_proof(proof: &[u8], len: usize) -> Vec<u8> {
+        proof[..len].to_vec()
+    }
+
+    pub fn extend_proof(proof: &[u8], extra: &[u8]) -> Vec<u8> {
+        [proof, extra].concat()
+    }
+
+    pub fn swap_public_inputs(public_inputs: &[Fr], i: usize, j: usize) -> Vec<Fr> {
+        let mut public_inputs = public_inputs.to_vec();
+        public_inputs.swap(i, j);
+        public_inputs
+    }
+
+    /// Bit flips at the start, middle and end of `proof` and truncations of it, labelled for
+    /// assertion messages. Every backend must reject all of them.
+    pub fn corrupted_proofs(proof: &[u8]) -> Vec<(String, Vec<u8>)> {
+        let bits = proof.len() * 8;
+        let mut corrupted = Vec::new();
+        for bit in [0, 7, bits / 2, bits - 8, bits - 1] {
+            corrupted.push((format!("bit {} flipped", bit), flip_bit(proof, bit)));
+        }
+        for len in [0, 32, proof.len() / 2, proof.len() - 1] {
+            corrupted.push((
+                format!("truncated to {} bytes", len),
+                truncate_proof(proof, len),
+            ));
+        }
+        for len in [1, 32] {
+            corrupted.push((
+                format!("extended by {} bytes", len),
+                extend_proof(proof, &vec![0; len]),
+            ));
+        }
+        corrupted
+    }
+
+    /// Valid inputs of a sequence: `out` is F(`steps`) of the sequence starting with `a`, `b`
+    #[derive(Debug, Clone)]
+    pub struct SequenceInput {
+        pub a: Fr,
+        pub b: Fr,
+        pub steps: usize,
+        pub out: Fr,
+    }
+
+    impl SequenceInput {
+        /// Random starting values and a random length of up to `max_steps`.
+        pub fn random(rng: &mut impl RngCore, max_steps: usize) -> Self {
+            let a = Fr::random(&mut *rng);
+            let b = Fr::random(&mut *rng);
+            let steps = rng.gen_range(MIN_STEPS..=max_steps);
+            Self {
+                a,
+                b,
+                steps,
+                out: fibonacci(a, b, steps),
+            }
+        }
+
+        pub fn public_input(&self) -> Vec<Fr> {
+            vec![self.a, self.b, self.out]
+        }
+
+        /// Prover inputs in the form of `generate_halo2_proof`
+        pub fn prover_input(&self) -> HashMap<String, Vec<Fr>> {
+            HashMap::from([
+                ("a".to_string(), vec![self.a]),
+                ("b".to_string(), vec![self.b]),
+                ("out".to_string(), vec![self.out]),
+            ])
+        }
+    }
+
     fn initialize_params_and_circuit<PC>(
         k: usize,
         public_input: Vec<Fr>,
@@ -330,6 +943,7 @@ pub mod test_utils {
     {
         let circuit = FibonacciCircuit::<Fr> {
             public_input: vec![public_input.clone()],
+            ..Default::default()
         };
 
         let circuit_fn = |k| {
@@ -382,7 +996,7 @@ pub mod test_utils {
         // Verifying Proof
         let mut transcript;
         let result: Result<(), plonkish_backend::Error> = {
-            transcript = Keccak256Transcript::from_proof((), proof.as_slice());
+            transcript = Keccak256Transcript::from_proof((), proof);
             PC::ProvingBackend::verify(
                 &verifier_parameters,
                 &[public_input],
@@ -396,7 +1010,7 @@ pub mod test_utils {
         let invalid_public_input = vec![a, b, Fr::from(56)];
 
         let invalid_result_with_wrong_input = {
-            transcript = Keccak256Transcript::from_proof((), proof.as_slice());
+            transcript = Keccak256Transcript::from_proof((), proof);
             PC::ProvingBackend::verify(
                 &verifier_parameters,
                 &[invalid_public_input],
@@ -419,6 +1033,29 @@ pub mod test_utils {
         ProofTranscript: TranscriptWrite<CommitmentChunk<Fr, PC::Pcs>, Fr>
             + TranscriptRead<CommitmentChunk<Fr, PC::Pcs>, Fr>,
     {
+        // One wrapped circuit serves key generation and proving
+        let shape = CircuitShape::default();
+        let circuit = build_halo2_circuit::<PC, _>(
+            shape.k,
+            FibonacciCircuit {
+                public_input: vec![vec![Fr::from(1), Fr::from(1), Fr::from(55)]],
+                steps: shape.steps,
+            },
+        );
+        let circuit_info = circuit.circuit_info().unwrap();
+        assert_eq!(circuit_info.k, shape.k);
+        let srs = PC::ProvingBackend::setup(&circuit_info, seeded_std_rng()).unwrap();
+        let (pp, vp) = PC::ProvingBackend::preprocess(&srs, &circuit_info).unwrap();
+        let proof =
+            prove_synthesized_circuit::<PC>(&pp, &circuit, TranscriptBinding::default()).unwrap();
+        assert!(verify_halo2_proof_with_vk::<PC>(
+            &vp,
+            &proof,
+            circuit.instances()[0].clone(),
+            TranscriptBinding::default()
+        )
+        .unwrap());
+
         let mut input = HashMap::new();
         input.insert("out".to_string(), vec![Fr::from(55)]);
 
@@ -429,10 +1066,784 @@ pub mod test_utils {
 
         assert_eq!(inputs, public_input);
 
-        let result = verify_halo2_proof::<PC>(&srs, &vp, proof, inputs);
+        let result = verify_halo2_proof::<PC>(&srs, &vp, &proof, inputs.clone());
+        assert_eq!(result.unwrap(), true);
+
+        let result =
+            verify_halo2_proof_with_vk::<PC>(&vp, &proof, inputs, TranscriptBinding::default());
         assert_eq!(result.unwrap(), true);
     }
 
+    /// Check the circuit hash is the hash of the circuit info's canonical encoding, served from
+    /// the cache on repeated calls, and tells shapes and circuit variants apart.
+    pub fn circuit_hash_test<PC>()
+    where
+        PC: PlonkishComponents,
+    {
+        let shape = CircuitShape::default();
+        let circuit_info = build_halo2_circuit::<PC, _>(
+            shape.k,
+            FibonacciCircuit {
+                public_input: vec![vec![Fr::from(1), Fr::from(1), Fr::from(55)]],
+                steps: shape.steps,
+            },
+        )
+        .circuit_info()
+        .unwrap();
+        let hash = circuit_hash::<PC>().unwrap();
+        assert_eq!(hash, hash_circuit_info(&circuit_info).unwrap());
+        assert_eq!(circuit_hash_for_shape::<PC>(shape).unwrap(), hash);
+
+        assert_ne!(
+            circuit_hash_for_shape::<PC>(CircuitShape::new(20)).unwrap(),
+            hash
+        );
+        assert_ne!(nonce_circuit_hash::<PC>().unwrap(), hash);
+        assert_ne!(step_count_circuit_hash::<PC>(shape).unwrap(), hash);
+    }
+
+    pub fn transcript_binding_test<PC>()
+    where
+        PC: PlonkishComponents,
+        ProofTranscript: TranscriptWrite<CommitmentChunk<Fr, PC::Pcs>, Fr>
+            + TranscriptRead<CommitmentChunk<Fr, PC::Pcs>, Fr>,
+    {
+        let mut input = HashMap::new();
+        input.insert("out".to_string(), vec![Fr::from(55)]);
+
+        let public_input = vec![Fr::from(1), Fr::from(1), Fr::from(55)];
+        let (_, srs, pp, vp) = initialize_params_and_circuit::<PC>(4, public_input);
+
+        let binding = TranscriptBinding {
+            label: b"app-a",
+            context: b"session-1",
+        };
+        let (proof, inputs) =
+            generate_halo2_proof_with_binding::<PC>(&srs, &pp, input, binding).unwrap();
+
+        let verify_with = |binding: TranscriptBinding| {
+            verify_halo2_proof_with_binding::<PC>(&srs, &vp, &proof, inputs.clone(), binding)
+                .unwrap_or(false)
+        };
+
+        assert!(verify_with(binding));
+        assert!(!verify_with(TranscriptBinding {
+            label: b"app-b",
+            ..binding
+        }));
+        assert!(!verify_with(TranscriptBinding {
+            context: b"session-2",
+            ..binding
+        }));
+        assert!(!verify_with(TranscriptBinding::label(b"app-a")));
+        assert!(!verify_halo2_proof::<PC>(&srs, &vp, &proof, inputs.clone()).unwrap_or(false));
+    }
+
+    pub fn circuit_shape_test<PC>()
+    where
+        PC: PlonkishComponents,
+        ProofTranscript: TranscriptWrite<CommitmentChunk<Fr, PC::Pcs>, Fr>
+            + TranscriptRead<CommitmentChunk<Fr, PC::Pcs>, Fr>,
+    {
+        assert_eq!(CircuitShape::new(9), CircuitShape::default());
+        assert_eq!(CircuitShape::max_steps(4), 11);
+        assert_eq!(CircuitShape::min_k(12), 5);
+        assert!(CircuitShape { steps: 20, k: 4 }.validate().is_err());
+        assert!(CircuitShape { steps: 1, k: 4 }.validate().is_err());
+
+        // F(20) of the sequence starting with 1, 1
+        let shape = CircuitShape::new(20);
+        let public_input = vec![Fr::from(1), Fr::from(1), Fr::from(10946)];
+        let circuit = Halo2Circuit::<Fr, FibonacciCircuit<Fr>>::new::<PC::ProvingBackend>(
+            shape.k,
+            FibonacciCircuit {
+                public_input: vec![public_input.clone()],
+                steps: shape.steps,
+            },
+        );
+        let circuit_info = circuit.circuit_info().unwrap();
+        let srs = PC::ProvingBackend::setup(&circuit_info, seeded_std_rng()).unwrap();
+        let (pp, vp) = PC::ProvingBackend::preprocess(&srs, &circuit_info).unwrap();
+
+        let mut input = HashMap::new();
+        input.insert("out".to_string(), vec![Fr::from(10946)]);
+        let (proof, inputs) = generate_halo2_proof_with_shape::<PC>(
+            &srs,
+            &pp,
+            input,
+            shape,
+            TranscriptBinding::default(),
+        )
+        .unwrap();
+        assert_eq!(inputs, public_input);
+
+        let result =
+            verify_halo2_proof_with_vk::<PC>(&vp, &proof, inputs, TranscriptBinding::default());
+        assert!(result.unwrap());
+    }
+
+    /// Prove the default sequence in a domain larger than it needs, and check the SRS and keys are
+    /// validated against the k proven with.
+    pub fn proving_k_test<PC>()
+    where
+        PC: PlonkishComponents,
+        ProofTranscript: TranscriptWrite<CommitmentChunk<Fr, PC::Pcs>, Fr>
+            + TranscriptRead<CommitmentChunk<Fr, PC::Pcs>, Fr>,
+    {
+        let shape = CircuitShape {
+            k: 5,
+            ..Default::default()
+        };
+        let mut input = HashMap::new();
+        input.insert("out".to_string(), vec![Fr::from(55)]);
+
+        let public_input = vec![Fr::from(1), Fr::from(1), Fr::from(55)];
+        let (_, srs, pp, vp) = initialize_params_and_circuit::<PC>(shape.k, public_input.clone());
+        let (proof, inputs) = generate_halo2_proof_with_shape::<PC>(
+            &srs,
+            &pp,
+            input.clone(),
+            shape,
+            TranscriptBinding::default(),
+        )
+        .unwrap();
+        assert!(verify_halo2_proof_with_vk::<PC>(
+            &vp,
+            &proof,
+            inputs,
+            TranscriptBinding::default()
+        )
+        .unwrap());
+
+        // An SRS of 2^4 rows can't hold the circuit
+        let (_, small_srs, _, _) = initialize_params_and_circuit::<PC>(4, public_input);
+        let result = generate_halo2_proof_with_shape::<PC>(
+            &small_srs,
+            &pp,
+            input,
+            shape,
+            TranscriptBinding::default(),
+        );
+        assert!(matches!(
+            result,
+            Err(FibonacciError::SrsTooSmall { needed: 5, .. })
+        ));
+
+        // Keys record the k they were generated for
+        let (proving_key, _) = crate::encode_keys::<PC>(&srs, shape).unwrap();
+        assert!(crate::decode_key::<PC, PC::ProverParam>(&proving_key, shape).is_ok());
+        assert!(matches!(
+            crate::decode_key::<PC, PC::ProverParam>(&proving_key, CircuitShape::default()),
+            Err(FibonacciError::CircuitMismatch(_))
+        ));
+    }
+
+    pub fn corrupted_proof_rejected_test<PC>()
+    where
+        PC: PlonkishComponents,
+        ProofTranscript: TranscriptWrite<CommitmentChunk<Fr, PC::Pcs>, Fr>
+            + TranscriptRead<CommitmentChunk<Fr, PC::Pcs>, Fr>,
+    {
+        let mut input = HashMap::new();
+        input.insert("out".to_string(), vec![Fr::from(55)]);
+
+        let public_input = vec![Fr::from(1), Fr::from(1), Fr::from(55)];
+        let (_, srs, pp, vp) = initialize_params_and_circuit::<PC>(4, public_input);
+        let (proof, inputs) = generate_halo2_proof::<PC>(&srs, &pp, input).unwrap();
+
+        let verify = |proof: &[u8], inputs: Vec<Fr>| {
+            verify_halo2_proof_with_vk::<PC>(&vp, proof, inputs, TranscriptBinding::default())
+                .unwrap_or(false)
+        };
+        assert!(verify(&proof, inputs.clone()));
+
+        for (mutation, corrupted) in corrupted_proofs(&proof) {
+            assert!(
+                !verify(&corrupted, inputs.clone()),
+                "Proof with {} verified",
+                mutation
+            );
+        }
+        assert!(!verify(&proof, swap_public_inputs(&inputs, 0, 2)));
+
+        // Trailing bytes are rejected, rather than ignored by the verifier
+        assert!(!verify(&extend_proof(&proof, &[0]), inputs.clone()));
+        assert!(!verify(
+            &extend_proof(&proof, &[0; 32]),
+            swap_public_inputs(&inputs, 0, 2)
+        ));
+    }
+
+    /// Preprocess the circuit of `input` in the domain of `shape`, then prove and verify it with
+    /// the real backend.
+    fn prove_and_verify<PC>(input: &SequenceInput, shape: CircuitShape) -> bool
+    where
+        PC: PlonkishComponents,
+        ProofTranscript: TranscriptWrite<CommitmentChunk<Fr, PC::Pcs>, Fr>
+            + TranscriptRead<CommitmentChunk<Fr, PC::Pcs>, Fr>,
+    {
+        let circuit = Halo2Circuit::<Fr, FibonacciCircuit<Fr>>::new::<PC::ProvingBackend>(
+            shape.k,
+            FibonacciCircuit {
+                public_input: vec![input.public_input()],
+                steps: shape.steps,
+            },
+        );
+        let circuit_info = circuit.circuit_info().unwrap();
+        let srs = PC::ProvingBackend::setup(&circuit_info, seeded_std_rng()).unwrap();
+        let (pp, vp) = PC::ProvingBackend::preprocess(&srs, &circuit_info).unwrap();
+
+        let (proof, inputs) = generate_halo2_proof_with_shape::<PC>(
+            &srs,
+            &pp,
+            input.prover_input(),
+            shape,
+            TranscriptBinding::default(),
+        )
+        .unwrap();
+        assert_eq!(inputs, input.public_input());
+
+        verify_halo2_proof_with_vk::<PC>(&vp, &proof, inputs, TranscriptBinding::default())
+            .unwrap_or(false)
+    }
+
+    /// Prove and verify random valid sequences, each in the smallest domain holding it.
+    pub fn random_round_trip_test<PC>()
+    where
+        PC: PlonkishComponents,
+        ProofTranscript: TranscriptWrite<CommitmentChunk<Fr, PC::Pcs>, Fr>
+            + TranscriptRead<CommitmentChunk<Fr, PC::Pcs>, Fr>,
+    {
+        let mut rng = seeded_std_rng();
+        for _ in 0..4 {
+            let input = SequenceInput::random(&mut rng, 40);
+            assert!(
+                prove_and_verify::<PC>(&input, CircuitShape::new(input.steps)),
+                "{:?} didn't verify",
+                input
+            );
+        }
+    }
+
+    /// Check the real prover and verifier agree with `MockProver` on random valid and invalid
+    /// inputs, so an edit to the circuit which breaks completeness or soundness of one of them
+    /// shows up as a disagreement.
+    pub fn mock_prover_differential_test<PC>()
+    where
+        PC: PlonkishComponents,
+        ProofTranscript: TranscriptWrite<CommitmentChunk<Fr, PC::Pcs>, Fr>
+            + TranscriptRead<CommitmentChunk<Fr, PC::Pcs>, Fr>,
+    {
+        let mut rng = seeded_std_rng();
+        for i in 0..6 {
+            let mut input = SequenceInput::random(&mut rng, 20);
+            // Every other input claims a wrong output
+            let valid = i % 2 == 0;
+            if !valid {
+                input.out += Fr::from(1);
+            }
+            let shape = CircuitShape::new(input.steps);
+
+            let circuit = FibonacciCircuit {
+                public_input: vec![input.public_input()],
+                steps: shape.steps,
+            };
+            let mock = MockProver::run(shape.k as u32, &circuit, circuit.public_input.clone())
+                .unwrap()
+                .verify()
+                .is_ok();
+            let real = prove_and_verify::<PC>(&input, shape);
+
+            assert_eq!(
+                mock, real,
+                "MockProver and the prover disagree on {:?}",
+                input
+            );
+            assert_eq!(mock, valid, "{:?} was misjudged", input);
+        }
+    }
+
+    /// Prove and verify one of the example circuits, rejected proofs count as failed verification.
+    fn prove_and_verify_example<PC, C>(k: usize, circuit: C) -> bool
+    where
+        PC: PlonkishComponents,
+        C: CircuitExt<Fr>,
+        ProofTranscript: TranscriptWrite<CommitmentChunk<Fr, PC::Pcs>, Fr>
+            + TranscriptRead<CommitmentChunk<Fr, PC::Pcs>, Fr>,
+    {
+        let circuit = Halo2Circuit::<Fr, C>::new::<PC::ProvingBackend>(k, circuit);
+        let circuit_info = circuit.circuit_info().unwrap();
+        let srs = PC::ProvingBackend::setup(&circuit_info, seeded_std_rng()).unwrap();
+        let (pp, vp) = PC::ProvingBackend::preprocess(&srs, &circuit_info).unwrap();
+
+        let mut transcript = Keccak256Transcript::new(());
+        // Provers may already refuse unsatisfied constraints
+        if PC::ProvingBackend::prove(&pp, &circuit, &mut transcript, seeded_std_rng()).is_err() {
+            return false;
+        }
+        let proof = transcript.into_proof();
+
+        let mut transcript = Keccak256Transcript::from_proof((), proof);
+        PC::ProvingBackend::verify(&vp, circuit.instances(), &mut transcript, seeded_std_rng())
+            .is_ok()
+    }
+
+    /// Check the variant with separate input and output columns proves the same sequences, and
+    /// that a wrong output is rejected.
+    pub fn multi_instance_test<PC>()
+    where
+        PC: PlonkishComponents,
+        ProofTranscript: TranscriptWrite<CommitmentChunk<Fr, PC::Pcs>, Fr>
+            + TranscriptRead<CommitmentChunk<Fr, PC::Pcs>, Fr>,
+    {
+        let circuit = MultiInstanceCircuit::new(Fr::from(1), Fr::from(1), DEFAULT_STEPS);
+        assert_eq!(
+            circuit.instances(),
+            vec![vec![Fr::from(1), Fr::from(1)], vec![Fr::from(55)]]
+        );
+        let k = CircuitShape::default().k;
+        assert!(MockProver::run(k as u32, &circuit, circuit.instances())
+            .unwrap()
+            .verify()
+            .is_ok());
+        assert!(prove_and_verify_example::<PC, _>(k, circuit.clone()));
+
+        let mut wrong = circuit;
+        wrong.public_input[1][0] += Fr::from(1);
+        assert!(MockProver::run(k as u32, &wrong, wrong.instances())
+            .unwrap()
+            .verify()
+            .is_err());
+        assert!(!prove_and_verify_example::<PC, _>(k, wrong));
+
+        // Both columns round-trip through the encoded public inputs
+        let circuit = MultiInstanceCircuit::new(Fr::from(2), Fr::from(3), DEFAULT_STEPS);
+        let circuit_info = Halo2Circuit::<Fr, _>::new::<PC::ProvingBackend>(k, circuit.clone())
+            .circuit_info()
+            .unwrap();
+        let srs = PC::ProvingBackend::setup(&circuit_info, seeded_std_rng()).unwrap();
+        let (pp, vp) = preprocess_circuit::<PC, _>(&srs, k, circuit.clone()).unwrap();
+        let (proof, public_inputs) =
+            prove_instances::<PC, _>(&pp, k, circuit.clone(), TranscriptBinding::default())
+                .unwrap();
+        let decoded: InstanceColumnsSerialisationWrapper =
+            io::decode_artifact(&public_inputs).unwrap();
+        assert_eq!(decoded.0, circuit.instances());
+        assert!(verify_instances_with_vk::<PC>(
+            &vp,
+            &proof,
+            &public_inputs,
+            TranscriptBinding::default()
+        )
+        .unwrap());
+
+        // Swapping the columns is rejected
+        let mut swapped = decoded.0;
+        swapped.reverse();
+        let swapped = bincode::serialize(&InstanceColumnsSerialisationWrapper(swapped)).unwrap();
+        assert!(!verify_instances_with_vk::<PC>(
+            &vp,
+            &proof,
+            &swapped,
+            TranscriptBinding::default()
+        )
+        .unwrap_or(false));
+    }
+
+    /// Check a witness file reads back into a circuit which proves, and that witnesses which
+    /// aren't consistent are rejected.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn witness_file_test<PC>()
+    where
+        PC: PlonkishComponents,
+        ProofTranscript: TranscriptWrite<CommitmentChunk<Fr, PC::Pcs>, Fr>
+            + TranscriptRead<CommitmentChunk<Fr, PC::Pcs>, Fr>,
+    {
+        let dir = crate::test_output_dir(&format!("witness-{}", PC::BACKEND.name()));
+        let path = dir.join("witness.bin");
+        let witness = FibonacciWitness::new(Fr::from(2), Fr::from(3), DEFAULT_STEPS);
+        assert_eq!(
+            witness.instance.0[2],
+            fibonacci(Fr::from(2), Fr::from(3), DEFAULT_STEPS)
+        );
+        witness.write(&path, Codec::Bincode, false).unwrap();
+
+        let circuit = FibonacciCircuit::from_witness_file(&path).unwrap();
+        assert_eq!(circuit.steps, DEFAULT_STEPS);
+        assert_eq!(circuit.instances(), vec![witness.instance.0.clone()]);
+        let k = CircuitShape::default().k;
+        assert!(prove_and_verify_example::<PC, _>(k, circuit));
+
+        // Sequences which aren't Fibonacci sequences or don't end in the public inputs
+        let mut wrong = witness.clone();
+        wrong.sequence.0[4] += Fr::from(1);
+        let bytes = wrong.to_bytes(Codec::Bincode).unwrap();
+        assert!(matches!(
+            FibonacciCircuit::from_witness_bytes(&bytes),
+            Err(FibonacciError::InvalidInput(_))
+        ));
+        let mut wrong = witness;
+        wrong.instance.0[2] += Fr::from(1);
+        let bytes = wrong.to_bytes(Codec::Bincode).unwrap();
+        assert!(FibonacciCircuit::from_witness_bytes(&bytes).is_err());
+
+        assert!(FibonacciCircuit::from_witness_file(&dir.join("missing.bin")).is_err());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    /// Check the variant with constant starting values proves the sequence of its config with
+    /// `out` as the only public input, and that its keys reject other starting values.
+    pub fn fixed_start_test<PC>()
+    where
+        PC: PlonkishComponents,
+        ProofTranscript: TranscriptWrite<CommitmentChunk<Fr, PC::Pcs>, Fr>
+            + TranscriptRead<CommitmentChunk<Fr, PC::Pcs>, Fr>,
+    {
+        let config = CircuitConfig::default();
+        let circuit = config.fixed_start_circuit().unwrap();
+        assert_eq!(circuit.instances(), vec![vec![Fr::from(55)]]);
+        let k = config.shape.k;
+        assert!(MockProver::run(k as u32, &circuit, circuit.instances())
+            .unwrap()
+            .verify()
+            .is_ok());
+
+        let mut wrong = circuit.clone();
+        wrong.public_input[0][0] += Fr::ONE;
+        assert!(MockProver::run(k as u32, &wrong, wrong.instances())
+            .unwrap()
+            .verify()
+            .is_err());
+
+        let circuit_info = build_halo2_circuit::<PC, _>(k, circuit)
+            .circuit_info()
+            .unwrap();
+        let srs = PC::ProvingBackend::setup(&circuit_info, seeded_std_rng()).unwrap();
+        let (pp, vp) = keygen_with_fixed_start::<PC>(&srs, &config).unwrap();
+        let binding = TranscriptBinding::default();
+        let (proof, public_inputs) = prove_with_fixed_start::<PC>(&pp, &config, binding).unwrap();
+        assert_eq!(public_inputs, encode_public_inputs(&[Fr::from(55)]));
+        assert!(verify_with_fixed_start::<PC>(&vp, &proof, &public_inputs, binding).unwrap());
+
+        // The starting values are part of the keys, not of the statement
+        let other = CircuitConfig {
+            a: Fr::from(2),
+            b: Fr::from(3),
+            ..config
+        };
+        let (other_pp, _) = keygen_with_fixed_start::<PC>(&srs, &other).unwrap();
+        let (proof, public_inputs) =
+            prove_with_fixed_start::<PC>(&other_pp, &other, binding).unwrap();
+        assert!(
+            !verify_with_fixed_start::<PC>(&vp, &proof, &public_inputs, binding).unwrap_or(false)
+        );
+    }
+
+    /// Check the nonce variant, with keys from the SRS contents `srs_key`, only verifies proofs
+    /// against the nonce they were made for, and that keys of the tutorial circuit reject them.
+    pub fn nonce_test<PC>(srs_key: &[u8])
+    where
+        PC: PlonkishComponents,
+        ProofTranscript: TranscriptWrite<CommitmentChunk<Fr, PC::Pcs>, Fr>
+            + TranscriptRead<CommitmentChunk<Fr, PC::Pcs>, Fr>,
+    {
+        let (proving_key, verifying_key) = keygen_with_nonce::<PC>(srs_key).unwrap();
+        let binding = TranscriptBinding::default();
+        let input = HashMap::from([("out".to_string(), vec!["55".to_string()])]);
+
+        let nonce = nonce_from_bytes(b"request 1");
+        let (proof, public_inputs) =
+            prove_with_nonce::<PC>(srs_key, &proving_key, input, nonce, binding).unwrap();
+        let verify = |nonce, public_inputs: &[u8]| {
+            verify_with_nonce::<PC>(&verifying_key, &proof, public_inputs, nonce, binding).unwrap()
+        };
+        assert!(verify(nonce, &public_inputs));
+
+        // Replaying the proof for another request fails, whether or not its nonce is swapped in
+        let other = nonce_from_bytes(b"request 2");
+        assert!(!verify(other, &public_inputs));
+        let mut replayed = decode_public_inputs(&public_inputs).unwrap();
+        replayed[INSTANCE_NONCE_ROW] = other;
+        assert!(!verify(other, &encode_public_inputs(&replayed)));
+
+        // Keys of the tutorial circuit don't verify proofs of the nonce variant
+        let (_, tutorial_key) = keygen_from_bytes::<PC>(srs_key).unwrap();
+        assert!(
+            verify_with_nonce::<PC>(&tutorial_key, &proof, &public_inputs, nonce, binding).is_err()
+        );
+    }
+
+    /// Check one key of the step count variant, from the SRS contents `srs_key`, proves every
+    /// sequence length up to its shape's, and that the proven length can't be swapped.
+    pub fn step_count_test<PC>(srs_key: &[u8])
+    where
+        PC: PlonkishComponents,
+        ProofTranscript: TranscriptWrite<CommitmentChunk<Fr, PC::Pcs>, Fr>
+            + TranscriptRead<CommitmentChunk<Fr, PC::Pcs>, Fr>,
+    {
+        let shape = CircuitShape::default();
+        let (proving_key, verifying_key) = keygen_with_step_count::<PC>(srs_key, shape).unwrap();
+        let binding = TranscriptBinding::default();
+        let input = |steps: usize, out: &str| {
+            HashMap::from([
+                ("steps".to_string(), vec![steps.to_string()]),
+                ("out".to_string(), vec![out.to_string()]),
+            ])
+        };
+        let prove = |steps, out: &str| {
+            prove_with_step_count::<PC>(srs_key, &proving_key, input(steps, out), shape, binding)
+        };
+        let verify = |proof: &[u8], public_inputs: &[u8]| {
+            verify_with_step_count::<PC>(&verifying_key, proof, public_inputs, shape, binding)
+                .unwrap()
+        };
+
+        // One key proves every sequence length up to the shape's, and the proof tells which
+        for (steps, out) in [(9, "55"), (6, "13"), (2, "2")] {
+            let (proof, public_inputs) = prove(steps, out).unwrap();
+            assert!(verify(&proof, &public_inputs));
+            assert_eq!(proven_steps(&public_inputs).unwrap(), steps);
+        }
+
+        // Claiming F(7) with the proof of F(6) fails
+        let (proof, public_inputs) = prove(6, "13").unwrap();
+        let mut claimed = decode_public_inputs(&public_inputs).unwrap();
+        claimed[INSTANCE_STEPS_ROW] = Fr::from(7);
+        assert!(!verify(&proof, &encode_public_inputs(&claimed)));
+
+        // F(6) isn't 21, whether proving or verifying catches it
+        if let Ok((proof, public_inputs)) = prove(6, "21") {
+            assert!(!verify(&proof, &public_inputs));
+        }
+
+        // Longer sequences than the key's are rejected upfront
+        assert!(prove(10, "89").is_err());
+    }
+
+    /// Check the Merkle example proves every leaf of a tree against its root, and that another
+    /// leaf, root or path position is rejected.
+    pub fn merkle_test<PC>()
+    where
+        PC: PlonkishComponents,
+        ProofTranscript: TranscriptWrite<CommitmentChunk<Fr, PC::Pcs>, Fr>
+            + TranscriptRead<CommitmentChunk<Fr, PC::Pcs>, Fr>,
+    {
+        let leaves: Vec<Fr> = (0..13)
+            .map(|leaf| fibonacci(Fr::ONE, Fr::ONE, leaf))
+            .collect();
+        let tree = MerkleTree::new(leaves.clone());
+        assert_eq!(tree.depth(), DEFAULT_MERKLE_DEPTH);
+        for (index, leaf) in leaves.iter().enumerate() {
+            assert_eq!(tree.path(index).unwrap().root(*leaf), tree.root());
+        }
+
+        let index = 6;
+        let path = tree.path(index).unwrap();
+        let circuit = MerkleCircuit::new(leaves[index], &path);
+        let k = merkle_k(path.depth());
+        assert!(MockProver::run(k as u32, &circuit, circuit.instances())
+            .unwrap()
+            .verify()
+            .is_ok());
+
+        // The position of a node is part of the witness, flipping one changes the root
+        let mut flipped = circuit.clone();
+        flipped.positions[1] = flipped.positions[1].map(|is_right| Fr::ONE - is_right);
+        assert!(MockProver::run(k as u32, &flipped, flipped.instances())
+            .unwrap()
+            .verify()
+            .is_err());
+
+        let circuit_info = build_halo2_circuit::<PC, _>(k, circuit.clone())
+            .circuit_info()
+            .unwrap();
+        let srs = PC::ProvingBackend::setup(&circuit_info, seeded_std_rng()).unwrap();
+        let (pp, vp) = merkle::keygen::<PC>(&srs, path.depth()).unwrap();
+        let binding = TranscriptBinding::default();
+
+        let input = MerkleInput::new(leaves[index], &path);
+        assert_eq!(input.root(), tree.root());
+        let (proof, public_inputs) = merkle::prove::<PC>(&pp, &input, binding).unwrap();
+        assert!(merkle::verify::<PC>(&vp, &proof, &public_inputs, binding).unwrap());
+
+        // Another leaf or root
+        for row in [LEAF_ROW, ROOT_ROW] {
+            let mut wrong = circuit.instances().remove(0);
+            wrong[row] += Fr::ONE;
+            let wrong = encode_public_inputs(&wrong);
+            assert!(!merkle::verify::<PC>(&vp, &proof, &wrong, binding).unwrap_or(false));
+        }
+    }
+
+    /// Check `verify_container` accepts a container of valid proofs, and rejects one holding an
+    /// invalid proof, bound to another context or made for another verifying key. Containers of
+    /// keys for another shape are verified with `verify_container_for_shape`.
+    pub fn proof_container_test<PC>()
+    where
+        PC: PlonkishComponents,
+        ProofTranscript: TranscriptWrite<CommitmentChunk<Fr, PC::Pcs>, Fr>
+            + TranscriptRead<CommitmentChunk<Fr, PC::Pcs>, Fr>,
+    {
+        let mut input = HashMap::new();
+        input.insert("out".to_string(), vec![Fr::from(55)]);
+
+        let public_input = vec![Fr::from(1), Fr::from(1), Fr::from(55)];
+        let (_, srs, pp, vp) = initialize_params_and_circuit::<PC>(4, public_input);
+        let verifying_key =
+            io::encode_artifact_for_circuit(&vp, Codec::Bincode, circuit_hash::<PC>().unwrap())
+                .unwrap();
+
+        let mut container = ProofContainer::new(PC::BACKEND, &verifying_key);
+        for _ in 0..3 {
+            let (proof, inputs) = generate_halo2_proof::<PC>(&srs, &pp, input.clone()).unwrap();
+            let public_inputs = encode_public_inputs(&inputs);
+            container
+                .push(ProofBundle::new(proof, public_inputs))
+                .unwrap();
+        }
+        let binding = TranscriptBinding::default();
+        assert!(verify_container::<PC>(&verifying_key, &container, binding).unwrap());
+
+        let context = TranscriptBinding::context(b"another session");
+        assert!(!verify_container::<PC>(&verifying_key, &container, context).unwrap());
+
+        assert!(verify_container::<PC>(b"another verifying key", &container, binding).is_err());
+
+        let mut invalid = container;
+        invalid.bundles[1].proof = flip_bit(&invalid.bundles[1].proof, 7);
+        assert!(!verify_container::<PC>(&verifying_key, &invalid, binding).unwrap());
+
+        // The default sequence in a larger domain, as a key set may hold
+        let shape = CircuitShape {
+            k: 5,
+            ..Default::default()
+        };
+        let public_input = vec![Fr::from(1), Fr::from(1), Fr::from(55)];
+        let (_, srs, pp, vp) = initialize_params_and_circuit::<PC>(shape.k, public_input);
+        let verifying_key = io::encode_artifact_for_circuit(
+            &vp,
+            Codec::Bincode,
+            circuit_hash_for_shape::<PC>(shape).unwrap(),
+        )
+        .unwrap();
+        let (proof, inputs) =
+            generate_halo2_proof_with_shape::<PC>(&srs, &pp, input, shape, binding).unwrap();
+        let mut container = ProofContainer::new(PC::BACKEND, &verifying_key);
+        container
+            .push(ProofBundle::new(proof, encode_public_inputs(&inputs)))
+            .unwrap();
+        assert!(
+            verify_container_for_shape::<PC>(&verifying_key, &container, shape, binding).unwrap()
+        );
+        let err = verify_container::<PC>(&verifying_key, &container, binding).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<FibonacciError>(),
+            Some(FibonacciError::CircuitMismatch(_))
+        ));
+    }
+
+    /// Check the shuffle example accepts a permutation of the input column, and that both
+    /// `MockProver` and the backend reject a column which isn't one.
+    pub fn shuffle_test<PC>()
+    where
+        PC: PlonkishComponents,
+        ProofTranscript: TranscriptWrite<CommitmentChunk<Fr, PC::Pcs>, Fr>
+            + TranscriptRead<CommitmentChunk<Fr, PC::Pcs>, Fr>,
+    {
+        let input: Vec<Fr> = (1..=8).map(Fr::from).collect();
+        let mut shuffled = input.clone();
+        shuffled.reverse();
+        shuffled.swap(0, 5);
+
+        let circuit = ShuffleCircuit::new(input.clone(), shuffled.clone());
+        assert!(MockProver::run(SHUFFLE_K as u32, &circuit, Vec::new())
+            .unwrap()
+            .verify()
+            .is_ok());
+        assert!(prove_and_verify_example::<PC, _>(SHUFFLE_K, circuit));
+
+        // Same sum and length, but not the same multiset
+        shuffled[0] += Fr::from(1);
+        shuffled[1] -= Fr::from(1);
+        let circuit = ShuffleCircuit::new(input, shuffled);
+        assert!(MockProver::run(SHUFFLE_K as u32, &circuit, Vec::new())
+            .unwrap()
+            .verify()
+            .is_err());
+        assert!(!prove_and_verify_example::<PC, _>(SHUFFLE_K, circuit));
+    }
+
+    /// Check the dynamic lookup example accepts queries which are all in the witness table, and
+    /// that both `MockProver` and the backend reject a query which isn't.
+    pub fn dynamic_lookup_test<PC>()
+    where
+        PC: PlonkishComponents,
+        ProofTranscript: TranscriptWrite<CommitmentChunk<Fr, PC::Pcs>, Fr>
+            + TranscriptRead<CommitmentChunk<Fr, PC::Pcs>, Fr>,
+    {
+        let table: Vec<Fr> = [2, 3, 5, 8, 13, 21].into_iter().map(Fr::from).collect();
+
+        let circuit = DynamicLookupCircuit::new(table.clone(), vec![table[4], table[0], table[4]]);
+        assert!(MockProver::run(LOOKUP_K as u32, &circuit, Vec::new())
+            .unwrap()
+            .verify()
+            .is_ok());
+        assert!(prove_and_verify_example::<PC, _>(LOOKUP_K, circuit));
+
+        let circuit = DynamicLookupCircuit::new(table, vec![Fr::from(2), Fr::from(4)]);
+        assert!(MockProver::run(LOOKUP_K as u32, &circuit, Vec::new())
+            .unwrap()
+            .verify()
+            .is_err());
+        assert!(!prove_and_verify_example::<PC, _>(LOOKUP_K, circuit));
+    }
+
+    /// Prove a chain of links, check it verifies to the expected final state and that links
+    /// can't be altered, reordered or verified from another starting state.
+    pub fn chain_test<PC>()
+    where
+        PC: PlonkishComponents,
+        ProofTranscript: TranscriptWrite<CommitmentChunk<Fr, PC::Pcs>, Fr>
+            + TranscriptRead<CommitmentChunk<Fr, PC::Pcs>, Fr>,
+    {
+        let steps = 5;
+        let circuit = Halo2Circuit::<Fr, FibonacciCircuit<Fr>>::new::<PC::ProvingBackend>(
+            CircuitShape::new(steps + 1).k,
+            FibonacciCircuit {
+                public_input: vec![vec![Fr::from(1), Fr::from(1), Fr::from(13)]],
+                steps: steps + 1,
+            },
+        );
+        let srs =
+            PC::ProvingBackend::setup(&circuit.circuit_info().unwrap(), seeded_std_rng()).unwrap();
+        let keys = ChainKeys::<PC>::preprocess(&srs, steps).unwrap();
+
+        let start = ChainState::new(Fr::from(1), Fr::from(1));
+        let chain = prove_chain::<PC>(&srs, &keys, start, 3).unwrap();
+
+        // F(15) and F(16) of the sequence 1, 1, 2, ...
+        let end = verify_chain::<PC>(&keys, start, &chain).unwrap();
+        assert_eq!(end, ChainState::new(Fr::from(987), Fr::from(1597)));
+        assert_eq!(end.current, fibonacci(start.current, start.next, 3 * steps));
+
+        // A prefix of the chain verifies to an intermediate state
+        assert_eq!(
+            verify_chain::<PC>(&keys, start, &chain[..1]).unwrap(),
+            chain[0].end
+        );
+
+        let mut wrong_end = chain.clone();
+        wrong_end[1].end.next += Fr::from(1);
+        assert!(verify_chain::<PC>(&keys, start, &wrong_end).is_err());
+
+        let mut reordered = chain.clone();
+        reordered.swap(0, 1);
+        assert!(verify_chain::<PC>(&keys, start, &reordered).is_err());
+
+        // The proofs of the second link are bound to the commitment of the whole chain before it
+        assert!(verify_chain::<PC>(&keys, chain[0].end, &chain[1..]).is_err());
+    }
+
     pub fn bad_proof_not_verified_test<PC>()
     where
         PC: PlonkishComponents,
@@ -449,7 +1860,7 @@ pub mod test_utils {
 
         assert_eq!(inputs, invalid_public_input);
 
-        let verified = verify_halo2_proof::<PC>(&srs, &vp, proof, inputs).unwrap_or(false);
+        let verified = verify_halo2_proof::<PC>(&srs, &vp, &proof, inputs).unwrap_or(false);
         assert!(!verified);
     }
 
@@ -469,6 +1880,7 @@ pub mod test_utils {
         let public_input = vec![a, b, out];
         let circuit = FibonacciCircuit::<Fr> {
             public_input: vec![public_input],
+            ..Default::default()
         };
 
         halo2_proofs::dev::CircuitLayout::default()
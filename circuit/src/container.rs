@@ -0,0 +1,170 @@
+//! Container of many proof bundles of one backend and verifying key, for consumers which submit
+//! and verify proofs in batches, e.g. rollup sequencers.
+//!
+//! The backend and a fingerprint of the verifying key are recorded once for all proofs, so a
+//! verifier rejects a batch made for another key before checking any proof. Verify containers
+//! with `verify_container` of the backend crates.
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Keccak256};
+
+use crate::{serialisation::Codec, BackendId, FibonacciError, ProofBundle};
+
+/// Keccak256 of the verifying key contents
+pub type VkFingerprint = [u8; 32];
+
+pub fn vk_fingerprint(verifying_key: &[u8]) -> VkFingerprint {
+    Keccak256::digest(verifying_key).into()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProofContainer {
+    pub backend: BackendId,
+    pub vk_fingerprint: VkFingerprint,
+    pub bundles: Vec<ProofBundle>,
+}
+
+impl ProofContainer {
+    /// Empty container for proofs of `backend` verified with `verifying_key`.
+    pub fn new(backend: BackendId, verifying_key: &[u8]) -> Self {
+        Self {
+            backend,
+            vk_fingerprint: vk_fingerprint(verifying_key),
+            bundles: Vec::new(),
+        }
+    }
+
+    /// Add a bundle, which must be of the container's backend if it records one.
+    pub fn push(&mut self, bundle: ProofBundle) -> Result<(), FibonacciError> {
+        if bundle
+            .backend
+            .is_some_and(|backend| backend != self.backend)
+        {
+            return Err(FibonacciError::InvalidInput(
+                format!(
+                    "Can't add a proof of another backend to a {} proof container",
+                    self.backend.name()
+                )
+                .into(),
+            ));
+        }
+        self.bundles.push(bundle.with_backend(self.backend));
+        Ok(())
+    }
+
+    pub fn encode(&self, codec: Codec) -> Result<Vec<u8>, FibonacciError> {
+        codec.encode(self)
+    }
+
+    pub fn decode(bytes: &[u8], codec: Codec) -> Result<Self, FibonacciError> {
+        codec.decode(bytes)
+    }
+
+    /// Check the container holds proofs of `backend` made for `verifying_key`, and that every
+    /// bundle is well-formed, see `ProofBundle::validate`.
+    pub fn validate(&self, backend: BackendId, verifying_key: &[u8]) -> Result<(), FibonacciError> {
+        self.validate_fingerprint(backend, &vk_fingerprint(verifying_key))
+    }
+
+    /// Like `validate`, given the fingerprint of the verifying key rather than its contents.
+    pub fn validate_fingerprint(
+        &self,
+        backend: BackendId,
+        fingerprint: &VkFingerprint,
+    ) -> Result<(), FibonacciError> {
+        if self.backend != backend {
+            return Err(FibonacciError::InvalidInput(
+                format!(
+                    "Proof container holds {} proofs, not {}",
+                    self.backend.name(),
+                    backend.name()
+                )
+                .into(),
+            ));
+        }
+        if self.vk_fingerprint != *fingerprint {
+            return Err(FibonacciError::InvalidInput(
+                "Proof container was made for another verifying key".into(),
+            ));
+        }
+        if self.bundles.is_empty() {
+            return Err(FibonacciError::InvalidInput(
+                "Proof container holds no proofs".into(),
+            ));
+        }
+
+        for (index, bundle) in self.bundles.iter().enumerate() {
+            if bundle
+                .backend
+                .is_some_and(|bundle_backend| bundle_backend != backend)
+            {
+                return Err(FibonacciError::InvalidInput(
+                    format!(
+                        "Proof {} of the container is not a {} proof",
+                        index,
+                        backend.name()
+                    )
+                    .into(),
+                ));
+            }
+            bundle.validate().map_err(|e| {
+                FibonacciError::InvalidInput(
+                    format!("Proof {} of the container: {}", index, e).into(),
+                )
+            })?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::halo2curves::bn256::Fr;
+
+    use super::*;
+    use crate::serialisation::encode_public_inputs;
+
+    #[test]
+    fn test_container_validate() {
+        let verifying_key = b"verifying key";
+        let public_inputs = encode_public_inputs(&[Fr::from(1), Fr::from(1), Fr::from(55)]);
+
+        let mut container = ProofContainer::new(BackendId::Gemini, verifying_key);
+        assert!(container
+            .validate(BackendId::Gemini, verifying_key)
+            .is_err());
+
+        container
+            .push(ProofBundle::new(vec![1, 2, 3], public_inputs.clone()))
+            .unwrap();
+        container
+            .push(
+                ProofBundle::new(vec![4, 5], public_inputs.clone()).with_backend(BackendId::Gemini),
+            )
+            .unwrap();
+        assert!(container
+            .push(ProofBundle::new(vec![6], public_inputs.clone()).with_backend(BackendId::Plonk))
+            .is_err());
+        assert_eq!(container.bundles.len(), 2);
+        assert_eq!(container.bundles[0].backend, Some(BackendId::Gemini));
+
+        assert!(container.validate(BackendId::Gemini, verifying_key).is_ok());
+        assert!(container.validate(BackendId::Plonk, verifying_key).is_err());
+        assert!(container
+            .validate(BackendId::Gemini, b"another verifying key")
+            .is_err());
+
+        let encoded = container.encode(Codec::Bincode).unwrap();
+        assert_eq!(
+            ProofContainer::decode(&encoded, Codec::Bincode).unwrap(),
+            container
+        );
+
+        // Malformed bundles are reported with their index
+        container.bundles[1].proof.clear();
+        let error = container
+            .validate(BackendId::Gemini, verifying_key)
+            .unwrap_err();
+        assert!(error.to_string().contains("Proof 1"));
+    }
+}
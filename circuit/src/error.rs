@@ -0,0 +1,302 @@
+use std::{collections::BTreeMap, error::Error, fmt, ops::Deref};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Errors of key generation, proving and verification, classified by the stage that failed.
+///
+/// Errors caused by a dependency, e.g. an I/O, bincode, halo2 or proving backend error, return it
+/// from `source()`. New variants may be added, e.g. for new backends, match on `code()` to handle
+/// every class.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum FibonacciError {
+    /// Circuit inputs, public inputs or other caller-supplied values are malformed
+    #[error(transparent)]
+    InvalidInput(ErrorMessage),
+    /// Keys, inputs or bundles failed to encode or decode
+    #[error(transparent)]
+    Serialization(ErrorMessage),
+    /// The SRS couldn't be loaded or the circuit couldn't be preprocessed into keys
+    #[error(transparent)]
+    Setup(ErrorMessage),
+    /// The SRS holds fewer points than the circuit's 2^k rows need
+    #[error("SRS supports circuits up to k = {available}, but k = {needed} is needed")]
+    SrsTooSmall { needed: usize, available: usize },
+    #[error(transparent)]
+    Proving(ErrorMessage),
+    #[error(transparent)]
+    Verification(ErrorMessage),
+    /// Keys were generated for another version of the circuit
+    #[error(transparent)]
+    CircuitMismatch(ErrorMessage),
+    /// The transcript binding couldn't be absorbed
+    #[error(transparent)]
+    Transcript(ErrorMessage),
+    #[error(transparent)]
+    Encryption(ErrorMessage),
+    #[error(transparent)]
+    Signature(ErrorMessage),
+    #[error(transparent)]
+    Io(ErrorMessage),
+    #[error(transparent)]
+    Profiling(ErrorMessage),
+}
+
+/// Message of a `FibonacciError`, with the error which caused it. Messages already include the
+/// description of their source, so displaying the error alone loses nothing.
+#[derive(Debug)]
+pub struct ErrorMessage {
+    message: String,
+    source: Option<Box<dyn Error + Send + Sync>>,
+}
+
+impl ErrorMessage {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            source: None,
+        }
+    }
+
+    pub fn with_source(
+        message: impl Into<String>,
+        source: impl Into<Box<dyn Error + Send + Sync>>,
+    ) -> Self {
+        Self {
+            message: message.into(),
+            source: Some(source.into()),
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for ErrorMessage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl Error for ErrorMessage {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.source
+            .as_deref()
+            .map(|source| source as &(dyn Error + 'static))
+    }
+}
+
+impl Deref for ErrorMessage {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.message
+    }
+}
+
+impl From<String> for ErrorMessage {
+    fn from(message: String) -> Self {
+        Self::new(message)
+    }
+}
+
+impl From<&str> for ErrorMessage {
+    fn from(message: &str) -> Self {
+        Self::new(message)
+    }
+}
+
+/// Error of the proving backend, which only implements `Debug`
+#[derive(Debug)]
+pub struct BackendError(pub plonkish_backend::Error);
+
+impl fmt::Display for BackendError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self.0)
+    }
+}
+
+impl Error for BackendError {}
+
+impl FibonacciError {
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            FibonacciError::InvalidInput(_) => ErrorCode::InvalidInput,
+            FibonacciError::Serialization(_) => ErrorCode::Serialization,
+            FibonacciError::Setup(_) => ErrorCode::Setup,
+            FibonacciError::SrsTooSmall { .. } => ErrorCode::SrsTooSmall,
+            FibonacciError::Proving(_) => ErrorCode::Proving,
+            FibonacciError::Verification(_) => ErrorCode::Verification,
+            FibonacciError::CircuitMismatch(_) => ErrorCode::CircuitMismatch,
+            FibonacciError::Transcript(_) => ErrorCode::Transcript,
+            FibonacciError::Encryption(_) => ErrorCode::Encryption,
+            FibonacciError::Signature(_) => ErrorCode::Signature,
+            FibonacciError::Io(_) => ErrorCode::Io,
+            FibonacciError::Profiling(_) => ErrorCode::Profiling,
+        }
+    }
+}
+
+/// Stable identifier of a failure class, serialized in snake case (e.g. `invalid_input`).
+///
+/// Codes are never renamed or reused, scripts and monitoring can match on them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    InvalidInput,
+    Serialization,
+    Setup,
+    SrsTooSmall,
+    Proving,
+    Verification,
+    CircuitMismatch,
+    Transcript,
+    Encryption,
+    Signature,
+    Io,
+    Profiling,
+    /// A service can't take the request right now, e.g. its job queue is full or it's shutting
+    /// down, and it may be retried later
+    Unavailable,
+    /// Errors raised outside this crate, e.g. by a proving backend
+    Internal,
+}
+
+impl ErrorCode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorCode::InvalidInput => "invalid_input",
+            ErrorCode::Serialization => "serialization",
+            ErrorCode::Setup => "setup",
+            ErrorCode::SrsTooSmall => "srs_too_small",
+            ErrorCode::Proving => "proving",
+            ErrorCode::Verification => "verification",
+            ErrorCode::CircuitMismatch => "circuit_mismatch",
+            ErrorCode::Transcript => "transcript",
+            ErrorCode::Encryption => "encryption",
+            ErrorCode::Signature => "signature",
+            ErrorCode::Io => "io",
+            ErrorCode::Profiling => "profiling",
+            ErrorCode::Unavailable => "unavailable",
+            ErrorCode::Internal => "internal",
+        }
+    }
+}
+
+/// Serializable form of an error for CLI and server output, e.g.
+/// `{"code": "invalid_input", "message": "..", "context": {"backend": "plonk"}}`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ErrorReport {
+    pub code: ErrorCode,
+    pub message: String,
+    /// Details added by the caller, such as the backend or the file being processed
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub context: BTreeMap<String, String>,
+}
+
+impl ErrorReport {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            context: BTreeMap::new(),
+        }
+    }
+
+    /// Report any error returned by this workspace. `std::io::Error`s, e.g. passed on with `?`
+    /// from the filesystem, get the `io` code, errors from other crates the `internal` code.
+    pub fn from_error(error: &(dyn Error + 'static)) -> Self {
+        if let Some(error) = error.downcast_ref::<FibonacciError>() {
+            return error.into();
+        }
+        let code = if error.is::<std::io::Error>() {
+            ErrorCode::Io
+        } else {
+            ErrorCode::Internal
+        };
+        Self::new(code, error.to_string())
+    }
+
+    pub fn with_context(mut self, key: impl Into<String>, value: impl ToString) -> Self {
+        self.context.insert(key.into(), value.to_string());
+        self
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("Error report should serialize")
+    }
+}
+
+impl From<&FibonacciError> for ErrorReport {
+    fn from(error: &FibonacciError) -> Self {
+        let report = Self::new(error.code(), error.to_string());
+        match error {
+            FibonacciError::SrsTooSmall { needed, available } => report
+                .with_context("needed_k", needed)
+                .with_context("available_k", available),
+            _ => report,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_report() {
+        let error: Box<dyn Error> = Box::new(FibonacciError::InvalidInput(
+            "Failed to get `out` value".into(),
+        ));
+        let report = ErrorReport::from_error(error.as_ref()).with_context("backend", "plonk");
+
+        assert_eq!(
+            report.to_json(),
+            r#"{"code":"invalid_input","message":"Failed to get `out` value","context":{"backend":"plonk"}}"#
+        );
+        assert_eq!(
+            serde_json::from_str::<ErrorReport>(&report.to_json()).unwrap(),
+            report
+        );
+
+        let report = ErrorReport::from(&FibonacciError::SrsTooSmall {
+            needed: 5,
+            available: 4,
+        });
+        assert_eq!(report.code, ErrorCode::SrsTooSmall);
+        assert_eq!(report.context["needed_k"], "5");
+        assert_eq!(report.context["available_k"], "4");
+
+        let io_error: Box<dyn Error> = Box::new(std::io::Error::other("disk full"));
+        let report = ErrorReport::from_error(io_error.as_ref());
+        assert_eq!(report.code, ErrorCode::Io);
+        assert_eq!(report.to_json(), r#"{"code":"io","message":"disk full"}"#);
+
+        let other: Box<dyn Error> = "unexpected".into();
+        assert_eq!(
+            ErrorReport::from_error(other.as_ref()).code,
+            ErrorCode::Internal
+        );
+    }
+
+    #[test]
+    fn test_error_source() {
+        let io_error = std::io::Error::new(std::io::ErrorKind::NotFound, "no such file");
+        let error = FibonacciError::Io(ErrorMessage::with_source(
+            format!("Couldn't read 'pk.bin': {}", io_error),
+            io_error,
+        ));
+        assert_eq!(error.to_string(), "Couldn't read 'pk.bin': no such file");
+        let source = error.source().unwrap();
+        assert_eq!(
+            source.downcast_ref::<std::io::Error>().unwrap().kind(),
+            std::io::ErrorKind::NotFound
+        );
+
+        let error = FibonacciError::InvalidInput("Failed to get `out` value".into());
+        assert!(error.source().is_none());
+        assert_eq!(error.code(), ErrorCode::InvalidInput);
+    }
+}
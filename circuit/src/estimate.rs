@@ -0,0 +1,201 @@
+//! Proving time estimates from calibration runs, so apps can show an ETA before proving.
+//!
+//! A `HardwareProfile` holds prover times the benchmark harness measured on one class of device,
+//! see the `calibrate` binary of `fibonacci-benchmark`. Estimates for other domain sizes scale
+//! the closest measurement by the n log n cost of the FFTs and MSMs which dominate proving, with
+//! n = 2^k rows. They're meant for progress UIs, not for comparing backends. The scaling is only
+//! as good as the samples around the estimated k, so profiles should cover several sizes.
+use std::{cmp::Reverse, time::Duration};
+#[cfg(not(target_arch = "wasm32"))]
+use std::{collections::HashMap, time::Instant};
+
+#[cfg(not(target_arch = "wasm32"))]
+use plonkish_backend::{
+    backend::PlonkishBackend, halo2_curves::bn256::Fr, pcs::CommitmentChunk, util::test::std_rng,
+    util::transcript::TranscriptWrite,
+};
+use serde::{Deserialize, Serialize};
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::{
+    circuit::{fibonacci, generate_halo2_proof_with_shape},
+    BackendError, CircuitShape, ErrorMessage, PlonkishComponents, ProofTranscript,
+    TranscriptBinding,
+};
+use crate::{BackendId, FibonacciError};
+
+/// Largest k of a BN254 domain, the 2-adicity of the scalar field
+pub const MAX_K: usize = 28;
+
+/// Prover time of one backend at domain size 2^`k`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CalibrationSample {
+    pub backend: BackendId,
+    pub k: usize,
+    pub prover_time: Duration,
+}
+
+/// Calibration data of a class of devices, e.g. `"pixel-7"` or `"m1-laptop"`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HardwareProfile {
+    pub name: String,
+    pub samples: Vec<CalibrationSample>,
+}
+
+impl HardwareProfile {
+    pub fn from_json(json: &str) -> Result<Self, FibonacciError> {
+        serde_json::from_str(json).map_err(|e| {
+            FibonacciError::Serialization(format!("Invalid hardware profile: {}", e).into())
+        })
+    }
+
+    pub fn to_json(&self) -> Result<String, FibonacciError> {
+        serde_json::to_string_pretty(self).map_err(|e| {
+            FibonacciError::Serialization(
+                format!("Failed to serialize hardware profile: {}", e).into(),
+            )
+        })
+    }
+}
+
+// Relative cost of proving over 2^k rows
+fn proving_cost(k: usize) -> f64 {
+    k.max(1) as f64 * 2f64.powi(k as i32)
+}
+
+fn check_k(k: usize) -> Result<(), FibonacciError> {
+    if k > MAX_K {
+        return Err(FibonacciError::InvalidInput(
+            format!("Domain size 2^{} is larger than 2^{}", k, MAX_K).into(),
+        ));
+    }
+    Ok(())
+}
+
+/// Estimated time `backend` takes to prove a circuit of 2^`k` rows on `profile`'s hardware.
+pub fn estimate_proving_time(
+    backend: BackendId,
+    k: usize,
+    profile: &HardwareProfile,
+) -> Result<Duration, FibonacciError> {
+    check_k(k)?;
+
+    // The closest measurement, the larger one of two equally close
+    let sample = profile
+        .samples
+        .iter()
+        .filter(|sample| sample.backend == backend)
+        .min_by_key(|sample| (sample.k.abs_diff(k), Reverse(sample.k)))
+        .ok_or_else(|| {
+            FibonacciError::InvalidInput(
+                format!(
+                    "Hardware profile '{}' has no calibration data for {}",
+                    profile.name,
+                    backend.name()
+                )
+                .into(),
+            )
+        })?;
+    // Profiles are loaded from JSON, their sizes aren't trusted either
+    check_k(sample.k)?;
+
+    let scale = proving_cost(k) / proving_cost(sample.k);
+    Duration::try_from_secs_f64(sample.prover_time.as_secs_f64() * scale).map_err(|_| {
+        FibonacciError::InvalidInput(format!("No proving time estimate for k = {}", k).into())
+    })
+}
+
+/// Median time of `runs` proofs of the longest sequence fitting 2^`k` rows with `PC`, for the
+/// samples of a `HardwareProfile`. The SRS comes from a seeded setup, which is only good for
+/// timing, so calibrating at large k doesn't need SRS files of that size.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn measure_prover_time<PC>(k: usize, runs: usize) -> Result<Duration, FibonacciError>
+where
+    PC: PlonkishComponents,
+    ProofTranscript: TranscriptWrite<CommitmentChunk<Fr, PC::Pcs>, Fr>,
+{
+    check_k(k)?;
+    let shape = CircuitShape {
+        steps: CircuitShape::max_steps(k),
+        k,
+    };
+    let circuit_info = crate::circuit_info::<PC>(shape)?;
+
+    let backend_error = |e| {
+        FibonacciError::Setup(ErrorMessage::with_source(
+            format!("Failed to set up calibration at k = {}: {:?}", k, e),
+            BackendError(e),
+        ))
+    };
+    let param = PC::ProvingBackend::setup(&circuit_info, std_rng()).map_err(backend_error)?;
+    let (prover_parameters, _) =
+        PC::ProvingBackend::preprocess(&param, &circuit_info).map_err(backend_error)?;
+
+    let out = fibonacci(Fr::from(1), Fr::from(1), shape.steps);
+    let inputs = HashMap::from([("out".to_string(), vec![out])]);
+
+    let mut times = (0..runs.max(1))
+        .map(|_| {
+            let start = Instant::now();
+            generate_halo2_proof_with_shape::<PC>(
+                &param,
+                &prover_parameters,
+                inputs.clone(),
+                shape,
+                TranscriptBinding::default(),
+            )?;
+            Ok(start.elapsed())
+        })
+        .collect::<Result<Vec<_>, FibonacciError>>()?;
+    times.sort();
+    Ok(times[times.len() / 2])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate_proving_time() {
+        let profile = HardwareProfile {
+            name: "test".to_string(),
+            samples: vec![
+                CalibrationSample {
+                    backend: BackendId::HyperPlonk,
+                    k: 4,
+                    prover_time: Duration::from_millis(100),
+                },
+                CalibrationSample {
+                    backend: BackendId::HyperPlonk,
+                    k: 10,
+                    prover_time: Duration::from_secs(10),
+                },
+            ],
+        };
+
+        // Measured sizes are returned as measured
+        let estimate = estimate_proving_time(BackendId::HyperPlonk, 4, &profile).unwrap();
+        assert_eq!(estimate, Duration::from_millis(100));
+
+        // k = 5 scales the k = 4 sample by (5 * 32) / (4 * 16)
+        let estimate = estimate_proving_time(BackendId::HyperPlonk, 5, &profile).unwrap();
+        assert_eq!(estimate.as_millis(), 250);
+
+        // Larger domains take longer
+        let at_11 = estimate_proving_time(BackendId::HyperPlonk, 11, &profile).unwrap();
+        assert!(at_11 > Duration::from_secs(10));
+
+        assert!(estimate_proving_time(BackendId::Plonk, 4, &profile).is_err());
+        assert!(estimate_proving_time(BackendId::HyperPlonk, 2000, &profile).is_err());
+        // Sizes which don't fit in an i32 exponent are rejected rather than wrapped
+        assert!(estimate_proving_time(BackendId::HyperPlonk, usize::MAX, &profile).is_err());
+
+        let mut huge = profile.clone();
+        huge.samples.truncate(1);
+        huge.samples[0].k = usize::MAX;
+        assert!(estimate_proving_time(BackendId::HyperPlonk, 4, &huge).is_err());
+
+        let json = profile.to_json().unwrap();
+        assert_eq!(HardwareProfile::from_json(&json).unwrap(), profile);
+    }
+}
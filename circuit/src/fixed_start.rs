@@ -0,0 +1,208 @@
+//! Variant of the Fibonacci circuit with the starting values baked into the keys.
+//!
+//! The tutorial circuit takes `a` and `b` as public inputs, so one key proves sequences of any
+//! starting values. Most users prove the standard sequence starting with 1 and 1, and here
+//! `a` and `b` are constants assigned from a fixed column with `assign_advice_from_constant`:
+//! they become part of the constraint system, the keys only prove sequences starting with them
+//! and the instance column only holds `out`, row `FIXED_START_OUT_ROW`. The constants are those
+//! of a `CircuitConfig`, keys of one config don't verify proofs of another.
+use std::error::Error;
+
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner},
+    halo2curves::ff::Field,
+    plonk::{Circuit, Column, ConstraintSystem, Error as PlonkError, Fixed},
+};
+use plonkish_backend::{
+    frontend::halo2::CircuitExt,
+    halo2_curves::bn256::Fr,
+    pcs::CommitmentChunk,
+    util::transcript::{TranscriptRead, TranscriptWrite},
+};
+use rand::RngCore;
+
+use crate::{
+    circuit::{
+        fibonacci, generate_halo2_proof_for_circuit, verify_halo2_proof_with_vk, CircuitConfig,
+        FibonacciChip, FibonacciConfig, TranscriptBinding, MIN_STEPS,
+    },
+    preprocess_circuit,
+    serialisation::{decode_public_inputs, encode_public_inputs},
+    FibonacciError, GenerateProofResult, PlonkishComponents, ProofTranscript,
+};
+
+/// Row of the instance column holding the output, the only public input
+pub const FIXED_START_OUT_ROW: usize = 0;
+
+#[derive(Debug, Clone)]
+pub struct FixedStartConfig {
+    /// The tutorial circuit, whose instance column only holds `out`
+    pub fibonacci: FibonacciConfig,
+    /// Constants assigned to advice cells, `a` and `b`
+    pub constants: Column<Fixed>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FixedStartCircuit<F> {
+    /// F(0), fixed at key generation
+    pub a: F,
+    /// F(1), fixed at key generation
+    pub b: F,
+    /// `out`, empty during key generation
+    pub public_input: Vec<Vec<F>>,
+    /// Index n of the output F(n)
+    pub steps: usize,
+}
+
+impl<F: Field> FixedStartCircuit<F> {
+    /// Circuit proving F(`steps`) of the sequence starting with the constants `a` and `b`.
+    pub fn new(a: F, b: F, steps: usize) -> Self {
+        Self {
+            a,
+            b,
+            public_input: vec![vec![fibonacci(a, b, steps)]],
+            steps,
+        }
+    }
+}
+
+impl<F: Field> Circuit<F> for FixedStartCircuit<F> {
+    type Config = FixedStartConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    // The starting values are constants of the layout, only the output is dropped
+    fn without_witnesses(&self) -> Self {
+        Self {
+            public_input: Vec::new(),
+            ..self.clone()
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let fibonacci = FibonacciChip::configure(meta);
+        let constants = meta.fixed_column();
+        meta.enable_constant(constants);
+
+        FixedStartConfig {
+            fibonacci,
+            constants,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), PlonkError> {
+        let fibonacci = config.fibonacci.clone();
+        let chip = FibonacciChip::construct(config.fibonacci);
+
+        let (mut prev_b, mut prev_c) = layouter.assign_region(
+            || "first row",
+            |mut region| {
+                fibonacci.selector.enable(&mut region, 0)?;
+
+                let a_cell =
+                    region.assign_advice_from_constant(|| "f(0)", fibonacci.col_a, 0, self.a)?;
+                let b_cell =
+                    region.assign_advice_from_constant(|| "f(1)", fibonacci.col_b, 0, self.b)?;
+                let c_cell = region.assign_advice(
+                    || "a + b",
+                    fibonacci.col_c,
+                    0,
+                    || a_cell.value().copied() + b_cell.value(),
+                )?;
+
+                Ok((b_cell, c_cell))
+            },
+        )?;
+
+        for _ in MIN_STEPS..self.steps {
+            let c_cell = chip.assign_row(layouter.namespace(|| "next row"), &prev_b, &prev_c)?;
+            prev_b = prev_c;
+            prev_c = c_cell;
+        }
+
+        chip.expose_public(layouter.namespace(|| "out"), &prev_c, FIXED_START_OUT_ROW)
+    }
+}
+
+impl<F: Field> CircuitExt<F> for FixedStartCircuit<F> {
+    fn rand(_: usize, _: impl RngCore) -> Self {
+        unimplemented!()
+    }
+
+    fn instances(&self) -> Vec<Vec<F>> {
+        self.public_input.clone()
+    }
+}
+
+impl CircuitConfig {
+    /// Circuit with the starting values of the config as constants, after checking its shape.
+    pub fn fixed_start_circuit(&self) -> Result<FixedStartCircuit<Fr>, FibonacciError> {
+        self.shape.validate()?;
+        Ok(FixedStartCircuit::new(self.a, self.b, self.shape.steps))
+    }
+}
+
+/// Preprocess the circuit of `config` with its starting values as constants into prover and
+/// verifier parameters.
+pub fn keygen_with_fixed_start<PC>(
+    param: &PC::Param,
+    config: &CircuitConfig,
+) -> Result<(PC::ProverParam, PC::VerifierParam), FibonacciError>
+where
+    PC: PlonkishComponents,
+{
+    preprocess_circuit::<PC, _>(param, config.shape.k, config.fixed_start_circuit()?)
+}
+
+/// Prove the sequence of `config` with prover parameters of `keygen_with_fixed_start` for it.
+/// The public inputs only hold `out`.
+pub fn prove_with_fixed_start<PC>(
+    prover_param: &PC::ProverParam,
+    config: &CircuitConfig,
+    binding: TranscriptBinding,
+) -> Result<GenerateProofResult, Box<dyn Error>>
+where
+    PC: PlonkishComponents,
+    ProofTranscript: TranscriptWrite<CommitmentChunk<Fr, PC::Pcs>, Fr>,
+{
+    let (proof, instances) = generate_halo2_proof_for_circuit::<PC, _>(
+        prover_param,
+        config.shape.k,
+        config.fixed_start_circuit()?,
+        binding,
+    )?;
+    Ok((proof, encode_public_inputs(&instances[0])))
+}
+
+/// Verify a proof of `prove_with_fixed_start`, whose starting values are those the verifier
+/// parameters were generated with.
+pub fn verify_with_fixed_start<PC>(
+    verifier_param: &PC::VerifierParam,
+    proof: &[u8],
+    public_inputs: &[u8],
+    binding: TranscriptBinding,
+) -> Result<bool, Box<dyn Error>>
+where
+    PC: PlonkishComponents,
+    ProofTranscript: TranscriptRead<CommitmentChunk<Fr, PC::Pcs>, Fr>,
+{
+    let inputs = decode_public_inputs(public_inputs)?;
+    if inputs.len() != 1 {
+        return Err(FibonacciError::InvalidInput(
+            format!(
+                "Expected the output as the only public input, found {} inputs",
+                inputs.len()
+            )
+            .into(),
+        )
+        .into());
+    }
+
+    match verify_halo2_proof_with_vk::<PC>(verifier_param, proof, inputs, binding) {
+        Err(FibonacciError::Verification(_)) => Ok(false),
+        result => Ok(result?),
+    }
+}
@@ -0,0 +1,57 @@
+//! Lowercase hex encoding of fingerprints, signatures and proofs.
+
+use crate::FibonacciError;
+
+/// `bytes` as lowercase hex, two digits per byte.
+pub fn encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Decode hex in either case. Odd lengths and anything but hex digits, including non-ASCII
+/// characters, are `InvalidInput` errors.
+pub fn decode(hex: &str) -> Result<Vec<u8>, FibonacciError> {
+    let digits = hex.as_bytes();
+    if digits.len() % 2 != 0 {
+        return Err(FibonacciError::InvalidInput(
+            "Hex string has odd length".into(),
+        ));
+    }
+    digits
+        .chunks(2)
+        .enumerate()
+        .map(|(i, pair)| match (digit(pair[0]), digit(pair[1])) {
+            (Some(high), Some(low)) => Ok(high << 4 | low),
+            _ => Err(FibonacciError::InvalidInput(
+                format!("Invalid hex digit at offset {}", 2 * i).into(),
+            )),
+        })
+        .collect()
+}
+
+fn digit(byte: u8) -> Option<u8> {
+    (byte as char).to_digit(16).map(|d| d as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hex_roundtrip() {
+        let bytes = vec![0u8, 1, 0xab, 0xff];
+        assert_eq!(encode(&bytes), "0001abff");
+        assert_eq!(decode("0001abff").unwrap(), bytes);
+        assert_eq!(decode("0001ABFF").unwrap(), bytes);
+        assert!(decode("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_hex_rejects_bad_digits() {
+        assert!(decode("abc").is_err());
+        assert!(decode("zz").is_err());
+        assert!(decode("+1").is_err());
+        // Multi-byte characters used to panic when sliced by byte index
+        assert!(decode("é0").is_err());
+        assert!(decode("0é").is_err());
+    }
+}
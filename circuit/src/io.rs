@@ -1,51 +1,947 @@
 use std::{
     error::Error,
-    fs::File,
+    fs::{self, File, OpenOptions},
     io::{BufReader, Read, Write},
     path::Path,
+    sync::atomic::{AtomicU64, Ordering},
 };
 
 use plonkish_backend::backend::PlonkishBackend;
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
-use crate::PlonkishComponents;
+use crate::{
+    circuit::CircuitShape,
+    lock::{ArtifactLock, LockedFile},
+    serialisation::Codec,
+    BackendError, BackendId, ErrorMessage, FibonacciError, PlonkishComponents,
+};
+
+/// Capacity of the reader used for SRS files, so that parsing doesn't issue a read call per point
+const SRS_READ_BUFFER_LEN: usize = 1 << 20;
+
+/// Capacity of the reader used for key files, which are decoded while reading them
+const KEY_READ_BUFFER_LEN: usize = 1 << 20;
+
+/// Open an SRS file for parsing, buffered with `SRS_READ_BUFFER_LEN`.
+pub fn open_srs_file(path: &Path) -> std::io::Result<BufReader<File>> {
+    Ok(BufReader::with_capacity(
+        SRS_READ_BUFFER_LEN,
+        File::open(path)?,
+    ))
+}
 
-/// Read SRS from file.
+/// Read SRS from file, parsing while streaming it instead of loading the whole file first.
+/// Panics on a missing or malformed file.
+#[deprecated(note = "use `parse_srs_path`, which returns an error for a malformed SRS")]
 pub fn read_srs_path<PC: PlonkishComponents>(path: &Path) -> PC::Param {
-    let filename = path.as_os_str().to_str().unwrap();
-    let mut reader = File::open(filename).unwrap();
-    PC::ProvingBackend::setup_custom(&mut reader).unwrap()
+    parse_srs_path::<PC>(path).unwrap()
 }
 
+/// Read SRS from file, parsing while streaming it instead of loading the whole file first.
+/// Missing files are `FibonacciError::Io` errors and malformed ones `FibonacciError::Setup`.
+pub fn parse_srs_path<PC: PlonkishComponents>(path: &Path) -> Result<PC::Param, FibonacciError> {
+    let mut reader = open_srs_file(path).map_err(|e| {
+        FibonacciError::Io(ErrorMessage::with_source(
+            format!("Failed to open SRS file {}", path.display()),
+            e,
+        ))
+    })?;
+    PC::ProvingBackend::setup_custom(&mut reader).map_err(|e| {
+        FibonacciError::Setup(ErrorMessage::with_source(
+            format!("Failed to parse SRS file {}: {:?}", path.display(), e),
+            BackendError(e),
+        ))
+    })
+}
+
+/// Parse the SRS contents, panicking on a malformed SRS.
+#[deprecated(note = "use `parse_srs_bytes`, which returns an error for a malformed SRS")]
 pub fn read_srs_bytes<PC: PlonkishComponents>(bytes: &[u8]) -> PC::Param {
-    let mut reader = BufReader::new(bytes);
-    PC::ProvingBackend::setup_custom(&mut reader).unwrap()
+    parse_srs_bytes::<PC>(bytes).unwrap()
+}
+
+/// Parse the SRS contents, returning an error for a malformed SRS, e.g. one received from an
+/// untrusted source.
+pub fn parse_srs_bytes<PC: PlonkishComponents>(bytes: &[u8]) -> Result<PC::Param, FibonacciError> {
+    // A slice is already a reader, parse from it directly rather than copying through a buffer
+    let mut reader = bytes;
+    PC::ProvingBackend::setup_custom(&mut reader).map_err(|e| {
+        FibonacciError::Setup(ErrorMessage::with_source(
+            format!("Failed to parse SRS: {:?}", e),
+            BackendError(e),
+        ))
+    })
+}
+
+/// Check the SRS is large enough for a circuit of 2^`needed` rows, instead of failing later with
+/// an opaque backend error.
+pub fn check_srs_size<PC: PlonkishComponents>(
+    param: &PC::Param,
+    needed: usize,
+) -> Result<(), FibonacciError> {
+    let available = PC::srs_k(param);
+    if available < needed {
+        return Err(FibonacciError::SrsTooSmall { needed, available });
+    }
+    Ok(())
+}
+
+/// Read SRS from file after checking its detached signature against the publisher key.
+#[cfg(feature = "signatures")]
+pub fn read_srs_path_verified<PC: PlonkishComponents>(
+    path: &Path,
+    publisher_key: &[u8; 32],
+) -> Result<PC::Param, FibonacciError> {
+    let bytes = crate::signature::read_verified_file(path, publisher_key)?;
+    parse_srs_bytes::<PC>(&bytes)
+}
+
+/// Magic bytes at the start of artifacts written with a header
+pub const ARTIFACT_MAGIC: [u8; 4] = *b"FIBA";
+pub const ARTIFACT_HEADER_VERSION: u8 = 1;
+/// Header version of artifacts recording the hash of the circuit they were generated for
+pub const ARTIFACT_HEADER_VERSION_CIRCUIT_HASH: u8 = 2;
+
+/// Hash of a circuit's constraint system, see `fibonacci_circuit::circuit_hash`
+pub type CircuitHash = [u8; 32];
+
+/// Header prefixed to keys written by `save_to_file`, selecting the codec of the payload.
+///
+/// Artifacts without the header are read as plain bincode, the format used before the header
+/// was introduced. Keys written by the key generators also record the circuit hash (version 2),
+/// so keys of an older circuit version are rejected instead of producing invalid proofs. Keys
+/// without it are rejected as well when a circuit hash is expected, see
+/// `decode_artifact_for_circuit`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArtifactHeader {
+    pub version: u8,
+    pub codec: Codec,
+    pub circuit_hash: Option<CircuitHash>,
+}
+
+impl ArtifactHeader {
+    pub const LEN: usize = 6;
+
+    pub fn new(codec: Codec) -> Self {
+        Self {
+            version: ARTIFACT_HEADER_VERSION,
+            codec,
+            circuit_hash: None,
+        }
+    }
+
+    pub fn with_circuit_hash(codec: Codec, circuit_hash: CircuitHash) -> Self {
+        Self {
+            version: ARTIFACT_HEADER_VERSION_CIRCUIT_HASH,
+            codec,
+            circuit_hash: Some(circuit_hash),
+        }
+    }
+
+    /// Length of the header in bytes
+    pub fn encoded_len(&self) -> usize {
+        Self::LEN + self.circuit_hash.map_or(0, |hash| hash.len())
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = ARTIFACT_MAGIC.to_vec();
+        bytes.push(self.version);
+        bytes.push(self.codec.id());
+        if let Some(circuit_hash) = self.circuit_hash {
+            bytes.extend(circuit_hash);
+        }
+        bytes
+    }
+
+    /// Split the header from the payload, returns `None` for artifacts without a header.
+    pub fn parse(bytes: &[u8]) -> Result<Option<(Self, &[u8])>, FibonacciError> {
+        if bytes.len() < Self::LEN || bytes[..4] != ARTIFACT_MAGIC {
+            return Ok(None);
+        }
+        let version = bytes[4];
+        let codec = Codec::from_id(bytes[5])?;
+        let header = match version {
+            ARTIFACT_HEADER_VERSION => Self::new(codec),
+            ARTIFACT_HEADER_VERSION_CIRCUIT_HASH => {
+                let circuit_hash = bytes
+                    .get(Self::LEN..Self::LEN + 32)
+                    .ok_or_else(|| {
+                        FibonacciError::Serialization("Artifact header is truncated".into())
+                    })?
+                    .try_into()
+                    .unwrap();
+                Self::with_circuit_hash(codec, circuit_hash)
+            }
+            _ => {
+                return Err(FibonacciError::Serialization(
+                    format!("Unsupported artifact header version {}", version).into(),
+                ))
+            }
+        };
+        Ok(Some((header, &bytes[header.encoded_len()..])))
+    }
+}
+
+/// Serialize `data` with `codec`, prefixed with the artifact header.
+pub fn encode_artifact<T: Serialize>(data: &T, codec: Codec) -> Result<Vec<u8>, FibonacciError> {
+    let mut bytes = ArtifactHeader::new(codec).to_bytes();
+    bytes.extend(codec.encode(data)?);
+    Ok(bytes)
+}
+
+/// Like `encode_artifact`, recording the hash of the circuit the artifact was generated for.
+pub fn encode_artifact_for_circuit<T: Serialize>(
+    data: &T,
+    codec: Codec,
+    circuit_hash: CircuitHash,
+) -> Result<Vec<u8>, FibonacciError> {
+    let mut bytes = ArtifactHeader::with_circuit_hash(codec, circuit_hash).to_bytes();
+    bytes.extend(codec.encode(data)?);
+    Ok(bytes)
 }
 
+/// Like `decode_artifact`, rejecting artifacts generated for a circuit other than
+/// `circuit_hash`. Artifacts which don't record a circuit hash, without a header or with a
+/// version 1 header, are rejected too, as nothing tells which circuit they were generated for.
+/// Decode those with `decode_artifact` to load them without the check.
+pub fn decode_artifact_for_circuit<T: DeserializeOwned>(
+    bytes: &[u8],
+    circuit_hash: &CircuitHash,
+) -> Result<T, FibonacciError> {
+    // Encrypted artifacts have no readable header, `decode_artifact` reports them
+    if !is_encrypted_artifact(bytes) {
+        let header = ArtifactHeader::parse(bytes)?.map(|(header, _)| header);
+        check_circuit_hash(header.as_ref(), circuit_hash)?;
+    }
+    decode_artifact(bytes)
+}
+
+fn check_circuit_hash(
+    header: Option<&ArtifactHeader>,
+    circuit_hash: &CircuitHash,
+) -> Result<(), FibonacciError> {
+    match header.and_then(|header| header.circuit_hash) {
+        Some(artifact_hash) if artifact_hash == *circuit_hash => Ok(()),
+        Some(_) => Err(FibonacciError::CircuitMismatch(
+            "Key was generated for a different circuit, regenerate the keys".into(),
+        )),
+        None => Err(FibonacciError::CircuitMismatch(
+            "Key doesn't record the circuit it was generated for, as written by earlier \
+             versions, regenerate the keys"
+                .into(),
+        )),
+    }
+}
+
+/// Deserialize an artifact with the codec selected by its header.
+pub fn decode_artifact<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, FibonacciError> {
+    if is_encrypted_artifact(bytes) {
+        return Err(FibonacciError::Encryption(
+            "Artifact is encrypted, load it with the decryption key".into(),
+        ));
+    }
+    match ArtifactHeader::parse(bytes)? {
+        Some((header, payload)) => header.codec.decode(payload),
+        None => Codec::Bincode.decode(bytes),
+    }
+}
+
+/// Like `decode_artifact`, deserializing while reading from `reader`, so large keys aren't held
+/// in memory twice, once encoded and once decoded. With `circuit_hash`, artifacts generated for
+/// another circuit or without a circuit hash are rejected like with
+/// `decode_artifact_for_circuit`.
+pub fn read_artifact<T: DeserializeOwned, R: Read>(
+    mut reader: R,
+    circuit_hash: Option<&CircuitHash>,
+) -> Result<T, FibonacciError> {
+    let io_error = |e: std::io::Error| {
+        FibonacciError::Io(ErrorMessage::with_source(
+            format!("Failed to read artifact: {}", e),
+            e,
+        ))
+    };
+    // Only the header is buffered, the payload is decoded from the reader
+    let mut prefix = Vec::with_capacity(ArtifactHeader::LEN + 32);
+    (&mut reader)
+        .take(ArtifactHeader::LEN as u64)
+        .read_to_end(&mut prefix)
+        .map_err(io_error)?;
+    if prefix.starts_with(&ARTIFACT_MAGIC)
+        && prefix.get(4) == Some(&ARTIFACT_HEADER_VERSION_CIRCUIT_HASH)
+    {
+        (&mut reader)
+            .take(32)
+            .read_to_end(&mut prefix)
+            .map_err(io_error)?;
+    }
+    if is_encrypted_artifact(&prefix) {
+        return Err(FibonacciError::Encryption(
+            "Artifact is encrypted, load it with the decryption key".into(),
+        ));
+    }
+
+    let header = ArtifactHeader::parse(&prefix)?;
+    if let Some(circuit_hash) = circuit_hash {
+        check_circuit_hash(header.as_ref().map(|(header, _)| header), circuit_hash)?;
+    }
+    match header {
+        Some((header, _)) => header.codec.decode_from_reader(reader),
+        // Without a header, the bytes read so far are part of the payload
+        None => Codec::Bincode.decode_from_reader(prefix.as_slice().chain(reader)),
+    }
+}
+
+/// Open a key file for `read_artifact`, buffered with `KEY_READ_BUFFER_LEN`. Its directory stays
+/// locked against key generators until the reader is dropped, see `lock`.
+pub fn open_key_file(path: &Path) -> std::io::Result<BufReader<LockedFile>> {
+    Ok(BufReader::with_capacity(
+        KEY_READ_BUFFER_LEN,
+        LockedFile::open(path)?,
+    ))
+}
+
+/// Read the whole artifact at `path` under a shared lock on its directory.
+fn read_locked(path: &Path) -> std::io::Result<Vec<u8>> {
+    let mut contents = Vec::new();
+    LockedFile::open(path)?.read_to_end(&mut contents)?;
+    Ok(contents)
+}
+
+/// Magic bytes at the start of artifacts encrypted with `encrypt_artifact`
+pub const ENCRYPTED_ARTIFACT_MAGIC: [u8; 4] = *b"FIBE";
+pub const ENCRYPTED_ARTIFACT_VERSION: u8 = 1;
+#[cfg(feature = "encryption")]
+const ENCRYPTION_NONCE_LEN: usize = 24;
+
+pub fn is_encrypted_artifact(bytes: &[u8]) -> bool {
+    bytes.len() > 4 && bytes[..4] == ENCRYPTED_ARTIFACT_MAGIC
+}
+
+/// Encrypt an encoded artifact with XChaCha20-Poly1305 under a caller-provided 256-bit key.
+///
+/// The output is `magic || version || nonce || ciphertext`, where the magic and version are
+/// authenticated as associated data.
+#[cfg(feature = "encryption")]
+pub fn encrypt_artifact(plaintext: &[u8], key: &[u8; 32]) -> Result<Vec<u8>, FibonacciError> {
+    use chacha20poly1305::{
+        aead::{Aead, AeadCore, KeyInit, OsRng, Payload},
+        Key, XChaCha20Poly1305,
+    };
+
+    let mut header = ENCRYPTED_ARTIFACT_MAGIC.to_vec();
+    header.push(ENCRYPTED_ARTIFACT_VERSION);
+
+    let cipher = XChaCha20Poly1305::new(Key::from_slice(key));
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(
+            &nonce,
+            Payload {
+                msg: plaintext,
+                aad: &header,
+            },
+        )
+        .map_err(|_| FibonacciError::Encryption("Failed to encrypt artifact".into()))?;
+
+    let mut bytes = header;
+    bytes.extend_from_slice(&nonce);
+    bytes.extend(ciphertext);
+    Ok(bytes)
+}
+
+/// Decrypt an artifact produced by `encrypt_artifact`, failing if it was tampered with or the
+/// key is wrong.
+#[cfg(feature = "encryption")]
+pub fn decrypt_artifact(bytes: &[u8], key: &[u8; 32]) -> Result<Vec<u8>, FibonacciError> {
+    use chacha20poly1305::{
+        aead::{Aead, KeyInit, Payload},
+        Key, XChaCha20Poly1305, XNonce,
+    };
+
+    let header_len = ENCRYPTED_ARTIFACT_MAGIC.len() + 1;
+    if !is_encrypted_artifact(bytes) || bytes.len() < header_len + ENCRYPTION_NONCE_LEN {
+        return Err(FibonacciError::Encryption(
+            "Artifact is not encrypted".into(),
+        ));
+    }
+    let (header, rest) = bytes.split_at(header_len);
+    if header[4] != ENCRYPTED_ARTIFACT_VERSION {
+        return Err(FibonacciError::Encryption(
+            format!("Unsupported encrypted artifact version {}", header[4]).into(),
+        ));
+    }
+    let (nonce, ciphertext) = rest.split_at(ENCRYPTION_NONCE_LEN);
+
+    let cipher = XChaCha20Poly1305::new(Key::from_slice(key));
+    cipher
+        .decrypt(
+            XNonce::from_slice(nonce),
+            Payload {
+                msg: ciphertext,
+                aad: header,
+            },
+        )
+        .map_err(|_| {
+            FibonacciError::Encryption(
+                "Failed to decrypt artifact, wrong key or corrupted file".into(),
+            )
+        })
+}
+
+/// Write `contents` to `path` through a temporary file next to it, synced and then renamed over
+/// the target, so a crash mid-write leaves either the old file or the new one but never a
+/// truncated artifact.
+///
+/// Unless `overwrite` is set, fails with `FibonacciError::Io` if `path` already exists. The
+/// temporary file is then hard linked to `path` rather than renamed, which fails if another
+/// writer created `path` meanwhile instead of replacing its file.
+pub fn write_atomic(path: &Path, contents: &[u8], overwrite: bool) -> Result<(), FibonacciError> {
+    let exists_error = || {
+        FibonacciError::Io(
+            format!("Refusing to overwrite existing file '{}'", path.display()).into(),
+        )
+    };
+    // Checked up front too, so an existing file isn't rewritten just to fail
+    if !overwrite && path.exists() {
+        return Err(exists_error());
+    }
+    let write_error = |e: std::io::Error| {
+        FibonacciError::Io(ErrorMessage::with_source(
+            format!("Couldn't write '{}': {}", path.display(), e),
+            e,
+        ))
+    };
+
+    // Same directory as the target, as a rename across filesystems isn't atomic
+    let file_name = path.file_name().ok_or_else(|| {
+        FibonacciError::Io(format!("'{}' is not a file path", path.display()).into())
+    })?;
+    // Unique per write, so threads of one process writing the same file don't share it either
+    let temp_path = path.with_file_name(format!(
+        ".{}.{}.{}.tmp",
+        file_name.to_string_lossy(),
+        std::process::id(),
+        TEMP_FILE_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+
+    let written = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&temp_path)
+        .and_then(|mut file| {
+            file.write_all(contents)?;
+            file.sync_all()
+        });
+    let placed = written.and_then(|()| {
+        if overwrite {
+            fs::rename(&temp_path, path)
+        } else {
+            fs::hard_link(&temp_path, path)
+        }
+    });
+    // Gone after a rename, only the link left after a hard link
+    let _ = fs::remove_file(&temp_path);
+    if let Err(e) = placed {
+        if e.kind() == std::io::ErrorKind::AlreadyExists {
+            return Err(exists_error());
+        }
+        return Err(write_error(e));
+    }
+    Ok(())
+}
+
+/// Suffix of the temporary files of `write_atomic`
+static TEMP_FILE_COUNTER: AtomicU64 = AtomicU64::new(0);
+
 // This method only for prover/verifier params
 pub fn save_to_file<P: AsRef<Path>, T: Serialize>(
     path: &P,
     data: &T,
+    overwrite: bool,
+) -> Result<(), Box<dyn Error>> {
+    save_to_file_with_codec(path, data, Codec::Bincode, overwrite)
+}
+
+/// Write `data` to `path` with `codec` atomically, see `write_atomic`.
+pub fn save_to_file_with_codec<P: AsRef<Path>, T: Serialize>(
+    path: &P,
+    data: &T,
+    codec: Codec,
+    overwrite: bool,
 ) -> Result<(), Box<dyn Error>> {
-    let serialized_data = bincode::serialize(data)?;
-    let mut file = File::create(path)?;
-    file.write_all(&serialized_data)?;
+    let serialized_data = encode_artifact(data, codec)?;
+    let _lock = ArtifactLock::exclusive_for(path.as_ref())?;
+    write_atomic(path.as_ref(), &serialized_data, overwrite)?;
+    Ok(())
+}
+
+/// Like `save_to_file_with_codec`, encrypting the artifact with `key`.
+#[cfg(feature = "encryption")]
+pub fn save_to_file_encrypted<P: AsRef<Path>, T: Serialize>(
+    path: &P,
+    data: &T,
+    codec: Codec,
+    key: &[u8; 32],
+    overwrite: bool,
+) -> Result<(), Box<dyn Error>> {
+    let encrypted_data = encrypt_artifact(&encode_artifact(data, codec)?, key)?;
+    let _lock = ArtifactLock::exclusive_for(path.as_ref())?;
+    write_atomic(path.as_ref(), &encrypted_data, overwrite)?;
     Ok(())
 }
 
 // Read proving/verifying key from file
-pub fn load_from_file<P: AsRef<Path> + ?Sized, T: for<'de> Deserialize<'de>>(
+pub fn load_from_file<P: AsRef<Path> + ?Sized, T: DeserializeOwned>(
     path: &P,
 ) -> Result<T, Box<dyn Error>> {
-    let mut file = File::open(path)?;
-    let mut buffer = Vec::new();
-    file.read_to_end(&mut buffer)?;
-    let deserialized_data = bincode::deserialize(&buffer)?;
+    let deserialized_data = read_artifact(open_key_file(path.as_ref())?, None)?;
     Ok(deserialized_data)
 }
 
 // Read proving/verifying key from bytes
-pub fn load_from_bytes<T: for<'de> Deserialize<'de>>(bytes: &[u8]) -> Result<T, Box<dyn Error>> {
-    let deserialized_data = bincode::deserialize(&bytes)?;
+pub fn load_from_bytes<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, Box<dyn Error>> {
+    let deserialized_data = decode_artifact(bytes)?;
     Ok(deserialized_data)
 }
+
+// Read proving/verifying key from file after checking its detached signature
+#[cfg(feature = "signatures")]
+pub fn load_from_file_verified<T: DeserializeOwned>(
+    path: &Path,
+    publisher_key: &[u8; 32],
+) -> Result<T, Box<dyn Error>> {
+    let bytes = crate::signature::read_verified_file(path, publisher_key)?;
+    load_from_bytes(&bytes)
+}
+
+// Read encrypted proving/verifying key from file
+#[cfg(feature = "encryption")]
+pub fn load_from_file_encrypted<P: AsRef<Path> + ?Sized, T: DeserializeOwned>(
+    path: &P,
+    key: &[u8; 32],
+) -> Result<T, Box<dyn Error>> {
+    let buffer = read_locked(path.as_ref())?;
+    load_from_bytes_encrypted(&buffer, key)
+}
+
+// Read encrypted proving/verifying key from bytes
+#[cfg(feature = "encryption")]
+pub fn load_from_bytes_encrypted<T: DeserializeOwned>(
+    bytes: &[u8],
+    key: &[u8; 32],
+) -> Result<T, Box<dyn Error>> {
+    let deserialized_data = decode_artifact(&decrypt_artifact(bytes, key)?)?;
+    Ok(deserialized_data)
+}
+
+/// Magic bytes at the start of `.fibz` key bundles
+pub const BUNDLE_MAGIC: [u8; 4] = *b"FIBZ";
+pub const BUNDLE_VERSION: u8 = 1;
+/// Extension of key bundle files
+pub const BUNDLE_EXTENSION: &str = "fibz";
+
+/// Name of the `kind` ("pk" or "vk") key file of the default shape, as written by the key
+/// generators of every backend.
+pub fn key_file_name(filename_prefix: &str, kind: &str) -> String {
+    format!("{}_fibonacci_{}.bin", filename_prefix, kind)
+}
+
+/// Name of the `kind` ("pk" or "vk") key file of `shape` in a key set.
+pub fn key_set_file_name(filename_prefix: &str, shape: CircuitShape, kind: &str) -> String {
+    format!(
+        "{}_fibonacci_n{}_k{}_{}.bin",
+        filename_prefix, shape.steps, shape.k, kind
+    )
+}
+
+/// Name of the index of a key set, see `write_key_set`.
+pub fn key_set_index_name(filename_prefix: &str) -> String {
+    format!("{}_fibonacci_keys.json", filename_prefix)
+}
+
+/// Name of the key bundle written next to the keys of the default shape.
+pub fn bundle_file_name(filename_prefix: &str) -> String {
+    format!("{}_fibonacci.{}", filename_prefix, BUNDLE_EXTENSION)
+}
+
+const BUNDLE_SECTION_MANIFEST: u8 = 1;
+const BUNDLE_SECTION_PROVING_KEY: u8 = 2;
+const BUNDLE_SECTION_VERIFYING_KEY: u8 = 3;
+
+/// Describes the keys of a bundle
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BundleManifest {
+    pub backend: BackendId,
+    /// Version of the crate which generated the keys
+    pub generator: String,
+}
+
+impl BundleManifest {
+    pub fn new(backend: BackendId) -> Self {
+        Self {
+            backend,
+            generator: format!("fibonacci-circuit {}", env!("CARGO_PKG_VERSION")),
+        }
+    }
+}
+
+/// Proving key, verifying key and manifest of one backend, shipped as a single `.fibz` file.
+///
+/// The file starts with `BUNDLE_MAGIC` and `BUNDLE_VERSION`, followed by sections made of a tag
+/// byte, a little-endian `u64` length and the section contents. The manifest is JSON, the keys
+/// are stored as written by the backend's key generator. Readers skip unknown sections.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyBundle {
+    pub manifest: BundleManifest,
+    pub proving_key: Vec<u8>,
+    pub verifying_key: Vec<u8>,
+}
+
+impl KeyBundle {
+    pub fn to_bytes(&self) -> Result<Vec<u8>, FibonacciError> {
+        let manifest = serde_json::to_vec(&self.manifest).map_err(|e| {
+            FibonacciError::Serialization(
+                format!("Failed to serialize bundle manifest: {}", e).into(),
+            )
+        })?;
+
+        let mut bytes = BUNDLE_MAGIC.to_vec();
+        bytes.push(BUNDLE_VERSION);
+        for (tag, section) in [
+            (BUNDLE_SECTION_MANIFEST, &manifest),
+            (BUNDLE_SECTION_PROVING_KEY, &self.proving_key),
+            (BUNDLE_SECTION_VERIFYING_KEY, &self.verifying_key),
+        ] {
+            bytes.push(tag);
+            bytes.extend((section.len() as u64).to_le_bytes());
+            bytes.extend(section);
+        }
+        Ok(bytes)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, FibonacciError> {
+        let invalid = |reason: &str| {
+            FibonacciError::Serialization(format!("Invalid key bundle: {}", reason).into())
+        };
+
+        let header_len = BUNDLE_MAGIC.len() + 1;
+        if bytes.len() < header_len || bytes[..4] != BUNDLE_MAGIC {
+            return Err(invalid("missing FIBZ magic bytes"));
+        }
+        if bytes[4] != BUNDLE_VERSION {
+            return Err(FibonacciError::Serialization(
+                format!("Unsupported key bundle version {}", bytes[4]).into(),
+            ));
+        }
+
+        let (mut manifest, mut proving_key, mut verifying_key) = (None, None, None);
+        let mut rest = &bytes[header_len..];
+        while !rest.is_empty() {
+            if rest.len() < 9 {
+                return Err(invalid("truncated section header"));
+            }
+            let tag = rest[0];
+            let len = u64::from_le_bytes(rest[1..9].try_into().unwrap());
+            let len = usize::try_from(len)
+                .ok()
+                .filter(|len| *len <= rest.len() - 9)
+                .ok_or_else(|| invalid("truncated section"))?;
+            let section = &rest[9..9 + len];
+            rest = &rest[9 + len..];
+
+            match tag {
+                BUNDLE_SECTION_MANIFEST => manifest = Some(section),
+                BUNDLE_SECTION_PROVING_KEY => proving_key = Some(section.to_vec()),
+                BUNDLE_SECTION_VERIFYING_KEY => verifying_key = Some(section.to_vec()),
+                _ => {}
+            }
+        }
+
+        let manifest = serde_json::from_slice(manifest.ok_or_else(|| invalid("missing manifest"))?)
+            .map_err(|e| {
+                FibonacciError::Serialization(
+                    format!("Failed to parse bundle manifest: {}", e).into(),
+                )
+            })?;
+        Ok(Self {
+            manifest,
+            proving_key: proving_key.ok_or_else(|| invalid("missing proving key"))?,
+            verifying_key: verifying_key.ok_or_else(|| invalid("missing verifying key"))?,
+        })
+    }
+}
+
+pub fn write_bundle<P: AsRef<Path>>(path: &P, bundle: &KeyBundle) -> Result<(), Box<dyn Error>> {
+    let _lock = ArtifactLock::exclusive_for(path.as_ref())?;
+    write_atomic(path.as_ref(), &bundle.to_bytes()?, true)?;
+    Ok(())
+}
+
+/// Pack the proving and verifying key files written by a key generator into a bundle at
+/// `bundle_path`.
+pub fn bundle_key_files(
+    backend: BackendId,
+    proving_key_path: &Path,
+    verifying_key_path: &Path,
+    bundle_path: &Path,
+) -> Result<(), Box<dyn Error>> {
+    let bundle = KeyBundle {
+        manifest: BundleManifest::new(backend),
+        proving_key: read_locked(proving_key_path)?,
+        verifying_key: read_locked(verifying_key_path)?,
+    };
+    write_bundle(&bundle_path, &bundle)
+}
+
+pub fn read_bundle<P: AsRef<Path>>(path: &P) -> Result<KeyBundle, Box<dyn Error>> {
+    Ok(KeyBundle::from_bytes(&read_locked(path.as_ref())?)?)
+}
+
+/// Keys of one circuit shape in a key set
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeySetEntry {
+    pub shape: CircuitShape,
+    /// Key file names, relative to the directory of the key set index
+    pub proving_key: String,
+    pub verifying_key: String,
+}
+
+/// Index of keys generated for several circuit shapes, written as JSON next to the key files.
+///
+/// Keys are specific to a sequence length, a key set lets one set of artifacts serve inputs of
+/// varying length. The same length may be listed for several k, proving picks the smallest.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeySet {
+    pub backend: BackendId,
+    pub entries: Vec<KeySetEntry>,
+}
+
+impl KeySet {
+    /// Entry of a sequence of `steps` with the smallest k, the cheapest one to prove with.
+    pub fn select(&self, steps: usize) -> Result<&KeySetEntry, FibonacciError> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.shape.steps == steps)
+            .min_by_key(|entry| entry.shape.k)
+            .ok_or_else(|| {
+                FibonacciError::InvalidInput(
+                    format!(
+                        "No keys for sequence length {}, the key set covers {:?}",
+                        steps,
+                        self.steps()
+                    )
+                    .into(),
+                )
+            })
+    }
+
+    /// Sequence lengths with keys in the set, in ascending order
+    pub fn steps(&self) -> Vec<usize> {
+        let mut steps: Vec<usize> = self.entries.iter().map(|entry| entry.shape.steps).collect();
+        steps.sort_unstable();
+        steps.dedup();
+        steps
+    }
+}
+
+/// Write the key set index at `path`, under the lock the caller holds on its directory as it
+/// writes the keys.
+pub fn write_key_set<P: AsRef<Path>>(path: &P, key_set: &KeySet) -> Result<(), Box<dyn Error>> {
+    write_atomic(path.as_ref(), &serde_json::to_vec_pretty(key_set)?, true)?;
+    Ok(())
+}
+
+pub fn read_key_set<P: AsRef<Path>>(path: &P) -> Result<KeySet, Box<dyn Error>> {
+    serde_json::from_slice(&read_locked(path.as_ref())?).map_err(|e| {
+        FibonacciError::Serialization(format!("Failed to parse key set index: {}", e).into()).into()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::halo2curves::bn256::Fr;
+
+    use super::*;
+    use crate::serialisation::InputsSerialisationWrapper;
+
+    #[test]
+    fn test_write_atomic() {
+        let dir = crate::test_output_dir("write-atomic");
+        let path = dir.join("pk.bin");
+
+        write_atomic(&path, b"first", false).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"first");
+
+        let err = write_atomic(&path, b"second", false).unwrap_err();
+        assert!(matches!(err, FibonacciError::Io(_)));
+        assert_eq!(fs::read(&path).unwrap(), b"first");
+
+        write_atomic(&path, b"second", true).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"second");
+
+        // No temporary files are left behind
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+        // Of writers racing to create the same file, only one succeeds
+        let raced = dir.join("vk.bin");
+        let written = std::thread::scope(|scope| {
+            let writers: Vec<_> = (0..8u8)
+                .map(|i| {
+                    let raced = &raced;
+                    scope.spawn(move || write_atomic(raced, &[i], false).is_ok())
+                })
+                .collect();
+            writers
+                .into_iter()
+                .filter(|writer| writer.join().unwrap())
+                .count()
+        });
+        assert_eq!(written, 1);
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 2);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_artifact_header() {
+        let inputs = InputsSerialisationWrapper(vec![Fr::from(1), Fr::from(1), Fr::from(55)]);
+
+        let encoded = encode_artifact(&inputs, Codec::Bincode).unwrap();
+        let (header, _) = ArtifactHeader::parse(&encoded).unwrap().unwrap();
+        assert_eq!(header, ArtifactHeader::new(Codec::Bincode));
+        let decoded: InputsSerialisationWrapper = decode_artifact(&encoded).unwrap();
+        assert_eq!(decoded.0, inputs.0);
+
+        // Artifacts written before the header existed are plain bincode
+        let legacy = bincode::serialize(&inputs).unwrap();
+        assert!(ArtifactHeader::parse(&legacy).unwrap().is_none());
+        let decoded: InputsSerialisationWrapper = decode_artifact(&legacy).unwrap();
+        assert_eq!(decoded.0, inputs.0);
+    }
+
+    #[test]
+    fn test_artifact_circuit_hash() {
+        let inputs = InputsSerialisationWrapper(vec![Fr::from(1), Fr::from(1), Fr::from(55)]);
+
+        let encoded = encode_artifact_for_circuit(&inputs, Codec::Bincode, [1; 32]).unwrap();
+        let (header, _) = ArtifactHeader::parse(&encoded).unwrap().unwrap();
+        assert_eq!(header.circuit_hash, Some([1; 32]));
+
+        let decoded: InputsSerialisationWrapper =
+            decode_artifact_for_circuit(&encoded, &[1; 32]).unwrap();
+        assert_eq!(decoded.0, inputs.0);
+        let mismatch =
+            decode_artifact_for_circuit::<InputsSerialisationWrapper>(&encoded, &[2; 32]);
+        assert!(matches!(mismatch, Err(FibonacciError::CircuitMismatch(_))));
+
+        // Artifacts without a circuit hash are rejected, unless decoded without the check
+        let encoded = encode_artifact(&inputs, Codec::Bincode).unwrap();
+        let legacy = bincode::serialize(&inputs).unwrap();
+        for artifact in [&encoded, &legacy] {
+            assert!(matches!(
+                decode_artifact_for_circuit::<InputsSerialisationWrapper>(artifact, &[2; 32]),
+                Err(FibonacciError::CircuitMismatch(_))
+            ));
+            let decoded: InputsSerialisationWrapper = decode_artifact(artifact).unwrap();
+            assert_eq!(decoded.0, inputs.0);
+        }
+    }
+
+    #[test]
+    fn test_read_artifact() {
+        let inputs = InputsSerialisationWrapper(vec![Fr::from(1), Fr::from(1), Fr::from(55)]);
+        let read = |bytes: &[u8], circuit_hash: Option<&CircuitHash>| {
+            read_artifact::<InputsSerialisationWrapper, _>(bytes, circuit_hash)
+        };
+
+        // Headers of either version, and none, only the circuit hash of version 2 passes the check
+        let encoded = encode_artifact(&inputs, Codec::Bincode).unwrap();
+        assert_eq!(read(&encoded, None).unwrap().0, inputs.0);
+        assert!(matches!(
+            read(&encoded, Some(&[2; 32])),
+            Err(FibonacciError::CircuitMismatch(_))
+        ));
+        let encoded = encode_artifact_for_circuit(&inputs, Codec::Bincode, [1; 32]).unwrap();
+        assert_eq!(read(&encoded, Some(&[1; 32])).unwrap().0, inputs.0);
+        assert_eq!(read(&encoded, None).unwrap().0, inputs.0);
+        assert!(matches!(
+            read(&encoded, Some(&[2; 32])),
+            Err(FibonacciError::CircuitMismatch(_))
+        ));
+        let legacy = bincode::serialize(&inputs).unwrap();
+        assert_eq!(read(&legacy, None).unwrap().0, inputs.0);
+        assert!(matches!(
+            read(&legacy, Some(&[1; 32])),
+            Err(FibonacciError::CircuitMismatch(_))
+        ));
+
+        assert!(read(&encoded[..20], None).is_err());
+        assert!(read(&[], None).is_err());
+    }
+
+    #[cfg(feature = "encryption")]
+    #[test]
+    fn test_artifact_encryption() {
+        let inputs = InputsSerialisationWrapper(vec![Fr::from(1), Fr::from(1), Fr::from(55)]);
+        let key = [7u8; 32];
+
+        let encoded = encode_artifact(&inputs, Codec::Bincode).unwrap();
+        let encrypted = encrypt_artifact(&encoded, &key).unwrap();
+        assert!(is_encrypted_artifact(&encrypted));
+        assert!(decode_artifact::<InputsSerialisationWrapper>(&encrypted).is_err());
+
+        let decrypted: InputsSerialisationWrapper =
+            load_from_bytes_encrypted(&encrypted, &key).unwrap();
+        assert_eq!(decrypted.0, inputs.0);
+
+        // Wrong key and tampered ciphertext are rejected
+        assert!(decrypt_artifact(&encrypted, &[8u8; 32]).is_err());
+        let mut tampered = encrypted.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert!(decrypt_artifact(&tampered, &key).is_err());
+    }
+
+    #[test]
+    fn test_key_bundle() {
+        let bundle = KeyBundle {
+            manifest: BundleManifest::new(BackendId::HyperPlonk),
+            proving_key: vec![1, 2, 3],
+            verifying_key: vec![4, 5],
+        };
+
+        let bytes = bundle.to_bytes().unwrap();
+        assert_eq!(bytes[..4], BUNDLE_MAGIC);
+        assert_eq!(KeyBundle::from_bytes(&bytes).unwrap(), bundle);
+
+        // Unknown sections are skipped
+        let mut extended = bytes.clone();
+        extended.push(0xff);
+        extended.extend(1u64.to_le_bytes());
+        extended.push(0);
+        assert_eq!(KeyBundle::from_bytes(&extended).unwrap(), bundle);
+
+        assert!(KeyBundle::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert!(KeyBundle::from_bytes(b"FIBA\x01").is_err());
+    }
+
+    #[test]
+    fn test_key_set_select() {
+        let entry = |steps, k| KeySetEntry {
+            shape: CircuitShape { steps, k },
+            proving_key: format!("n{}_k{}_pk.bin", steps, k),
+            verifying_key: format!("n{}_k{}_vk.bin", steps, k),
+        };
+        let key_set = KeySet {
+            backend: BackendId::Gemini,
+            entries: vec![entry(9, 5), entry(20, 5), entry(9, 4)],
+        };
+
+        assert_eq!(key_set.select(9).unwrap(), &entry(9, 4));
+        assert_eq!(key_set.select(20).unwrap(), &entry(20, 5));
+        assert!(matches!(
+            key_set.select(10),
+            Err(FibonacciError::InvalidInput(_))
+        ));
+        assert_eq!(key_set.steps(), vec![9, 20]);
+    }
+}
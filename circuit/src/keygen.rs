@@ -0,0 +1,226 @@
+//! Files written by the key generators, so callers such as tests, servers or build tooling can
+//! use them directly rather than parse the summary the `gen-*-keys` binaries print.
+use std::{
+    collections::BTreeMap,
+    fmt,
+    fs::File,
+    io,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Keccak256};
+
+use crate::{hex, BackendId, CircuitShape};
+
+/// File name of the manifest of a directory of keys, as written by `gen-all-keys` and read by
+/// the key store of `fibonacci-server`
+pub const KEYS_MANIFEST: &str = "keys.json";
+
+/// Manifest of a directory of keys, stored as `keys.json` next to them. Paths are relative to its
+/// directory.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeysManifest {
+    pub backends: Vec<BackendKeys>,
+}
+
+/// Keys of one circuit of a `KeysManifest`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackendKeys {
+    /// Circuit id a server hosts the keys under, the backend name if left out
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// Backend name, see `BackendId::name`
+    pub backend: String,
+    /// SRS the keys were generated from
+    pub srs: String,
+    pub proving_key: String,
+    pub verifying_key: String,
+}
+
+/// Keccak256 of a key file's contents
+pub type KeyFingerprint = [u8; 32];
+
+/// Keccak256 of the file at `path`, hashed while reading it.
+pub fn file_fingerprint(path: &Path) -> io::Result<KeyFingerprint> {
+    let mut hasher = Keccak256::new();
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(hasher.finalize().into())
+}
+
+/// Keys `generate_keys` writes besides the keys of the default shape
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct KeygenOptions {
+    /// Write a key set with the keys of each shape instead, see `write_key_set`
+    pub shapes: Option<Vec<CircuitShape>>,
+    /// Also pack both keys into a `.fibz` bundle, ignored for key sets
+    pub bundle: bool,
+}
+
+/// Files written by a key generator
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeygenArtifacts {
+    pub backend: BackendId,
+    /// SRS the keys were generated from
+    pub srs_path: PathBuf,
+    /// Proving key, of the first shape for key sets
+    pub pk_path: PathBuf,
+    /// Verifying key, of the first shape for key sets
+    pub vk_path: PathBuf,
+    /// Key set index listing the keys of every shape
+    pub manifest: Option<PathBuf>,
+    /// Key bundle holding both keys
+    pub bundle_path: Option<PathBuf>,
+    /// Fingerprint of every key file written, including the key set and bundle files
+    pub fingerprints: BTreeMap<PathBuf, KeyFingerprint>,
+}
+
+impl KeygenArtifacts {
+    /// Artifacts of the keys at `pk_path` and `vk_path`, fingerprinting both files.
+    pub fn new(
+        backend: BackendId,
+        srs_path: &Path,
+        pk_path: PathBuf,
+        vk_path: PathBuf,
+    ) -> io::Result<Self> {
+        let mut artifacts = Self {
+            backend,
+            srs_path: srs_path.to_path_buf(),
+            pk_path: pk_path.clone(),
+            vk_path: vk_path.clone(),
+            manifest: None,
+            bundle_path: None,
+            fingerprints: BTreeMap::new(),
+        };
+        artifacts.add_file(pk_path)?;
+        artifacts.add_file(vk_path)?;
+        Ok(artifacts)
+    }
+
+    /// Record the key set index at `path`.
+    pub fn with_manifest(mut self, path: PathBuf) -> io::Result<Self> {
+        self.add_file(path.clone())?;
+        self.manifest = Some(path);
+        Ok(self)
+    }
+
+    /// Record the key bundle at `path`.
+    pub fn with_bundle(mut self, path: PathBuf) -> io::Result<Self> {
+        self.add_file(path.clone())?;
+        self.bundle_path = Some(path);
+        Ok(self)
+    }
+
+    /// Fingerprint another file written along the keys, e.g. the keys of other shapes.
+    pub fn add_file(&mut self, path: PathBuf) -> io::Result<()> {
+        let fingerprint = file_fingerprint(&path)?;
+        self.fingerprints.insert(path, fingerprint);
+        Ok(())
+    }
+}
+
+impl fmt::Display for KeygenArtifacts {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "SRS read from {}", self.srs_path.display())?;
+        writeln!(f, "Proving key stored in {}", self.pk_path.display())?;
+        writeln!(f, "Verification key stored in {}", self.vk_path.display())?;
+        if let Some(manifest) = &self.manifest {
+            writeln!(f, "Key set index stored in {}", manifest.display())?;
+        }
+        if let Some(bundle_path) = &self.bundle_path {
+            writeln!(f, "Key bundle stored in {}", bundle_path.display())?;
+        }
+        write!(f, "Keccak256 fingerprints:")?;
+        for (path, fingerprint) in &self.fingerprints {
+            write!(f, "\n  {} {}", hex::encode(fingerprint), path.display())?;
+        }
+        Ok(())
+    }
+}
+
+/// Keys of one shape a key generator would write, see `KeygenPlan`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlannedKeys {
+    pub shape: CircuitShape,
+    pub pk_path: PathBuf,
+    pub vk_path: PathBuf,
+    /// Encoded sizes of the proving and verifying keys in bytes, `None` when the SRS is too
+    /// small for the shape
+    pub sizes: Option<(usize, usize)>,
+}
+
+/// Files a key generator would write, reported by `plan_keys` without writing any of them
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeygenPlan {
+    pub backend: BackendId,
+    pub srs_path: PathBuf,
+    /// Largest k the SRS supports
+    pub srs_k: usize,
+    /// Keys of each shape, a single entry unless generating a key set
+    pub keys: Vec<PlannedKeys>,
+    /// Key set index listing the keys of every shape
+    pub manifest: Option<PathBuf>,
+    /// Key bundle holding both keys
+    pub bundle_path: Option<PathBuf>,
+}
+
+impl KeygenPlan {
+    /// Whether the SRS supports every planned shape.
+    pub fn is_compatible(&self) -> bool {
+        self.keys.iter().all(|keys| keys.sizes.is_some())
+    }
+}
+
+impl fmt::Display for KeygenPlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "SRS {} supports circuits up to k = {}",
+            self.srs_path.display(),
+            self.srs_k
+        )?;
+        for keys in &self.keys {
+            write!(f, "n = {}, k = {}: ", keys.shape.steps, keys.shape.k)?;
+            match keys.sizes {
+                Some((pk_size, vk_size)) => {
+                    writeln!(f, "would write")?;
+                    writeln!(
+                        f,
+                        "  proving key of {} bytes to {}",
+                        pk_size,
+                        keys.pk_path.display()
+                    )?;
+                    writeln!(
+                        f,
+                        "  verification key of {} bytes to {}",
+                        vk_size,
+                        keys.vk_path.display()
+                    )?;
+                }
+                None => writeln!(f, "SRS too small, needs k >= {}", keys.shape.k)?,
+            }
+        }
+        if let Some(manifest) = &self.manifest {
+            writeln!(
+                f,
+                "Key set index would be written to {}",
+                manifest.display()
+            )?;
+        }
+        if let Some(bundle_path) = &self.bundle_path {
+            writeln!(
+                f,
+                "Key bundle would be written to {}",
+                bundle_path.display()
+            )?;
+        }
+        if self.is_compatible() {
+            write!(f, "Dry run, no files written.")
+        } else {
+            write!(
+                f,
+                "Dry run, no files written. The SRS is too small for some keys."
+            )
+        }
+    }
+}
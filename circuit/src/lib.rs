@@ -1,27 +1,108 @@
 use std::{
-    collections::HashMap, env, error::Error, fmt::Display, io::Cursor, path::Path,
-    process::Command, sync::Once,
+    any::type_name,
+    collections::HashMap,
+    env,
+    error::Error,
+    io::Cursor,
+    path::{Path, PathBuf},
+    sync::{Mutex, OnceLock},
 };
 
 use plonkish_backend::{
-    backend::{PlonkishBackend, PlonkishCircuit, WitnessEncoding},
-    frontend::halo2::Halo2Circuit,
+    backend::{PlonkishBackend, PlonkishCircuit, PlonkishCircuitInfo, WitnessEncoding},
+    frontend::halo2::CircuitExt,
     halo2_curves::bn256::Fr,
     pcs::{CommitmentChunk, PolynomialCommitmentScheme},
     util::transcript::{Keccak256Transcript, TranscriptRead, TranscriptWrite},
 };
 use serde::{de::DeserializeOwned, Serialize};
-use thiserror::Error;
+use sha3::{Digest, Keccak256};
 
+#[cfg(not(target_os = "zkvm"))]
+pub mod artifact_dirs;
+pub mod bundle;
+pub use bundle::{BackendId, Hiding, ProofBundle, VerificationOutcome};
+pub mod capabilities;
+pub use capabilities::Capabilities;
+pub mod chain;
 /// Halo2 Fibonacci circuit
 pub mod circuit;
-use crate::circuit::{generate_halo2_proof, verify_halo2_proof};
-pub use circuit::FibonacciCircuit;
+pub use crate::circuit::build_halo2_circuit;
+use crate::circuit::{
+    generate_halo2_proof_for_circuit, generate_halo2_proof_with_shape,
+    verify_halo2_proof_with_instances, verify_halo2_proof_with_vk,
+};
+pub use circuit::{
+    CircuitConfig, CircuitShape, FibonacciCircuit, TranscriptBinding, DEFAULT_STEPS,
+};
+pub mod container;
+use crate::container::ProofContainer;
+pub mod error;
+pub use error::{BackendError, ErrorCode, ErrorMessage, ErrorReport, FibonacciError};
+pub mod estimate;
+pub mod fixed_start;
+pub mod hex;
 pub mod io;
+#[cfg(not(target_os = "zkvm"))]
+pub mod keygen;
+#[cfg(not(target_os = "zkvm"))]
+pub use keygen::{KeygenArtifacts, KeygenOptions, KeygenPlan};
+pub mod lock;
+pub mod lookup;
+pub mod merkle;
+pub mod multi_instance;
+pub mod nonce;
+pub mod poseidon;
+#[cfg(feature = "profiling")]
+pub mod profiling;
+pub mod prover;
+pub use prover::{prove_prepared, PreparedCircuit, ProverContext};
+#[cfg(feature = "protobuf")]
+pub mod proto;
+pub mod scheme;
 pub mod serialisation;
-use crate::serialisation::{deserialize_circuit_inputs, InputsSerialisationWrapper};
+pub mod shuffle;
+#[cfg(feature = "signatures")]
+pub mod signature;
+pub mod step_count;
+pub mod telemetry;
+pub mod verifier;
+pub use verifier::VerifierContext;
+pub mod witness;
+use crate::serialisation::{
+    decode_public_inputs, deserialize_circuit_inputs, encode_public_inputs, Codec,
+    InstanceColumnsSerialisationWrapper,
+};
+
+// The target feature is what switches the code generation, the cargo feature only records that
+// the build is meant to have it
+#[cfg(all(
+    feature = "simd",
+    target_arch = "wasm32",
+    not(target_feature = "simd128")
+))]
+compile_error!("the `simd` feature needs RUSTFLAGS=\"-C target-feature=+simd128\" on wasm32");
+
+/// Whether this is a wasm build with SIMD128 field arithmetic, which only runs on engines
+/// supporting SIMD. Lets a page check which of its builds it loaded.
+pub const WASM_SIMD: bool = cfg!(all(target_arch = "wasm32", target_feature = "simd128"));
+
+/// Version of the stored formats: the canonical public input encoding, proof bundles, artifact
+/// headers and key bundles. Bumped deliberately whenever one of their encodings changes, the
+/// golden files in `circuit/tests/golden` pin the encodings of each version.
+pub const FORMAT_VERSION: u32 = 1;
+
+/// Open a puffin scope until the end of the enclosing block, a no-op without `profiling`.
+#[cfg(not(feature = "profiling"))]
+#[macro_export]
+macro_rules! profile_scope {
+    ($name:expr) => {};
+}
 
 pub trait PlonkishComponents {
+    /// Backend reported in proof bundles and metrics
+    const BACKEND: BackendId;
+
     type Param: Clone + Serialize + DeserializeOwned;
     type ProverParam: Clone + Serialize + DeserializeOwned;
     type VerifierParam: Clone + Serialize + DeserializeOwned;
@@ -32,85 +113,815 @@ pub trait PlonkishComponents {
             ProverParam = Self::ProverParam,
             VerifierParam = Self::VerifierParam,
         > + WitnessEncoding;
+
+    /// Largest k of the circuits the SRS can commit to, i.e. circuits of up to 2^k rows
+    fn srs_k(param: &Self::Param) -> usize;
 }
 
-#[derive(Debug, Error)]
-pub struct FibonacciError(pub String);
+pub type GenerateProofResult = (Vec<u8>, Vec<u8>);
+pub type ProofTranscript = Keccak256Transcript<Cursor<Vec<u8>>>;
 
-impl Display for FibonacciError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.0)
+/// Generate keys as the `gen-*-keys` binaries do: from the SRS named by the first command line
+/// argument into the standard keys directory, a key set with `--steps` or `--k` and also a key
+/// bundle with `--bundle`. Combinations of `--steps` and `--k` which don't fit are left out
+/// silently, see `keygen_args` to report them.
+#[cfg(not(target_os = "zkvm"))]
+pub fn gen_keys<PC>(filename_prefix: &str) -> Result<KeygenArtifacts, Box<dyn Error>>
+where
+    PC: PlonkishComponents,
+{
+    let args = keygen_args()?;
+    generate_keys::<PC>(
+        &args.srs_path,
+        &args.out_dir,
+        filename_prefix,
+        &args.options,
+    )
+}
+
+/// Like `gen_keys`, reporting the keys it would generate without writing any files, for the
+/// binaries' `--dry-run`.
+#[cfg(not(target_os = "zkvm"))]
+pub fn plan_gen_keys<PC>(filename_prefix: &str) -> Result<KeygenPlan, Box<dyn Error>>
+where
+    PC: PlonkishComponents,
+{
+    let args = keygen_args()?;
+    plan_keys::<PC>(
+        &args.srs_path,
+        &args.out_dir,
+        filename_prefix,
+        &args.options,
+    )
+}
+
+/// Body of the key generators' `main`: with `--dry-run` print the keys `plan` reports and fail if
+/// the SRS can't be used, otherwise print the files `generate` wrote. Errors are printed and exit
+/// with status 1, see `exit_with_error`.
+#[cfg(not(target_os = "zkvm"))]
+pub fn run_keygen(
+    plan: impl FnOnce() -> Result<KeygenPlan, Box<dyn Error>>,
+    generate: impl FnOnce() -> Result<KeygenArtifacts, Box<dyn Error>>,
+) {
+    if env::args().any(|arg| arg == "--dry-run") {
+        let plan = plan().unwrap_or_else(exit_with_error);
+        println!("{}", plan);
+        if !plan.is_compatible() {
+            std::process::exit(1);
+        }
+        return;
     }
+
+    let artifacts = generate().unwrap_or_else(exit_with_error);
+    println!("Preparation finished successfully.");
+    println!("{}", artifacts);
 }
 
-pub type GenerateProofResult = (Vec<u8>, Vec<u8>);
-pub type ProofTranscript = Keccak256Transcript<Cursor<Vec<u8>>>;
+/// Print `error` and exit with status 1, for the key generators' `main` rather than a panic
+/// message and backtrace hint.
+#[cfg(not(target_os = "zkvm"))]
+pub fn exit_with_error(error: impl std::fmt::Display) -> ! {
+    eprintln!("Error: {}", error);
+    std::process::exit(1)
+}
 
-pub fn gen_keys<PC>(filename_prefix: &str)
+/// Parsed command line of the key generators, see `gen_keys`
+#[cfg(not(target_os = "zkvm"))]
+#[derive(Debug)]
+pub struct KeygenArgs {
+    pub srs_path: PathBuf,
+    pub out_dir: PathBuf,
+    pub options: KeygenOptions,
+    /// Combinations of `--steps` and `--k` left out of the key set, as the sequence doesn't fit
+    /// in the domain
+    pub skipped: Vec<(CircuitShape, FibonacciError)>,
+}
+
+/// Parse the key generator command line: the SRS path, the output directory and the options of
+/// `--steps`, `--k` and `--bundle`. Malformed lists are `InvalidInput` errors.
+#[cfg(not(target_os = "zkvm"))]
+pub fn keygen_args() -> Result<KeygenArgs, Box<dyn Error>> {
+    let dirs = artifact_dirs::ArtifactDirs::from_env()?;
+
+    // Read SRS from file, looked up in the standard SRS directory unless it's a path
+    let srs_filename = env::args().nth(1).ok_or("Please specify SRS file path")?;
+    let srs_path = dirs.find_srs(&srs_filename)?;
+
+    // Store the keys in the standard keys directory, see `artifact_dirs`
+    let out_dir = dirs.keys_dir;
+
+    // Generate keys for several sequence lengths or domain sizes with `--steps` and `--k`
+    let args: Vec<String> = env::args().collect();
+    let steps = list_arg(&args, "--steps")?;
+    let ks = list_arg(&args, "--k")?;
+    let (shapes, skipped) = if steps.is_some() || ks.is_some() {
+        let steps = steps.unwrap_or_else(|| vec![DEFAULT_STEPS]);
+        let (shapes, skipped) = key_set_shapes(&steps, ks.as_deref())?;
+        (Some(shapes), skipped)
+    } else {
+        (None, Vec::new())
+    };
+
+    let options = KeygenOptions {
+        shapes,
+        bundle: args.iter().any(|arg| arg == "--bundle"),
+    };
+    Ok(KeygenArgs {
+        srs_path,
+        out_dir,
+        options,
+        skipped,
+    })
+}
+
+/// Generate keys from the SRS file into `out_dir`, the keys of the default shape unless
+/// `options` asks for a key set.
+#[cfg(not(target_os = "zkvm"))]
+pub fn generate_keys<PC>(
+    srs_path: &Path,
+    out_dir: &Path,
+    filename_prefix: &str,
+    options: &KeygenOptions,
+) -> Result<KeygenArtifacts, Box<dyn Error>>
+where
+    PC: PlonkishComponents,
+{
+    if let Some(shapes) = &options.shapes {
+        if shapes.is_empty() {
+            return Err(
+                FibonacciError::InvalidInput("No shapes to generate keys for".into()).into(),
+            );
+        }
+        let key_set_path = write_key_set::<PC>(srs_path, out_dir, filename_prefix, shapes)?;
+        let key_set = io::read_key_set(&key_set_path)?;
+
+        let (first, others) = key_set.entries.split_first().unwrap();
+        let mut artifacts = KeygenArtifacts::new(
+            PC::BACKEND,
+            srs_path,
+            out_dir.join(&first.proving_key),
+            out_dir.join(&first.verifying_key),
+        )?;
+        for entry in others {
+            artifacts.add_file(out_dir.join(&entry.proving_key))?;
+            artifacts.add_file(out_dir.join(&entry.verifying_key))?;
+        }
+        return Ok(artifacts.with_manifest(key_set_path)?);
+    }
+
+    let (pk_path, vk_path) = try_write_keys::<PC>(srs_path, out_dir, filename_prefix)?;
+    let artifacts = KeygenArtifacts::new(PC::BACKEND, srs_path, pk_path, vk_path)?;
+    if !options.bundle {
+        return Ok(artifacts);
+    }
+
+    let bundle_path = out_dir.join(io::bundle_file_name(filename_prefix));
+    io::bundle_key_files(
+        PC::BACKEND,
+        &artifacts.pk_path,
+        &artifacts.vk_path,
+        &bundle_path,
+    )?;
+    Ok(artifacts.with_bundle(bundle_path)?)
+}
+
+/// Report the files `generate_keys` would write with the same arguments, with the size of each
+/// key and whether the SRS supports its shape, without writing anything.
+///
+/// Keys are still generated in memory to measure them, so this takes about as long as
+/// `generate_keys`.
+#[cfg(not(target_os = "zkvm"))]
+pub fn plan_keys<PC>(
+    srs_path: &Path,
+    out_dir: &Path,
+    filename_prefix: &str,
+    options: &KeygenOptions,
+) -> Result<KeygenPlan, Box<dyn Error>>
 where
     PC: PlonkishComponents,
 {
-    // Get the project's root directory from the `CARGO_MANIFEST_DIR` environment variable
-    let project_root = env::var("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR is not set");
+    let param = io::parse_srs_path::<PC>(srs_path)?;
+    let srs_k = PC::srs_k(&param);
 
-    // Read SRS from file
-    let srs_filename = env::args().nth(1).expect("Please specify SRS file path");
-    let srs_path = Path::new(&project_root).join(srs_filename);
-    let param = io::read_srs_path::<PC>(&srs_path);
+    let planned = |shape: CircuitShape,
+                   pk_name: String,
+                   vk_name: String|
+     -> Result<keygen::PlannedKeys, FibonacciError> {
+        let sizes = match io::check_srs_size::<PC>(&param, shape.k) {
+            Ok(()) => {
+                let (proving_key, verifying_key) = encode_keys::<PC>(&param, shape)?;
+                Some((proving_key.len(), verifying_key.len()))
+            }
+            Err(FibonacciError::SrsTooSmall { .. }) => None,
+            Err(e) => return Err(e),
+        };
+        Ok(keygen::PlannedKeys {
+            shape,
+            pk_path: out_dir.join(pk_name),
+            vk_path: out_dir.join(vk_name),
+            sizes,
+        })
+    };
 
-    // Create the path to the `out` directory under the project's root directory
-    let out_dir = Path::new(&project_root).join("out");
+    let mut plan = KeygenPlan {
+        backend: PC::BACKEND,
+        srs_path: srs_path.to_path_buf(),
+        srs_k,
+        keys: Vec::new(),
+        manifest: None,
+        bundle_path: None,
+    };
+    if let Some(shapes) = &options.shapes {
+        if shapes.is_empty() {
+            return Err(
+                FibonacciError::InvalidInput("No shapes to generate keys for".into()).into(),
+            );
+        }
+        for &shape in shapes {
+            plan.keys.push(planned(
+                shape,
+                io::key_set_file_name(filename_prefix, shape, "pk"),
+                io::key_set_file_name(filename_prefix, shape, "vk"),
+            )?);
+        }
+        plan.manifest = Some(out_dir.join(io::key_set_index_name(filename_prefix)));
+        return Ok(plan);
+    }
 
-    // Check if the `out` directory exists, if not, create it
-    if !out_dir.exists() {
-        std::fs::create_dir(&out_dir).expect("Unable to create out directory");
+    plan.keys.push(planned(
+        CircuitShape::default(),
+        io::key_file_name(filename_prefix, "pk"),
+        io::key_file_name(filename_prefix, "vk"),
+    )?);
+    if options.bundle {
+        plan.bundle_path = Some(out_dir.join(io::bundle_file_name(filename_prefix)));
     }
+    Ok(plan)
+}
 
-    // Setup circuit
-    let circuit = FibonacciCircuit::<Fr> {
-        public_input: vec![vec![Fr::from(1), Fr::from(1), Fr::from(55)]],
+/// Comma separated values following `name` in `args`, e.g. `--k 4,5,6`.
+#[cfg(not(target_os = "zkvm"))]
+fn list_arg(args: &[String], name: &str) -> Result<Option<Vec<usize>>, FibonacciError> {
+    let Some(value) = args.iter().skip_while(|arg| *arg != name).nth(1) else {
+        return Ok(None);
     };
+    value
+        .split(',')
+        .map(|v| {
+            v.trim().parse().map_err(|_| {
+                FibonacciError::InvalidInput(
+                    format!(
+                        "Expected a comma separated list of numbers after {}, got '{}'",
+                        name, value
+                    )
+                    .into(),
+                )
+            })
+        })
+        .collect::<Result<_, _>>()
+        .map(Some)
+}
+
+/// Shapes for each sequence length at each of `ks`, or at the smallest k holding it without
+/// `ks`, and the combinations skipped as the domain is too small for the sequence. Sequences
+/// too short for any domain are errors.
+#[cfg(not(target_os = "zkvm"))]
+fn key_set_shapes(
+    steps: &[usize],
+    ks: Option<&[usize]>,
+) -> Result<(Vec<CircuitShape>, Vec<(CircuitShape, FibonacciError)>), FibonacciError> {
+    let mut shapes = Vec::new();
+    let mut skipped = Vec::new();
+    for &steps in steps {
+        let Some(ks) = ks else {
+            let shape = CircuitShape::new(steps);
+            shape.validate()?;
+            shapes.push(shape);
+            continue;
+        };
+        for &k in ks {
+            let shape = CircuitShape { steps, k };
+            match shape.validate() {
+                Ok(()) => shapes.push(shape),
+                Err(e) => skipped.push((shape, e)),
+            }
+        }
+    }
+    Ok((shapes, skipped))
+}
+
+/// Generate prover and verifier parameters from the SRS file and store them in `out_dir`.
+///
+/// Returns the paths of the proving key and verifying key files. Panics on failure, see
+/// `try_write_keys`.
+pub fn write_keys<PC>(srs_path: &Path, out_dir: &Path, filename_prefix: &str) -> (PathBuf, PathBuf)
+where
+    PC: PlonkishComponents,
+{
+    try_write_keys::<PC>(srs_path, out_dir, filename_prefix).expect("Writing keys should not fail")
+}
+
+/// Like `write_keys`, returning an error for an unreadable SRS or an unwritable `out_dir`.
+pub fn try_write_keys<PC>(
+    srs_path: &Path,
+    out_dir: &Path,
+    filename_prefix: &str,
+) -> Result<(PathBuf, PathBuf), Box<dyn Error>>
+where
+    PC: PlonkishComponents,
+{
+    let param = io::parse_srs_path::<PC>(srs_path)?;
+    std::fs::create_dir_all(out_dir)?;
+
+    let (proving_key, verifying_key) = encode_keys::<PC>(&param, CircuitShape::default())?;
+
+    // Keep other processes from writing or reading keys in `out_dir` meanwhile
+    let _lock = lock::ArtifactLock::exclusive(out_dir)?;
+
+    let pk_path = out_dir.join(io::key_file_name(filename_prefix, "pk"));
+    io::write_atomic(&pk_path, &proving_key, true)?;
+    let vk_path = out_dir.join(io::key_file_name(filename_prefix, "vk"));
+    io::write_atomic(&vk_path, &verifying_key, true)?;
+
+    Ok((pk_path, vk_path))
+}
+
+/// Generate keys for each of `shapes` from the SRS file, which must hold the largest k, and store
+/// them in `out_dir` with a key set index listing them.
+///
+/// Returns the path of the index, `<prefix>_fibonacci_keys.json`.
+pub fn write_key_set<PC>(
+    srs_path: &Path,
+    out_dir: &Path,
+    filename_prefix: &str,
+    shapes: &[CircuitShape],
+) -> Result<PathBuf, Box<dyn Error>>
+where
+    PC: PlonkishComponents,
+{
+    let param = io::parse_srs_path::<PC>(srs_path)?;
+    std::fs::create_dir_all(out_dir)?;
+    let _lock = lock::ArtifactLock::exclusive(out_dir)?;
+
+    let mut entries = Vec::new();
+    for &shape in shapes {
+        let (proving_key, verifying_key) = encode_keys::<PC>(&param, shape)?;
+
+        let entry = io::KeySetEntry {
+            shape,
+            proving_key: io::key_set_file_name(filename_prefix, shape, "pk"),
+            verifying_key: io::key_set_file_name(filename_prefix, shape, "vk"),
+        };
+        io::write_atomic(&out_dir.join(&entry.proving_key), &proving_key, true)?;
+        io::write_atomic(&out_dir.join(&entry.verifying_key), &verifying_key, true)?;
+        entries.push(entry);
+    }
+
+    let key_set_path = out_dir.join(io::key_set_index_name(filename_prefix));
+    io::write_key_set(
+        &key_set_path,
+        &io::KeySet {
+            backend: PC::BACKEND,
+            entries,
+        },
+    )?;
+
+    Ok(key_set_path)
+}
+
+/// Preprocess the Fibonacci circuit into prover and verifier parameters.
+pub fn preprocess<PC>(
+    param: &PC::Param,
+) -> Result<(PC::ProverParam, PC::VerifierParam), FibonacciError>
+where
+    PC: PlonkishComponents,
+{
+    preprocess_for_shape::<PC>(param, CircuitShape::default())
+}
+
+/// Like `preprocess`, for a sequence length and domain size other than the default.
+pub fn preprocess_for_shape<PC>(
+    param: &PC::Param,
+    shape: CircuitShape,
+) -> Result<(PC::ProverParam, PC::VerifierParam), FibonacciError>
+where
+    PC: PlonkishComponents,
+{
+    crate::profile_scope!("preprocess");
+
+    io::check_srs_size::<PC>(param, shape.k)?;
+    let circuit_info = circuit_info::<PC>(shape)?;
+
+    PC::ProvingBackend::preprocess(param, &circuit_info).map_err(|e| {
+        FibonacciError::Setup(ErrorMessage::with_source(
+            format!("Failed to preprocess the circuit: {:?}", e),
+            BackendError(e),
+        ))
+    })
+}
+
+/// Like `preprocess`, for any halo2 circuit laid out in 2^`k` rows, e.g.
+/// `multi_instance::MultiInstanceCircuit`. Prove with `prove_instances`.
+///
+/// The number of public inputs of each instance column is taken from `circuit`, so it must hold
+/// public inputs of the same lengths as the proven circuits.
+pub fn preprocess_circuit<PC, C>(
+    param: &PC::Param,
+    k: usize,
+    circuit: C,
+) -> Result<(PC::ProverParam, PC::VerifierParam), FibonacciError>
+where
+    PC: PlonkishComponents,
+    C: CircuitExt<Fr>,
+{
+    crate::profile_scope!("preprocess");
+
+    io::check_srs_size::<PC>(param, k)?;
+    let circuit_info = build_halo2_circuit::<PC, C>(k, circuit)
+        .circuit_info()
+        .map_err(|e| {
+            FibonacciError::Setup(ErrorMessage::with_source(
+                format!("Failed to get circuit info: {:?}", e),
+                BackendError(e),
+            ))
+        })?;
+
+    PC::ProvingBackend::preprocess(param, &circuit_info).map_err(|e| {
+        FibonacciError::Setup(ErrorMessage::with_source(
+            format!("Failed to preprocess the circuit: {:?}", e),
+            BackendError(e),
+        ))
+    })
+}
+
+/// The circuit of `shape` keys are generated from. Public inputs don't affect the keys.
+fn keygen_circuit(shape: CircuitShape) -> FibonacciCircuit<Fr> {
+    FibonacciCircuit {
+        public_input: vec![vec![Fr::from(1), Fr::from(1), Fr::from(55)]],
+        steps: shape.steps,
+    }
+}
+
+pub(crate) fn circuit_info<PC>(
+    shape: CircuitShape,
+) -> Result<PlonkishCircuitInfo<Fr>, FibonacciError>
+where
+    PC: PlonkishComponents,
+{
+    shape.validate()?;
 
+    let circuit = keygen_circuit(shape);
     let circuit_fn = |k| {
-        let circuit =
-            Halo2Circuit::<Fr, FibonacciCircuit<Fr>>::new::<PC::ProvingBackend>(k, circuit.clone());
-        (circuit.circuit_info().unwrap(), circuit)
+        let circuit = build_halo2_circuit::<PC, _>(k, circuit.clone());
+        (circuit.circuit_info(), circuit)
     };
-    let (circuit_info, _) = circuit_fn(4usize);
+    let (circuit_info, _) = circuit_fn(shape.k);
+    circuit_info.map_err(|e| {
+        FibonacciError::Setup(ErrorMessage::with_source(
+            format!("Failed to get circuit info: {:?}", e),
+            BackendError(e),
+        ))
+    })
+}
 
-    let (prover_parameters, verifier_parameters) =
-        PC::ProvingBackend::preprocess(&param, &circuit_info).unwrap();
+/// Hash of the Fibonacci constraint system (gates, columns, fixed values and permutation) as
+/// compiled for `PC`. Generated keys record it, and proving or verifying with keys of another
+/// circuit version fails with `FibonacciError::CircuitMismatch`.
+pub fn circuit_hash<PC>() -> Result<io::CircuitHash, FibonacciError>
+where
+    PC: PlonkishComponents,
+{
+    circuit_hash_for_shape::<PC>(CircuitShape::default())
+}
 
-    let pk_path = out_dir.join(format!("{}_fibonacci_pk.bin", filename_prefix));
-    let _ = io::save_to_file::<_, PC::ProverParam>(&pk_path, &prover_parameters);
-    let vk_path = out_dir.join(format!("{}_fibonacci_vk.bin", filename_prefix));
-    let _ = io::save_to_file::<_, PC::VerifierParam>(&vk_path, &verifier_parameters);
+/// Like `circuit_hash`, for a sequence length and domain size other than the default.
+pub fn circuit_hash_for_shape<PC>(shape: CircuitShape) -> Result<io::CircuitHash, FibonacciError>
+where
+    PC: PlonkishComponents,
+{
+    shape.validate()?;
+    circuit_hash_of::<PC, _>("fibonacci", shape.k, shape.steps, || keygen_circuit(shape))
+}
 
-    println!("Preparation finished successfully.");
-    println!("SRS readed from {}", srs_path.display());
-    println!("Proving key stored in {}", pk_path.display());
-    println!("Verification key stored in {}", vk_path.display());
+/// Hash of the constraint system of the circuit `circuit` builds, laid out in 2^`k` rows for
+/// `PC`: Keccak-256 of a canonical encoding of its circuit info, see `hash_circuit_info`.
+///
+/// Synthesizing the circuit info costs about as much as preprocessing, while the hash only
+/// depends on the circuit, so it's computed once per `PC`, circuit `name`, `k` and `variant`
+/// (the parameter the circuit was built for, e.g. its sequence length or tree depth) and cached.
+pub fn circuit_hash_of<PC, C>(
+    name: &'static str,
+    k: usize,
+    variant: usize,
+    circuit: impl FnOnce() -> C,
+) -> Result<io::CircuitHash, FibonacciError>
+where
+    PC: PlonkishComponents,
+    C: CircuitExt<Fr>,
+{
+    type HashKey = (&'static str, &'static str, usize, usize);
+    static HASHES: OnceLock<Mutex<HashMap<HashKey, io::CircuitHash>>> = OnceLock::new();
+
+    let key = (type_name::<PC>(), name, k, variant);
+    let hashes = HASHES.get_or_init(Default::default);
+    if let Some(hash) = hashes.lock().unwrap().get(&key) {
+        return Ok(*hash);
+    }
+
+    let circuit_info = build_halo2_circuit::<PC, C>(k, circuit())
+        .circuit_info()
+        .map_err(|e| {
+            FibonacciError::Setup(ErrorMessage::with_source(
+                format!("Failed to get circuit info: {:?}", e),
+                BackendError(e),
+            ))
+        })?;
+    let hash = hash_circuit_info(&circuit_info)?;
+    hashes.lock().unwrap().insert(key, hash);
+    Ok(hash)
+}
+
+/// Generate the proving and verifying key contents of a circuit variant of 2^`k` rows from the
+/// SRS contents, with `circuit_hash` in their headers. `circuit` only needs its public input
+/// lengths, e.g. the nonce and step count variants pass placeholder values.
+pub(crate) fn keygen_variant<PC, C>(
+    srs_key: &[u8],
+    k: usize,
+    circuit: C,
+    circuit_hash: io::CircuitHash,
+) -> Result<(Vec<u8>, Vec<u8>), Box<dyn Error>>
+where
+    PC: PlonkishComponents,
+    C: CircuitExt<Fr>,
+{
+    let param = io::parse_srs_bytes::<PC>(srs_key)?;
+    let (prover_param, verifier_param) = preprocess_circuit::<PC, _>(&param, k, circuit)?;
+
+    Ok((
+        io::encode_artifact_for_circuit(&prover_param, Codec::Bincode, circuit_hash)?,
+        io::encode_artifact_for_circuit(&verifier_param, Codec::Bincode, circuit_hash)?,
+    ))
+}
+
+/// Prove `circuit` with a proving key of `keygen_variant`, recording the proof metrics. The
+/// public inputs are those of the circuit's instance column.
+pub(crate) fn prove_variant<PC, C>(
+    srs_key: &[u8],
+    proving_key: &[u8],
+    k: usize,
+    circuit: C,
+    circuit_hash: &io::CircuitHash,
+    binding: TranscriptBinding,
+) -> Result<GenerateProofResult, Box<dyn Error>>
+where
+    PC: PlonkishComponents,
+    C: CircuitExt<Fr>,
+    ProofTranscript: TranscriptWrite<CommitmentChunk<Fr, PC::Pcs>, Fr>,
+{
+    let srs = io::parse_srs_bytes::<PC>(srs_key)?;
+    io::check_srs_size::<PC>(&srs, k)?;
+    let proving_key: PC::ProverParam = io::decode_artifact_for_circuit(proving_key, circuit_hash)?;
+
+    let timer = telemetry::Timer::start();
+    let result = generate_halo2_proof_for_circuit::<PC, _>(&proving_key, k, circuit, binding);
+    telemetry::record_proof_result(PC::BACKEND, timer, &result);
+    let (proof, instances) = result?;
+
+    Ok((proof, encode_public_inputs(&instances[0])))
+}
+
+/// Verify a proof of `prove_variant` for the already checked public `inputs`, with a verifying
+/// key of `keygen_variant`, recording the verification metrics.
+pub(crate) fn verify_variant<PC>(
+    verifying_key: &[u8],
+    proof: &[u8],
+    inputs: Vec<Fr>,
+    circuit_hash: &io::CircuitHash,
+    binding: TranscriptBinding,
+) -> Result<bool, Box<dyn Error>>
+where
+    PC: PlonkishComponents,
+    ProofTranscript: TranscriptRead<CommitmentChunk<Fr, PC::Pcs>, Fr>,
+{
+    let verifying_key: PC::VerifierParam =
+        io::decode_artifact_for_circuit(verifying_key, circuit_hash)?;
+
+    let timer = telemetry::Timer::start();
+    let result = match verify_halo2_proof_with_vk::<PC>(&verifying_key, proof, inputs, binding) {
+        Err(FibonacciError::Verification(_)) => Ok(false),
+        result => result,
+    };
+    telemetry::record_verification(PC::BACKEND, timer.elapsed(), result.as_ref().ok().copied());
+
+    Ok(result?)
+}
+
+/// Keccak-256 of the fields of `circuit_info` in declaration order, bincode encoded. Unlike its
+/// `Debug` output, the encoding doesn't change with how the backend's types format themselves.
+pub fn hash_circuit_info(
+    circuit_info: &PlonkishCircuitInfo<Fr>,
+) -> Result<io::CircuitHash, FibonacciError> {
+    let encoded = bincode::serialize(&(
+        circuit_info.k,
+        &circuit_info.num_instances,
+        &circuit_info.preprocess_polys,
+        &circuit_info.num_witness_polys,
+        &circuit_info.num_challenges,
+        &circuit_info.constraints,
+        &circuit_info.lookups,
+        &circuit_info.permutations,
+        circuit_info.max_degree,
+    ))
+    .map_err(|e| {
+        FibonacciError::Serialization(ErrorMessage::with_source(
+            format!("Failed to encode the circuit info: {}", e),
+            e,
+        ))
+    })?;
+    Ok(Keccak256::digest(encoded).into())
+}
+
+/// Preprocess the circuit and encode the keys, recording the circuit hash in their headers.
+fn encode_keys<PC>(
+    param: &PC::Param,
+    shape: CircuitShape,
+) -> Result<(Vec<u8>, Vec<u8>), FibonacciError>
+where
+    PC: PlonkishComponents,
+{
+    let (prover_parameters, verifier_parameters) = preprocess_for_shape::<PC>(param, shape)?;
+    let circuit_hash = circuit_hash_for_shape::<PC>(shape)?;
+
+    Ok((
+        io::encode_artifact_for_circuit(&prover_parameters, Codec::Bincode, circuit_hash)?,
+        io::encode_artifact_for_circuit(&verifier_parameters, Codec::Bincode, circuit_hash)?,
+    ))
+}
+
+/// Decode a proving or verifying key, checking it was generated for the compiled-in circuit of
+/// `shape`.
+fn decode_key<PC, T>(bytes: &[u8], shape: CircuitShape) -> Result<T, FibonacciError>
+where
+    PC: PlonkishComponents,
+    T: DeserializeOwned,
+{
+    io::decode_artifact_for_circuit(bytes, &circuit_hash_for_shape::<PC>(shape)?)
+}
+
+/// Like `decode_key`, decoding the key while reading it from `path`.
+#[cfg(not(target_arch = "wasm32"))]
+fn load_key<PC, T>(path: &Path, shape: CircuitShape) -> Result<T, Box<dyn Error>>
+where
+    PC: PlonkishComponents,
+    T: DeserializeOwned,
+{
+    let reader = io::open_key_file(path)?;
+    Ok(io::read_artifact(
+        reader,
+        Some(&circuit_hash_for_shape::<PC>(shape)?),
+    )?)
+}
+
+/// Sequence length requested with the optional `steps` input, `DEFAULT_STEPS` without it.
+pub fn requested_steps(input: &HashMap<String, Vec<String>>) -> Result<usize, FibonacciError> {
+    match input.get("steps").and_then(|steps| steps.first()) {
+        Some(steps) => steps.parse().map_err(|e| {
+            FibonacciError::InvalidInput(format!("Invalid `steps` value {}: {}", steps, e).into())
+        }),
+        None => Ok(DEFAULT_STEPS),
+    }
+}
+
+/// Like `write_keys`, taking the SRS contents and returning the proving and verifying key
+/// contents instead of writing them.
+pub fn keygen_from_bytes<PC>(srs_key: &[u8]) -> Result<(Vec<u8>, Vec<u8>), Box<dyn Error>>
+where
+    PC: PlonkishComponents,
+{
+    keygen_from_bytes_with_shape::<PC>(srs_key, CircuitShape::default())
+}
+
+/// Like `keygen_from_bytes`, for a sequence length and domain size other than the default.
+pub fn keygen_from_bytes_with_shape<PC>(
+    srs_key: &[u8],
+    shape: CircuitShape,
+) -> Result<(Vec<u8>, Vec<u8>), Box<dyn Error>>
+where
+    PC: PlonkishComponents,
+{
+    let param = io::parse_srs_bytes::<PC>(srs_key)?;
+
+    Ok(encode_keys::<PC>(&param, shape)?)
 }
 
 fn prove_with_params<PC>(
     srs: PC::Param,
     proving_key: PC::ProverParam,
     input: HashMap<String, Vec<String>>,
+    shape: CircuitShape,
+    binding: TranscriptBinding,
 ) -> Result<GenerateProofResult, Box<dyn Error>>
 where
     PC: PlonkishComponents,
     ProofTranscript: TranscriptWrite<CommitmentChunk<Fr, PC::Pcs>, Fr>,
 {
-    let circuit_inputs = deserialize_circuit_inputs(input)
-        .map_err(|e| FibonacciError(format!("Failed to deserialize circuit inputs: {}", e)))?;
+    io::check_srs_size::<PC>(&srs, shape.k)?;
 
-    let (proof, inputs) = generate_halo2_proof::<PC>(&srs, &proving_key, circuit_inputs)
-        .map_err(|e| FibonacciError(format!("Failed to generate the proof: {}", e)))?;
+    let timer = telemetry::Timer::start();
+    let result = prove_circuit::<PC>(srs, proving_key, input, shape, binding);
+    telemetry::record_proof_result(PC::BACKEND, timer, &result);
 
-    let serialized_inputs = bincode::serialize(&InputsSerialisationWrapper(inputs))
-        .map_err(|e| FibonacciError(format!("Serialization of Inputs failed: {}", e)))?;
+    result
+}
 
-    Ok((proof, serialized_inputs))
+fn prove_circuit<PC>(
+    srs: PC::Param,
+    proving_key: PC::ProverParam,
+    input: HashMap<String, Vec<String>>,
+    shape: CircuitShape,
+    binding: TranscriptBinding,
+) -> Result<GenerateProofResult, Box<dyn Error>>
+where
+    PC: PlonkishComponents,
+    ProofTranscript: TranscriptWrite<CommitmentChunk<Fr, PC::Pcs>, Fr>,
+{
+    let steps = requested_steps(&input)?;
+    if steps != shape.steps {
+        return Err(FibonacciError::InvalidInput(
+            format!(
+                "Requested {} steps, but the proving key is for {} steps",
+                steps, shape.steps
+            )
+            .into(),
+        )
+        .into());
+    }
+
+    let circuit_inputs = deserialize_circuit_inputs(input).map_err(|e| {
+        FibonacciError::InvalidInput(format!("Failed to deserialize circuit inputs: {}", e).into())
+    })?;
+
+    // Already typed, so a missing `out` stays `InvalidInput` rather than a proving failure
+    let (proof, inputs) =
+        generate_halo2_proof_with_shape::<PC>(&srs, &proving_key, circuit_inputs, shape, binding)?;
+
+    Ok((proof, encode_public_inputs(&inputs)))
+}
+
+/// Like `prove_with_binding`, taking the SRS and proving key contents instead of paths. Available
+/// on every target for embedders (e.g. mobile apps) which don't keep artifacts on the filesystem.
+pub fn prove_from_bytes<PC>(
+    srs_key: &[u8],
+    proving_key: &[u8],
+    input: HashMap<String, Vec<String>>,
+    binding: TranscriptBinding,
+) -> Result<GenerateProofResult, Box<dyn Error>>
+where
+    PC: PlonkishComponents,
+    ProofTranscript: TranscriptWrite<CommitmentChunk<Fr, PC::Pcs>, Fr>,
+{
+    prove_from_bytes_with_shape::<PC>(
+        srs_key,
+        proving_key,
+        input,
+        CircuitShape::default(),
+        binding,
+    )
+}
+
+/// Like `prove_from_bytes`, with a proving key generated for `shape`. Proving keys of another
+/// sequence length or k are rejected with `FibonacciError::CircuitMismatch`, and an SRS smaller
+/// than 2^k with `FibonacciError::SrsTooSmall`.
+pub fn prove_from_bytes_with_shape<PC>(
+    srs_key: &[u8],
+    proving_key: &[u8],
+    input: HashMap<String, Vec<String>>,
+    shape: CircuitShape,
+    binding: TranscriptBinding,
+) -> Result<GenerateProofResult, Box<dyn Error>>
+where
+    PC: PlonkishComponents,
+    ProofTranscript: TranscriptWrite<CommitmentChunk<Fr, PC::Pcs>, Fr>,
+{
+    let srs = io::parse_srs_bytes::<PC>(srs_key)?;
+    let proving_key = decode_key::<PC, PC::ProverParam>(proving_key, shape)?;
+
+    prove_with_params::<PC>(srs, proving_key, input, shape, binding)
+}
+
+/// Prove the sequence a circuit config describes, with a proving key generated for its shape.
+pub fn prove_with_config<PC>(
+    srs_key: &[u8],
+    proving_key: &[u8],
+    config: &CircuitConfig,
+    binding: TranscriptBinding,
+) -> Result<GenerateProofResult, Box<dyn Error>>
+where
+    PC: PlonkishComponents,
+    ProofTranscript: TranscriptWrite<CommitmentChunk<Fr, PC::Pcs>, Fr>,
+{
+    config.shape.validate()?;
+    prove_from_bytes_with_shape::<PC>(srs_key, proving_key, config.input(), config.shape, binding)
 }
 
 #[cfg(not(target_arch = "wasm32"))]
@@ -123,11 +934,75 @@ where
     PC: PlonkishComponents,
     ProofTranscript: TranscriptWrite<CommitmentChunk<Fr, PC::Pcs>, Fr>,
 {
-    let srs = io::read_srs_path::<PC>(Path::new(&srs_key_path));
-    let proving_key =
-        io::load_from_file::<_, PC::ProverParam>(Path::new(&proving_key_path)).unwrap();
+    prove_with_label::<PC>(srs_key_path, proving_key_path, input, &[])
+}
 
-    prove_with_params::<PC>(srs, proving_key, input)
+/// Like `prove`, binding the proof to a domain separation label (e.g. the application name)
+/// which the verifier must supply identically.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn prove_with_label<PC>(
+    srs_key_path: &str,
+    proving_key_path: &str,
+    input: HashMap<String, Vec<String>>,
+    label: &[u8],
+) -> Result<GenerateProofResult, Box<dyn Error>>
+where
+    PC: PlonkishComponents,
+    ProofTranscript: TranscriptWrite<CommitmentChunk<Fr, PC::Pcs>, Fr>,
+{
+    prove_with_binding::<PC>(
+        srs_key_path,
+        proving_key_path,
+        input,
+        TranscriptBinding::label(label),
+    )
+}
+
+/// Like `prove`, binding the proof to a label and caller-supplied context (e.g. a session id or
+/// chain id), so it can't be replayed where the verifier expects a different context.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn prove_with_binding<PC>(
+    srs_key_path: &str,
+    proving_key_path: &str,
+    input: HashMap<String, Vec<String>>,
+    binding: TranscriptBinding,
+) -> Result<GenerateProofResult, Box<dyn Error>>
+where
+    PC: PlonkishComponents,
+    ProofTranscript: TranscriptWrite<CommitmentChunk<Fr, PC::Pcs>, Fr>,
+{
+    let srs = io::parse_srs_path::<PC>(Path::new(&srs_key_path))?;
+    let shape = CircuitShape::default();
+    let proving_key = load_key::<PC, PC::ProverParam>(Path::new(proving_key_path), shape)?;
+
+    prove_with_params::<PC>(srs, proving_key, input, shape, binding)
+}
+
+/// Like `prove_with_binding`, with the keys of a key set written by `write_key_set`. The
+/// sequence length is taken from the `steps` input (`DEFAULT_STEPS` without it), proving with
+/// the keys of the smallest k generated for it.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn prove_with_key_set<PC>(
+    srs_key_path: &str,
+    key_set_path: &str,
+    input: HashMap<String, Vec<String>>,
+    binding: TranscriptBinding,
+) -> Result<GenerateProofResult, Box<dyn Error>>
+where
+    PC: PlonkishComponents,
+    ProofTranscript: TranscriptWrite<CommitmentChunk<Fr, PC::Pcs>, Fr>,
+{
+    let key_set_path = Path::new(key_set_path);
+    let key_set = io::read_key_set(&key_set_path)?;
+    let entry = key_set.select(requested_steps(&input)?)?;
+
+    let srs = io::parse_srs_path::<PC>(Path::new(&srs_key_path))?;
+    let proving_key = load_key::<PC, PC::ProverParam>(
+        &key_set_path.with_file_name(&entry.proving_key),
+        entry.shape,
+    )?;
+
+    prove_with_params::<PC>(srs, proving_key, input, entry.shape, binding)
 }
 
 #[cfg(target_arch = "wasm32")]
@@ -140,95 +1015,381 @@ where
     PC: PlonkishComponents,
     ProofTranscript: TranscriptWrite<CommitmentChunk<Fr, PC::Pcs>, Fr>,
 {
-    let srs = io::read_srs_bytes::<PC>(srs_key);
-    let proving_key = io::load_from_bytes::<PC::ProverParam>(proving_key).unwrap();
+    prove_with_label::<PC>(srs_key, proving_key, input, &[])
+}
+
+/// Like `prove`, binding the proof to a domain separation label (e.g. the application name)
+/// which the verifier must supply identically.
+#[cfg(target_arch = "wasm32")]
+pub fn prove_with_label<PC>(
+    srs_key: &[u8],
+    proving_key: &[u8],
+    input: HashMap<String, Vec<String>>,
+    label: &[u8],
+) -> Result<GenerateProofResult, Box<dyn Error>>
+where
+    PC: PlonkishComponents,
+    ProofTranscript: TranscriptWrite<CommitmentChunk<Fr, PC::Pcs>, Fr>,
+{
+    prove_with_binding::<PC>(srs_key, proving_key, input, TranscriptBinding::label(label))
+}
 
-    prove_with_params::<PC>(srs, proving_key, input)
+/// Like `prove`, binding the proof to a label and caller-supplied context (e.g. a session id or
+/// chain id), so it can't be replayed where the verifier expects a different context.
+#[cfg(target_arch = "wasm32")]
+pub fn prove_with_binding<PC>(
+    srs_key: &[u8],
+    proving_key: &[u8],
+    input: HashMap<String, Vec<String>>,
+    binding: TranscriptBinding,
+) -> Result<GenerateProofResult, Box<dyn Error>>
+where
+    PC: PlonkishComponents,
+    ProofTranscript: TranscriptWrite<CommitmentChunk<Fr, PC::Pcs>, Fr>,
+{
+    prove_from_bytes::<PC>(srs_key, proving_key, input, binding)
 }
 
 fn verify_with_params<PC>(
-    srs: PC::Param,
     verifying_key: PC::VerifierParam,
-    proof: Vec<u8>,
-    public_inputs: Vec<u8>,
+    proof: &[u8],
+    public_inputs: &[u8],
+    binding: TranscriptBinding,
 ) -> Result<bool, Box<dyn Error>>
 where
     PC: PlonkishComponents,
     ProofTranscript: TranscriptRead<CommitmentChunk<Fr, PC::Pcs>, Fr>,
 {
-    let deserialized_inputs: Vec<Fr> =
-        bincode::deserialize::<InputsSerialisationWrapper>(&public_inputs)
-            .map_err(|e| FibonacciError(e.to_string()))?
-            .0;
+    let timer = telemetry::Timer::start();
+    let result = verify_circuit::<PC>(verifying_key, proof, public_inputs, binding);
+    telemetry::record_verification(PC::BACKEND, timer.elapsed(), result.as_ref().ok().copied());
 
-    let is_valid = verify_halo2_proof::<PC>(&srs, &verifying_key, proof, deserialized_inputs)
-        .map_err(|e| FibonacciError(format!("Verification failed: {}", e)))?;
+    result
+}
+
+fn verify_circuit<PC>(
+    verifying_key: PC::VerifierParam,
+    proof: &[u8],
+    public_inputs: &[u8],
+    binding: TranscriptBinding,
+) -> Result<bool, Box<dyn Error>>
+where
+    PC: PlonkishComponents,
+    ProofTranscript: TranscriptRead<CommitmentChunk<Fr, PC::Pcs>, Fr>,
+{
+    let deserialized_inputs = decode_public_inputs(public_inputs)?;
+
+    let is_valid =
+        verify_halo2_proof_with_vk::<PC>(&verifying_key, proof, deserialized_inputs, binding)
+            .map_err(|e| {
+                FibonacciError::Verification(ErrorMessage::with_source(
+                    format!("Verification failed: {}", e),
+                    e,
+                ))
+            })?;
+
+    Ok(is_valid)
+}
+
+/// Prove a circuit preprocessed with `preprocess_circuit`. The public inputs of every instance
+/// column are encoded with `InstanceColumnsSerialisationWrapper`, one vector per column, rather
+/// than flattened like those of the Fibonacci circuit.
+pub fn prove_instances<PC, C>(
+    prover_param: &PC::ProverParam,
+    k: usize,
+    circuit: C,
+    binding: TranscriptBinding,
+) -> Result<GenerateProofResult, Box<dyn Error>>
+where
+    PC: PlonkishComponents,
+    C: CircuitExt<Fr>,
+    ProofTranscript: TranscriptWrite<CommitmentChunk<Fr, PC::Pcs>, Fr>,
+{
+    let (proof, instances) =
+        generate_halo2_proof_for_circuit::<PC, C>(prover_param, k, circuit, binding)?;
+
+    let serialized_inputs = bincode::serialize(&InstanceColumnsSerialisationWrapper(instances))
+        .map_err(|e| {
+            FibonacciError::Serialization(format!("Serialization of Inputs failed: {}", e).into())
+        })?;
+
+    Ok((proof, serialized_inputs))
+}
+
+/// Verify a proof of `prove_instances`, given public inputs of every instance column encoded with
+/// `InstanceColumnsSerialisationWrapper`.
+pub fn verify_instances_with_vk<PC>(
+    verifier_param: &PC::VerifierParam,
+    proof: &[u8],
+    public_inputs: &[u8],
+    binding: TranscriptBinding,
+) -> Result<bool, Box<dyn Error>>
+where
+    PC: PlonkishComponents,
+    ProofTranscript: TranscriptRead<CommitmentChunk<Fr, PC::Pcs>, Fr>,
+{
+    let instances = io::decode_artifact::<InstanceColumnsSerialisationWrapper>(public_inputs)?.0;
+
+    let is_valid =
+        verify_halo2_proof_with_instances::<PC>(verifier_param, proof, &instances, binding)
+            .map_err(|e| {
+                FibonacciError::Verification(ErrorMessage::with_source(
+                    format!("Verification failed: {}", e),
+                    e,
+                ))
+            })?;
 
     Ok(is_valid)
 }
 
+/// Like `verify_with_binding`, taking the SRS and verifying key contents instead of paths.
+///
+/// The SRS is not parsed, see `verify_with_vk`. It is kept in the signature to match the `plonk`
+/// backend, whose verifier needs it.
+pub fn verify_from_bytes<PC>(
+    _srs_key: &[u8],
+    verifying_key: &[u8],
+    proof: &[u8],
+    public_inputs: &[u8],
+    binding: TranscriptBinding,
+) -> Result<bool, Box<dyn Error>>
+where
+    PC: PlonkishComponents,
+    ProofTranscript: TranscriptRead<CommitmentChunk<Fr, PC::Pcs>, Fr>,
+{
+    verify_with_vk::<PC>(verifying_key, proof, public_inputs, binding)
+}
+
+/// Verify with the verifying key contents only. Verifying keys carry the PCS verifier parameters
+/// trimmed from the SRS, so verify-only flows (e.g. devices which can't hold the SRS) don't need
+/// to load it.
+pub fn verify_with_vk<PC>(
+    verifying_key: &[u8],
+    proof: &[u8],
+    public_inputs: &[u8],
+    binding: TranscriptBinding,
+) -> Result<bool, Box<dyn Error>>
+where
+    PC: PlonkishComponents,
+    ProofTranscript: TranscriptRead<CommitmentChunk<Fr, PC::Pcs>, Fr>,
+{
+    verify_with_vk_for_shape::<PC>(
+        verifying_key,
+        proof,
+        public_inputs,
+        CircuitShape::default(),
+        binding,
+    )
+}
+
+/// Like `verify_with_vk`, with a verifying key generated for `shape`.
+pub fn verify_with_vk_for_shape<PC>(
+    verifying_key: &[u8],
+    proof: &[u8],
+    public_inputs: &[u8],
+    shape: CircuitShape,
+    binding: TranscriptBinding,
+) -> Result<bool, Box<dyn Error>>
+where
+    PC: PlonkishComponents,
+    ProofTranscript: TranscriptRead<CommitmentChunk<Fr, PC::Pcs>, Fr>,
+{
+    let verifying_key = decode_key::<PC, PC::VerifierParam>(verifying_key, shape)?;
+
+    verify_with_params::<PC>(verifying_key, proof, public_inputs, binding)
+}
+
+/// Verify every proof of a container made for `verifying_key`, returning whether all of them are
+/// valid. Containers of another backend or key and malformed bundles are errors. The key is of
+/// the default shape, see `verify_container_for_shape` for the keys of a key set.
+pub fn verify_container<PC>(
+    verifying_key: &[u8],
+    container: &ProofContainer,
+    binding: TranscriptBinding,
+) -> Result<bool, Box<dyn Error>>
+where
+    PC: PlonkishComponents,
+    ProofTranscript: TranscriptRead<CommitmentChunk<Fr, PC::Pcs>, Fr>,
+{
+    verify_container_for_shape::<PC>(verifying_key, container, CircuitShape::default(), binding)
+}
+
+/// Like `verify_container`, with a verifying key generated for `shape`.
+pub fn verify_container_for_shape<PC>(
+    verifying_key: &[u8],
+    container: &ProofContainer,
+    shape: CircuitShape,
+    binding: TranscriptBinding,
+) -> Result<bool, Box<dyn Error>>
+where
+    PC: PlonkishComponents,
+    ProofTranscript: TranscriptRead<CommitmentChunk<Fr, PC::Pcs>, Fr>,
+{
+    container.validate(PC::BACKEND, verifying_key)?;
+    let verifying_key = decode_key::<PC, PC::VerifierParam>(verifying_key, shape)?;
+
+    for bundle in &container.bundles {
+        let inputs = decode_public_inputs(&bundle.public_inputs)?;
+        match verify_halo2_proof_with_vk::<PC>(&verifying_key, &bundle.proof, inputs, binding) {
+            Ok(true) => {}
+            Ok(false) | Err(FibonacciError::Verification(_)) => return Ok(false),
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    Ok(true)
+}
+
 #[cfg(not(target_arch = "wasm32"))]
 pub fn verify<PC>(
     srs_key_path: &str,
     verifying_key_path: &str,
-    proof: Vec<u8>,
-    public_inputs: Vec<u8>,
+    proof: &[u8],
+    public_inputs: &[u8],
+) -> Result<bool, Box<dyn Error>>
+where
+    PC: PlonkishComponents,
+    ProofTranscript: TranscriptRead<CommitmentChunk<Fr, PC::Pcs>, Fr>,
+{
+    verify_with_label::<PC>(srs_key_path, verifying_key_path, proof, public_inputs, &[])
+}
+
+/// Like `verify`, only accepting proofs created with the same domain separation label.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn verify_with_label<PC>(
+    srs_key_path: &str,
+    verifying_key_path: &str,
+    proof: &[u8],
+    public_inputs: &[u8],
+    label: &[u8],
+) -> Result<bool, Box<dyn Error>>
+where
+    PC: PlonkishComponents,
+    ProofTranscript: TranscriptRead<CommitmentChunk<Fr, PC::Pcs>, Fr>,
+{
+    verify_with_binding::<PC>(
+        srs_key_path,
+        verifying_key_path,
+        proof,
+        public_inputs,
+        TranscriptBinding::label(label),
+    )
+}
+
+/// Like `verify`, only accepting proofs created with the same label and context.
+///
+/// The SRS file is not read, see `verify_with_vk`.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn verify_with_binding<PC>(
+    _srs_key_path: &str,
+    verifying_key_path: &str,
+    proof: &[u8],
+    public_inputs: &[u8],
+    binding: TranscriptBinding,
 ) -> Result<bool, Box<dyn Error>>
 where
     PC: PlonkishComponents,
     ProofTranscript: TranscriptRead<CommitmentChunk<Fr, PC::Pcs>, Fr>,
 {
-    let srs = io::read_srs_path::<PC>(Path::new(srs_key_path));
     let verifying_key =
-        io::load_from_file::<_, PC::VerifierParam>(Path::new(verifying_key_path)).unwrap();
+        load_key::<PC, PC::VerifierParam>(Path::new(verifying_key_path), CircuitShape::default())?;
+
+    verify_with_params::<PC>(verifying_key, proof, public_inputs, binding)
+}
+
+/// Like `verify_with_binding`, with the verifying key a key set selects for `steps`, see
+/// `prove_with_key_set`.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn verify_with_key_set<PC>(
+    key_set_path: &str,
+    steps: usize,
+    proof: &[u8],
+    public_inputs: &[u8],
+    binding: TranscriptBinding,
+) -> Result<bool, Box<dyn Error>>
+where
+    PC: PlonkishComponents,
+    ProofTranscript: TranscriptRead<CommitmentChunk<Fr, PC::Pcs>, Fr>,
+{
+    let key_set_path = Path::new(key_set_path);
+    let key_set = io::read_key_set(&key_set_path)?;
+    let entry = key_set.select(steps)?;
+
+    let verifying_key = load_key::<PC, PC::VerifierParam>(
+        &key_set_path.with_file_name(&entry.verifying_key),
+        entry.shape,
+    )?;
 
-    verify_with_params::<PC>(srs, verifying_key, proof, public_inputs)
+    verify_with_params::<PC>(verifying_key, proof, public_inputs, binding)
 }
 
 #[cfg(target_arch = "wasm32")]
 pub fn verify<PC>(
     srs_key: &[u8],
     verifying_key: &[u8],
-    proof: Vec<u8>,
-    public_inputs: Vec<u8>,
+    proof: &[u8],
+    public_inputs: &[u8],
 ) -> Result<bool, Box<dyn Error>>
 where
     PC: PlonkishComponents,
     ProofTranscript: TranscriptRead<CommitmentChunk<Fr, PC::Pcs>, Fr>,
 {
-    let srs = io::read_srs_bytes::<PC>(srs_key);
-    let verifying_key = io::load_from_bytes::<PC::VerifierParam>(verifying_key).unwrap();
-
-    verify_with_params::<PC>(srs, verifying_key, proof, public_inputs)
+    verify_with_label::<PC>(srs_key, verifying_key, proof, public_inputs, &[])
 }
 
-pub fn setup_keys(genkey_cmd: &str, srs_filename: &str) {
-    let once = Once::new();
+/// Like `verify`, only accepting proofs created with the same domain separation label.
+#[cfg(target_arch = "wasm32")]
+pub fn verify_with_label<PC>(
+    srs_key: &[u8],
+    verifying_key: &[u8],
+    proof: &[u8],
+    public_inputs: &[u8],
+    label: &[u8],
+) -> Result<bool, Box<dyn Error>>
+where
+    PC: PlonkishComponents,
+    ProofTranscript: TranscriptRead<CommitmentChunk<Fr, PC::Pcs>, Fr>,
+{
+    verify_with_binding::<PC>(
+        srs_key,
+        verifying_key,
+        proof,
+        public_inputs,
+        TranscriptBinding::label(label),
+    )
+}
 
-    once.call_once(|| {
-        let mut gen_keys_command = Command::new("cargo");
-        gen_keys_command
-            .arg("run")
-            .arg("--bin")
-            .arg(genkey_cmd)
-            .arg(srs_filename);
+/// Like `verify`, only accepting proofs created with the same label and context.
+#[cfg(target_arch = "wasm32")]
+pub fn verify_with_binding<PC>(
+    srs_key: &[u8],
+    verifying_key: &[u8],
+    proof: &[u8],
+    public_inputs: &[u8],
+    binding: TranscriptBinding,
+) -> Result<bool, Box<dyn Error>>
+where
+    PC: PlonkishComponents,
+    ProofTranscript: TranscriptRead<CommitmentChunk<Fr, PC::Pcs>, Fr>,
+{
+    verify_from_bytes::<PC>(srs_key, verifying_key, proof, public_inputs, binding)
+}
 
-        gen_keys_command
-            .spawn()
-            .expect("Failed to spawn cargo build")
-            .wait()
-            .expect("cargo build errored");
-    });
+/// Fresh directory under the system temp dir for the artifacts of one test, unique per process
+/// so parallel test binaries don't share keys.
+pub fn test_output_dir(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("fibonacci-{}-{}", name, std::process::id()));
+    if dir.exists() {
+        std::fs::remove_dir_all(&dir).expect("Unable to clear test output directory");
+    }
+    std::fs::create_dir_all(&dir).expect("Unable to create test output directory");
+    dir
 }
 
 // For external integration tests
 #[cfg(not(target_arch = "wasm32"))]
-pub fn test_prove_verify_end_to_end<PC>(
-    genkey_cmd: &str,
-    srs_key_path: &str,
-    proving_key_path: &str,
-    verifying_key_path: &str,
-) where
+pub fn test_prove_verify_end_to_end<PC>(srs_key_path: &str)
+where
     PC: PlonkishComponents,
     ProofTranscript: TranscriptRead<CommitmentChunk<Fr, PC::Pcs>, Fr>
         + TranscriptWrite<CommitmentChunk<Fr, PC::Pcs>, Fr>,
@@ -236,10 +1397,55 @@ pub fn test_prove_verify_end_to_end<PC>(
     let mut input = HashMap::new();
     input.insert("out".to_string(), vec!["55".to_string()]);
 
-    setup_keys(genkey_cmd, &srs_key_path);
+    // Generate the keys in-process rather than running the key generator binary
+    let out_dir = test_output_dir(PC::BACKEND.name());
+    let (proving_key_path, verifying_key_path) =
+        write_keys::<PC>(Path::new(srs_key_path), &out_dir, PC::BACKEND.name());
+    let proving_key_path = proving_key_path.to_str().unwrap();
+    let verifying_key_path = verifying_key_path.to_str().unwrap();
 
-    let result = prove::<PC>(&srs_key_path, &proving_key_path, input).unwrap();
+    let result = prove::<PC>(srs_key_path, proving_key_path, input).unwrap();
 
-    let verified = verify::<PC>(&srs_key_path, &verifying_key_path, result.0, result.1).unwrap();
+    let verified = verify::<PC>(srs_key_path, verifying_key_path, &result.0, &result.1).unwrap();
     assert!(verified);
+
+    std::fs::remove_dir_all(out_dir).unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_list_arg() {
+        let command_line = args(&["gen-keys", "srs", "--k", "4, 5,6"]);
+        assert_eq!(list_arg(&command_line, "--k").unwrap(), Some(vec![4, 5, 6]));
+        assert_eq!(list_arg(&command_line, "--steps").unwrap(), None);
+
+        let command_line = args(&["gen-keys", "srs", "--steps", "9,x"]);
+        assert!(matches!(
+            list_arg(&command_line, "--steps"),
+            Err(FibonacciError::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn test_key_set_shapes_reports_skipped() {
+        let (shapes, skipped) = key_set_shapes(&[DEFAULT_STEPS], Some(&[1, 4])).unwrap();
+        assert_eq!(
+            shapes,
+            vec![CircuitShape {
+                steps: DEFAULT_STEPS,
+                k: 4
+            }]
+        );
+        assert_eq!(skipped.len(), 1);
+        assert_eq!(skipped[0].0.k, 1);
+
+        assert!(key_set_shapes(&[0], None).is_err());
+    }
 }
@@ -0,0 +1,185 @@
+//! Advisory locks on artifact directories, so processes generating keys into the same directory,
+//! e.g. parallel test binaries, don't interleave their writes or read keys mid-generation.
+//!
+//! Key generators hold an exclusive lock on the output directory while writing, readers a shared
+//! one while reading. The locks are advisory and only exclude other users of this module. On
+//! wasm and zkVM targets they are no-ops.
+#[cfg(not(any(target_arch = "wasm32", target_os = "zkvm")))]
+use std::fs::OpenOptions;
+use std::{
+    fs::File,
+    io::{self, Read},
+    path::Path,
+};
+
+#[cfg(not(any(target_arch = "wasm32", target_os = "zkvm")))]
+use fs2::FileExt;
+
+use crate::{ErrorMessage, FibonacciError};
+
+/// Lock file created in locked directories
+pub const LOCK_FILE_NAME: &str = ".fibonacci.lock";
+
+/// Lock on an artifact directory, released when dropped
+#[derive(Debug)]
+pub struct ArtifactLock {
+    #[cfg(not(any(target_arch = "wasm32", target_os = "zkvm")))]
+    file: Option<File>,
+}
+
+impl ArtifactLock {
+    /// Lock `dir` for writing artifacts, waiting for other writers and readers to finish.
+    pub fn exclusive(dir: &Path) -> Result<Self, FibonacciError> {
+        #[cfg(not(any(target_arch = "wasm32", target_os = "zkvm")))]
+        {
+            let lock_error = |e: io::Error| {
+                FibonacciError::Io(ErrorMessage::with_source(
+                    format!("Couldn't lock '{}': {}", dir.display(), e),
+                    e,
+                ))
+            };
+            let file = OpenOptions::new()
+                .create(true)
+                .truncate(false)
+                .write(true)
+                .open(dir.join(LOCK_FILE_NAME))
+                .map_err(lock_error)?;
+            file.lock_exclusive().map_err(lock_error)?;
+            Ok(Self { file: Some(file) })
+        }
+        #[cfg(any(target_arch = "wasm32", target_os = "zkvm"))]
+        {
+            let _ = dir;
+            Ok(Self {})
+        }
+    }
+
+    /// Lock `dir` for reading artifacts, waiting for a writer to finish.
+    ///
+    /// Only writers create the lock file, so reading doesn't write to the directory, which may be
+    /// read-only. Without a lock file no writer has locked `dir` yet and it's read without a
+    /// lock, a writer starting meanwhile still replaces files atomically, see `io::write_atomic`.
+    pub fn shared(dir: &Path) -> Result<Self, FibonacciError> {
+        #[cfg(not(any(target_arch = "wasm32", target_os = "zkvm")))]
+        {
+            let Ok(file) = File::open(dir.join(LOCK_FILE_NAME)) else {
+                return Ok(Self { file: None });
+            };
+            file.lock_shared().map_err(|e| {
+                FibonacciError::Io(ErrorMessage::with_source(
+                    format!("Couldn't lock '{}': {}", dir.display(), e),
+                    e,
+                ))
+            })?;
+            Ok(Self { file: Some(file) })
+        }
+        #[cfg(any(target_arch = "wasm32", target_os = "zkvm"))]
+        {
+            let _ = dir;
+            Ok(Self {})
+        }
+    }
+
+    /// Shared lock on the directory holding the artifact at `path`.
+    pub fn shared_for(path: &Path) -> Result<Self, FibonacciError> {
+        Self::shared(parent_dir(path))
+    }
+
+    /// Exclusive lock on the directory holding the artifact at `path`.
+    pub fn exclusive_for(path: &Path) -> Result<Self, FibonacciError> {
+        Self::exclusive(parent_dir(path))
+    }
+}
+
+impl Drop for ArtifactLock {
+    fn drop(&mut self) {
+        // Closing the file releases the lock as well, unlock explicitly to not depend on it
+        #[cfg(not(any(target_arch = "wasm32", target_os = "zkvm")))]
+        if let Some(file) = &self.file {
+            let _ = file.unlock();
+        }
+    }
+}
+
+/// Directory of the artifact at `path`, the working directory for bare file names.
+fn parent_dir(path: &Path) -> &Path {
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    }
+}
+
+/// File read under a shared lock on its directory, see `io::open_key_file`
+#[derive(Debug)]
+pub struct LockedFile {
+    file: File,
+    _lock: ArtifactLock,
+}
+
+impl LockedFile {
+    /// Open the file at `path` for reading, holding a shared lock on its directory until the
+    /// file is dropped.
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let lock = ArtifactLock::shared_for(path).map_err(io::Error::other)?;
+        Ok(Self {
+            file: File::open(path)?,
+            _lock: lock,
+        })
+    }
+}
+
+impl Read for LockedFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.file.read(buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
+        thread,
+        time::Duration,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_exclusive_lock_blocks_readers() {
+        let dir = crate::test_output_dir("artifact-lock");
+        let released = Arc::new(AtomicBool::new(false));
+
+        let writer = ArtifactLock::exclusive(&dir).unwrap();
+        let reader = {
+            let dir = dir.clone();
+            let released = released.clone();
+            thread::spawn(move || {
+                let _lock = ArtifactLock::shared(&dir).unwrap();
+                assert!(released.load(Ordering::SeqCst));
+            })
+        };
+        thread::sleep(Duration::from_millis(100));
+        released.store(true, Ordering::SeqCst);
+        drop(writer);
+        reader.join().unwrap();
+
+        // Readers don't exclude each other
+        let _first = ArtifactLock::shared(&dir).unwrap();
+        let _second = ArtifactLock::shared(&dir).unwrap();
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_shared_lock_does_not_create_lock_file() {
+        let dir = crate::test_output_dir("artifact-lock-shared");
+
+        let _lock = ArtifactLock::shared(&dir).unwrap();
+        assert!(!dir.join(LOCK_FILE_NAME).exists());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,116 @@
+//! Dynamic lookup example: proves every value of the `query` column occurs in the `table` column,
+//! where the table is part of the witness instead of fixed at key generation.
+//!
+//! Fixed tables are committed to once in the keys, a dynamic table is committed to with every
+//! proof like any other advice column. Backends differ a lot in what that costs, which is what
+//! this circuit lets you compare.
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    halo2curves::ff::PrimeField,
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Selector},
+    poly::Rotation,
+};
+use plonkish_backend::frontend::halo2::CircuitExt;
+use rand::RngCore;
+
+/// Domain size of the example circuit, 2^4 rows
+pub const LOOKUP_K: usize = 4;
+
+#[derive(Debug, Clone)]
+pub struct DynamicLookupConfig {
+    pub table: Column<Advice>,
+    pub query: Column<Advice>,
+    pub q_table: Selector,
+    pub q_query: Selector,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DynamicLookupCircuit<F> {
+    pub table: Vec<Value<F>>,
+    pub queries: Vec<Value<F>>,
+}
+
+impl<F: PrimeField> DynamicLookupCircuit<F> {
+    pub fn new(table: Vec<F>, queries: Vec<F>) -> Self {
+        Self {
+            table: table.into_iter().map(Value::known).collect(),
+            queries: queries.into_iter().map(Value::known).collect(),
+        }
+    }
+}
+
+impl<F: PrimeField> Circuit<F> for DynamicLookupCircuit<F> {
+    type Config = DynamicLookupConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    // The number of rows is part of the layout, only the values are dropped
+    fn without_witnesses(&self) -> Self {
+        Self {
+            table: vec![Value::unknown(); self.table.len()],
+            queries: vec![Value::unknown(); self.queries.len()],
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let table = meta.advice_column();
+        let query = meta.advice_column();
+        let q_table = meta.complex_selector();
+        let q_query = meta.complex_selector();
+
+        // Rows outside both selectors look up 0 in 0, the table always has unselected rows
+        meta.lookup_any("dynamic lookup", |meta| {
+            let q_table = meta.query_selector(q_table);
+            let q_query = meta.query_selector(q_query);
+            let table = meta.query_advice(table, Rotation::cur());
+            let query = meta.query_advice(query, Rotation::cur());
+
+            vec![(q_query * query, q_table * table)]
+        });
+
+        DynamicLookupConfig {
+            table,
+            query,
+            q_table,
+            q_query,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "table",
+            |mut region| {
+                for (row, value) in self.table.iter().enumerate() {
+                    config.q_table.enable(&mut region, row)?;
+                    region.assign_advice(|| "table", config.table, row, || *value)?;
+                }
+                Ok(())
+            },
+        )?;
+
+        layouter.assign_region(
+            || "queries",
+            |mut region| {
+                for (row, value) in self.queries.iter().enumerate() {
+                    config.q_query.enable(&mut region, row)?;
+                    region.assign_advice(|| "query", config.query, row, || *value)?;
+                }
+                Ok(())
+            },
+        )
+    }
+}
+
+impl<F: PrimeField> CircuitExt<F> for DynamicLookupCircuit<F> {
+    fn rand(_: usize, _: impl RngCore) -> Self {
+        unimplemented!()
+    }
+
+    // Both the table and the queries stay private
+    fn instances(&self) -> Vec<Vec<F>> {
+        Vec::new()
+    }
+}
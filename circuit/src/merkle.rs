@@ -0,0 +1,497 @@
+//! Merkle inclusion example: proves a public leaf is in the tree of a public root, without
+//! revealing the path to it.
+//!
+//! The Fibonacci circuit only needs an addition gate. This one shows how a circuit grows
+//! beyond that: each level of the path is a region checking the swap of the node and its
+//! sibling into the left and right inputs, followed by a Poseidon permutation from
+//! `poseidon::PoseidonChip` hashing them into the parent, which the next level copies in. The
+//! leaf and root are rows `LEAF_ROW` and `ROOT_ROW` of the instance column, the siblings and
+//! their positions stay private.
+//!
+//! It goes through the same plumbing as the other circuits: keys from `keygen`
+//! (`preprocess_circuit` with the domain size of `merkle_k`), proofs from `prove` and checked
+//! with `verify`, for any `PlonkishComponents`.
+use std::error::Error;
+
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, SimpleFloorPlanner, Value},
+    halo2curves::ff::{FromUniformBytes, PrimeField},
+    plonk::{
+        Circuit, Column, ConstraintSystem, Error as PlonkError, Expression, Instance, Selector,
+    },
+    poly::Rotation,
+};
+use plonkish_backend::{
+    frontend::halo2::CircuitExt,
+    halo2_curves::bn256::Fr,
+    pcs::CommitmentChunk,
+    util::transcript::{TranscriptRead, TranscriptWrite},
+};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    circuit::{generate_halo2_proof_for_circuit, verify_halo2_proof_with_vk, TranscriptBinding},
+    circuit_hash_of, io,
+    poseidon::{PoseidonChip, PoseidonConfig, PoseidonParams, ROUNDS, TWO_TO_ONE_TAG, WIDTH},
+    preprocess_circuit,
+    serialisation::{decimal, decode_public_inputs, encode_public_inputs, Codec},
+    FibonacciError, GenerateProofResult, PlonkishComponents, ProofTranscript,
+};
+
+/// Row of the instance column holding the leaf
+pub const LEAF_ROW: usize = 0;
+/// Row of the instance column holding the root
+pub const ROOT_ROW: usize = 1;
+
+/// Depth of the example tree, 16 leaves
+pub const DEFAULT_MERKLE_DEPTH: usize = 4;
+/// Deepest path `prove` accepts, 2^32 leaves
+pub const MAX_MERKLE_DEPTH: usize = 32;
+
+/// Rows of one level: the swap row, the input state and one row per round
+pub const LEVEL_ROWS: usize = ROUNDS + 2;
+
+// Rows backends may reserve at the end of the domain
+const RESERVED_ROWS: usize = 8;
+
+/// Smallest domain size fitting a path of `depth` levels.
+pub fn merkle_k(depth: usize) -> usize {
+    let rows = depth * LEVEL_ROWS + RESERVED_ROWS;
+    rows.next_power_of_two().trailing_zeros() as usize
+}
+
+/// Tree over leaves padded with zeros to a power of two, hashing pairs with Poseidon.
+#[derive(Debug, Clone)]
+pub struct MerkleTree<F> {
+    /// Nodes per level, from the leaves up to the root
+    levels: Vec<Vec<F>>,
+}
+
+impl<F: PrimeField + FromUniformBytes<64>> MerkleTree<F> {
+    pub fn new(mut leaves: Vec<F>) -> Self {
+        leaves.resize(leaves.len().max(2).next_power_of_two(), F::ZERO);
+
+        let params = PoseidonParams::new();
+        let mut levels = vec![leaves];
+        while levels.last().unwrap().len() > 1 {
+            let parents = levels
+                .last()
+                .unwrap()
+                .chunks(2)
+                .map(|pair| params.hash_two(pair[0], pair[1]))
+                .collect();
+            levels.push(parents);
+        }
+        Self { levels }
+    }
+
+    pub fn depth(&self) -> usize {
+        self.levels.len() - 1
+    }
+
+    pub fn root(&self) -> F {
+        self.levels.last().unwrap()[0]
+    }
+
+    pub fn leaf(&self, index: usize) -> Option<F> {
+        self.levels[0].get(index).copied()
+    }
+
+    /// Path from the leaf at `index` up to the root.
+    pub fn path(&self, index: usize) -> Option<MerklePath<F>> {
+        if index >= self.levels[0].len() {
+            return None;
+        }
+        let (siblings, is_right) = self.levels[..self.depth()]
+            .iter()
+            .enumerate()
+            .map(|(level, nodes)| {
+                let node = index >> level;
+                (nodes[node ^ 1], node & 1 == 1)
+            })
+            .unzip();
+        Some(MerklePath { siblings, is_right })
+    }
+}
+
+/// Siblings of the nodes from a leaf up to the root, and whether each node is a right child
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MerklePath<F> {
+    pub siblings: Vec<F>,
+    pub is_right: Vec<bool>,
+}
+
+impl<F: PrimeField + FromUniformBytes<64>> MerklePath<F> {
+    pub fn depth(&self) -> usize {
+        self.siblings.len()
+    }
+
+    /// Root of the tree `leaf` is in if this is its path
+    pub fn root(&self, leaf: F) -> F {
+        let params = PoseidonParams::new();
+        self.siblings
+            .iter()
+            .zip(&self.is_right)
+            .fold(leaf, |node, (sibling, is_right)| {
+                if *is_right {
+                    params.hash_two(*sibling, node)
+                } else {
+                    params.hash_two(node, *sibling)
+                }
+            })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MerkleConfig {
+    pub poseidon: PoseidonConfig,
+    pub q_swap: Selector,
+    pub instance: Column<Instance>,
+}
+
+#[derive(Debug, Clone)]
+pub struct MerkleCircuit<F> {
+    /// Leaf and root, empty during key generation
+    pub public_input: Vec<Vec<F>>,
+    pub siblings: Vec<Value<F>>,
+    /// 1 where the node is a right child, 0 where it's a left one
+    pub positions: Vec<Value<F>>,
+}
+
+impl<F: PrimeField + FromUniformBytes<64>> MerkleCircuit<F> {
+    /// Circuit proving `leaf` is in the tree whose root `path` leads to.
+    pub fn new(leaf: F, path: &MerklePath<F>) -> Self {
+        Self {
+            public_input: vec![vec![leaf, path.root(leaf)]],
+            siblings: path.siblings.iter().copied().map(Value::known).collect(),
+            positions: path
+                .is_right
+                .iter()
+                .map(|is_right| Value::known(F::from(*is_right as u64)))
+                .collect(),
+        }
+    }
+
+    /// Circuit of a path of `depth` levels, with placeholder values for key generation
+    pub fn for_depth(depth: usize) -> Self {
+        Self::new(
+            F::ZERO,
+            &MerklePath {
+                siblings: vec![F::ZERO; depth],
+                is_right: vec![false; depth],
+            },
+        )
+    }
+}
+
+impl<F: PrimeField + FromUniformBytes<64>> Circuit<F> for MerkleCircuit<F> {
+    type Config = MerkleConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    // The depth is part of the layout, only the values are dropped
+    fn without_witnesses(&self) -> Self {
+        Self {
+            public_input: Vec::new(),
+            siblings: vec![Value::unknown(); self.siblings.len()],
+            positions: vec![Value::unknown(); self.positions.len()],
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let state = [(); WIDTH].map(|_| meta.advice_column());
+        let instance = meta.instance_column();
+        // The leaf, the hashes between levels and the root are copied through the first column
+        meta.enable_equality(state[0]);
+        meta.enable_equality(instance);
+
+        let poseidon = PoseidonChip::configure(meta, state);
+        let q_swap = meta.selector();
+
+        //
+        // state[0] | state[1] | state[2]  | q_swap
+        //   node     sibling    is_right      1
+        //   left     right      tag           0     <- input of the permutation
+        //
+        meta.create_gate("merkle swap", |meta| {
+            let q_swap = meta.query_selector(q_swap);
+            let node = meta.query_advice(state[0], Rotation::cur());
+            let sibling = meta.query_advice(state[1], Rotation::cur());
+            let is_right = meta.query_advice(state[2], Rotation::cur());
+            let left = meta.query_advice(state[0], Rotation::next());
+            let right = meta.query_advice(state[1], Rotation::next());
+            let tag = meta.query_advice(state[2], Rotation::next());
+
+            let one = Expression::Constant(F::ONE);
+            vec![
+                q_swap.clone() * is_right.clone() * (one - is_right.clone()),
+                q_swap.clone()
+                    * (left - node.clone() - is_right.clone() * (sibling.clone() - node.clone())),
+                q_swap.clone() * (right - sibling.clone() - is_right * (node - sibling)),
+                q_swap * (tag - Expression::Constant(F::from(TWO_TO_ONE_TAG))),
+            ]
+        });
+
+        MerkleConfig {
+            poseidon,
+            q_swap,
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), PlonkError> {
+        let chip = PoseidonChip::construct(config.poseidon.clone());
+        let state = config.poseidon.state;
+
+        let mut node: Option<AssignedCell<F, F>> = None;
+        for (level, (sibling, is_right)) in self.siblings.iter().zip(&self.positions).enumerate() {
+            let parent = layouter.assign_region(
+                || format!("level {}", level),
+                |mut region| {
+                    config.q_swap.enable(&mut region, 0)?;
+                    let current = match &node {
+                        Some(node) => node.copy_advice(|| "node", &mut region, state[0], 0)?,
+                        None => region.assign_advice_from_instance(
+                            || "leaf",
+                            config.instance,
+                            LEAF_ROW,
+                            state[0],
+                            0,
+                        )?,
+                    };
+                    region.assign_advice(|| "sibling", state[1], 0, || *sibling)?;
+                    region.assign_advice(|| "is right", state[2], 0, || *is_right)?;
+
+                    let input = current.value().copied().zip(*sibling).zip(*is_right).map(
+                        |((node, sibling), is_right)| {
+                            let tag = F::from(TWO_TO_ONE_TAG);
+                            if is_right == F::ONE {
+                                [sibling, node, tag]
+                            } else {
+                                [node, sibling, tag]
+                            }
+                        },
+                    );
+                    for (i, column) in state.iter().enumerate() {
+                        region.assign_advice(
+                            || "input",
+                            *column,
+                            1,
+                            || input.map(|input| input[i]),
+                        )?;
+                    }
+
+                    chip.permute(&mut region, 1, input)
+                },
+            )?;
+            node = Some(parent);
+        }
+
+        let root = node.ok_or(PlonkError::Synthesis)?;
+        layouter.constrain_instance(root.cell(), config.instance, ROOT_ROW)
+    }
+}
+
+impl<F: PrimeField + FromUniformBytes<64>> CircuitExt<F> for MerkleCircuit<F> {
+    fn rand(_: usize, _: impl RngCore) -> Self {
+        unimplemented!()
+    }
+
+    fn instances(&self) -> Vec<Vec<F>> {
+        self.public_input.clone()
+    }
+}
+
+/// Inputs of `prove` as JSON, e.g. `{"leaf": "5", "path": [{"sibling": "7", "is_right": false}]}`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MerkleInput {
+    #[serde(with = "decimal")]
+    pub leaf: Fr,
+    /// From the leaf up to the root
+    pub path: Vec<MerkleNode>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MerkleNode {
+    #[serde(with = "decimal")]
+    pub sibling: Fr,
+    #[serde(default)]
+    pub is_right: bool,
+}
+
+impl MerkleInput {
+    pub fn new(leaf: Fr, path: &MerklePath<Fr>) -> Self {
+        Self {
+            leaf,
+            path: path
+                .siblings
+                .iter()
+                .zip(&path.is_right)
+                .map(|(sibling, is_right)| MerkleNode {
+                    sibling: *sibling,
+                    is_right: *is_right,
+                })
+                .collect(),
+        }
+    }
+
+    pub fn depth(&self) -> usize {
+        self.path.len()
+    }
+
+    pub fn merkle_path(&self) -> MerklePath<Fr> {
+        MerklePath {
+            siblings: self.path.iter().map(|node| node.sibling).collect(),
+            is_right: self.path.iter().map(|node| node.is_right).collect(),
+        }
+    }
+
+    /// Root the path leads to
+    pub fn root(&self) -> Fr {
+        self.merkle_path().root(self.leaf)
+    }
+}
+
+fn check_depth(depth: usize) -> Result<(), FibonacciError> {
+    if !(1..=MAX_MERKLE_DEPTH).contains(&depth) {
+        return Err(FibonacciError::InvalidInput(
+            format!(
+                "Merkle paths must have 1 to {} levels, not {}",
+                MAX_MERKLE_DEPTH, depth
+            )
+            .into(),
+        ));
+    }
+    Ok(())
+}
+
+/// Preprocess the circuit of paths of `depth` levels into prover and verifier parameters.
+pub fn keygen<PC>(
+    param: &PC::Param,
+    depth: usize,
+) -> Result<(PC::ProverParam, PC::VerifierParam), FibonacciError>
+where
+    PC: PlonkishComponents,
+{
+    check_depth(depth)?;
+    preprocess_circuit::<PC, _>(param, merkle_k(depth), MerkleCircuit::for_depth(depth))
+}
+
+/// Prove the leaf of `input` is in the tree its path leads to, with prover parameters of
+/// `keygen` for the depth of the path. The public inputs are the leaf and root, encoded like
+/// those of the Fibonacci circuit.
+pub fn prove<PC>(
+    prover_param: &PC::ProverParam,
+    input: &MerkleInput,
+    binding: TranscriptBinding,
+) -> Result<GenerateProofResult, Box<dyn Error>>
+where
+    PC: PlonkishComponents,
+    ProofTranscript: TranscriptWrite<CommitmentChunk<Fr, PC::Pcs>, Fr>,
+{
+    check_depth(input.depth())?;
+    let circuit = MerkleCircuit::new(input.leaf, &input.merkle_path());
+
+    let (proof, instances) = generate_halo2_proof_for_circuit::<PC, _>(
+        prover_param,
+        merkle_k(input.depth()),
+        circuit,
+        binding,
+    )?;
+    Ok((proof, encode_public_inputs(&instances[0])))
+}
+
+/// Verify a proof of `prove`, returning whether its leaf is in the tree of its root.
+pub fn verify<PC>(
+    verifier_param: &PC::VerifierParam,
+    proof: &[u8],
+    public_inputs: &[u8],
+    binding: TranscriptBinding,
+) -> Result<bool, Box<dyn Error>>
+where
+    PC: PlonkishComponents,
+    ProofTranscript: TranscriptRead<CommitmentChunk<Fr, PC::Pcs>, Fr>,
+{
+    let inputs = decode_public_inputs(public_inputs)?;
+    if inputs.len() != 2 {
+        return Err(FibonacciError::InvalidInput(
+            format!(
+                "Expected a leaf and a root as public inputs, found {} inputs",
+                inputs.len()
+            )
+            .into(),
+        )
+        .into());
+    }
+
+    match verify_halo2_proof_with_vk::<PC>(verifier_param, proof, inputs, binding) {
+        Err(FibonacciError::Verification(_)) => Ok(false),
+        result => Ok(result?),
+    }
+}
+
+/// Hash of the constraint system of paths of `depth` levels, recorded in the headers of keys
+/// of `keygen_from_bytes`.
+pub fn merkle_circuit_hash<PC>(depth: usize) -> Result<io::CircuitHash, FibonacciError>
+where
+    PC: PlonkishComponents,
+{
+    check_depth(depth)?;
+    circuit_hash_of::<PC, _>("merkle", merkle_k(depth), depth, || {
+        MerkleCircuit::<Fr>::for_depth(depth)
+    })
+}
+
+/// Like `keygen`, from the SRS contents and encoding the keys with the circuit hash.
+pub fn keygen_from_bytes<PC>(
+    srs_key: &[u8],
+    depth: usize,
+) -> Result<(Vec<u8>, Vec<u8>), Box<dyn Error>>
+where
+    PC: PlonkishComponents,
+{
+    let param = io::parse_srs_bytes::<PC>(srs_key)?;
+    let (prover_param, verifier_param) = keygen::<PC>(&param, depth)?;
+
+    let circuit_hash = merkle_circuit_hash::<PC>(depth)?;
+    Ok((
+        io::encode_artifact_for_circuit(&prover_param, Codec::Bincode, circuit_hash)?,
+        io::encode_artifact_for_circuit(&verifier_param, Codec::Bincode, circuit_hash)?,
+    ))
+}
+
+/// Like `prove`, with a proving key of `keygen_from_bytes` for the depth of the path.
+pub fn prove_from_bytes<PC>(
+    proving_key: &[u8],
+    input: &MerkleInput,
+    binding: TranscriptBinding,
+) -> Result<GenerateProofResult, Box<dyn Error>>
+where
+    PC: PlonkishComponents,
+    ProofTranscript: TranscriptWrite<CommitmentChunk<Fr, PC::Pcs>, Fr>,
+{
+    let prover_param: PC::ProverParam =
+        io::decode_artifact_for_circuit(proving_key, &merkle_circuit_hash::<PC>(input.depth())?)?;
+    prove::<PC>(&prover_param, input, binding)
+}
+
+/// Like `verify`, with a verifying key of `keygen_from_bytes` for paths of `depth` levels.
+pub fn verify_from_bytes<PC>(
+    verifying_key: &[u8],
+    proof: &[u8],
+    public_inputs: &[u8],
+    depth: usize,
+    binding: TranscriptBinding,
+) -> Result<bool, Box<dyn Error>>
+where
+    PC: PlonkishComponents,
+    ProofTranscript: TranscriptRead<CommitmentChunk<Fr, PC::Pcs>, Fr>,
+{
+    let verifier_param: PC::VerifierParam =
+        io::decode_artifact_for_circuit(verifying_key, &merkle_circuit_hash::<PC>(depth)?)?;
+    verify::<PC>(&verifier_param, proof, public_inputs, binding)
+}
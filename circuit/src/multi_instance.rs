@@ -0,0 +1,100 @@
+//! Variant of the Fibonacci circuit with separate instance columns for its inputs and outputs.
+//!
+//! The tutorial circuit packs `a`, `b` and `out` into the rows of a single instance column, so
+//! callers have to know which row holds what. Circuits which grow more public values are easier
+//! to use when each group gets its own column: here column `INPUT_COLUMN` holds the starting
+//! values and column `OUTPUT_COLUMN` the result. Public inputs are then a `Vec<Vec<F>>` with one
+//! vector per column, serialized with `InstanceColumnsSerialisationWrapper`. Keys come from
+//! `preprocess_circuit`, proofs from `prove_instances` and are checked with
+//! `verify_instances_with_vk`.
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner},
+    halo2curves::ff::Field,
+    plonk::{Circuit, Column, ConstraintSystem, Error, Instance},
+};
+use plonkish_backend::frontend::halo2::CircuitExt;
+use rand::RngCore;
+
+use crate::circuit::{fibonacci, FibonacciChip, FibonacciConfig, DEFAULT_STEPS};
+
+/// Instance column holding the starting values F(0) and F(1)
+pub const INPUT_COLUMN: usize = 0;
+/// Instance column holding the output F(n)
+pub const OUTPUT_COLUMN: usize = 1;
+
+#[derive(Debug, Clone)]
+pub struct MultiInstanceConfig {
+    /// The tutorial circuit, whose instance column is the input column
+    pub fibonacci: FibonacciConfig,
+    pub output: Column<Instance>,
+}
+
+#[derive(Debug, Clone)]
+pub struct MultiInstanceCircuit<F> {
+    /// Public inputs per instance column, empty during key generation
+    pub public_input: Vec<Vec<F>>,
+    /// Index n of the output F(n)
+    pub steps: usize,
+}
+
+impl<F> Default for MultiInstanceCircuit<F> {
+    fn default() -> Self {
+        Self {
+            public_input: Vec::new(),
+            steps: DEFAULT_STEPS,
+        }
+    }
+}
+
+impl<F: Field> MultiInstanceCircuit<F> {
+    /// Circuit proving F(`steps`) of the sequence starting with `a` and `b`.
+    pub fn new(a: F, b: F, steps: usize) -> Self {
+        Self {
+            public_input: vec![vec![a, b], vec![fibonacci(a, b, steps)]],
+            steps,
+        }
+    }
+}
+
+impl<F: Field> Circuit<F> for MultiInstanceCircuit<F> {
+    type Config = MultiInstanceConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            public_input: Vec::new(),
+            steps: self.steps,
+        }
+    }
+
+    // The chip allocates the input column first, so column indices follow `INPUT_COLUMN` and
+    // `OUTPUT_COLUMN`
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let fibonacci = FibonacciChip::configure(meta);
+        let output = meta.instance_column();
+        meta.enable_equality(output);
+
+        MultiInstanceConfig { fibonacci, output }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = FibonacciChip::construct(config.fibonacci);
+
+        let out = chip.assign_sequence(layouter.namespace(|| "sequence"), self.steps)?;
+        layouter.constrain_instance(out.cell(), config.output, 0)
+    }
+}
+
+impl<F: Field> CircuitExt<F> for MultiInstanceCircuit<F> {
+    fn rand(_: usize, _: impl RngCore) -> Self {
+        unimplemented!()
+    }
+
+    fn instances(&self) -> Vec<Vec<F>> {
+        self.public_input.clone()
+    }
+}
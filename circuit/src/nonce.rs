@@ -0,0 +1,241 @@
+//! Variant of the Fibonacci circuit binding a caller-supplied nonce, for replay protection.
+//!
+//! A transcript binding ties a proof to a context both sides agree on, but the context isn't
+//! part of the statement. `NonceCircuit` adds the nonce to the public inputs instead, as row
+//! `INSTANCE_NONCE_ROW` of the instance column, copied into an advice cell so the proof commits
+//! to it. A verifier issuing a fresh nonce per request (or accepting recent timestamps) then
+//! rejects proofs made for another request with `verify_with_nonce`.
+//!
+//! Keys of the variant are generated with `keygen_with_nonce`, and don't verify proofs of the
+//! tutorial circuit or the other way around.
+use std::{collections::HashMap, error::Error};
+
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner},
+    halo2curves::ff::{Field, FromUniformBytes},
+    plonk::{Circuit, ConstraintSystem, Error as PlonkError},
+};
+use plonkish_backend::{
+    frontend::halo2::CircuitExt,
+    halo2_curves::bn256::Fr,
+    pcs::CommitmentChunk,
+    util::transcript::{TranscriptRead, TranscriptWrite},
+};
+use rand::RngCore;
+use sha3::{Digest, Keccak256};
+
+use crate::{
+    circuit::{
+        fibonacci, CircuitShape, FibonacciChip, FibonacciCircuit, FibonacciConfig,
+        TranscriptBinding, INSTANCE_OUT_ROW, NUM_PUBLIC_INPUTS,
+    },
+    circuit_hash_of, io, keygen_variant, prove_variant, requested_steps,
+    serialisation::{decode_public_inputs, deserialize_circuit_inputs},
+    verify_variant, FibonacciError, GenerateProofResult, PlonkishComponents, ProofTranscript,
+};
+
+/// Row of the instance column holding the nonce, after `a`, `b` and `out`
+pub const INSTANCE_NONCE_ROW: usize = NUM_PUBLIC_INPUTS;
+
+#[derive(Debug, Clone)]
+pub struct NonceCircuit<F> {
+    /// `a`, `b`, `out` and the nonce, empty during key generation
+    pub public_input: Vec<Vec<F>>,
+    /// Index n of the output F(n)
+    pub steps: usize,
+}
+
+impl<F: Field> NonceCircuit<F> {
+    /// The Fibonacci `circuit`, additionally binding `nonce`.
+    pub fn new(circuit: FibonacciCircuit<F>, nonce: F) -> Self {
+        let mut public_input = circuit.public_input;
+        if let Some(column) = public_input.first_mut() {
+            column.push(nonce);
+        }
+        Self {
+            public_input,
+            steps: circuit.steps,
+        }
+    }
+
+    /// The nonce the circuit binds
+    pub fn nonce(&self) -> Option<F> {
+        self.public_input
+            .first()
+            .and_then(|column| column.get(INSTANCE_NONCE_ROW))
+            .copied()
+    }
+}
+
+impl<F: Field> Circuit<F> for NonceCircuit<F> {
+    type Config = FibonacciConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            public_input: Vec::new(),
+            steps: self.steps,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        FibonacciChip::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), PlonkError> {
+        let chip = FibonacciChip::construct(config.clone());
+
+        let out = chip.assign_sequence(layouter.namespace(|| "sequence"), self.steps)?;
+        chip.expose_public(layouter.namespace(|| "out"), &out, INSTANCE_OUT_ROW)?;
+
+        // An instance row without a copy constraint isn't part of the permutation argument
+        layouter.assign_region(
+            || "nonce",
+            |mut region| {
+                region.assign_advice_from_instance(
+                    || "nonce",
+                    config.instance,
+                    INSTANCE_NONCE_ROW,
+                    config.col_a,
+                    0,
+                )?;
+                Ok(())
+            },
+        )
+    }
+}
+
+impl<F: Field> CircuitExt<F> for NonceCircuit<F> {
+    fn rand(_: usize, _: impl RngCore) -> Self {
+        unimplemented!()
+    }
+
+    fn instances(&self) -> Vec<Vec<F>> {
+        self.public_input.clone()
+    }
+}
+
+/// Nonce of an arbitrary byte string, e.g. a request id, by hashing it to a field element.
+/// Timestamps and counters can be used directly with `Fr::from`.
+pub fn nonce_from_bytes(bytes: &[u8]) -> Fr {
+    let mut uniform = [0u8; 64];
+    uniform[..32].copy_from_slice(&Keccak256::digest(bytes));
+    Fr::from_uniform_bytes(&uniform)
+}
+
+/// Hash of the nonce variant's constraint system, recorded in the headers of its keys.
+pub fn nonce_circuit_hash<PC>() -> Result<io::CircuitHash, FibonacciError>
+where
+    PC: PlonkishComponents,
+{
+    let shape = CircuitShape::default();
+    circuit_hash_of::<PC, _>("nonce", shape.k, shape.steps, || keygen_circuit(shape))
+}
+
+/// Generate the proving and verifying keys of the nonce variant from the SRS contents.
+pub fn keygen_with_nonce<PC>(srs_key: &[u8]) -> Result<(Vec<u8>, Vec<u8>), Box<dyn Error>>
+where
+    PC: PlonkishComponents,
+{
+    let shape = CircuitShape::default();
+    keygen_variant::<PC, _>(
+        srs_key,
+        shape.k,
+        keygen_circuit(shape),
+        nonce_circuit_hash::<PC>()?,
+    )
+}
+
+/// Like `prove_from_bytes`, with a proving key of `keygen_with_nonce`, binding `nonce` in the
+/// public inputs.
+pub fn prove_with_nonce<PC>(
+    srs_key: &[u8],
+    proving_key: &[u8],
+    input: HashMap<String, Vec<String>>,
+    nonce: Fr,
+    binding: TranscriptBinding,
+) -> Result<GenerateProofResult, Box<dyn Error>>
+where
+    PC: PlonkishComponents,
+    ProofTranscript: TranscriptWrite<CommitmentChunk<Fr, PC::Pcs>, Fr>,
+{
+    let shape = CircuitShape::default();
+    let steps = requested_steps(&input)?;
+    if steps != shape.steps {
+        return Err(FibonacciError::InvalidInput(
+            format!(
+                "Requested {} steps, but the proving key is for {} steps",
+                steps, shape.steps
+            )
+            .into(),
+        )
+        .into());
+    }
+    let circuit = NonceCircuit::new(
+        FibonacciCircuit::from_inputs(&deserialize_circuit_inputs(input)?, steps)?,
+        nonce,
+    );
+
+    prove_variant::<PC, _>(
+        srs_key,
+        proving_key,
+        shape.k,
+        circuit,
+        &nonce_circuit_hash::<PC>()?,
+        binding,
+    )
+}
+
+/// Verify a proof of `prove_with_nonce` with its verifying key. Proofs binding another nonce
+/// are invalid, so the verifier must supply the nonce it issued for the request.
+pub fn verify_with_nonce<PC>(
+    verifying_key: &[u8],
+    proof: &[u8],
+    public_inputs: &[u8],
+    nonce: Fr,
+    binding: TranscriptBinding,
+) -> Result<bool, Box<dyn Error>>
+where
+    PC: PlonkishComponents,
+    ProofTranscript: TranscriptRead<CommitmentChunk<Fr, PC::Pcs>, Fr>,
+{
+    let inputs = decode_public_inputs(public_inputs)?;
+    if inputs.len() != NUM_PUBLIC_INPUTS + 1 {
+        return Err(FibonacciError::InvalidInput(
+            format!(
+                "Expected {} public inputs including the nonce, found {}",
+                NUM_PUBLIC_INPUTS + 1,
+                inputs.len()
+            )
+            .into(),
+        )
+        .into());
+    }
+    if inputs[INSTANCE_NONCE_ROW] != nonce {
+        return Ok(false);
+    }
+
+    verify_variant::<PC>(
+        verifying_key,
+        proof,
+        inputs,
+        &nonce_circuit_hash::<PC>()?,
+        binding,
+    )
+}
+
+/// Circuit of `shape` with placeholder public inputs, which only fix the instance column length
+fn keygen_circuit(shape: CircuitShape) -> NonceCircuit<Fr> {
+    let (a, b) = (Fr::from(1), Fr::from(1));
+    NonceCircuit::new(
+        FibonacciCircuit {
+            public_input: vec![vec![a, b, fibonacci(a, b, shape.steps)]],
+            steps: shape.steps,
+        },
+        Fr::ZERO,
+    )
+}
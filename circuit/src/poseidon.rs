@@ -0,0 +1,221 @@
+//! Poseidon permutation over a state of 3 field elements, natively and as a chip.
+//!
+//! The chip lays the permutation out one round per row: the state in 3 advice columns and the
+//! round constants in 3 fixed columns, with one gate for full rounds (every element through the
+//! x^5 S-box) and one for partial rounds (only the first element), both followed by the MDS
+//! matrix. A permutation takes `ROUNDS + 1` rows, the input state and the state after each round.
+//!
+//! The round constants are derived from Keccak256 and the MDS matrix is a Cauchy matrix, with the
+//! round numbers recommended for x^5 over the BN254 scalar field. It's an example of how a hash
+//! gadget is built, not a standardized instance: hashes don't match other Poseidon libraries.
+use std::array;
+
+use halo2_proofs::{
+    circuit::{AssignedCell, Region, Value},
+    halo2curves::ff::{FromUniformBytes, PrimeField},
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Fixed, Selector},
+    poly::Rotation,
+};
+use sha3::{Digest, Keccak256};
+
+/// Elements of the state, 2 absorbed per permutation and 1 of capacity
+pub const WIDTH: usize = 3;
+pub const FULL_ROUNDS: usize = 8;
+pub const PARTIAL_ROUNDS: usize = 57;
+pub const ROUNDS: usize = FULL_ROUNDS + PARTIAL_ROUNDS;
+
+/// Capacity element of the state when hashing two elements into one
+pub const TWO_TO_ONE_TAG: u64 = 2;
+
+const ROUND_CONSTANTS_SEED: &[u8] = b"fibonacci-poseidon-round-constants";
+
+/// Round constants and MDS matrix of the permutation
+#[derive(Debug, Clone)]
+pub struct PoseidonParams<F> {
+    pub round_constants: Vec<[F; WIDTH]>,
+    pub mds: [[F; WIDTH]; WIDTH],
+}
+
+impl<F: PrimeField + FromUniformBytes<64>> Default for PoseidonParams<F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<F: PrimeField + FromUniformBytes<64>> PoseidonParams<F> {
+    pub fn new() -> Self {
+        let round_constants = (0..ROUNDS)
+            .map(|round| array::from_fn(|i| round_constant(round, i)))
+            .collect();
+        // Cauchy matrix 1 / (x_i + y_j) with x_i = i and y_j = WIDTH + j, which is MDS
+        let mds = array::from_fn(|i| {
+            array::from_fn(|j| F::from((i + j + WIDTH) as u64).invert().unwrap())
+        });
+
+        Self {
+            round_constants,
+            mds,
+        }
+    }
+
+    pub fn permute(&self, state: [F; WIDTH]) -> [F; WIDTH] {
+        (0..ROUNDS).fold(state, |state, round| self.round(round, state))
+    }
+
+    /// Hash of two elements, the first element of the permuted state `[left, right, tag]`
+    pub fn hash_two(&self, left: F, right: F) -> F {
+        self.permute([left, right, F::from(TWO_TO_ONE_TAG)])[0]
+    }
+
+    fn round(&self, round: usize, state: [F; WIDTH]) -> [F; WIDTH] {
+        let sboxed: [F; WIDTH] = array::from_fn(|i| {
+            let x = state[i] + self.round_constants[round][i];
+            if i == 0 || is_full_round(round) {
+                x.square().square() * x
+            } else {
+                x
+            }
+        });
+        array::from_fn(|i| (0..WIDTH).map(|j| self.mds[i][j] * sboxed[j]).sum())
+    }
+}
+
+fn round_constant<F: FromUniformBytes<64>>(round: usize, i: usize) -> F {
+    let mut uniform = [0u8; 64];
+    uniform[..32].copy_from_slice(
+        &Keccak256::new()
+            .chain_update(ROUND_CONSTANTS_SEED)
+            .chain_update((round as u64).to_le_bytes())
+            .chain_update((i as u64).to_le_bytes())
+            .finalize(),
+    );
+    F::from_uniform_bytes(&uniform)
+}
+
+// Half of the full rounds come before the partial rounds, half after
+fn is_full_round(round: usize) -> bool {
+    round < FULL_ROUNDS / 2 || round >= FULL_ROUNDS / 2 + PARTIAL_ROUNDS
+}
+
+#[derive(Debug, Clone)]
+pub struct PoseidonConfig {
+    pub state: [Column<Advice>; WIDTH],
+    pub round_constants: [Column<Fixed>; WIDTH],
+    pub q_full: Selector,
+    pub q_partial: Selector,
+}
+
+#[derive(Debug, Clone)]
+pub struct PoseidonChip<F> {
+    config: PoseidonConfig,
+    params: PoseidonParams<F>,
+}
+
+impl<F: PrimeField + FromUniformBytes<64>> PoseidonChip<F> {
+    pub fn construct(config: PoseidonConfig) -> Self {
+        Self {
+            config,
+            params: PoseidonParams::new(),
+        }
+    }
+
+    /// Configure the round gates over the `state` columns, which callers can use in rows
+    /// outside of permutations, e.g. to lay out the input state.
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        state: [Column<Advice>; WIDTH],
+    ) -> PoseidonConfig {
+        let round_constants = array::from_fn(|_| meta.fixed_column());
+        let q_full = meta.selector();
+        let q_partial = meta.selector();
+        let mds = PoseidonParams::<F>::new().mds;
+
+        // next_i = sum_j mds_ij * sbox_j(state_j + rc_j)
+        let round_gate =
+            |meta: &mut ConstraintSystem<F>, name: &'static str, selector: Selector, full: bool| {
+                meta.create_gate(name, |meta| {
+                    let selector = meta.query_selector(selector);
+                    let sboxed: Vec<Expression<F>> = (0..WIDTH)
+                        .map(|j| {
+                            let x = meta.query_advice(state[j], Rotation::cur())
+                                + meta.query_fixed(round_constants[j], Rotation::cur());
+                            if j == 0 || full {
+                                let x2 = x.clone() * x.clone();
+                                x2.clone() * x2 * x
+                            } else {
+                                x
+                            }
+                        })
+                        .collect();
+
+                    (0..WIDTH)
+                        .map(|i| {
+                            let mixed = (0..WIDTH)
+                                .map(|j| Expression::Constant(mds[i][j]) * sboxed[j].clone())
+                                .reduce(|sum, term| sum + term)
+                                .unwrap();
+                            selector.clone()
+                                * (mixed - meta.query_advice(state[i], Rotation::next()))
+                        })
+                        .collect::<Vec<_>>()
+                });
+            };
+        round_gate(meta, "poseidon full round", q_full, true);
+        round_gate(meta, "poseidon partial round", q_partial, false);
+
+        PoseidonConfig {
+            state,
+            round_constants,
+            q_full,
+            q_partial,
+        }
+    }
+
+    pub fn params(&self) -> &PoseidonParams<F> {
+        &self.params
+    }
+
+    /// Permute the state the caller assigned at row `offset` of `region`, filling the next
+    /// `ROUNDS` rows, and return the cell of the first element of the permuted state.
+    pub fn permute(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        input: Value<[F; WIDTH]>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let config = &self.config;
+        let mut state = input;
+        let mut output = None;
+        for round in 0..ROUNDS {
+            let row = offset + round;
+            if is_full_round(round) {
+                config.q_full.enable(region, row)?;
+            } else {
+                config.q_partial.enable(region, row)?;
+            }
+            for i in 0..WIDTH {
+                region.assign_fixed(
+                    || "round constant",
+                    config.round_constants[i],
+                    row,
+                    || Value::known(self.params.round_constants[round][i]),
+                )?;
+            }
+
+            state = state.map(|state| self.params.round(round, state));
+            let cells = (0..WIDTH)
+                .map(|i| {
+                    region.assign_advice(
+                        || "state",
+                        config.state[i],
+                        row + 1,
+                        || state.map(|state| state[i]),
+                    )
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            output = cells.into_iter().next();
+        }
+
+        Ok(output.expect("the permutation has rounds"))
+    }
+}
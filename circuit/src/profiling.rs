@@ -0,0 +1,55 @@
+//! Profiling of proof generation, enabled with the `profiling` feature.
+//!
+//! Proving phases are wrapped in puffin scopes with `profile_scope!`, which compiles to nothing
+//! without the feature. `Flamegraph` samples the whole process with pprof and writes the result
+//! as an SVG flamegraph.
+use std::{fs::File, path::Path};
+
+pub use puffin;
+
+use crate::FibonacciError;
+
+/// Samples the process from `start` until dropped.
+pub struct Flamegraph(pprof::ProfilerGuard<'static>);
+
+impl Flamegraph {
+    /// Start sampling at `frequency` Hz and turn on the puffin scopes.
+    pub fn start(frequency: i32) -> Result<Self, FibonacciError> {
+        puffin::set_scopes_on(true);
+        pprof::ProfilerGuardBuilder::default()
+            .frequency(frequency)
+            .blocklist(&["libc", "libgcc", "pthread", "vdso"])
+            .build()
+            .map(Self)
+            .map_err(|e| {
+                FibonacciError::Profiling(format!("Failed to start profiler: {}", e).into())
+            })
+    }
+
+    /// Write the samples collected so far as a flamegraph SVG.
+    pub fn write_svg(&self, path: &Path) -> Result<(), FibonacciError> {
+        let report = self.0.report().build().map_err(|e| {
+            FibonacciError::Profiling(format!("Failed to build profile report: {}", e).into())
+        })?;
+        let file = File::create(path).map_err(|e| {
+            FibonacciError::Io(format!("Failed to create '{}': {}", path.display(), e).into())
+        })?;
+        report.flamegraph(file).map_err(|e| {
+            FibonacciError::Profiling(format!("Failed to write flamegraph: {}", e).into())
+        })
+    }
+}
+
+/// Close the current puffin frame, so the scopes recorded since the last call show up in
+/// `puffin_viewer` as one frame.
+pub fn finish_frame() {
+    puffin::GlobalProfiler::lock().new_frame();
+}
+
+/// Open a puffin scope until the end of the enclosing block.
+#[macro_export]
+macro_rules! profile_scope {
+    ($name:expr) => {
+        $crate::profiling::puffin::profile_scope!($name);
+    };
+}
@@ -0,0 +1,155 @@
+//! Prost types for `proto/fibonacci.proto`.
+//!
+//! The types are kept in sync with the schema by hand, in the shape `prost-build` generates,
+//! so building the crate doesn't require `protoc`.
+use halo2_proofs::halo2curves::bn256::Fr;
+
+use crate::{
+    bundle,
+    serialisation::{decode_public_inputs, encode_public_inputs},
+    FibonacciError,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum BackendId {
+    Unspecified = 0,
+    Plonk = 1,
+    Hyperplonk = 2,
+    Gemini = 3,
+    Unihyperplonk = 4,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PublicInputs {
+    #[prost(bytes = "vec", repeated, tag = "1")]
+    pub values: Vec<Vec<u8>>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ProofBundle {
+    #[prost(enumeration = "BackendId", tag = "1")]
+    pub backend: i32,
+    #[prost(bytes = "vec", tag = "2")]
+    pub proof: Vec<u8>,
+    #[prost(message, optional, tag = "3")]
+    pub public_inputs: Option<PublicInputs>,
+}
+
+impl From<Option<bundle::BackendId>> for BackendId {
+    fn from(backend: Option<bundle::BackendId>) -> Self {
+        match backend {
+            None => BackendId::Unspecified,
+            Some(bundle::BackendId::Plonk) => BackendId::Plonk,
+            Some(bundle::BackendId::HyperPlonk) => BackendId::Hyperplonk,
+            Some(bundle::BackendId::Gemini) => BackendId::Gemini,
+            Some(bundle::BackendId::UniHyperPlonk) => BackendId::Unihyperplonk,
+        }
+    }
+}
+
+impl From<BackendId> for Option<bundle::BackendId> {
+    fn from(backend: BackendId) -> Self {
+        match backend {
+            BackendId::Unspecified => None,
+            BackendId::Plonk => Some(bundle::BackendId::Plonk),
+            BackendId::Hyperplonk => Some(bundle::BackendId::HyperPlonk),
+            BackendId::Gemini => Some(bundle::BackendId::Gemini),
+            BackendId::Unihyperplonk => Some(bundle::BackendId::UniHyperPlonk),
+        }
+    }
+}
+
+impl From<&[Fr]> for PublicInputs {
+    fn from(inputs: &[Fr]) -> Self {
+        Self {
+            values: inputs.iter().map(|fe| fe.to_bytes().to_vec()).collect(),
+        }
+    }
+}
+
+impl TryFrom<&PublicInputs> for Vec<Fr> {
+    type Error = FibonacciError;
+
+    fn try_from(inputs: &PublicInputs) -> Result<Self, Self::Error> {
+        inputs
+            .values
+            .iter()
+            .map(|value| {
+                let bytes: [u8; 32] = value.as_slice().try_into().map_err(|_| {
+                    FibonacciError::InvalidInput(
+                        format!("Public input must be 32 bytes, got {}", value.len()).into(),
+                    )
+                })?;
+                Option::<Fr>::from(Fr::from_bytes(&bytes)).ok_or_else(|| {
+                    FibonacciError::InvalidInput(
+                        "Public input is not a canonical field element".into(),
+                    )
+                })
+            })
+            .collect()
+    }
+}
+
+impl TryFrom<&bundle::ProofBundle> for ProofBundle {
+    type Error = FibonacciError;
+
+    fn try_from(bundle: &bundle::ProofBundle) -> Result<Self, Self::Error> {
+        let inputs = decode_public_inputs(&bundle.public_inputs)?;
+        Ok(Self {
+            backend: BackendId::from(bundle.backend) as i32,
+            proof: bundle.proof.clone(),
+            public_inputs: Some(PublicInputs::from(inputs.as_slice())),
+        })
+    }
+}
+
+impl TryFrom<&ProofBundle> for bundle::ProofBundle {
+    type Error = FibonacciError;
+
+    fn try_from(message: &ProofBundle) -> Result<Self, Self::Error> {
+        let backend = BackendId::try_from(message.backend).map_err(|e| {
+            FibonacciError::InvalidInput(format!("Unknown backend id: {}", e).into())
+        })?;
+        let inputs: Vec<Fr> = match &message.public_inputs {
+            Some(inputs) => inputs.try_into()?,
+            None => Vec::new(),
+        };
+        let public_inputs = encode_public_inputs(&inputs);
+
+        Ok(Self {
+            backend: backend.into(),
+            proof: message.proof.clone(),
+            public_inputs,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use prost::Message;
+
+    use super::*;
+
+    #[test]
+    fn test_proto_roundtrip() {
+        let public_inputs = encode_public_inputs(&[Fr::from(1), Fr::from(55)]);
+        let bundle = bundle::ProofBundle::new(vec![7, 8, 9], public_inputs)
+            .with_backend(bundle::BackendId::HyperPlonk);
+
+        let message = ProofBundle::try_from(&bundle).unwrap();
+        assert_eq!(message.backend, BackendId::Hyperplonk as i32);
+        assert_eq!(message.public_inputs.as_ref().unwrap().values.len(), 2);
+
+        let decoded = ProofBundle::decode(message.encode_to_vec().as_slice()).unwrap();
+        assert_eq!(bundle::ProofBundle::try_from(&decoded).unwrap(), bundle);
+
+        let invalid = ProofBundle {
+            public_inputs: Some(PublicInputs {
+                values: vec![vec![0; 31]],
+            }),
+            ..decoded
+        };
+        assert!(bundle::ProofBundle::try_from(&invalid).is_err());
+    }
+}
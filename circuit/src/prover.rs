@@ -0,0 +1,453 @@
+//! Prover keeping its proving key across proofs and the witness of recently proven inputs.
+//!
+//! Synthesizing the circuit, laying out its witness columns, only depends on the input, not on
+//! the randomness of the proof. When the same input is proven again, e.g. on a retry or in a
+//! benchmark, `ProverContext` reuses the synthesized circuit and only runs the commitments and
+//! openings of the backend. Callers which know they'll prove a witness several times, e.g. with
+//! different transcript bindings, can prepare it once with `ProverContext::prepare` and prove the
+//! `PreparedCircuit` with `prove_prepared`.
+//!
+//! The first proof of a process is slower than the following ones, e.g. it starts the thread pool
+//! and grows the heap to the working set of a proof. The backends keep no FFT or MSM tables which
+//! could be precomputed instead: the MSM bases are part of the proving key, computed by the
+//! preprocessing, and the FFT twiddles are computed within each proof. `warm_up` therefore only
+//! runs a throwaway proof ahead of time, so servers and apps can pay for it at startup.
+use std::{
+    collections::{HashMap, VecDeque},
+    error::Error,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use plonkish_backend::{
+    backend::{PlonkishCircuit, PlonkishCircuitInfo},
+    frontend::halo2::Halo2Circuit,
+    halo2_curves::bn256::Fr,
+    pcs::CommitmentChunk,
+    util::transcript::TranscriptWrite,
+};
+use sha3::{Digest, Keccak256};
+
+use crate::{
+    circuit::{
+        build_halo2_circuit, prove_synthesized_circuit, CircuitConfig, CircuitShape,
+        FibonacciCircuit, TranscriptBinding,
+    },
+    decode_key, io, requested_steps,
+    serialisation::{deserialize_circuit_inputs, encode_public_inputs},
+    telemetry, BackendError, BackendId, ErrorMessage, FibonacciError, GenerateProofResult,
+    PlonkishComponents, ProofTranscript,
+};
+
+/// Inputs whose witness `ProverContext` keeps by default. Each holds every witness column, so
+/// the default stays small, servers proving many repeated inputs can raise it.
+pub const DEFAULT_WITNESS_CACHE_CAPACITY: usize = 4;
+
+/// Keccak256 of the steps, domain size and public inputs of a proof
+type WitnessKey = [u8; 32];
+
+/// Witness columns of a synthesis round and the challenges it was given
+type RoundWitness = (usize, Vec<Fr>, Vec<Vec<Fr>>);
+
+/// Prover for one circuit shape, caching the synthesized witness of the last inputs it proved.
+pub struct ProverContext<PC: PlonkishComponents> {
+    prover_param: PC::ProverParam,
+    shape: CircuitShape,
+    cache: Mutex<WitnessCache>,
+    warm: AtomicBool,
+}
+
+/// Witness cache counters, see `ProverContext::cache_stats`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WitnessCacheStats {
+    /// Proofs which reused a cached witness
+    pub hits: u64,
+    /// Proofs which synthesized their witness
+    pub misses: u64,
+    /// Inputs currently cached
+    pub entries: usize,
+}
+
+impl<PC> ProverContext<PC>
+where
+    PC: PlonkishComponents,
+    ProofTranscript: TranscriptWrite<CommitmentChunk<Fr, PC::Pcs>, Fr>,
+{
+    /// Prover with the proving key of `shape`, checking `srs` can commit to circuits of its size.
+    pub fn new(
+        srs: &PC::Param,
+        prover_param: PC::ProverParam,
+        shape: CircuitShape,
+    ) -> Result<Self, FibonacciError> {
+        shape.validate()?;
+        io::check_srs_size::<PC>(srs, shape.k)?;
+
+        Ok(Self {
+            prover_param,
+            shape,
+            cache: Mutex::new(WitnessCache::new(DEFAULT_WITNESS_CACHE_CAPACITY)),
+            warm: AtomicBool::new(false),
+        })
+    }
+
+    /// Like `new`, taking the SRS and proving key contents as `prove_from_bytes_with_shape` does.
+    pub fn from_bytes(
+        srs_key: &[u8],
+        proving_key: &[u8],
+        shape: CircuitShape,
+    ) -> Result<Self, FibonacciError> {
+        let srs = io::parse_srs_bytes::<PC>(srs_key)?;
+        let prover_param = decode_key::<PC, PC::ProverParam>(proving_key, shape)?;
+        Self::new(&srs, prover_param, shape)
+    }
+
+    /// Keep the witness of up to `capacity` inputs, dropping the least recently proven ones
+    /// first. A capacity of 0 disables the cache.
+    pub fn with_cache_capacity(self, capacity: usize) -> Self {
+        self.cache.lock().unwrap().resize(capacity);
+        self
+    }
+
+    pub fn shape(&self) -> CircuitShape {
+        self.shape
+    }
+
+    /// Prove and drop a proof of the sequence starting with 1 and 1, so the slower first proof of
+    /// the process isn't a caller's. It precomputes nothing, the backends have no tables to
+    /// prepare ahead of a proof, see the module docs. The proof isn't cached nor counted in
+    /// telemetry. Later calls return immediately.
+    pub fn warm_up(&self) -> Result<(), FibonacciError> {
+        if self.is_warm() {
+            return Ok(());
+        }
+        crate::profile_scope!("warm_up");
+
+        let circuit = CircuitConfig::new(self.shape).circuit()?;
+        let prepared = PreparedCircuit::new::<PC>(circuit, self.shape)?;
+        self.prove_circuit(&prepared, TranscriptBinding::default())
+            .map_err(|e| match e.downcast::<FibonacciError>() {
+                Ok(e) => *e,
+                Err(e) => FibonacciError::Proving(e.to_string().into()),
+            })?;
+        self.warm.store(true, Ordering::Release);
+        Ok(())
+    }
+
+    /// Whether `warm_up` ran
+    pub fn is_warm(&self) -> bool {
+        self.warm.load(Ordering::Acquire)
+    }
+
+    /// Prove `input` as `prove_from_bytes_with_shape` does, reusing the witness of an earlier
+    /// proof of the same input.
+    pub fn prove(
+        &self,
+        input: HashMap<String, Vec<String>>,
+        binding: TranscriptBinding,
+    ) -> Result<GenerateProofResult, Box<dyn Error>> {
+        let timer = telemetry::Timer::start();
+        let result = self.prove_input(input, binding);
+        telemetry::record_proof_result(PC::BACKEND, timer, &result);
+        result
+    }
+
+    /// Parse `input` and synthesize its witness for proving with `prove_prepared`, any number of
+    /// times. Bypasses the witness cache.
+    pub fn prepare(
+        &self,
+        input: HashMap<String, Vec<String>>,
+    ) -> Result<PreparedCircuit, FibonacciError> {
+        PreparedCircuit::new::<PC>(self.parse_input(input)?, self.shape)
+    }
+
+    /// Prove a circuit prepared for this prover's backend and shape, without synthesizing it.
+    pub fn prove_prepared(
+        &self,
+        prepared: &PreparedCircuit,
+        binding: TranscriptBinding,
+    ) -> Result<GenerateProofResult, Box<dyn Error>> {
+        let timer = telemetry::Timer::start();
+        let result = self.prove_circuit(prepared, binding);
+        telemetry::record_proof_result(PC::BACKEND, timer, &result);
+        result
+    }
+
+    pub fn cache_stats(&self) -> WitnessCacheStats {
+        self.cache.lock().unwrap().stats()
+    }
+
+    /// Drop all cached witnesses, keeping the counters.
+    pub fn clear_cache(&self) {
+        self.cache.lock().unwrap().entries.clear();
+    }
+
+    fn parse_input(
+        &self,
+        input: HashMap<String, Vec<String>>,
+    ) -> Result<FibonacciCircuit<Fr>, FibonacciError> {
+        let steps = requested_steps(&input)?;
+        if steps != self.shape.steps {
+            return Err(FibonacciError::InvalidInput(
+                format!(
+                    "Requested {} steps, but the proving key is for {} steps",
+                    steps, self.shape.steps
+                )
+                .into(),
+            ));
+        }
+
+        let circuit_inputs = deserialize_circuit_inputs(input).map_err(|e| {
+            FibonacciError::InvalidInput(
+                format!("Failed to deserialize circuit inputs: {}", e).into(),
+            )
+        })?;
+        FibonacciCircuit::from_inputs(&circuit_inputs, self.shape.steps)
+    }
+
+    fn prove_input(
+        &self,
+        input: HashMap<String, Vec<String>>,
+        binding: TranscriptBinding,
+    ) -> Result<GenerateProofResult, Box<dyn Error>> {
+        let circuit = self.parse_input(input)?;
+
+        let key = self.witness_key(&encode_public_inputs(&circuit.public_input[0]));
+        let cached = self.cache.lock().unwrap().get(&key);
+        let prepared = match cached {
+            Some(prepared) => prepared,
+            None => {
+                // Synthesize without holding the lock, so other inputs can be proven meanwhile
+                let prepared = Arc::new(PreparedCircuit::new::<PC>(circuit, self.shape)?);
+                self.cache.lock().unwrap().insert(key, prepared.clone());
+                prepared
+            }
+        };
+
+        self.prove_circuit(&prepared, binding)
+    }
+
+    fn prove_circuit(
+        &self,
+        prepared: &PreparedCircuit,
+        binding: TranscriptBinding,
+    ) -> Result<GenerateProofResult, Box<dyn Error>> {
+        if prepared.backend != PC::BACKEND || prepared.shape != self.shape {
+            return Err(FibonacciError::CircuitMismatch(
+                format!(
+                "Circuit was prepared for {} with {:?}, but the proving key is for {} with {:?}",
+                prepared.backend.name(),
+                prepared.shape,
+                PC::BACKEND.name(),
+                self.shape
+            )
+                .into(),
+            )
+            .into());
+        }
+
+        let proof = prove_synthesized_circuit::<PC>(&self.prover_param, prepared, binding)
+            .map_err(|e| {
+                FibonacciError::Proving(ErrorMessage::with_source(
+                    format!("Failed to generate the proof: {}", e),
+                    e,
+                ))
+            })?;
+
+        Ok((proof, prepared.public_inputs.clone()))
+    }
+
+    fn witness_key(&self, public_inputs: &[u8]) -> WitnessKey {
+        Keccak256::new()
+            .chain_update((self.shape.steps as u64).to_le_bytes())
+            .chain_update((self.shape.k as u64).to_le_bytes())
+            .chain_update(public_inputs)
+            .finalize()
+            .into()
+    }
+}
+
+/// Prove `prepared` with the proving key of `ctx`, see `ProverContext::prove_prepared`.
+pub fn prove_prepared<PC>(
+    ctx: &ProverContext<PC>,
+    prepared: &PreparedCircuit,
+    binding: TranscriptBinding,
+) -> Result<GenerateProofResult, Box<dyn Error>>
+where
+    PC: PlonkishComponents,
+    ProofTranscript: TranscriptWrite<CommitmentChunk<Fr, PC::Pcs>, Fr>,
+{
+    ctx.prove_prepared(prepared, binding)
+}
+
+/// Least recently used cache of prepared circuits
+struct WitnessCache {
+    capacity: usize,
+    /// Most recently used last
+    entries: VecDeque<(WitnessKey, Arc<PreparedCircuit>)>,
+    hits: u64,
+    misses: u64,
+}
+
+impl WitnessCache {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: VecDeque::new(),
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    fn get(&mut self, key: &WitnessKey) -> Option<Arc<PreparedCircuit>> {
+        match self.entries.iter().position(|(cached, _)| cached == key) {
+            Some(index) => {
+                self.hits += 1;
+                let entry = self.entries.remove(index)?;
+                let prepared = entry.1.clone();
+                self.entries.push_back(entry);
+                Some(prepared)
+            }
+            None => {
+                self.misses += 1;
+                None
+            }
+        }
+    }
+
+    fn insert(&mut self, key: WitnessKey, prepared: Arc<PreparedCircuit>) {
+        // Another thread may have prepared the same input meanwhile
+        self.entries.retain(|(cached, _)| *cached != key);
+        self.entries.push_back((key, prepared));
+        self.resize(self.capacity);
+    }
+
+    fn resize(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        while self.entries.len() > capacity {
+            self.entries.pop_front();
+        }
+    }
+
+    fn stats(&self) -> WitnessCacheStats {
+        WitnessCacheStats {
+            hits: self.hits,
+            misses: self.misses,
+            entries: self.entries.len(),
+        }
+    }
+}
+
+/// Fibonacci circuit wrapped for a backend, with its witness synthesized. Keeps the witness
+/// columns of each round the backend asks for, so proving it again only runs the commitments and
+/// openings.
+pub struct PreparedCircuit {
+    backend: BackendId,
+    shape: CircuitShape,
+    circuit: Halo2Circuit<Fr, FibonacciCircuit<Fr>>,
+    /// Encoded public inputs, as returned with proofs
+    public_inputs: Vec<u8>,
+    witness: Mutex<Vec<RoundWitness>>,
+}
+
+impl PreparedCircuit {
+    /// Wrap `circuit` for the backend of `PC` in the domain of `shape` and synthesize its
+    /// witness.
+    pub fn new<PC: PlonkishComponents>(
+        circuit: FibonacciCircuit<Fr>,
+        shape: CircuitShape,
+    ) -> Result<Self, FibonacciError> {
+        crate::profile_scope!("synthesize");
+
+        shape.validate()?;
+        if circuit.steps != shape.steps || circuit.public_input.len() != 1 {
+            return Err(FibonacciError::InvalidInput(
+                format!(
+                    "Circuit of {} steps and {} instance columns doesn't fit {:?}",
+                    circuit.steps,
+                    circuit.public_input.len(),
+                    shape
+                )
+                .into(),
+            ));
+        }
+
+        let prepared = Self {
+            backend: PC::BACKEND,
+            shape,
+            public_inputs: encode_public_inputs(&circuit.public_input[0]),
+            circuit: build_halo2_circuit::<PC, _>(shape.k, circuit),
+            witness: Mutex::default(),
+        };
+        // The circuit has a single phase, the backends synthesize it in round 0 without challenges
+        prepared.synthesize(0, &[]).map_err(|e| {
+            FibonacciError::Proving(ErrorMessage::with_source(
+                format!("Failed to synthesize the witness: {:?}", e),
+                BackendError(e),
+            ))
+        })?;
+        Ok(prepared)
+    }
+
+    pub fn backend(&self) -> BackendId {
+        self.backend
+    }
+
+    pub fn shape(&self) -> CircuitShape {
+        self.shape
+    }
+
+    /// Public inputs of each instance column
+    pub fn instances(&self) -> &[Vec<Fr>] {
+        self.circuit.instances()
+    }
+
+    /// Encoded public inputs, see `serialisation::encode_public_inputs`
+    pub fn public_inputs(&self) -> &[u8] {
+        &self.public_inputs
+    }
+}
+
+impl PlonkishCircuit<Fr> for PreparedCircuit {
+    fn circuit_info_without_preprocess(
+        &self,
+    ) -> Result<PlonkishCircuitInfo<Fr>, plonkish_backend::Error> {
+        self.circuit.circuit_info_without_preprocess()
+    }
+
+    fn circuit_info(&self) -> Result<PlonkishCircuitInfo<Fr>, plonkish_backend::Error> {
+        self.circuit.circuit_info()
+    }
+
+    fn instances(&self) -> &[Vec<Fr>] {
+        self.circuit.instances()
+    }
+
+    fn synthesize(
+        &self,
+        round: usize,
+        challenges: &[Fr],
+    ) -> Result<Vec<Vec<Fr>>, plonkish_backend::Error> {
+        let cached = self
+            .witness
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|(cached_round, cached_challenges, _)| {
+                *cached_round == round && cached_challenges.as_slice() == challenges
+            })
+            .map(|(_, _, witness)| witness.clone());
+        if let Some(witness) = cached {
+            return Ok(witness);
+        }
+
+        let witness = self.circuit.synthesize(round, challenges)?;
+        // Challenges of later rounds come from the randomized transcript, so they're rarely seen
+        // again, only the latest witness of each round is kept
+        let mut rounds = self.witness.lock().unwrap();
+        rounds.retain(|(cached_round, _, _)| *cached_round != round);
+        rounds.push((round, challenges.to_vec(), witness.clone()));
+        Ok(witness)
+    }
+}
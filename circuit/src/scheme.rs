@@ -0,0 +1,517 @@
+//! Macros generating the boilerplate of a plonkish backend crate from its scheme.
+//!
+//! The `gemini`, `hyperplonk` and `unihyperplonk` crates only differ in their
+//! `PlonkishComponents` impl, everything else forwards to the generic functions of this crate.
+//! A new PCS backend declares its scheme and invokes the macros instead of copying a crate:
+//!
+//! ```ignore
+//! pub struct MyScheme;
+//!
+//! impl PlonkishComponents for MyScheme {
+//!     // backend id, params, PCS, proving backend and `srs_k`
+//! }
+//!
+//! // `prove`, `verify`, `keygen_from_bytes`, ... of `MyScheme`
+//! fibonacci_circuit::define_scheme!(MyScheme);
+//! // The tests every backend passes, proving with the SRS file shipped for its `BACKEND`
+//! fibonacci_circuit::scheme_tests!(MyScheme);
+//! ```
+//!
+//! and the key generator binary, e.g. `src/bin/gen_my_keys.rs`, is a single line:
+//!
+//! ```ignore
+//! fibonacci_circuit::gen_keys_main!(my_fibonacci::MyScheme, "my");
+//! ```
+//!
+//! The backend also needs a `BackendId` variant, which the scheme reports as its `BACKEND`.
+
+/// Define the prove, verify and keygen functions of a backend crate for `$scheme`, forwarding
+/// to the generic functions of `fibonacci_circuit`. On wasm32 the file path arguments are
+/// replaced by the file contents.
+#[macro_export]
+macro_rules! define_scheme {
+    ($scheme:ty) => {
+        #[cfg(not(target_arch = "wasm32"))]
+        pub fn prove(
+            srs_key_path: &str,
+            proving_key_path: &str,
+            input: ::std::collections::HashMap<String, Vec<String>>,
+        ) -> Result<$crate::GenerateProofResult, Box<dyn ::std::error::Error>> {
+            $crate::prove::<$scheme>(srs_key_path, proving_key_path, input)
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        pub fn prove_with_label(
+            srs_key_path: &str,
+            proving_key_path: &str,
+            input: ::std::collections::HashMap<String, Vec<String>>,
+            label: &[u8],
+        ) -> Result<$crate::GenerateProofResult, Box<dyn ::std::error::Error>> {
+            $crate::prove_with_label::<$scheme>(srs_key_path, proving_key_path, input, label)
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        pub fn prove_with_binding(
+            srs_key_path: &str,
+            proving_key_path: &str,
+            input: ::std::collections::HashMap<String, Vec<String>>,
+            binding: $crate::TranscriptBinding,
+        ) -> Result<$crate::GenerateProofResult, Box<dyn ::std::error::Error>> {
+            $crate::prove_with_binding::<$scheme>(srs_key_path, proving_key_path, input, binding)
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        pub fn prove_with_key_set(
+            srs_key_path: &str,
+            key_set_path: &str,
+            input: ::std::collections::HashMap<String, Vec<String>>,
+            binding: $crate::TranscriptBinding,
+        ) -> Result<$crate::GenerateProofResult, Box<dyn ::std::error::Error>> {
+            $crate::prove_with_key_set::<$scheme>(srs_key_path, key_set_path, input, binding)
+        }
+
+        #[cfg(target_arch = "wasm32")]
+        pub fn prove(
+            srs_key: &[u8],
+            proving_key: &[u8],
+            input: ::std::collections::HashMap<String, Vec<String>>,
+        ) -> Result<$crate::GenerateProofResult, Box<dyn ::std::error::Error>> {
+            $crate::prove::<$scheme>(srs_key, proving_key, input)
+        }
+
+        #[cfg(target_arch = "wasm32")]
+        pub fn prove_with_label(
+            srs_key: &[u8],
+            proving_key: &[u8],
+            input: ::std::collections::HashMap<String, Vec<String>>,
+            label: &[u8],
+        ) -> Result<$crate::GenerateProofResult, Box<dyn ::std::error::Error>> {
+            $crate::prove_with_label::<$scheme>(srs_key, proving_key, input, label)
+        }
+
+        #[cfg(target_arch = "wasm32")]
+        pub fn prove_with_binding(
+            srs_key: &[u8],
+            proving_key: &[u8],
+            input: ::std::collections::HashMap<String, Vec<String>>,
+            binding: $crate::TranscriptBinding,
+        ) -> Result<$crate::GenerateProofResult, Box<dyn ::std::error::Error>> {
+            $crate::prove_with_binding::<$scheme>(srs_key, proving_key, input, binding)
+        }
+
+        pub fn keygen_from_bytes(
+            srs_key: &[u8],
+        ) -> Result<(Vec<u8>, Vec<u8>), Box<dyn ::std::error::Error>> {
+            $crate::keygen_from_bytes::<$scheme>(srs_key)
+        }
+
+        pub fn prove_from_bytes(
+            srs_key: &[u8],
+            proving_key: &[u8],
+            input: ::std::collections::HashMap<String, Vec<String>>,
+            binding: $crate::TranscriptBinding,
+        ) -> Result<$crate::GenerateProofResult, Box<dyn ::std::error::Error>> {
+            $crate::prove_from_bytes::<$scheme>(srs_key, proving_key, input, binding)
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        pub fn verify(
+            srs_key_path: &str,
+            verifying_key_path: &str,
+            proof: &[u8],
+            public_inputs: &[u8],
+        ) -> Result<bool, Box<dyn ::std::error::Error>> {
+            $crate::verify::<$scheme>(srs_key_path, verifying_key_path, proof, public_inputs)
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        pub fn verify_with_label(
+            srs_key_path: &str,
+            verifying_key_path: &str,
+            proof: &[u8],
+            public_inputs: &[u8],
+            label: &[u8],
+        ) -> Result<bool, Box<dyn ::std::error::Error>> {
+            $crate::verify_with_label::<$scheme>(
+                srs_key_path,
+                verifying_key_path,
+                proof,
+                public_inputs,
+                label,
+            )
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        pub fn verify_with_binding(
+            srs_key_path: &str,
+            verifying_key_path: &str,
+            proof: &[u8],
+            public_inputs: &[u8],
+            binding: $crate::TranscriptBinding,
+        ) -> Result<bool, Box<dyn ::std::error::Error>> {
+            $crate::verify_with_binding::<$scheme>(
+                srs_key_path,
+                verifying_key_path,
+                proof,
+                public_inputs,
+                binding,
+            )
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        pub fn verify_with_key_set(
+            key_set_path: &str,
+            steps: usize,
+            proof: &[u8],
+            public_inputs: &[u8],
+            binding: $crate::TranscriptBinding,
+        ) -> Result<bool, Box<dyn ::std::error::Error>> {
+            $crate::verify_with_key_set::<$scheme>(
+                key_set_path,
+                steps,
+                proof,
+                public_inputs,
+                binding,
+            )
+        }
+
+        #[cfg(target_arch = "wasm32")]
+        pub fn verify(
+            srs_key: &[u8],
+            verifying_key: &[u8],
+            proof: &[u8],
+            public_inputs: &[u8],
+        ) -> Result<bool, Box<dyn ::std::error::Error>> {
+            $crate::verify::<$scheme>(srs_key, verifying_key, proof, public_inputs)
+        }
+
+        #[cfg(target_arch = "wasm32")]
+        pub fn verify_with_label(
+            srs_key: &[u8],
+            verifying_key: &[u8],
+            proof: &[u8],
+            public_inputs: &[u8],
+            label: &[u8],
+        ) -> Result<bool, Box<dyn ::std::error::Error>> {
+            $crate::verify_with_label::<$scheme>(
+                srs_key,
+                verifying_key,
+                proof,
+                public_inputs,
+                label,
+            )
+        }
+
+        #[cfg(target_arch = "wasm32")]
+        pub fn verify_with_binding(
+            srs_key: &[u8],
+            verifying_key: &[u8],
+            proof: &[u8],
+            public_inputs: &[u8],
+            binding: $crate::TranscriptBinding,
+        ) -> Result<bool, Box<dyn ::std::error::Error>> {
+            $crate::verify_with_binding::<$scheme>(
+                srs_key,
+                verifying_key,
+                proof,
+                public_inputs,
+                binding,
+            )
+        }
+
+        pub fn verify_from_bytes(
+            srs_key: &[u8],
+            verifying_key: &[u8],
+            proof: &[u8],
+            public_inputs: &[u8],
+            binding: $crate::TranscriptBinding,
+        ) -> Result<bool, Box<dyn ::std::error::Error>> {
+            $crate::verify_from_bytes::<$scheme>(
+                srs_key,
+                verifying_key,
+                proof,
+                public_inputs,
+                binding,
+            )
+        }
+
+        /// Verify without the SRS, see `fibonacci_circuit::verify_with_vk`.
+        pub fn verify_with_vk(
+            verifying_key: &[u8],
+            proof: &[u8],
+            public_inputs: &[u8],
+            binding: $crate::TranscriptBinding,
+        ) -> Result<bool, Box<dyn ::std::error::Error>> {
+            $crate::verify_with_vk::<$scheme>(verifying_key, proof, public_inputs, binding)
+        }
+
+        /// Verify every proof of a container, see `fibonacci_circuit::verify_container`.
+        pub fn verify_container(
+            verifying_key: &[u8],
+            container: &$crate::container::ProofContainer,
+            binding: $crate::TranscriptBinding,
+        ) -> Result<bool, Box<dyn ::std::error::Error>> {
+            $crate::verify_container::<$scheme>(verifying_key, container, binding)
+        }
+
+        /// Like `verify_container`, see `fibonacci_circuit::verify_container_for_shape`.
+        pub fn verify_container_for_shape(
+            verifying_key: &[u8],
+            container: &$crate::container::ProofContainer,
+            shape: $crate::CircuitShape,
+            binding: $crate::TranscriptBinding,
+        ) -> Result<bool, Box<dyn ::std::error::Error>> {
+            $crate::verify_container_for_shape::<$scheme>(verifying_key, container, shape, binding)
+        }
+    };
+}
+
+/// `main` of the key generator binary of `$scheme`, writing its keys as `gen_keys` does under
+/// `$name`. With `--dry-run` it prints the keys it would write instead, and fails if the SRS
+/// can't be used.
+#[macro_export]
+macro_rules! gen_keys_main {
+    ($scheme:ty, $name:expr) => {
+        pub fn main() {
+            let args = $crate::keygen_args().unwrap_or_else($crate::exit_with_error);
+            for (shape, e) in &args.skipped {
+                eprintln!("Skipping k = {}: {}", shape.k, e);
+            }
+
+            $crate::run_keygen(
+                || {
+                    $crate::plan_keys::<$scheme>(
+                        &args.srs_path,
+                        &args.out_dir,
+                        $name,
+                        &args.options,
+                    )
+                },
+                || {
+                    $crate::generate_keys::<$scheme>(
+                        &args.srs_path,
+                        &args.out_dir,
+                        $name,
+                        &args.options,
+                    )
+                },
+            );
+        }
+    };
+}
+
+/// The tests of `circuit::test_utils` every backend passes, run for `$scheme`, and a round trip
+/// through the functions of `define_scheme!` with the SRS file shipped for the scheme's
+/// `BACKEND`, see `artifact_dirs::shipped_srs`. Expands to a `scheme_tests` module, so the crate
+/// keeps its own `tests` module for the rest.
+#[macro_export]
+macro_rules! scheme_tests {
+    ($scheme:ty) => {
+        #[cfg(test)]
+        mod scheme_tests {
+            use $crate::circuit::test_utils;
+
+            use super::*;
+
+            fn read_srs() -> Vec<u8> {
+                let path = $crate::artifact_dirs::shipped_srs_path(
+                    <$scheme as $crate::PlonkishComponents>::BACKEND,
+                );
+                ::std::fs::read(path).unwrap()
+            }
+
+            #[test]
+            fn test_dynamic_lookup() {
+                test_utils::dynamic_lookup_test::<$scheme>();
+            }
+
+            #[test]
+            fn test_fibonacci_circuit() {
+                test_utils::fibonacci_circuit_test::<$scheme>();
+            }
+
+            #[test]
+            fn test_bad_proof_not_verified() {
+                test_utils::bad_proof_not_verified_test::<$scheme>();
+            }
+
+            #[test]
+            fn test_chain() {
+                test_utils::chain_test::<$scheme>();
+            }
+
+            #[test]
+            fn test_circuit_hash() {
+                test_utils::circuit_hash_test::<$scheme>();
+            }
+
+            #[test]
+            fn test_circuit_shape() {
+                test_utils::circuit_shape_test::<$scheme>();
+            }
+
+            #[test]
+            fn test_corrupted_proof_rejected() {
+                test_utils::corrupted_proof_rejected_test::<$scheme>();
+            }
+
+            #[test]
+            fn test_helper_functions() {
+                test_utils::helper_functions_test::<$scheme>();
+            }
+
+            #[test]
+            fn test_mock_prover_differential() {
+                test_utils::mock_prover_differential_test::<$scheme>();
+            }
+
+            #[test]
+            fn test_fixed_start() {
+                test_utils::fixed_start_test::<$scheme>();
+            }
+
+            #[test]
+            fn test_merkle() {
+                test_utils::merkle_test::<$scheme>();
+            }
+
+            #[test]
+            fn test_multi_instance() {
+                test_utils::multi_instance_test::<$scheme>();
+            }
+
+            #[test]
+            fn test_nonce() {
+                test_utils::nonce_test::<$scheme>(&read_srs());
+            }
+
+            #[test]
+            fn test_proof_container() {
+                test_utils::proof_container_test::<$scheme>();
+            }
+
+            #[test]
+            fn test_proving_k() {
+                test_utils::proving_k_test::<$scheme>();
+            }
+
+            #[test]
+            fn test_random_round_trip() {
+                test_utils::random_round_trip_test::<$scheme>();
+            }
+
+            #[test]
+            fn test_shuffle() {
+                test_utils::shuffle_test::<$scheme>();
+            }
+
+            #[test]
+            fn test_step_count() {
+                test_utils::step_count_test::<$scheme>(&read_srs());
+            }
+
+            #[test]
+            fn test_transcript_binding() {
+                test_utils::transcript_binding_test::<$scheme>();
+            }
+
+            #[test]
+            fn test_verify_with_vk() {
+                let srs_key = read_srs();
+                let (proving_key, verifying_key) = keygen_from_bytes(&srs_key).unwrap();
+
+                let input = ::std::collections::HashMap::from([(
+                    "out".to_string(),
+                    vec!["55".to_string()],
+                )]);
+                let (proof, public_inputs) = prove_from_bytes(
+                    &srs_key,
+                    &proving_key,
+                    input,
+                    $crate::TranscriptBinding::default(),
+                )
+                .unwrap();
+
+                assert!(verify_with_vk(
+                    &verifying_key,
+                    &proof,
+                    &public_inputs,
+                    $crate::TranscriptBinding::default()
+                )
+                .unwrap());
+            }
+
+            #[test]
+            fn test_prove_invalid_input() {
+                let srs_key = read_srs();
+                let (proving_key, _) = keygen_from_bytes(&srs_key).unwrap();
+
+                let error = prove_from_bytes(
+                    &srs_key,
+                    &proving_key,
+                    ::std::collections::HashMap::new(),
+                    $crate::TranscriptBinding::default(),
+                )
+                .unwrap_err();
+                assert!(matches!(
+                    error.downcast_ref::<$crate::FibonacciError>(),
+                    Some($crate::FibonacciError::InvalidInput(_))
+                ));
+            }
+        }
+    };
+}
+
+/// The wasm round trips of the crate `$krate`, from the SRS bytes `$srs_key`, for its
+/// `tests/wasm.rs`. Run in node with `wasm-pack test --node <crate> --no-default-features`, or in
+/// a headless browser with `--headless --firefox` instead of `--node`. Add `--features simd` and
+/// `RUSTFLAGS="-C target-feature=+simd128"` to test the SIMD build.
+#[macro_export]
+macro_rules! wasm_tests {
+    ($krate:ident, $srs_key:expr) => {
+        use ::std::collections::HashMap;
+
+        use ::wasm_bindgen_test::wasm_bindgen_test;
+        use $krate::{keygen_from_bytes, prove, prove_with_label, verify, verify_with_label};
+
+        const SRS_KEY: &[u8] = $srs_key;
+
+        fn input(out: &str) -> HashMap<String, Vec<String>> {
+            HashMap::from([("out".to_string(), vec![out.to_string()])])
+        }
+
+        #[wasm_bindgen_test]
+        fn test_prove_verify() {
+            let (proving_key, verifying_key) = keygen_from_bytes(SRS_KEY).unwrap();
+
+            let (proof, public_inputs) = prove(SRS_KEY, &proving_key, input("55")).unwrap();
+            assert!(verify(SRS_KEY, &verifying_key, &proof, &public_inputs).unwrap());
+
+            let (proof, public_inputs) = prove(SRS_KEY, &proving_key, input("56")).unwrap();
+            assert!(!verify(SRS_KEY, &verifying_key, &proof, &public_inputs).unwrap_or(false));
+        }
+
+        #[wasm_bindgen_test]
+        fn test_simd_build() {
+            if cfg!(feature = "simd") {
+                assert!($crate::WASM_SIMD);
+            }
+        }
+
+        #[wasm_bindgen_test]
+        fn test_prove_verify_with_label() {
+            let (proving_key, verifying_key) = keygen_from_bytes(SRS_KEY).unwrap();
+
+            let (proof, public_inputs) =
+                prove_with_label(SRS_KEY, &proving_key, input("55"), b"app-a").unwrap();
+            assert!(
+                verify_with_label(SRS_KEY, &verifying_key, &proof, &public_inputs, b"app-a")
+                    .unwrap()
+            );
+            assert!(
+                !verify_with_label(SRS_KEY, &verifying_key, &proof, &public_inputs, b"app-b")
+                    .unwrap_or(false)
+            );
+        }
+    };
+}
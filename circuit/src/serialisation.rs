@@ -1,83 +1,501 @@
 use std::collections::HashMap;
-use std::fmt;
-use std::str::FromStr;
+use std::io::Read;
 
-use crate::FibonacciError;
-use halo2_proofs::halo2curves::{bn256::Fr, ff::PrimeField};
-use serde::de::{SeqAccess, Visitor};
+use crate::{ErrorMessage, FibonacciError};
+use fibonacci_verifier_core::FieldBytes;
+use halo2_proofs::halo2curves::bn256::Fr;
+use num_bigint::BigUint;
+use serde::de::Error as _;
 use serde::ser::SerializeSeq;
-use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
 
+/// Serialization format of keys and inputs, recorded in the artifact header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Codec {
+    Bincode,
+    /// Compact varint based encoding, requires the `postcard` feature
+    Postcard,
+    /// CBOR (RFC 8949) for interoperability with other languages, requires the `cbor` feature
+    Cbor,
+}
+
+impl Codec {
+    pub fn id(&self) -> u8 {
+        match self {
+            Codec::Bincode => 0,
+            Codec::Postcard => 1,
+            Codec::Cbor => 2,
+        }
+    }
+
+    pub fn from_id(id: u8) -> Result<Self, FibonacciError> {
+        match id {
+            0 => Ok(Codec::Bincode),
+            1 => Ok(Codec::Postcard),
+            2 => Ok(Codec::Cbor),
+            _ => Err(FibonacciError::Serialization(
+                format!("Unknown codec id {}", id).into(),
+            )),
+        }
+    }
+
+    pub fn encode<T: Serialize>(&self, data: &T) -> Result<Vec<u8>, FibonacciError> {
+        match self {
+            Codec::Bincode => bincode::serialize(data).map_err(|e| {
+                FibonacciError::Serialization(ErrorMessage::with_source(
+                    format!("Bincode serialization failed: {}", e),
+                    e,
+                ))
+            }),
+            #[cfg(feature = "postcard")]
+            Codec::Postcard => postcard::to_stdvec(data).map_err(|e| {
+                FibonacciError::Serialization(
+                    format!("Postcard serialization failed: {}", e).into(),
+                )
+            }),
+            #[cfg(not(feature = "postcard"))]
+            Codec::Postcard => Err(codec_disabled("postcard")),
+            #[cfg(feature = "cbor")]
+            Codec::Cbor => {
+                let mut bytes = Vec::new();
+                ciborium::into_writer(data, &mut bytes).map_err(|e| {
+                    FibonacciError::Serialization(
+                        format!("CBOR serialization failed: {:?}", e).into(),
+                    )
+                })?;
+                Ok(bytes)
+            }
+            #[cfg(not(feature = "cbor"))]
+            Codec::Cbor => Err(codec_disabled("cbor")),
+        }
+    }
+
+    pub fn decode<T: DeserializeOwned>(&self, bytes: &[u8]) -> Result<T, FibonacciError> {
+        match self {
+            Codec::Bincode => bincode::deserialize(bytes).map_err(|e| {
+                FibonacciError::Serialization(ErrorMessage::with_source(
+                    format!("Bincode deserialization failed: {}", e),
+                    e,
+                ))
+            }),
+            #[cfg(feature = "postcard")]
+            Codec::Postcard => postcard::from_bytes(bytes).map_err(|e| {
+                FibonacciError::Serialization(
+                    format!("Postcard deserialization failed: {}", e).into(),
+                )
+            }),
+            #[cfg(not(feature = "postcard"))]
+            Codec::Postcard => Err(codec_disabled("postcard")),
+            #[cfg(feature = "cbor")]
+            Codec::Cbor => ciborium::from_reader(bytes).map_err(|e| {
+                FibonacciError::Serialization(
+                    format!("CBOR deserialization failed: {:?}", e).into(),
+                )
+            }),
+            #[cfg(not(feature = "cbor"))]
+            Codec::Cbor => Err(codec_disabled("cbor")),
+        }
+    }
+
+    /// Like `decode`, deserializing while reading from `reader` instead of from the whole
+    /// encoding in memory.
+    pub fn decode_from_reader<T: DeserializeOwned, R: Read>(
+        &self,
+        reader: R,
+    ) -> Result<T, FibonacciError> {
+        match self {
+            Codec::Bincode => bincode::deserialize_from(reader).map_err(|e| {
+                FibonacciError::Serialization(ErrorMessage::with_source(
+                    format!("Bincode deserialization failed: {}", e),
+                    e,
+                ))
+            }),
+            // postcard only decodes from a buffer
+            #[cfg(feature = "postcard")]
+            Codec::Postcard => {
+                let mut reader = reader;
+                let mut bytes = Vec::new();
+                reader.read_to_end(&mut bytes).map_err(|e| {
+                    FibonacciError::Io(ErrorMessage::with_source(
+                        format!("Failed to read postcard encoding: {}", e),
+                        e,
+                    ))
+                })?;
+                self.decode(&bytes)
+            }
+            #[cfg(not(feature = "postcard"))]
+            Codec::Postcard => Err(codec_disabled("postcard")),
+            #[cfg(feature = "cbor")]
+            Codec::Cbor => ciborium::from_reader(reader).map_err(|e| {
+                FibonacciError::Serialization(
+                    format!("CBOR deserialization failed: {:?}", e).into(),
+                )
+            }),
+            #[cfg(not(feature = "cbor"))]
+            Codec::Cbor => Err(codec_disabled("cbor")),
+        }
+    }
+}
+
+#[cfg(any(not(feature = "postcard"), not(feature = "cbor")))]
+fn codec_disabled(feature: &str) -> FibonacciError {
+    FibonacciError::Serialization(
+        format!("The {} codec requires the `{}` feature", feature, feature).into(),
+    )
+}
+
+/// Public inputs of a circuit with one instance column, serialized as a single byte string
+/// holding their canonical encoding, see `encode_public_inputs`.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct InputsSerialisationWrapper(pub Vec<Fr>);
 
+/// Public inputs of circuits with several instance columns, one vector per column. Each column
+/// is encoded like `InputsSerialisationWrapper`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InstanceColumnsSerialisationWrapper(pub Vec<Vec<Fr>>);
+
+/// Field elements as a byte string of their canonical encoding, the encoding of an instance
+/// column
+struct FieldElementsRef<'a>(&'a [Fr]);
+
+/// Encode public inputs canonically: the number of elements as a little-endian u64, followed by
+/// the canonical 32 byte little-endian representation of each element.
+///
+/// The layout doesn't depend on a serde framework, so given inputs always encode to the same
+/// bytes, which can be hashed or compared. It matches the bincode layout public inputs had
+/// before, so earlier proofs' public inputs still decode.
+pub fn encode_public_inputs(inputs: &[Fr]) -> Vec<u8> {
+    let elements: Vec<FieldBytes> = inputs.iter().map(|fe| fe.to_bytes()).collect();
+    fibonacci_verifier_core::encode_public_inputs(&elements)
+}
+
+/// Decode public inputs encoded with `encode_public_inputs`, rejecting truncated encodings,
+/// trailing bytes and elements which aren't smaller than the field modulus.
+///
+/// Public inputs written as an artifact with a header are accepted too, see
+/// `canonical_public_inputs`.
+pub fn decode_public_inputs(bytes: &[u8]) -> Result<Vec<Fr>, FibonacciError> {
+    decode_canonical(&canonical_public_inputs(bytes)?)
+}
+
+/// Canonical encoding of public inputs, unwrapping an `InputsSerialisationWrapper` written as an
+/// artifact with a header. The elements aren't checked.
+///
+/// Artifacts written before the wrapper serialized as a byte string hold a sequence of 32 byte
+/// arrays instead, those are re-encoded canonically.
+pub fn canonical_public_inputs(bytes: &[u8]) -> Result<Vec<u8>, FibonacciError> {
+    if crate::io::ArtifactHeader::parse(bytes)?.is_none() {
+        return Ok(bytes.to_vec());
+    }
+    let current = crate::io::decode_artifact::<serde_bytes::ByteBuf>(bytes).map(|b| b.into_vec());
+    match current {
+        Ok(canonical) if is_canonical_layout(&canonical) => Ok(canonical),
+        _ => match crate::io::decode_artifact::<Vec<FieldBytes>>(bytes) {
+            Ok(legacy) => Ok(fibonacci_verifier_core::encode_public_inputs(&legacy)),
+            Err(_) => current,
+        },
+    }
+}
+
+/// Whether `bytes` has the length its element count announces, see `encode_public_inputs`
+fn is_canonical_layout(bytes: &[u8]) -> bool {
+    if bytes.len() < 8 {
+        return false;
+    }
+    let (len, elements) = bytes.split_at(8);
+    let len = u64::from_le_bytes(len.try_into().unwrap());
+    elements.len() % 32 == 0 && len == (elements.len() / 32) as u64
+}
+
+fn decode_canonical(bytes: &[u8]) -> Result<Vec<Fr>, FibonacciError> {
+    let elements = fibonacci_verifier_core::decode_public_inputs(bytes).map_err(|e| {
+        FibonacciError::Serialization(format!("Invalid public inputs: {}", e).into())
+    })?;
+    Ok(elements.iter().map(field_element).collect())
+}
+
 pub fn deserialize_circuit_inputs(
     ser_inputs: HashMap<String, Vec<String>>,
 ) -> Result<HashMap<String, Vec<Fr>>, FibonacciError> {
     ser_inputs
         .iter()
         .map(|(k, v)| {
-            let fp_vec: Result<Vec<Fr>, FibonacciError> = v
-                .iter()
-                .map(|s| {
-                    // TODO - support big integers full range, not just u128
-                    let int = u128::from_str(s).map_err(|e| {
-                        FibonacciError(format!("Failed to parse input as u128: {}", e))
-                    });
-
-                    int.map(|i| Fr::from_u128(i))
-                })
-                .collect();
+            let fp_vec: Result<Vec<Fr>, FibonacciError> =
+                v.iter().map(|s| parse_field_element(s)).collect();
             fp_vec.map(|v| (k.clone(), v))
         })
         .collect()
 }
 
+/// Parse a decimal or `0x` prefixed hexadecimal integer into a field element.
+///
+/// Like circom, values of any size are accepted and reduced modulo the field order,
+/// and a leading `-` yields the additive inverse.
+pub fn parse_field_element(s: &str) -> Result<Fr, FibonacciError> {
+    let trimmed = s.trim();
+    let (negative, unsigned) = match trimmed.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, trimmed),
+    };
+    let (radix, digits) = match unsigned
+        .strip_prefix("0x")
+        .or_else(|| unsigned.strip_prefix("0X"))
+    {
+        Some(hex) => (16, hex),
+        None => (10, unsigned),
+    };
+
+    if digits.is_empty() {
+        return Err(FibonacciError::InvalidInput(
+            format!("Failed to parse input '{}' as integer", s).into(),
+        ));
+    }
+
+    let base = Fr::from(radix as u64);
+    let value = digits.chars().try_fold(Fr::from(0), |acc, c| {
+        c.to_digit(radix)
+            .map(|digit| acc * base + Fr::from(digit as u64))
+            .ok_or_else(|| {
+                FibonacciError::InvalidInput(
+                    format!("Invalid digit '{}' in input '{}'", c, s).into(),
+                )
+            })
+    })?;
+
+    Ok(if negative { -value } else { value })
+}
+
+/// Format a field element as a decimal string, the representation snarkjs and circom use.
+pub fn field_element_to_decimal(fe: &Fr) -> String {
+    BigUint::from_bytes_le(&fe.to_bytes()).to_str_radix(10)
+}
+
+/// Serde of a field element as a decimal string, for `#[serde(with = "decimal")]` fields of
+/// configs people write by hand. Parses whatever `parse_field_element` accepts.
+pub mod decimal {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(fe: &Fr, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&field_element_to_decimal(fe))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Fr, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        parse_field_element(&s).map_err(D::Error::custom)
+    }
+}
+
+/// Encode arbitrary bytes as field elements: the byte length followed by the bytes in 16-byte
+/// little-endian chunks, so distinct byte strings always map to distinct sequences.
+pub fn bytes_to_field_elements(bytes: &[u8]) -> Vec<Fr> {
+    fibonacci_verifier_core::bytes_to_field_elements(bytes)
+        .iter()
+        .map(field_element)
+        .collect()
+}
+
+/// Field element of a representation `fibonacci_verifier_core` derived, which is canonical.
+pub(crate) fn field_element(bytes: &FieldBytes) -> Fr {
+    Option::from(Fr::from_bytes(bytes)).expect("verifier core derives canonical elements")
+}
+
+/// Canonical 32 byte big-endian representation of a field element, the byte order of EVM
+/// calldata and most hex tooling. `Fr::to_bytes` is little-endian.
+pub fn field_element_to_be_bytes(fe: &Fr) -> FieldBytes {
+    let mut bytes = fe.to_bytes();
+    bytes.reverse();
+    bytes
+}
+
+/// Field element of a 32 byte big-endian representation, rejecting values which aren't smaller
+/// than the field modulus.
+pub fn field_element_from_be_bytes(bytes: &FieldBytes) -> Result<Fr, FibonacciError> {
+    let mut le_bytes = *bytes;
+    le_bytes.reverse();
+    Option::from(Fr::from_bytes(&le_bytes)).ok_or_else(|| {
+        FibonacciError::InvalidInput("Bytes are not a canonical field element".into())
+    })
+}
+
+/// Conversions between `Fr` and arkworks' `ark_bn254::Fr`, which represent the same BN254 scalar
+/// field, for inputs prepared with arkworks tooling.
+#[cfg(feature = "arkworks")]
+pub mod arkworks {
+    use ark_ff::{BigInteger, PrimeField};
+
+    use super::*;
+
+    pub type ArkFr = ark_bn254::Fr;
+
+    pub fn from_ark(fe: &ArkFr) -> Fr {
+        let mut bytes = [0u8; 32];
+        bytes.copy_from_slice(&fe.into_bigint().to_bytes_le());
+        Option::from(Fr::from_bytes(&bytes)).expect("arkworks elements are canonical")
+    }
+
+    pub fn to_ark(fe: &Fr) -> ArkFr {
+        ArkFr::from_le_bytes_mod_order(&fe.to_bytes())
+    }
+
+    /// Canonical encoding of arkworks public inputs, see `serialisation::encode_public_inputs`.
+    pub fn encode_public_inputs(inputs: &[ArkFr]) -> Vec<u8> {
+        let inputs: Vec<Fr> = inputs.iter().map(from_ark).collect();
+        super::encode_public_inputs(&inputs)
+    }
+
+    /// Public inputs of a proof as arkworks elements, see `serialisation::decode_public_inputs`.
+    pub fn decode_public_inputs(bytes: &[u8]) -> Result<Vec<ArkFr>, FibonacciError> {
+        Ok(super::decode_public_inputs(bytes)?
+            .iter()
+            .map(to_ark)
+            .collect())
+    }
+
+    /// Circuit inputs of arkworks elements in the decimal form `prove` takes, e.g.
+    /// `circuit_inputs([("out", vec![ArkFr::from(55)])])`.
+    pub fn circuit_inputs<I, S>(inputs: I) -> HashMap<String, Vec<String>>
+    where
+        I: IntoIterator<Item = (S, Vec<ArkFr>)>,
+        S: Into<String>,
+    {
+        inputs
+            .into_iter()
+            .map(|(name, values)| {
+                let values = values
+                    .iter()
+                    .map(|fe| field_element_to_decimal(&from_ark(fe)))
+                    .collect();
+                (name.into(), values)
+            })
+            .collect()
+    }
+}
+
+/// Parse inputs written in the circom/snarkjs `input.json` convention.
+///
+/// Signal values may be numbers or stringified integers, arrays of any depth are flattened
+/// in row-major order, and nested objects are flattened into dotted names (`main.sub.x`).
+/// The result can be passed directly to `prove`.
+pub fn parse_circom_inputs(json: &str) -> Result<HashMap<String, Vec<String>>, FibonacciError> {
+    let value: Value = serde_json::from_str(json).map_err(|e| {
+        FibonacciError::InvalidInput(format!("Failed to parse input JSON: {}", e).into())
+    })?;
+    let signals = value
+        .as_object()
+        .ok_or_else(|| FibonacciError::InvalidInput("Input JSON must be an object".into()))?;
+
+    let mut inputs = HashMap::new();
+    for (name, signal) in signals {
+        flatten_signal(name, signal, &mut inputs)?;
+    }
+
+    Ok(inputs)
+}
+
+fn flatten_signal(
+    name: &str,
+    signal: &Value,
+    inputs: &mut HashMap<String, Vec<String>>,
+) -> Result<(), FibonacciError> {
+    match signal {
+        Value::Object(fields) => {
+            for (field, value) in fields {
+                flatten_signal(&format!("{}.{}", name, field), value, inputs)?;
+            }
+        }
+        _ => {
+            let mut values = Vec::new();
+            flatten_values(name, signal, &mut values)?;
+            inputs.insert(name.to_string(), values);
+        }
+    }
+    Ok(())
+}
+
+fn flatten_values(
+    name: &str,
+    value: &Value,
+    values: &mut Vec<String>,
+) -> Result<(), FibonacciError> {
+    match value {
+        Value::Array(items) => {
+            for item in items {
+                flatten_values(name, item, values)?;
+            }
+        }
+        Value::String(s) => values.push(s.clone()),
+        Value::Number(n) => values.push(n.to_string()),
+        Value::Bool(b) => values.push((*b as u8).to_string()),
+        _ => {
+            return Err(FibonacciError::InvalidInput(
+                format!("Unsupported value for signal '{}': {}", name, value).into(),
+            ))
+        }
+    }
+    Ok(())
+}
+
+impl Serialize for FieldElementsRef<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_bytes(&encode_public_inputs(self.0))
+    }
+}
+
 impl Serialize for InputsSerialisationWrapper {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        FieldElementsRef(&self.0).serialize(serializer)
+    }
+}
+
+impl Serialize for InstanceColumnsSerialisationWrapper {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
         let mut seq = serializer.serialize_seq(Some(self.0.len()))?;
-        for fp in &self.0 {
-            seq.serialize_element(&fp.to_bytes())?;
+        for column in &self.0 {
+            seq.serialize_element(&FieldElementsRef(column))?;
         }
         seq.end()
     }
 }
 
-impl<'de> Deserialize<'de> for InputsSerialisationWrapper {
+impl<'de> Deserialize<'de> for InstanceColumnsSerialisationWrapper {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
         D: Deserializer<'de>,
     {
-        struct SerializableInputsVisitor;
-
-        impl<'de> Visitor<'de> for SerializableInputsVisitor {
-            type Value = InputsSerialisationWrapper;
-
-            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-                formatter.write_str("a sequence of byte arrays of length 32")
-            }
-
-            fn visit_seq<A>(self, mut seq: A) -> Result<InputsSerialisationWrapper, A::Error>
-            where
-                A: SeqAccess<'de>,
-            {
-                let mut vec = Vec::new();
-                while let Some(bytes) = seq.next_element::<[u8; 32]>()? {
-                    vec.push(Fr::from_bytes(&bytes).expect("Invalid bytes"));
-                }
-                Ok(InputsSerialisationWrapper(vec))
-            }
-        }
+        let columns = Vec::<InputsSerialisationWrapper>::deserialize(deserializer)?;
+        Ok(InstanceColumnsSerialisationWrapper(
+            columns.into_iter().map(|column| column.0).collect(),
+        ))
+    }
+}
 
-        deserializer.deserialize_seq(SerializableInputsVisitor)
+impl<'de> Deserialize<'de> for InputsSerialisationWrapper {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        // Byte strings, or sequences of bytes in formats without them such as JSON
+        let bytes = serde_bytes::ByteBuf::deserialize(deserializer)?;
+        decode_canonical(&bytes)
+            .map(InputsSerialisationWrapper)
+            .map_err(D::Error::custom)
     }
 }
 
 // Tests for serialization and deserialization
 #[cfg(test)]
 mod tests {
+    use halo2_proofs::halo2curves::ff::PrimeField;
     use serde_json;
 
     use super::*;
@@ -98,6 +516,109 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_public_inputs_encoding() {
+        let inputs = vec![Fr::from(1), Fr::from(1), Fr::from(55)];
+
+        let encoded = encode_public_inputs(&inputs);
+        let mut expected = 3u64.to_le_bytes().to_vec();
+        for input in &inputs {
+            expected.extend(input.to_bytes());
+        }
+        assert_eq!(encoded, expected);
+        assert_eq!(decode_public_inputs(&encoded).unwrap(), inputs);
+
+        // Serde frameworks wrap the same bytes in a byte string
+        let wrapper = InputsSerialisationWrapper(inputs.clone());
+        let bincode = Codec::Bincode.encode(&wrapper).unwrap();
+        assert_eq!(bincode[8..], encoded[..]);
+        let artifact = crate::io::encode_artifact(&wrapper, Codec::Bincode).unwrap();
+        assert_eq!(canonical_public_inputs(&artifact).unwrap(), encoded);
+        assert_eq!(decode_public_inputs(&artifact).unwrap(), inputs);
+
+        // Artifacts of the earlier layout, a sequence of 32 byte arrays, still decode
+        let elements: Vec<FieldBytes> = inputs.iter().map(|fe| fe.to_bytes()).collect();
+        let legacy = crate::io::encode_artifact(&elements, Codec::Bincode).unwrap();
+        assert_eq!(canonical_public_inputs(&legacy).unwrap(), encoded);
+        assert_eq!(decode_public_inputs(&legacy).unwrap(), inputs);
+
+        // Non-canonical elements, truncation and trailing bytes are rejected
+        let mut non_canonical = encoded.clone();
+        non_canonical[8 + 31] = 0xff;
+        assert!(decode_public_inputs(&non_canonical).is_err());
+        let wrapped = [
+            &(non_canonical.len() as u64).to_le_bytes()[..],
+            &non_canonical,
+        ]
+        .concat();
+        assert!(Codec::Bincode
+            .decode::<InputsSerialisationWrapper>(&wrapped)
+            .is_err());
+        assert!(decode_public_inputs(&encoded[..encoded.len() - 1]).is_err());
+        assert!(decode_public_inputs(&[encoded.as_slice(), &[0]].concat()).is_err());
+    }
+
+    #[test]
+    fn test_instance_columns_serialization() {
+        let inputs = InstanceColumnsSerialisationWrapper(vec![
+            vec![Fr::from(1), Fr::from(1)],
+            vec![Fr::from(55)],
+        ]);
+
+        let encoded = Codec::Bincode.encode(&inputs).unwrap();
+        let decoded: InstanceColumnsSerialisationWrapper = Codec::Bincode.decode(&encoded).unwrap();
+        assert_eq!(decoded.0, inputs.0);
+
+        // A single column is encoded like the flat inputs
+        let flat = InputsSerialisationWrapper(vec![Fr::from(55)]);
+        let column = InstanceColumnsSerialisationWrapper(vec![flat.0.clone()]);
+        assert_eq!(
+            serde_json::to_string(&column).unwrap(),
+            format!("[{}]", serde_json::to_string(&flat).unwrap())
+        );
+
+        // Empty columns round-trip too
+        let with_empty = InstanceColumnsSerialisationWrapper(vec![vec![], vec![Fr::from(55)]]);
+        let encoded = Codec::Bincode.encode(&with_empty).unwrap();
+        assert_eq!(
+            Codec::Bincode
+                .decode::<InstanceColumnsSerialisationWrapper>(&encoded)
+                .unwrap(),
+            with_empty
+        );
+
+        // Non-canonical elements are rejected in any column
+        let mut encoded = Codec::Bincode.encode(&inputs).unwrap();
+        let last = encoded.len() - 1;
+        encoded[last] = 0xff;
+        assert!(Codec::Bincode
+            .decode::<InstanceColumnsSerialisationWrapper>(&encoded)
+            .is_err());
+    }
+
+    #[test]
+    fn test_codec_roundtrip() {
+        let inputs = InputsSerialisationWrapper(vec![Fr::from(1), Fr::from(55)]);
+
+        let encoded = Codec::Bincode.encode(&inputs).unwrap();
+        let decoded: InputsSerialisationWrapper = Codec::Bincode.decode(&encoded).unwrap();
+        assert_eq!(decoded.0, inputs.0);
+
+        #[cfg(feature = "postcard")]
+        {
+            let compact = Codec::Postcard.encode(&inputs).unwrap();
+            assert!(compact.len() < encoded.len());
+            let decoded: InputsSerialisationWrapper = Codec::Postcard.decode(&compact).unwrap();
+            assert_eq!(decoded.0, inputs.0);
+        }
+
+        assert_eq!(
+            Codec::from_id(Codec::Postcard.id()).unwrap(),
+            Codec::Postcard
+        );
+        assert!(Codec::from_id(42).is_err());
+    }
+
     #[test]
     fn test_circuit_inputs_deserialization() {
         let mut serialized = HashMap::new();
@@ -108,4 +629,103 @@ mod tests {
         assert_eq!(deserialized.get("out").unwrap()[0], Fr::from(1));
         assert_eq!(deserialized.get("out").unwrap()[1], Fr::from(2));
     }
+
+    #[test]
+    fn test_parse_field_element() {
+        assert_eq!(parse_field_element("55").unwrap(), Fr::from(55));
+        assert_eq!(parse_field_element("0x37").unwrap(), Fr::from(55));
+        assert_eq!(parse_field_element("-1").unwrap(), -Fr::from(1));
+
+        // Larger than u128, 2^128 + 1
+        let expected = Fr::from_u128(u128::MAX) + Fr::from(2);
+        assert_eq!(
+            parse_field_element("340282366920938463463374607431768211457").unwrap(),
+            expected
+        );
+
+        assert_eq!(
+            field_element_to_decimal(&expected),
+            "340282366920938463463374607431768211457"
+        );
+        assert_eq!(
+            field_element_to_decimal(&-Fr::from(1)),
+            "21888242871839275222246405745257275088548364400416034343698204186575808495616"
+        );
+
+        assert!(parse_field_element("").is_err());
+        assert!(parse_field_element("12a").is_err());
+    }
+
+    #[test]
+    fn test_bytes_to_field_elements() {
+        assert_eq!(bytes_to_field_elements(b""), vec![Fr::from(0)]);
+        assert_eq!(
+            bytes_to_field_elements(&[1, 0]),
+            vec![Fr::from(2), Fr::from(1)]
+        );
+        // Trailing zero bytes are distinguished by the length prefix
+        assert_ne!(
+            bytes_to_field_elements(&[1]),
+            bytes_to_field_elements(&[1, 0])
+        );
+        assert_eq!(bytes_to_field_elements(&[0xff; 17]).len(), 3);
+    }
+
+    #[test]
+    fn test_big_endian_bytes() {
+        let bytes = field_element_to_be_bytes(&Fr::from(0x0102));
+        assert_eq!(bytes[30..], [0x01, 0x02]);
+        assert_eq!(
+            field_element_from_be_bytes(&bytes).unwrap(),
+            Fr::from(0x0102)
+        );
+        assert!(field_element_from_be_bytes(&[0xff; 32]).is_err());
+    }
+
+    #[cfg(feature = "arkworks")]
+    #[test]
+    fn test_arkworks_conversions() {
+        use super::arkworks::{self, ArkFr};
+
+        for fe in [Fr::from(0), Fr::from(55), -Fr::from(1)] {
+            assert_eq!(arkworks::from_ark(&arkworks::to_ark(&fe)), fe);
+        }
+        assert_eq!(arkworks::to_ark(&-Fr::from(1)), -ArkFr::from(1u64));
+
+        let inputs = vec![ArkFr::from(1u64), ArkFr::from(1u64), ArkFr::from(55u64)];
+        let encoded = arkworks::encode_public_inputs(&inputs);
+        assert_eq!(
+            encoded,
+            encode_public_inputs(&[Fr::from(1), Fr::from(1), Fr::from(55)])
+        );
+        assert_eq!(arkworks::decode_public_inputs(&encoded).unwrap(), inputs);
+
+        let circuit_inputs = arkworks::circuit_inputs([("out", vec![ArkFr::from(55u64)])]);
+        assert_eq!(circuit_inputs["out"], vec!["55".to_string()]);
+    }
+
+    #[test]
+    fn test_parse_circom_inputs() {
+        let json = r#"{
+            "out": "55",
+            "steps": 10,
+            "matrix": [["1", "2"], [3]],
+            "main": { "sub": { "x": "0x10" } }
+        }"#;
+        let inputs = parse_circom_inputs(json).unwrap();
+
+        assert_eq!(inputs.get("out").unwrap(), &vec!["55".to_string()]);
+        assert_eq!(inputs.get("steps").unwrap(), &vec!["10".to_string()]);
+        assert_eq!(
+            inputs.get("matrix").unwrap(),
+            &vec!["1".to_string(), "2".to_string(), "3".to_string()]
+        );
+        assert_eq!(inputs.get("main.sub.x").unwrap(), &vec!["0x10".to_string()]);
+
+        let deserialized = deserialize_circuit_inputs(inputs).unwrap();
+        assert_eq!(deserialized.get("main.sub.x").unwrap()[0], Fr::from(16));
+
+        assert!(parse_circom_inputs(r#"["55"]"#).is_err());
+        assert!(parse_circom_inputs(r#"{"out": null}"#).is_err());
+    }
 }
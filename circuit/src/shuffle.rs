@@ -0,0 +1,142 @@
+//! Shuffle argument example: proves the committed column `shuffled` is a permutation of the
+//! committed column `input`, without revealing either.
+//!
+//! Copy constraints only fix a permutation at key generation. Here the permutation is part of the
+//! witness, so the circuit runs a grand product over a challenge `gamma` drawn after both columns
+//! are committed: z(0) = 1, z(i + 1) = z(i) * (input(i) + gamma) / (shuffled(i) + gamma) and
+//! z(n) = 1, which holds for a random `gamma` only if the columns hold the same multiset.
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    halo2curves::ff::PrimeField,
+    plonk::{
+        Advice, Challenge, Circuit, Column, ConstraintSystem, Error, FirstPhase, SecondPhase,
+        Selector,
+    },
+    poly::Rotation,
+};
+use plonkish_backend::frontend::halo2::CircuitExt;
+use rand::RngCore;
+
+/// Domain size of the example circuit, 2^4 rows
+pub const SHUFFLE_K: usize = 4;
+
+#[derive(Debug, Clone)]
+pub struct ShuffleConfig {
+    pub input: Column<Advice>,
+    pub shuffled: Column<Advice>,
+    /// Running product, committed in the second phase once `gamma` is known
+    pub z: Column<Advice>,
+    pub q_shuffle: Selector,
+    pub q_first: Selector,
+    pub q_last: Selector,
+    pub gamma: Challenge,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ShuffleCircuit<F> {
+    pub input: Vec<Value<F>>,
+    pub shuffled: Vec<Value<F>>,
+}
+
+impl<F: PrimeField> ShuffleCircuit<F> {
+    pub fn new(input: Vec<F>, shuffled: Vec<F>) -> Self {
+        assert_eq!(input.len(), shuffled.len());
+        Self {
+            input: input.into_iter().map(Value::known).collect(),
+            shuffled: shuffled.into_iter().map(Value::known).collect(),
+        }
+    }
+}
+
+impl<F: PrimeField> Circuit<F> for ShuffleCircuit<F> {
+    type Config = ShuffleConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    // The number of rows is part of the layout, only the values are dropped
+    fn without_witnesses(&self) -> Self {
+        Self {
+            input: vec![Value::unknown(); self.input.len()],
+            shuffled: vec![Value::unknown(); self.shuffled.len()],
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let input = meta.advice_column_in(FirstPhase);
+        let shuffled = meta.advice_column_in(FirstPhase);
+        let gamma = meta.challenge_usable_after(FirstPhase);
+        let z = meta.advice_column_in(SecondPhase);
+        let q_shuffle = meta.selector();
+        let q_first = meta.selector();
+        let q_last = meta.selector();
+
+        meta.create_gate("shuffle", |meta| {
+            let q_shuffle = meta.query_selector(q_shuffle);
+            let q_first = meta.query_selector(q_first);
+            let q_last = meta.query_selector(q_last);
+            let input = meta.query_advice(input, Rotation::cur());
+            let shuffled = meta.query_advice(shuffled, Rotation::cur());
+            let z = meta.query_advice(z, Rotation::cur());
+            let z_next = meta.query_advice(z, Rotation::next());
+            let gamma = meta.query_challenge(gamma);
+
+            vec![
+                // z(0) = 1 and z(n) = 1, written as q * z - q to avoid a constant
+                q_first.clone() * z.clone() - q_first,
+                q_last.clone() * z.clone() - q_last,
+                q_shuffle * (z_next * (shuffled + gamma.clone()) - z * (input + gamma)),
+            ]
+        });
+
+        ShuffleConfig {
+            input,
+            shuffled,
+            z,
+            q_shuffle,
+            q_first,
+            q_last,
+            gamma,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let gamma = layouter.get_challenge(config.gamma);
+
+        layouter.assign_region(
+            || "shuffle",
+            |mut region| {
+                let n = self.input.len();
+                config.q_first.enable(&mut region, 0)?;
+                config.q_last.enable(&mut region, n)?;
+
+                let mut z = Value::known(F::from(1));
+                for (row, (input, shuffled)) in self.input.iter().zip(&self.shuffled).enumerate() {
+                    config.q_shuffle.enable(&mut region, row)?;
+                    region.assign_advice(|| "input", config.input, row, || *input)?;
+                    region.assign_advice(|| "shuffled", config.shuffled, row, || *shuffled)?;
+                    region.assign_advice(|| "z", config.z, row, || z)?;
+
+                    let denominator = (*shuffled + gamma).map(|value| value.invert().unwrap());
+                    z = z * (*input + gamma) * denominator;
+                }
+                region.assign_advice(|| "z", config.z, n, || z)?;
+
+                Ok(())
+            },
+        )
+    }
+}
+
+impl<F: PrimeField> CircuitExt<F> for ShuffleCircuit<F> {
+    fn rand(_: usize, _: impl RngCore) -> Self {
+        unimplemented!()
+    }
+
+    // Both columns stay private
+    fn instances(&self) -> Vec<Vec<F>> {
+        Vec::new()
+    }
+}
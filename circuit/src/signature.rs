@@ -0,0 +1,121 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use ed25519_dalek::{Signature, VerifyingKey};
+
+use crate::{ErrorMessage, FibonacciError};
+
+/// Extension of detached signature files, `foo.bin` is signed by `foo.bin.sig`
+pub const SIGNATURE_EXTENSION: &str = "sig";
+
+pub fn signature_path(path: &Path) -> PathBuf {
+    let mut signature_path = path.as_os_str().to_owned();
+    signature_path.push(".");
+    signature_path.push(SIGNATURE_EXTENSION);
+    PathBuf::from(signature_path)
+}
+
+/// Verify an ed25519 signature over `data` with the publisher's public key.
+pub fn verify_signature(
+    data: &[u8],
+    signature: &[u8],
+    publisher_key: &[u8; 32],
+) -> Result<(), FibonacciError> {
+    let publisher_key = VerifyingKey::from_bytes(publisher_key)
+        .map_err(|e| FibonacciError::Signature(format!("Invalid publisher key: {}", e).into()))?;
+    let signature = Signature::from_slice(&parse_signature(signature)?)
+        .map_err(|e| FibonacciError::Signature(format!("Invalid signature: {}", e).into()))?;
+
+    publisher_key
+        .verify_strict(data, &signature)
+        .map_err(|_| FibonacciError::Signature("Signature verification failed".into()))
+}
+
+/// Read `path` and verify it against the detached signature stored next to it.
+///
+/// Returns the verified contents, so callers parse exactly the bytes that were checked.
+pub fn read_verified_file(
+    path: &Path,
+    publisher_key: &[u8; 32],
+) -> Result<Vec<u8>, FibonacciError> {
+    let data = fs::read(path).map_err(|e| {
+        FibonacciError::Io(ErrorMessage::with_source(
+            format!("Failed to read '{}': {}", path.display(), e),
+            e,
+        ))
+    })?;
+    let signature_path = signature_path(path);
+    let signature = fs::read(&signature_path).map_err(|e| {
+        FibonacciError::Signature(
+            format!(
+                "Failed to read signature '{}': {}",
+                signature_path.display(),
+                e
+            )
+            .into(),
+        )
+    })?;
+
+    verify_signature(&data, &signature, publisher_key).map_err(|e| {
+        FibonacciError::Signature(
+            format!("Artifact '{}' is not trusted: {}", path.display(), e).into(),
+        )
+    })?;
+
+    Ok(data)
+}
+
+// Signatures are stored either as 64 raw bytes or as hex text
+fn parse_signature(signature: &[u8]) -> Result<Vec<u8>, FibonacciError> {
+    if signature.len() == 64 {
+        return Ok(signature.to_vec());
+    }
+
+    let hex = std::str::from_utf8(signature)
+        .map_err(|_| FibonacciError::Signature("Signature must be 64 bytes or hex encoded".into()))?
+        .trim();
+    if hex.len() != 128 {
+        return Err(FibonacciError::Signature(
+            "Signature must be 64 bytes or hex encoded".into(),
+        ));
+    }
+    crate::hex::decode(hex)
+        .map_err(|e| FibonacciError::Signature(format!("Invalid hex signature: {}", e).into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use ed25519_dalek::{Signer, SigningKey};
+
+    use super::*;
+
+    #[test]
+    fn test_verify_signature() {
+        let signing_key = SigningKey::from_bytes(&[3u8; 32]);
+        let publisher_key = signing_key.verifying_key().to_bytes();
+        let data = b"proving key".to_vec();
+
+        let signature = signing_key.sign(&data).to_bytes();
+        assert!(verify_signature(&data, &signature, &publisher_key).is_ok());
+
+        let hex_signature = crate::hex::encode(&signature);
+        assert!(verify_signature(&data, hex_signature.as_bytes(), &publisher_key).is_ok());
+
+        assert!(verify_signature(b"tampered key", &signature, &publisher_key).is_err());
+
+        let other_key = SigningKey::from_bytes(&[4u8; 32])
+            .verifying_key()
+            .to_bytes();
+        assert!(verify_signature(&data, &signature, &other_key).is_err());
+    }
+
+    #[test]
+    fn test_signature_path() {
+        assert_eq!(
+            signature_path(Path::new("out/plonk_fibonacci_pk.bin")),
+            PathBuf::from("out/plonk_fibonacci_pk.bin.sig")
+        );
+    }
+}
@@ -0,0 +1,419 @@
+//! Variant of the Fibonacci circuit with the number of steps `n` as a public input.
+//!
+//! Keys of the tutorial circuit are generated for one sequence length, which verifiers have to
+//! know out of band to tell which F(n) a proof is about. `StepCountCircuit` lays out a sequence
+//! up to the `steps` of its `CircuitShape` and proves `out` = F(n) for the `n` in row
+//! `INSTANCE_STEPS_ROW` of the instance column, so one key covers every n from `MIN_STEPS` to
+//! the shape's steps and the proof itself says which one was proven.
+//!
+//! Every row computes the next element and carries a counter, starting at `MIN_STEPS` and
+//! incremented row by row, next to a copy of n. A boolean `is_last` may only be set on the row
+//! whose counter equals n, running sums check it's set on exactly one row, and accumulate the
+//! element of that row, which is exposed as `out`:
+//!
+//! ```text
+//! a | b | c | counter | n | is_last | found | selected | q_step | q_first | q_next | q_last
+//! ```
+use std::{collections::HashMap, error::Error};
+
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    halo2curves::ff::PrimeField,
+    plonk::{
+        Advice, Circuit, Column, ConstraintSystem, Error as PlonkError, Expression, Instance,
+        Selector, VirtualCells,
+    },
+    poly::Rotation,
+};
+use plonkish_backend::{
+    frontend::halo2::CircuitExt,
+    halo2_curves::bn256::Fr,
+    pcs::CommitmentChunk,
+    util::transcript::{TranscriptRead, TranscriptWrite},
+};
+use rand::RngCore;
+
+use crate::{
+    circuit::{
+        fibonacci, CircuitShape, FibonacciCircuit, TranscriptBinding, INSTANCE_A_ROW,
+        INSTANCE_B_ROW, INSTANCE_OUT_ROW, MIN_STEPS, NUM_PUBLIC_INPUTS,
+    },
+    circuit_hash_of, io, keygen_variant, prove_variant, requested_steps,
+    serialisation::{decode_public_inputs, deserialize_circuit_inputs},
+    verify_variant, FibonacciError, GenerateProofResult, PlonkishComponents, ProofTranscript,
+};
+
+/// Row of the instance column holding the number of steps n, after `a`, `b` and `out`
+pub const INSTANCE_STEPS_ROW: usize = NUM_PUBLIC_INPUTS;
+
+#[derive(Debug, Clone)]
+pub struct StepCountConfig {
+    pub a: Column<Advice>,
+    pub b: Column<Advice>,
+    pub c: Column<Advice>,
+    pub counter: Column<Advice>,
+    pub n: Column<Advice>,
+    pub is_last: Column<Advice>,
+    /// Running sum of `is_last`
+    pub found: Column<Advice>,
+    /// Running sum of `is_last * c`
+    pub selected: Column<Advice>,
+    pub q_step: Selector,
+    pub q_first: Selector,
+    pub q_next: Selector,
+    pub q_last: Selector,
+    pub instance: Column<Instance>,
+}
+
+#[derive(Debug, Clone)]
+pub struct StepCountCircuit<F> {
+    /// `a`, `b`, `out` and n, empty during key generation
+    pub public_input: Vec<Vec<F>>,
+    /// Longest sequence the circuit lays out
+    pub max_steps: usize,
+}
+
+impl<F: PrimeField> StepCountCircuit<F> {
+    /// The Fibonacci `circuit`, with its steps as a public input, laid out for sequences of up
+    /// to `max_steps`.
+    pub fn new(circuit: FibonacciCircuit<F>, max_steps: usize) -> Self {
+        let mut public_input = circuit.public_input;
+        if let Some(column) = public_input.first_mut() {
+            column.push(F::from(circuit.steps as u64));
+        }
+        Self {
+            public_input,
+            max_steps,
+        }
+    }
+}
+
+impl<F: PrimeField> Circuit<F> for StepCountCircuit<F> {
+    type Config = StepCountConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    // The maximum number of steps is part of the layout, n is only an instance value
+    fn without_witnesses(&self) -> Self {
+        Self {
+            public_input: Vec::new(),
+            max_steps: self.max_steps,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let [a, b, c, counter, n, is_last, found, selected] = [(); 8].map(|_| meta.advice_column());
+        let [q_step, q_first, q_next, q_last] = [(); 4].map(|_| meta.selector());
+        let instance = meta.instance_column();
+        for column in [a, b, n, selected] {
+            meta.enable_equality(column);
+        }
+        meta.enable_equality(instance);
+
+        meta.create_gate("step", |meta| {
+            let q_step = meta.query_selector(q_step);
+            let a = meta.query_advice(a, Rotation::cur());
+            let b = meta.query_advice(b, Rotation::cur());
+            let c = meta.query_advice(c, Rotation::cur());
+            let counter = meta.query_advice(counter, Rotation::cur());
+            let n = meta.query_advice(n, Rotation::cur());
+            let is_last = meta.query_advice(is_last, Rotation::cur());
+
+            let one = Expression::Constant(F::ONE);
+            vec![
+                q_step.clone() * (a + b - c),
+                q_step.clone() * is_last.clone() * (one - is_last.clone()),
+                // Only the row of F(n) can be the last one
+                q_step * is_last * (counter - n),
+            ]
+        });
+
+        meta.create_gate("first row", |meta| {
+            let q_first = meta.query_selector(q_first);
+            let c = meta.query_advice(c, Rotation::cur());
+            let counter = meta.query_advice(counter, Rotation::cur());
+            let is_last = meta.query_advice(is_last, Rotation::cur());
+            let found = meta.query_advice(found, Rotation::cur());
+            let selected = meta.query_advice(selected, Rotation::cur());
+
+            vec![
+                q_first.clone() * (counter - Expression::Constant(F::from(MIN_STEPS as u64))),
+                q_first.clone() * (found - is_last.clone()),
+                q_first * (selected - is_last * c),
+            ]
+        });
+
+        meta.create_gate("next row", |meta| {
+            let q_next = meta.query_selector(q_next);
+            let cur = |meta: &mut VirtualCells<F>, column: Column<Advice>| {
+                meta.query_advice(column, Rotation::cur())
+            };
+            let next = |meta: &mut VirtualCells<F>, column: Column<Advice>| {
+                meta.query_advice(column, Rotation::next())
+            };
+
+            let is_last_next = next(meta, is_last);
+            vec![
+                q_next.clone() * (next(meta, a) - cur(meta, b)),
+                q_next.clone() * (next(meta, b) - cur(meta, c)),
+                q_next.clone()
+                    * (next(meta, counter) - cur(meta, counter) - Expression::Constant(F::ONE)),
+                q_next.clone() * (next(meta, n) - cur(meta, n)),
+                q_next.clone() * (next(meta, found) - cur(meta, found) - is_last_next.clone()),
+                q_next
+                    * (next(meta, selected) - cur(meta, selected) - is_last_next * next(meta, c)),
+            ]
+        });
+
+        // Exactly one row is the last one
+        meta.create_gate("last row", |meta| {
+            let q_last = meta.query_selector(q_last);
+            let found = meta.query_advice(found, Rotation::cur());
+
+            vec![q_last * (found - Expression::Constant(F::ONE))]
+        });
+
+        StepCountConfig {
+            a,
+            b,
+            c,
+            counter,
+            n,
+            is_last,
+            found,
+            selected,
+            q_step,
+            q_first,
+            q_next,
+            q_last,
+            instance,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), PlonkError> {
+        // One row per step after F(1), like the tutorial circuit
+        let rows = self.max_steps - 1;
+
+        let selected = layouter.assign_region(
+            || "steps",
+            |mut region| {
+                let mut a = region
+                    .assign_advice_from_instance(
+                        || "f(0)",
+                        config.instance,
+                        INSTANCE_A_ROW,
+                        config.a,
+                        0,
+                    )?
+                    .value()
+                    .copied();
+                let mut b = region
+                    .assign_advice_from_instance(
+                        || "f(1)",
+                        config.instance,
+                        INSTANCE_B_ROW,
+                        config.b,
+                        0,
+                    )?
+                    .value()
+                    .copied();
+                let n = region
+                    .assign_advice_from_instance(
+                        || "n",
+                        config.instance,
+                        INSTANCE_STEPS_ROW,
+                        config.n,
+                        0,
+                    )?
+                    .value()
+                    .copied();
+
+                let (mut found, mut selected) = (Value::known(F::ZERO), Value::known(F::ZERO));
+                let mut selected_cell = None;
+                for row in 0..rows {
+                    config.q_step.enable(&mut region, row)?;
+                    if row == 0 {
+                        config.q_first.enable(&mut region, row)?;
+                    }
+                    if row + 1 < rows {
+                        config.q_next.enable(&mut region, row)?;
+                    } else {
+                        config.q_last.enable(&mut region, row)?;
+                    }
+                    if row > 0 {
+                        region.assign_advice(|| "a", config.a, row, || a)?;
+                        region.assign_advice(|| "b", config.b, row, || b)?;
+                        region.assign_advice(|| "n", config.n, row, || n)?;
+                    }
+
+                    let counter = F::from((row + MIN_STEPS) as u64);
+                    let c = a + b;
+                    let is_last = n.map(|n| if n == counter { F::ONE } else { F::ZERO });
+                    found = found + is_last;
+                    selected = selected + is_last * c;
+
+                    region.assign_advice(|| "c", config.c, row, || c)?;
+                    region.assign_advice(
+                        || "counter",
+                        config.counter,
+                        row,
+                        || Value::known(counter),
+                    )?;
+                    region.assign_advice(|| "is last", config.is_last, row, || is_last)?;
+                    region.assign_advice(|| "found", config.found, row, || found)?;
+                    selected_cell = Some(region.assign_advice(
+                        || "selected",
+                        config.selected,
+                        row,
+                        || selected,
+                    )?);
+                    (a, b) = (b, c);
+                }
+                selected_cell.ok_or(PlonkError::Synthesis)
+            },
+        )?;
+
+        layouter.constrain_instance(selected.cell(), config.instance, INSTANCE_OUT_ROW)
+    }
+}
+
+impl<F: PrimeField> CircuitExt<F> for StepCountCircuit<F> {
+    fn rand(_: usize, _: impl RngCore) -> Self {
+        unimplemented!()
+    }
+
+    fn instances(&self) -> Vec<Vec<F>> {
+        self.public_input.clone()
+    }
+}
+
+/// Hash of the step count variant's constraint system for sequences of up to `shape.steps`,
+/// recorded in the headers of its keys.
+pub fn step_count_circuit_hash<PC>(shape: CircuitShape) -> Result<io::CircuitHash, FibonacciError>
+where
+    PC: PlonkishComponents,
+{
+    shape.validate()?;
+    circuit_hash_of::<PC, _>("step_count", shape.k, shape.steps, || keygen_circuit(shape))
+}
+
+/// Generate the proving and verifying keys of the step count variant, for sequences of up to
+/// `shape.steps`, from the SRS contents.
+pub fn keygen_with_step_count<PC>(
+    srs_key: &[u8],
+    shape: CircuitShape,
+) -> Result<(Vec<u8>, Vec<u8>), Box<dyn Error>>
+where
+    PC: PlonkishComponents,
+{
+    let circuit_hash = step_count_circuit_hash::<PC>(shape)?;
+    keygen_variant::<PC, _>(srs_key, shape.k, keygen_circuit(shape), circuit_hash)
+}
+
+/// Prove `out` is F(n) for the `steps` input (`DEFAULT_STEPS` without it), with a proving key of
+/// `keygen_with_step_count` for `shape`. n is the fourth public input.
+pub fn prove_with_step_count<PC>(
+    srs_key: &[u8],
+    proving_key: &[u8],
+    input: HashMap<String, Vec<String>>,
+    shape: CircuitShape,
+    binding: TranscriptBinding,
+) -> Result<GenerateProofResult, Box<dyn Error>>
+where
+    PC: PlonkishComponents,
+    ProofTranscript: TranscriptWrite<CommitmentChunk<Fr, PC::Pcs>, Fr>,
+{
+    let steps = requested_steps(&input)?;
+    if !(MIN_STEPS..=shape.steps).contains(&steps) {
+        return Err(FibonacciError::InvalidInput(
+            format!(
+                "Requested {} steps, but the proving key is for {} to {} steps",
+                steps, MIN_STEPS, shape.steps
+            )
+            .into(),
+        )
+        .into());
+    }
+    let circuit = StepCountCircuit::new(
+        FibonacciCircuit::from_inputs(&deserialize_circuit_inputs(input)?, steps)?,
+        shape.steps,
+    );
+
+    prove_variant::<PC, _>(
+        srs_key,
+        proving_key,
+        shape.k,
+        circuit,
+        &step_count_circuit_hash::<PC>(shape)?,
+        binding,
+    )
+}
+
+/// Verify a proof of `prove_with_step_count` with its verifying key for `shape`. Whether it's
+/// valid doesn't tell which F(n) was proven, read it with `proven_steps`.
+pub fn verify_with_step_count<PC>(
+    verifying_key: &[u8],
+    proof: &[u8],
+    public_inputs: &[u8],
+    shape: CircuitShape,
+    binding: TranscriptBinding,
+) -> Result<bool, Box<dyn Error>>
+where
+    PC: PlonkishComponents,
+    ProofTranscript: TranscriptRead<CommitmentChunk<Fr, PC::Pcs>, Fr>,
+{
+    let inputs = step_count_inputs(public_inputs)?;
+
+    verify_variant::<PC>(
+        verifying_key,
+        proof,
+        inputs,
+        &step_count_circuit_hash::<PC>(shape)?,
+        binding,
+    )
+}
+
+/// Number of steps n of the public inputs of a step count proof, which is valid for F(n).
+pub fn proven_steps(public_inputs: &[u8]) -> Result<usize, FibonacciError> {
+    let steps = step_count_inputs(public_inputs)?[INSTANCE_STEPS_ROW];
+    // Counters are small, larger elements can't be a valid n
+    let repr = steps.to_repr();
+    if repr.as_ref()[8..].iter().any(|byte| *byte != 0) {
+        return Err(FibonacciError::InvalidInput(
+            "The number of steps is out of range".into(),
+        ));
+    }
+    let mut low = [0u8; 8];
+    low.copy_from_slice(&repr.as_ref()[..8]);
+    Ok(u64::from_le_bytes(low) as usize)
+}
+
+fn step_count_inputs(public_inputs: &[u8]) -> Result<Vec<Fr>, FibonacciError> {
+    let inputs = decode_public_inputs(public_inputs)?;
+    if inputs.len() != NUM_PUBLIC_INPUTS + 1 {
+        return Err(FibonacciError::InvalidInput(
+            format!(
+                "Expected {} public inputs including the number of steps, found {}",
+                NUM_PUBLIC_INPUTS + 1,
+                inputs.len()
+            )
+            .into(),
+        ));
+    }
+    Ok(inputs)
+}
+
+/// Circuit of `shape` with placeholder public inputs, which only fix the instance column length
+fn keygen_circuit(shape: CircuitShape) -> StepCountCircuit<Fr> {
+    let (a, b) = (Fr::from(1), Fr::from(1));
+    StepCountCircuit::new(
+        FibonacciCircuit {
+            public_input: vec![vec![a, b, fibonacci(a, b, shape.steps)]],
+            steps: shape.steps,
+        },
+        shape.steps,
+    )
+}
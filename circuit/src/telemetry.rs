@@ -0,0 +1,135 @@
+//! Proving and verification metrics, emitted through the [`metrics`](https://docs.rs/metrics)
+//! facade with the `metrics` feature.
+//!
+//! Nothing is collected until the embedding application installs a recorder, e.g.
+//! `metrics-exporter-prometheus`. Every metric carries a `backend` label. Without the feature the
+//! `record_*` functions compile to nothing.
+use std::time::Duration;
+
+use crate::BackendId;
+
+/// Counter of proofs generated successfully
+pub const PROOFS_GENERATED: &str = "fibonacci_proofs_generated_total";
+/// Counter of failed proof generations
+pub const PROOF_FAILURES: &str = "fibonacci_proof_failures_total";
+/// Histogram of proof generation time in seconds, including failed attempts
+pub const PROOF_DURATION: &str = "fibonacci_proof_duration_seconds";
+/// Histogram of the size of generated proofs in bytes
+pub const PROOF_SIZE: &str = "fibonacci_proof_size_bytes";
+/// Counter of verified proofs, labelled with `result` (`valid`, `invalid` or `error`)
+pub const VERIFICATIONS: &str = "fibonacci_verifications_total";
+/// Counter of proofs which were rejected or couldn't be checked
+pub const VERIFY_FAILURES: &str = "fibonacci_verify_failures_total";
+/// Histogram of verification time in seconds
+pub const VERIFY_DURATION: &str = "fibonacci_verify_duration_seconds";
+
+/// Start of a measured call. `Instant::now` panics on wasm32-unknown-unknown, which has no clock
+/// without JS bindings, and on zkVM guests, which have none at all, so durations are recorded as
+/// zero there.
+pub struct Timer {
+    #[cfg(not(any(target_arch = "wasm32", target_os = "zkvm")))]
+    start: std::time::Instant,
+}
+
+impl Timer {
+    #[cfg(not(any(target_arch = "wasm32", target_os = "zkvm")))]
+    pub fn start() -> Self {
+        Self {
+            start: std::time::Instant::now(),
+        }
+    }
+
+    #[cfg(any(target_arch = "wasm32", target_os = "zkvm"))]
+    pub fn start() -> Self {
+        Self {}
+    }
+
+    #[cfg(not(any(target_arch = "wasm32", target_os = "zkvm")))]
+    pub fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    #[cfg(any(target_arch = "wasm32", target_os = "zkvm"))]
+    pub fn elapsed(&self) -> Duration {
+        Duration::ZERO
+    }
+}
+
+/// Register units and descriptions of the metrics above with the installed recorder. Optional,
+/// recorders which export metadata (e.g. Prometheus `# HELP` lines) use it.
+pub fn describe() {
+    #[cfg(feature = "metrics")]
+    {
+        use metrics::{describe_counter, describe_histogram, Unit};
+
+        describe_counter!(PROOFS_GENERATED, Unit::Count, "Proofs generated");
+        describe_counter!(PROOF_FAILURES, Unit::Count, "Failed proof generations");
+        describe_histogram!(PROOF_DURATION, Unit::Seconds, "Proof generation time");
+        describe_histogram!(PROOF_SIZE, Unit::Bytes, "Size of generated proofs");
+        describe_counter!(VERIFICATIONS, Unit::Count, "Proof verifications");
+        describe_counter!(
+            VERIFY_FAILURES,
+            Unit::Count,
+            "Proofs rejected or failing to verify"
+        );
+        describe_histogram!(VERIFY_DURATION, Unit::Seconds, "Proof verification time");
+    }
+}
+
+/// Record a proof generation by `backend` which took `duration`.
+pub fn record_proof(backend: BackendId, duration: Duration, succeeded: bool) {
+    #[cfg(feature = "metrics")]
+    {
+        let backend = backend.name();
+        metrics::histogram!(PROOF_DURATION, "backend" => backend).record(duration);
+        if succeeded {
+            metrics::counter!(PROOFS_GENERATED, "backend" => backend).increment(1);
+        } else {
+            metrics::counter!(PROOF_FAILURES, "backend" => backend).increment(1);
+        }
+    }
+    #[cfg(not(feature = "metrics"))]
+    let _ = (backend, duration, succeeded);
+}
+
+/// Record the size of a proof generated by `backend`.
+pub fn record_proof_size(backend: BackendId, bytes: usize) {
+    #[cfg(feature = "metrics")]
+    metrics::histogram!(PROOF_SIZE, "backend" => backend.name()).record(bytes as f64);
+    #[cfg(not(feature = "metrics"))]
+    let _ = (backend, bytes);
+}
+
+/// Record the outcome of a proof generation by `backend` timed by `timer`, and the size of the
+/// proof if there is one. `result` holds the proof first, as `GenerateProofResult` does.
+pub fn record_proof_result<T, E>(
+    backend: BackendId,
+    timer: Timer,
+    result: &Result<(Vec<u8>, T), E>,
+) {
+    record_proof(backend, timer.elapsed(), result.is_ok());
+    if let Ok((proof, _)) = result {
+        record_proof_size(backend, proof.len());
+    }
+}
+
+/// Record a verification by `backend` which took `duration`, with `Some(is_valid)` when the
+/// proof could be checked and `None` when verification errored.
+pub fn record_verification(backend: BackendId, duration: Duration, is_valid: Option<bool>) {
+    #[cfg(feature = "metrics")]
+    {
+        let backend = backend.name();
+        let result = match is_valid {
+            Some(true) => "valid",
+            Some(false) => "invalid",
+            None => "error",
+        };
+        metrics::histogram!(VERIFY_DURATION, "backend" => backend).record(duration);
+        metrics::counter!(VERIFICATIONS, "backend" => backend, "result" => result).increment(1);
+        if is_valid != Some(true) {
+            metrics::counter!(VERIFY_FAILURES, "backend" => backend).increment(1);
+        }
+    }
+    #[cfg(not(feature = "metrics"))]
+    let _ = (backend, duration, is_valid);
+}
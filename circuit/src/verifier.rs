@@ -0,0 +1,167 @@
+//! Verifier trusting several verifying keys, so services can rotate keys without a flag day.
+//!
+//! After a circuit upgrade, proofs made with the old keys keep arriving for a while. A
+//! `VerifierContext` holds the old and new verifying keys in order of preference and verifies
+//! proof containers with the key whose fingerprint they record. Once no proofs for the old key
+//! are expected, it's retired.
+use std::error::Error;
+
+use plonkish_backend::{
+    halo2_curves::bn256::Fr, pcs::CommitmentChunk, util::transcript::TranscriptRead,
+};
+
+use crate::{
+    circuit::{verify_halo2_proof_with_vk, CircuitShape, TranscriptBinding},
+    container::{vk_fingerprint, ProofContainer, VkFingerprint},
+    decode_key,
+    serialisation::decode_public_inputs,
+    telemetry, FibonacciError, PlonkishComponents, ProofTranscript,
+};
+
+struct TrustedKey<PC: PlonkishComponents> {
+    fingerprint: VkFingerprint,
+    shape: CircuitShape,
+    verifier_param: PC::VerifierParam,
+}
+
+/// Verifier with an ordered list of trusted verifying keys of one backend
+pub struct VerifierContext<PC: PlonkishComponents> {
+    keys: Vec<TrustedKey<PC>>,
+}
+
+impl<PC: PlonkishComponents> Default for VerifierContext<PC> {
+    fn default() -> Self {
+        Self { keys: Vec::new() }
+    }
+}
+
+impl<PC> VerifierContext<PC>
+where
+    PC: PlonkishComponents,
+    ProofTranscript: TranscriptRead<CommitmentChunk<Fr, PC::Pcs>, Fr>,
+{
+    /// Verifier trusting `verifying_key` of the default circuit shape.
+    pub fn new(verifying_key: &[u8]) -> Result<Self, FibonacciError> {
+        Self::default().with_key(verifying_key)
+    }
+
+    /// Also trust `verifying_key`, after the keys trusted so far.
+    pub fn with_key(self, verifying_key: &[u8]) -> Result<Self, FibonacciError> {
+        self.with_key_for_shape(verifying_key, CircuitShape::default())
+    }
+
+    /// Like `with_key`, for a verifying key generated for `shape`.
+    pub fn with_key_for_shape(
+        mut self,
+        verifying_key: &[u8],
+        shape: CircuitShape,
+    ) -> Result<Self, FibonacciError> {
+        let fingerprint = vk_fingerprint(verifying_key);
+        if self.position(&fingerprint).is_none() {
+            self.keys.push(TrustedKey {
+                fingerprint,
+                shape,
+                verifier_param: decode_key::<PC, PC::VerifierParam>(verifying_key, shape)?,
+            });
+        }
+        Ok(self)
+    }
+
+    /// Stop trusting the key with `fingerprint`, returning whether it was trusted.
+    pub fn retire(&mut self, fingerprint: &VkFingerprint) -> bool {
+        match self.position(fingerprint) {
+            Some(index) => {
+                self.keys.remove(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Fingerprints of the trusted keys, in order of preference
+    pub fn fingerprints(&self) -> Vec<VkFingerprint> {
+        self.keys.iter().map(|key| key.fingerprint).collect()
+    }
+
+    /// Circuit shape of the trusted key with `fingerprint`
+    pub fn shape(&self, fingerprint: &VkFingerprint) -> Option<CircuitShape> {
+        self.position(fingerprint)
+            .map(|index| self.keys[index].shape)
+    }
+
+    /// Verify every proof of `container` with the trusted key it was made for. Containers of
+    /// keys which aren't trusted (anymore) are rejected with `FibonacciError::InvalidInput`.
+    pub fn verify_container(
+        &self,
+        container: &ProofContainer,
+        binding: TranscriptBinding,
+    ) -> Result<bool, Box<dyn Error>> {
+        let key = self
+            .position(&container.vk_fingerprint)
+            .map(|index| &self.keys[index])
+            .ok_or_else(|| {
+                FibonacciError::InvalidInput(
+                    "Proof container was made for a verifying key which isn't trusted".into(),
+                )
+            })?;
+        container.validate_fingerprint(PC::BACKEND, &key.fingerprint)?;
+
+        for bundle in &container.bundles {
+            let inputs = decode_public_inputs(&bundle.public_inputs)?;
+            if !self.verify_with_key(key, &bundle.proof, &inputs, binding)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Verify a proof which doesn't record its key, trying the trusted keys in order. A key the
+    /// proof can't be checked with, e.g. as it was made for another circuit shape, isn't the
+    /// proof's key, the next one is tried.
+    pub fn verify(
+        &self,
+        proof: &[u8],
+        public_inputs: &[u8],
+        binding: TranscriptBinding,
+    ) -> Result<bool, Box<dyn Error>> {
+        if self.keys.is_empty() {
+            return Err(FibonacciError::InvalidInput("No trusted verifying keys".into()).into());
+        }
+        let inputs = decode_public_inputs(public_inputs)?;
+        for key in &self.keys {
+            if let Ok(true) = self.verify_with_key(key, proof, &inputs, binding) {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    fn position(&self, fingerprint: &VkFingerprint) -> Option<usize> {
+        self.keys
+            .iter()
+            .position(|key| key.fingerprint == *fingerprint)
+    }
+
+    /// Whether the proof is valid for `key`, a proof the verifier rejects is invalid
+    fn verify_with_key(
+        &self,
+        key: &TrustedKey<PC>,
+        proof: &[u8],
+        inputs: &[Fr],
+        binding: TranscriptBinding,
+    ) -> Result<bool, Box<dyn Error>> {
+        let timer = telemetry::Timer::start();
+        let result = match verify_halo2_proof_with_vk::<PC>(
+            &key.verifier_param,
+            proof,
+            inputs.to_vec(),
+            binding,
+        ) {
+            Err(FibonacciError::Verification(_)) => Ok(false),
+            result => result,
+        };
+        telemetry::record_verification(PC::BACKEND, timer.elapsed(), result.as_ref().ok().copied());
+
+        Ok(result?)
+    }
+}
@@ -0,0 +1,128 @@
+//! Witnesses prepared outside the prover.
+//!
+//! Witness generation tooling writes a `FibonacciWitness`, the sequence together with the public
+//! inputs it proves, as an artifact file. The prover reads it back with
+//! `FibonacciCircuit::from_witness_file`, checking it's consistent, so preparing witnesses for
+//! larger circuits doesn't have to happen in the proving binary.
+//!
+//! The circuit only takes the public inputs from the file: synthesis derives the sequence from
+//! `a` and `b` again, as it does for any input. The sequence in the file is what tooling checks
+//! before proving, `FibonacciWitness::check` rejects files whose sequence doesn't lead from the
+//! starting values to `out`.
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::Path;
+
+use halo2_proofs::halo2curves::bn256::Fr;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    circuit::{FibonacciCircuit, MIN_STEPS},
+    io,
+    serialisation::{Codec, InputsSerialisationWrapper},
+    FibonacciError,
+};
+
+/// Sequence F(0), ..., F(steps) and the public inputs `a`, `b` and `out` of a Fibonacci circuit
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FibonacciWitness {
+    pub steps: usize,
+    /// Public inputs of the instance column, F(0), F(1) and F(steps)
+    pub instance: InputsSerialisationWrapper,
+    pub sequence: InputsSerialisationWrapper,
+}
+
+impl FibonacciWitness {
+    /// Witness of the sequence of `steps` starting with `a` and `b`.
+    pub fn new(a: Fr, b: Fr, steps: usize) -> Self {
+        let mut sequence = vec![a, b];
+        for n in 2..=steps {
+            sequence.push(sequence[n - 2] + sequence[n - 1]);
+        }
+        sequence.truncate(steps + 1);
+        Self {
+            steps,
+            instance: InputsSerialisationWrapper(vec![a, b, sequence[steps]]),
+            sequence: InputsSerialisationWrapper(sequence),
+        }
+    }
+
+    /// Check the sequence is a Fibonacci sequence of `steps` whose ends are the public inputs.
+    pub fn check(&self) -> Result<(), FibonacciError> {
+        let invalid = |message: String| Err(FibonacciError::InvalidInput(message.into()));
+        let (instance, sequence) = (&self.instance.0, &self.sequence.0);
+
+        if self.steps < MIN_STEPS {
+            return invalid(format!(
+                "Witness of {} steps is shorter than {}",
+                self.steps, MIN_STEPS
+            ));
+        }
+        if sequence.len() != self.steps + 1 {
+            return invalid(format!(
+                "Witness of {} steps has {} sequence values instead of {}",
+                self.steps,
+                sequence.len(),
+                self.steps + 1
+            ));
+        }
+        if instance.len() != 3 {
+            return invalid(format!(
+                "Witness has {} public inputs instead of 3",
+                instance.len()
+            ));
+        }
+        if let Some(n) =
+            (2..sequence.len()).find(|&n| sequence[n] != sequence[n - 2] + sequence[n - 1])
+        {
+            return invalid(format!(
+                "Witness value F({}) isn't F({}) + F({})",
+                n,
+                n - 2,
+                n - 1
+            ));
+        }
+        if [sequence[0], sequence[1], sequence[self.steps]] != instance[..] {
+            return invalid("Witness public inputs don't match its sequence".to_string());
+        }
+        Ok(())
+    }
+
+    /// Encode the witness as an artifact with `codec`.
+    pub fn to_bytes(&self, codec: Codec) -> Result<Vec<u8>, FibonacciError> {
+        io::encode_artifact(self, codec)
+    }
+
+    /// Decode a witness artifact, see `FibonacciCircuit::from_witness_bytes` for checking it.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, FibonacciError> {
+        io::decode_artifact(bytes)
+    }
+
+    /// Write the witness to `path`, refusing to replace an existing file unless `overwrite`.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn write(&self, path: &Path, codec: Codec, overwrite: bool) -> Result<(), FibonacciError> {
+        io::write_atomic(path, &self.to_bytes(codec)?, overwrite)
+    }
+}
+
+impl FibonacciCircuit<Fr> {
+    /// Circuit of a witness artifact, as written by `FibonacciWitness::write`, after checking
+    /// the witness is consistent. The circuit holds the public inputs of the witness, its
+    /// sequence is recomputed during synthesis.
+    pub fn from_witness_bytes(bytes: &[u8]) -> Result<Self, FibonacciError> {
+        let witness = FibonacciWitness::from_bytes(bytes)?;
+        witness.check()?;
+        Ok(Self {
+            public_input: vec![witness.instance.0],
+            steps: witness.steps,
+        })
+    }
+
+    /// Like `from_witness_bytes`, reading the witness from `path`.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn from_witness_file(path: &Path) -> Result<Self, FibonacciError> {
+        let bytes = std::fs::read(path).map_err(|e| {
+            FibonacciError::Io(format!("Failed to read witness '{}': {}", path.display(), e).into())
+        })?;
+        Self::from_witness_bytes(&bytes)
+    }
+}
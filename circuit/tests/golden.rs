@@ -0,0 +1,150 @@
+//! Golden encodings of the stored formats, so encodings can't drift unnoticed.
+//!
+//! The files of each `FORMAT_VERSION` live in `tests/golden/v<version>`. A failing comparison
+//! means an encoding changed: if that's deliberate, bump `FORMAT_VERSION` and write the files of
+//! the new version with `UPDATE_GOLDEN=1 cargo test -p fibonacci-circuit --test golden`. Files
+//! of released versions are never rewritten, and every version's files must still decode.
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use fibonacci_circuit::{
+    io::{decode_artifact, encode_artifact, ArtifactHeader, BundleManifest, KeyBundle},
+    serialisation::{
+        decode_public_inputs, encode_public_inputs, Codec, InputsSerialisationWrapper,
+    },
+    BackendId, ProofBundle, FORMAT_VERSION,
+};
+use halo2_proofs::halo2curves::bn256::Fr;
+
+const GOLDEN_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden");
+
+fn version_dir(version: u32) -> PathBuf {
+    Path::new(GOLDEN_DIR).join(format!("v{}", version))
+}
+
+/// Compare `bytes` with the golden file `name` of the current version, or write it with
+/// `UPDATE_GOLDEN` if the version has no such file yet.
+fn check(name: &str, bytes: &[u8]) {
+    let path = version_dir(FORMAT_VERSION).join(name);
+    if env::var_os("UPDATE_GOLDEN").is_some() && !path.exists() {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, bytes).unwrap();
+        return;
+    }
+
+    let golden =
+        fs::read(&path).unwrap_or_else(|e| panic!("Missing golden file {:?}: {}", path, e));
+    assert!(
+        golden == bytes,
+        "Encoding of {} drifted from format version {}, bump FORMAT_VERSION if the change is deliberate",
+        name,
+        FORMAT_VERSION
+    );
+}
+
+fn public_inputs() -> Vec<Fr> {
+    vec![Fr::from(1), Fr::from(1), Fr::from(55)]
+}
+
+fn proof_bundle() -> ProofBundle {
+    ProofBundle::new(
+        vec![0xde, 0xad, 0xbe, 0xef],
+        encode_public_inputs(&public_inputs()),
+    )
+    .with_backend(BackendId::Gemini)
+}
+
+fn key_bundle() -> KeyBundle {
+    KeyBundle {
+        // Not `BundleManifest::new`, which records the crate version
+        manifest: BundleManifest {
+            backend: BackendId::Gemini,
+            generator: "golden".to_string(),
+        },
+        proving_key: b"proving key".to_vec(),
+        verifying_key: b"verifying key".to_vec(),
+    }
+}
+
+#[test]
+fn test_public_inputs_golden() {
+    let wrapper = InputsSerialisationWrapper(public_inputs());
+
+    check("public_inputs.bin", &encode_public_inputs(&public_inputs()));
+    check(
+        "public_inputs.bincode",
+        &Codec::Bincode.encode(&wrapper).unwrap(),
+    );
+    check(
+        "public_inputs.json",
+        serde_json::to_string(&wrapper).unwrap().as_bytes(),
+    );
+}
+
+#[test]
+fn test_proof_bundle_golden() {
+    let bundle = proof_bundle();
+
+    check(
+        "proof_bundle.bincode",
+        &bundle.encode(Codec::Bincode).unwrap(),
+    );
+    check(
+        "proof_bundle.artifact",
+        &encode_artifact(&bundle, Codec::Bincode).unwrap(),
+    );
+}
+
+#[test]
+fn test_key_headers_golden() {
+    check(
+        "key_header_v1.bin",
+        &ArtifactHeader::new(Codec::Bincode).to_bytes(),
+    );
+    check(
+        "key_header_v2.bin",
+        &ArtifactHeader::with_circuit_hash(Codec::Bincode, [0x11; 32]).to_bytes(),
+    );
+    check("key_bundle.fibz", &key_bundle().to_bytes().unwrap());
+}
+
+// Stored proofs and keys outlive format versions, the files of every version must still decode
+#[test]
+fn test_golden_files_decode() {
+    for version in 1..=FORMAT_VERSION {
+        let read = |name: &str| fs::read(version_dir(version).join(name)).unwrap();
+
+        assert_eq!(
+            decode_public_inputs(&read("public_inputs.bin")).unwrap(),
+            public_inputs()
+        );
+        let wrapper: InputsSerialisationWrapper = Codec::Bincode
+            .decode(&read("public_inputs.bincode"))
+            .unwrap();
+        assert_eq!(wrapper.0, public_inputs());
+        let wrapper: InputsSerialisationWrapper =
+            serde_json::from_slice(&read("public_inputs.json")).unwrap();
+        assert_eq!(wrapper.0, public_inputs());
+
+        assert_eq!(
+            ProofBundle::decode(&read("proof_bundle.bincode"), Codec::Bincode).unwrap(),
+            proof_bundle()
+        );
+        assert_eq!(
+            decode_artifact::<ProofBundle>(&read("proof_bundle.artifact")).unwrap(),
+            proof_bundle()
+        );
+
+        let (header, rest) = ArtifactHeader::parse(&read("key_header_v2.bin"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(header.circuit_hash, Some([0x11; 32]));
+        assert!(rest.is_empty());
+        assert_eq!(
+            KeyBundle::from_bytes(&read("key_bundle.fibz")).unwrap(),
+            key_bundle()
+        );
+    }
+}
@@ -0,0 +1,948 @@
+//! The `fibonacci` command line tool, proving and verifying with any backend of the workspace.
+//!
+//! Exit codes are stable so shell pipelines and CI jobs can act on them: `verify` exits with
+//! `VALID` or `INVALID` when the proof could be checked, and with one of the codes from 2 on when
+//! it couldn't, see `error_exit_code`. With `--json` every command prints a `CommandReport`
+//! instead of its text output, see `report`.
+use std::{
+    collections::HashMap,
+    error::Error,
+    fs,
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, Instant, SystemTime},
+};
+
+use clap::{Args, CommandFactory, Parser, Subcommand};
+use clap_complete::Shell;
+use fibonacci_circuit::{
+    keygen::{file_fingerprint, KeyFingerprint},
+    merkle::DEFAULT_MERKLE_DEPTH,
+    serialisation::Codec,
+    BackendId, ErrorCode, ErrorMessage, ErrorReport, FibonacciError, ProofBundle,
+    VerificationOutcome,
+};
+
+pub mod report;
+use report::{Artifact, CommandReport};
+
+/// The proof is valid, or the command succeeded
+pub const VALID: u8 = 0;
+/// The verifier rejected the proof
+pub const INVALID: u8 = 1;
+/// Malformed arguments, inputs or bundles, also used by clap for usage errors
+pub const USAGE: u8 = 2;
+/// The SRS or keys can't be used, e.g. keys of another circuit version
+pub const SETUP: u8 = 3;
+/// Files couldn't be read or written
+pub const IO: u8 = 4;
+/// Any other failure
+pub const INTERNAL: u8 = 5;
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "fibonacci",
+    version,
+    about = "Prove and verify Fibonacci proofs"
+)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Prove the inputs of a JSON file and write the proof bundle
+    Prove(ProveArgs),
+    /// Verify a proof bundle
+    Verify(VerifyArgs),
+    /// Keys, proofs and verification of the Merkle inclusion example
+    #[command(subcommand)]
+    Merkle(MerkleCommand),
+    /// Print the completion script of a shell, e.g. `fibonacci completions bash`
+    Completions {
+        #[arg(value_enum)]
+        shell: Shell,
+    },
+}
+
+#[derive(Debug, Args)]
+pub struct ProveArgs {
+    /// Circuit inputs as JSON, e.g. `{"out": ["55"]}`
+    #[arg(required_unless_present = "watch")]
+    pub input: Option<PathBuf>,
+    /// Prove every JSON file appearing in this directory, writing proof bundles next to them
+    #[arg(long, conflicts_with_all = ["input", "out"])]
+    pub watch: Option<PathBuf>,
+    /// plonk, hyperplonk, gemini or unihyperplonk
+    #[arg(long, value_parser = parse_backend)]
+    pub backend: BackendId,
+    #[arg(long)]
+    pub srs: PathBuf,
+    #[arg(long)]
+    pub proving_key: PathBuf,
+    /// Where to write the proof bundle, the input path with a `proof` extension by default
+    #[arg(long)]
+    pub out: Option<PathBuf>,
+    /// Print a JSON report instead, one line per input in watch mode
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct VerifyArgs {
+    /// Proof bundle written by `prove`
+    #[arg(required_unless_present = "stdin")]
+    pub bundle: Option<PathBuf>,
+    /// Read the bundle from standard input, e.g. piped from `curl`, and print the outcome as JSON
+    #[arg(long, conflicts_with = "bundle")]
+    pub stdin: bool,
+    #[arg(long)]
+    pub verifying_key: PathBuf,
+    /// Only read by the plonk backend
+    #[arg(long)]
+    pub srs: Option<PathBuf>,
+    /// Backend of bundles which don't record it
+    #[arg(long, value_parser = parse_backend)]
+    pub backend: Option<BackendId>,
+    /// Print the outcome as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum MerkleCommand {
+    /// Generate the keys of paths of a given depth
+    Keygen(MerkleKeygenArgs),
+    /// Prove the leaf and path of a JSON file and write the proof bundle
+    Prove(MerkleProveArgs),
+    /// Verify a proof bundle of the Merkle example
+    Verify(MerkleVerifyArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct MerkleKeygenArgs {
+    /// hyperplonk, gemini or unihyperplonk
+    #[arg(long, value_parser = parse_backend)]
+    pub backend: BackendId,
+    /// SRS of at least 2^k points for the k of the depth, 2^9 for the default depth
+    #[arg(long)]
+    pub srs: PathBuf,
+    /// Levels of the paths, the tree has 2^depth leaves
+    #[arg(long, default_value_t = DEFAULT_MERKLE_DEPTH)]
+    pub depth: usize,
+    /// Directory to write `merkle.pk` and `merkle.vk` to
+    #[arg(long, default_value = ".")]
+    pub out_dir: PathBuf,
+    /// Print a JSON report instead
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct MerkleProveArgs {
+    /// Leaf and path as JSON, e.g. `{"leaf": "5", "path": [{"sibling": "7", "is_right": true}]}`
+    pub input: PathBuf,
+    #[arg(long, value_parser = parse_backend)]
+    pub backend: BackendId,
+    #[arg(long)]
+    pub proving_key: PathBuf,
+    /// Where to write the proof bundle, the input path with a `proof` extension by default
+    #[arg(long)]
+    pub out: Option<PathBuf>,
+    /// Print a JSON report instead, one line per input in watch mode
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct MerkleVerifyArgs {
+    /// Proof bundle written by `merkle prove`
+    pub bundle: PathBuf,
+    #[arg(long)]
+    pub verifying_key: PathBuf,
+    /// Levels of the paths the verifying key was generated for
+    #[arg(long, default_value_t = DEFAULT_MERKLE_DEPTH)]
+    pub depth: usize,
+    /// Backend of bundles which don't record it
+    #[arg(long, value_parser = parse_backend)]
+    pub backend: Option<BackendId>,
+    /// Print the outcome as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+fn parse_backend(name: &str) -> Result<BackendId, FibonacciError> {
+    BackendId::from_name(name)
+}
+
+fn read(path: &Path) -> Result<Vec<u8>, FibonacciError> {
+    fs::read(path).map_err(|e| {
+        FibonacciError::Io(ErrorMessage::with_source(
+            format!("Couldn't read '{}': {}", path.display(), e),
+            e,
+        ))
+    })
+}
+
+// Through a temporary file, so watchers of the output directory never see a partial file
+fn write(path: &Path, contents: &[u8]) -> Result<(), FibonacciError> {
+    fibonacci_circuit::io::write_atomic(path, contents, true)
+}
+
+/// Prove the inputs of `args.input`, returning the path the proof bundle was written to.
+pub fn prove(args: &ProveArgs) -> Result<PathBuf, Box<dyn Error>> {
+    let input = args
+        .input
+        .as_deref()
+        .ok_or_else(|| FibonacciError::InvalidInput("Pass an input file or --watch".into()))?;
+    let out = args.out.clone().unwrap_or_else(|| proof_path(input));
+
+    Prover::load(args)?.prove(input, &out)?;
+    Ok(out)
+}
+
+/// Prove every JSON file of `dir` whenever one appears or changes, until the directory can't be
+/// read anymore. Each proof bundle is written next to its input, see
+/// `proof_path`, and failures to an error report at `error_path`. `report` is called with the
+/// result of every input, the time proving it took and the SRS and proving key, fingerprinted
+/// once when they're loaded.
+///
+/// Writers should move finished files into the directory, files are picked up as soon as their
+/// modification time is at least as new as their outputs' and their contents differ from those
+/// last proven.
+pub fn watch(
+    args: &ProveArgs,
+    dir: &Path,
+    mut report: impl FnMut(&Path, &Result<PathBuf, Box<dyn Error>>, Duration, &[Artifact]),
+) -> Result<(), Box<dyn Error>> {
+    let mut prover = Prover::load(args)?;
+    loop {
+        for (input, result, elapsed) in prover.prove_pending(dir)? {
+            report(&input, &result, elapsed, &prover.artifacts);
+        }
+        thread::sleep(WATCH_INTERVAL);
+    }
+}
+
+/// How often watch mode looks for new inputs
+pub const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Proof bundle written for `input`, `input.json` is proven to `input.proof`
+pub fn proof_path(input: &Path) -> PathBuf {
+    input.with_extension("proof")
+}
+
+/// `ErrorReport` written in watch mode when proving `input` fails, `input.json` fails with
+/// `input.error`
+pub fn error_path(input: &Path) -> PathBuf {
+    input.with_extension("error")
+}
+
+/// Input of watch mode, the path of its proof bundle or why proving it failed, and the time it
+/// took
+type ProvenInput = (PathBuf, Result<PathBuf, Box<dyn Error>>, Duration);
+
+/// Backend and artifacts of `prove`, read once for all inputs of watch mode
+struct Prover {
+    backend: BackendId,
+    srs_key: Vec<u8>,
+    proving_key: Vec<u8>,
+    /// The SRS and proving key for the reports, fingerprinted once rather than for every input
+    artifacts: Vec<Artifact>,
+    /// Fingerprint of the contents of every input watch mode proved, or failed to
+    proven: HashMap<PathBuf, KeyFingerprint>,
+}
+
+impl Prover {
+    fn load(args: &ProveArgs) -> Result<Self, FibonacciError> {
+        let srs_key = read(&args.srs)?;
+        let proving_key = read(&args.proving_key)?;
+        let artifacts = vec![
+            Artifact::from_contents("srs", &args.srs, &srs_key),
+            Artifact::from_contents("proving_key", &args.proving_key, &proving_key),
+        ];
+        Ok(Self {
+            backend: args.backend,
+            srs_key,
+            proving_key,
+            artifacts,
+            proven: HashMap::new(),
+        })
+    }
+
+    fn prove(&self, input: &Path, out: &Path) -> Result<(), Box<dyn Error>> {
+        let input = fibonacci_mobile::parse_input(&read(input)?)?;
+        let bundle = ProofBundle::from(fibonacci_mobile::prove(
+            self.backend,
+            &self.srs_key,
+            &self.proving_key,
+            input,
+        )?)
+        .with_backend(self.backend);
+
+        write(out, &bundle.encode(Codec::Bincode)?)?;
+        Ok(())
+    }
+
+    /// Prove the JSON files of `dir` which are pending, see `is_pending`.
+    fn prove_pending(&mut self, dir: &Path) -> Result<Vec<ProvenInput>, FibonacciError> {
+        let entries = fs::read_dir(dir).map_err(|e| {
+            FibonacciError::Io(ErrorMessage::with_source(
+                format!("Couldn't read '{}': {}", dir.display(), e),
+                e,
+            ))
+        })?;
+        let mut inputs: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                path.extension()
+                    .is_some_and(|extension| extension == "json")
+            })
+            .filter(|path| self.is_pending(path))
+            .collect();
+        inputs.sort();
+
+        let mut proven = Vec::with_capacity(inputs.len());
+        for input in inputs {
+            let fingerprint = file_fingerprint(&input).ok();
+            let out = proof_path(&input);
+            let started = Instant::now();
+            let result = self.prove(&input, &out).map(|_| out);
+            let elapsed = started.elapsed();
+            if let Err(e) = &result {
+                let report =
+                    ErrorReport::from_error(e.as_ref()).with_context("input", input.display());
+                // Without the report the input is retried on the next pass
+                let _ = write(&error_path(&input), report.to_json().as_bytes());
+            }
+            if let Some(fingerprint) = fingerprint {
+                self.proven.insert(input.clone(), fingerprint);
+            }
+            proven.push((input, result, elapsed));
+        }
+        Ok(proven)
+    }
+
+    /// Whether `input` has neither a proof bundle nor an error report newer than it. Filesystems
+    /// with coarse timestamps give an input rewritten right after it was proven the same
+    /// modification time as its output, so on a tie the contents are compared with those last
+    /// proven instead.
+    fn is_pending(&self, input: &Path) -> bool {
+        let Some(input_modified) = modified(input) else {
+            return false;
+        };
+        let output_modified = [proof_path(input), error_path(input)]
+            .iter()
+            .filter_map(|output| modified(output))
+            .max();
+        match output_modified {
+            Some(output) if output > input_modified => false,
+            Some(output) if output == input_modified => {
+                self.proven.get(input).map_or(true, |proven| {
+                    file_fingerprint(input).map_or(true, |fingerprint| fingerprint != *proven)
+                })
+            }
+            _ => true,
+        }
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+/// Verify the bundle of `args.bundle`, or of standard input without one. Failures to read or
+/// decode the artifacts are part of the outcome, so they're reported in the same format.
+pub fn verify(args: &VerifyArgs) -> VerificationOutcome {
+    match &args.bundle {
+        Some(path) => verify_encoded(args, read(path)),
+        None => verify_from(args, io::stdin().lock()),
+    }
+}
+
+/// Verify the bincode-encoded bundle `reader` returns until its end, as `verify --stdin` does
+/// with standard input.
+pub fn verify_from(args: &VerifyArgs, mut reader: impl Read) -> VerificationOutcome {
+    let mut bytes = Vec::new();
+    let bytes = reader.read_to_end(&mut bytes).map(|_| bytes).map_err(|e| {
+        FibonacciError::Io(ErrorMessage::with_source(
+            format!("Couldn't read the bundle: {}", e),
+            e,
+        ))
+    });
+    verify_encoded(args, bytes)
+}
+
+fn verify_encoded(
+    args: &VerifyArgs,
+    bytes: Result<Vec<u8>, FibonacciError>,
+) -> VerificationOutcome {
+    let bundle = match bytes.and_then(|bytes| ProofBundle::decode(&bytes, Codec::Bincode)) {
+        Ok(bundle) => bundle,
+        Err(e) => return VerificationOutcome::from_result(args.backend, Err(e.into())),
+    };
+    let backend = bundle.backend.or(args.backend);
+
+    VerificationOutcome::from_result(backend, verify_bundle(args, bundle, backend))
+}
+
+fn verify_bundle(
+    args: &VerifyArgs,
+    bundle: ProofBundle,
+    backend: Option<BackendId>,
+) -> Result<bool, Box<dyn Error>> {
+    let backend = backend.ok_or_else(|| {
+        FibonacciError::InvalidInput("The bundle doesn't record its backend, pass --backend".into())
+    })?;
+    if args.backend.is_some_and(|requested| requested != backend) {
+        return Err(FibonacciError::InvalidInput(
+            format!("The bundle holds a {} proof", backend.name()).into(),
+        )
+        .into());
+    }
+    bundle.validate()?;
+
+    let srs_key = match &args.srs {
+        Some(path) => read(path)?,
+        None => Vec::new(),
+    };
+    let verifying_key = read(&args.verifying_key)?;
+
+    fibonacci_mobile::verify(
+        backend,
+        &srs_key,
+        &verifying_key,
+        &bundle.proof,
+        &bundle.public_inputs,
+    )
+}
+
+/// Generate the keys of the Merkle example, returning the paths of the proving and verifying
+/// keys.
+pub fn merkle_keygen(args: &MerkleKeygenArgs) -> Result<(PathBuf, PathBuf), Box<dyn Error>> {
+    let (proving_key, verifying_key) =
+        fibonacci_mobile::merkle::keygen(args.backend, &read(&args.srs)?, args.depth)?;
+
+    let paths = (
+        args.out_dir.join("merkle.pk"),
+        args.out_dir.join("merkle.vk"),
+    );
+    write(&paths.0, &proving_key)?;
+    write(&paths.1, &verifying_key)?;
+    Ok(paths)
+}
+
+/// Prove the leaf and path of `args.input`, returning the path the proof bundle was written to.
+pub fn merkle_prove(args: &MerkleProveArgs) -> Result<PathBuf, Box<dyn Error>> {
+    let input = fibonacci_mobile::merkle::parse_input(&read(&args.input)?)?;
+    let bundle = ProofBundle::from(fibonacci_mobile::merkle::prove(
+        args.backend,
+        &read(&args.proving_key)?,
+        &input,
+    )?)
+    .with_backend(args.backend);
+
+    let out = args.out.clone().unwrap_or_else(|| proof_path(&args.input));
+    write(&out, &bundle.encode(Codec::Bincode)?)?;
+    Ok(out)
+}
+
+/// Verify a bundle of `merkle prove`. Its public inputs are a leaf and a root rather than those
+/// of the Fibonacci circuit, so the bundle isn't checked with `ProofBundle::validate`.
+pub fn merkle_verify(args: &MerkleVerifyArgs) -> VerificationOutcome {
+    let bundle =
+        match read(&args.bundle).and_then(|bytes| ProofBundle::decode(&bytes, Codec::Bincode)) {
+            Ok(bundle) => bundle,
+            Err(e) => return VerificationOutcome::from_result(args.backend, Err(e.into())),
+        };
+    let backend = bundle.backend.or(args.backend);
+
+    VerificationOutcome::from_result(backend, verify_merkle_bundle(args, bundle, backend))
+}
+
+fn verify_merkle_bundle(
+    args: &MerkleVerifyArgs,
+    bundle: ProofBundle,
+    backend: Option<BackendId>,
+) -> Result<bool, Box<dyn Error>> {
+    let backend = backend.ok_or_else(|| {
+        FibonacciError::InvalidInput("The bundle doesn't record its backend, pass --backend".into())
+    })?;
+
+    fibonacci_mobile::merkle::verify(
+        backend,
+        &read(&args.verifying_key)?,
+        &bundle.proof,
+        &bundle.public_inputs,
+        args.depth,
+    )
+}
+
+/// SRS and proving key of `args` for `prove_report`, read to fingerprint them.
+pub fn prove_artifacts(args: &ProveArgs) -> Vec<Artifact> {
+    [("srs", &args.srs), ("proving_key", &args.proving_key)]
+        .into_iter()
+        .filter_map(|(role, path)| Artifact::of(role, path))
+        .collect()
+}
+
+/// `--json` report of proving `input` to `out` with the SRS and proving key `artifacts` of
+/// `args`, see `prove_artifacts`.
+pub fn prove_report<T>(
+    args: &ProveArgs,
+    input: &Path,
+    out: &Path,
+    artifacts: &[Artifact],
+    elapsed: Duration,
+    result: &Result<T, Box<dyn Error>>,
+) -> CommandReport {
+    let report = CommandReport::new("prove", Some(args.backend))
+        .with_artifact("input", input)
+        .with_artifacts(artifacts);
+    // A failed proof may leave the bundle of an earlier run behind
+    match result {
+        Ok(_) => report.with_artifact("bundle", out),
+        Err(_) => report,
+    }
+    .finish(elapsed, result)
+}
+
+/// `--json` report of watch mode, which only stops on the error of `result`.
+pub fn watch_report(
+    args: &ProveArgs,
+    elapsed: Duration,
+    result: &Result<(), Box<dyn Error>>,
+) -> CommandReport {
+    CommandReport::new("prove", Some(args.backend))
+        .with_artifacts(&prove_artifacts(args))
+        .finish(elapsed, result)
+}
+
+/// `--json` report of `verify`, with its `outcome`.
+pub fn verify_report(
+    args: &VerifyArgs,
+    elapsed: Duration,
+    outcome: &VerificationOutcome,
+) -> CommandReport {
+    let mut report = CommandReport::new("verify", args.backend);
+    if let Some(bundle) = &args.bundle {
+        report = report.with_artifact("bundle", bundle);
+    }
+    if let Some(srs) = &args.srs {
+        report = report.with_artifact("srs", srs);
+    }
+    report
+        .with_artifact("verifying_key", &args.verifying_key)
+        .finish_verification(elapsed, outcome)
+}
+
+/// `--json` report of `merkle keygen`.
+pub fn merkle_keygen_report(
+    args: &MerkleKeygenArgs,
+    elapsed: Duration,
+    result: &Result<(PathBuf, PathBuf), Box<dyn Error>>,
+) -> CommandReport {
+    let mut report =
+        CommandReport::new("merkle keygen", Some(args.backend)).with_artifact("srs", &args.srs);
+    if let Ok((proving_key, verifying_key)) = result {
+        report = report
+            .with_artifact("proving_key", proving_key)
+            .with_artifact("verifying_key", verifying_key);
+    }
+    report.finish(elapsed, result)
+}
+
+/// `--json` report of `merkle prove`.
+pub fn merkle_prove_report(
+    args: &MerkleProveArgs,
+    elapsed: Duration,
+    result: &Result<PathBuf, Box<dyn Error>>,
+) -> CommandReport {
+    let mut report = CommandReport::new("merkle prove", Some(args.backend))
+        .with_artifact("input", &args.input)
+        .with_artifact("proving_key", &args.proving_key);
+    if let Ok(out) = result {
+        report = report.with_artifact("bundle", out);
+    }
+    report.finish(elapsed, result)
+}
+
+/// `--json` report of `merkle verify`, with its `outcome`.
+pub fn merkle_verify_report(
+    args: &MerkleVerifyArgs,
+    elapsed: Duration,
+    outcome: &VerificationOutcome,
+) -> CommandReport {
+    CommandReport::new("merkle verify", args.backend)
+        .with_artifact("bundle", &args.bundle)
+        .with_artifact("verifying_key", &args.verifying_key)
+        .finish_verification(elapsed, outcome)
+}
+
+/// Write the completion script of `shell` for the `fibonacci` binary to `out`.
+pub fn completions(shell: Shell, out: &mut impl Write) {
+    clap_complete::generate(shell, &mut Cli::command(), "fibonacci", out);
+}
+
+/// Exit code of a failure with `code`.
+pub fn error_exit_code(code: ErrorCode) -> u8 {
+    match code {
+        ErrorCode::Verification => INVALID,
+        ErrorCode::InvalidInput | ErrorCode::Serialization | ErrorCode::Transcript => USAGE,
+        ErrorCode::Setup
+        | ErrorCode::SrsTooSmall
+        | ErrorCode::CircuitMismatch
+        | ErrorCode::Encryption
+        | ErrorCode::Signature => SETUP,
+        ErrorCode::Io => IO,
+        ErrorCode::Proving
+        | ErrorCode::Profiling
+        | ErrorCode::Unavailable
+        | ErrorCode::Internal => INTERNAL,
+    }
+}
+
+/// Exit code of `verify`: `VALID`, `INVALID` or the code of the error which kept the proof from
+/// being checked.
+pub fn exit_code(outcome: &VerificationOutcome) -> u8 {
+    if outcome.valid {
+        return VALID;
+    }
+    outcome
+        .error
+        .as_ref()
+        .map_or(INVALID, |error| error_exit_code(error.code))
+}
+
+/// Exit code of a failed command other than `verify`.
+pub fn command_exit_code(error: &(dyn Error + 'static)) -> u8 {
+    error_exit_code(ErrorReport::from_error(error).code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exit_codes() {
+        let outcome = |result: Result<bool, FibonacciError>| {
+            VerificationOutcome::from_result(None, result.map_err(|e| e.into()))
+        };
+
+        assert_eq!(exit_code(&outcome(Ok(true))), VALID);
+        assert_eq!(exit_code(&outcome(Ok(false))), INVALID);
+        assert_eq!(
+            exit_code(&outcome(Err(FibonacciError::Verification(
+                String::new().into()
+            )))),
+            INVALID
+        );
+        assert_eq!(
+            exit_code(&outcome(Err(FibonacciError::Serialization(
+                String::new().into()
+            )))),
+            USAGE
+        );
+        assert_eq!(
+            exit_code(&outcome(Err(FibonacciError::CircuitMismatch(
+                String::new().into()
+            )))),
+            SETUP
+        );
+        assert_eq!(
+            exit_code(&outcome(Err(FibonacciError::Io(String::new().into())))),
+            IO
+        );
+
+        let io_error: Box<dyn Error> = Box::new(std::io::Error::other("disk full"));
+        assert_eq!(command_exit_code(io_error.as_ref()), IO);
+        let other: Box<dyn Error> = "unexpected".into();
+        assert_eq!(command_exit_code(other.as_ref()), INTERNAL);
+    }
+
+    #[test]
+    fn test_prove_and_verify() {
+        let dir = std::env::temp_dir().join(format!("fibonacci-cli-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let srs = Path::new(env!("CARGO_MANIFEST_DIR")).join("../gemini/unihyperplonk-srs-4");
+        let (proving_key, verifying_key) =
+            fibonacci_mobile::keygen(BackendId::Gemini, &fs::read(&srs).unwrap()).unwrap();
+        fs::write(dir.join("pk"), proving_key).unwrap();
+        fs::write(dir.join("vk"), verifying_key).unwrap();
+
+        let input = HashMap::from([("out", vec!["55"])]);
+        fs::write(dir.join("input.json"), serde_json::to_vec(&input).unwrap()).unwrap();
+
+        let bundle = prove(&ProveArgs {
+            input: Some(dir.join("input.json")),
+            watch: None,
+            backend: BackendId::Gemini,
+            srs: srs.clone(),
+            proving_key: dir.join("pk"),
+            out: None,
+            json: false,
+        })
+        .unwrap();
+        assert_eq!(bundle, dir.join("input.proof"));
+
+        let mut args = VerifyArgs {
+            bundle: Some(bundle.clone()),
+            stdin: false,
+            verifying_key: dir.join("vk"),
+            srs: None,
+            backend: None,
+            json: true,
+        };
+        let outcome = verify(&args);
+        assert_eq!(outcome.backend, Some(BackendId::Gemini));
+        assert_eq!(exit_code(&outcome), VALID);
+
+        // The report keeps the fields of the outcome and fingerprints the files read
+        let report = verify_report(&args, Duration::from_millis(5), &outcome);
+        let json: serde_json::Value = serde_json::from_str(&report.to_json()).unwrap();
+        assert_eq!(json["valid"], true);
+        assert_eq!(json["backend"], "Gemini");
+        assert_eq!(
+            serde_json::from_value::<VerificationOutcome>(json).unwrap(),
+            outcome
+        );
+        let roles: Vec<_> = report.artifacts.iter().map(|a| a.role.as_str()).collect();
+        assert_eq!(roles, ["bundle", "verifying_key"]);
+        assert_eq!(
+            report.artifacts[0].bytes,
+            fs::metadata(&bundle).unwrap().len()
+        );
+        assert_eq!(CommandReport::from_json(&report.to_json()).unwrap(), report);
+
+        // The same bundle streamed like `--stdin` reads it
+        let outcome = verify_from(&args, fs::File::open(&bundle).unwrap());
+        assert_eq!(exit_code(&outcome), VALID);
+        assert_eq!(exit_code(&verify_from(&args, &b"not a bundle"[..])), USAGE);
+
+        // A bundle of another backend than requested
+        args.backend = Some(BackendId::HyperPlonk);
+        assert_eq!(exit_code(&verify(&args)), USAGE);
+        args.backend = None;
+
+        // Tampered public inputs
+        let mut tampered =
+            ProofBundle::decode(&fs::read(&bundle).unwrap(), Codec::Bincode).unwrap();
+        let last = tampered.public_inputs.len() - 32;
+        tampered.public_inputs[last] ^= 1;
+        fs::write(
+            dir.join("tampered.proof"),
+            tampered.encode(Codec::Bincode).unwrap(),
+        )
+        .unwrap();
+        args.bundle = Some(dir.join("tampered.proof"));
+        assert_eq!(exit_code(&verify(&args)), INVALID);
+
+        args.verifying_key = dir.join("missing-vk");
+        assert_eq!(exit_code(&verify(&args)), IO);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_merkle_errors() {
+        let dir = std::env::temp_dir().join(format!("fibonacci-cli-merkle-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fn exit_code_of<T>(result: Result<T, Box<dyn Error>>) -> u8 {
+            command_exit_code(result.err().expect("the command should fail").as_ref())
+        }
+
+        // A malformed SRS is reported rather than panicking, and no keys are written
+        fs::write(dir.join("garbage-srs"), b"not an srs").unwrap();
+        let mut keygen_args = MerkleKeygenArgs {
+            backend: BackendId::Gemini,
+            srs: dir.join("garbage-srs"),
+            depth: 1,
+            out_dir: dir.clone(),
+            json: false,
+        };
+        assert_eq!(exit_code_of(merkle_keygen(&keygen_args)), SETUP);
+        assert!(!dir.join("merkle.pk").exists());
+
+        // The shipped SRS is too small for the Merkle circuit
+        keygen_args.srs =
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("../gemini/unihyperplonk-srs-4");
+        let error = merkle_keygen(&keygen_args).unwrap_err();
+        assert_eq!(
+            ErrorReport::from_error(&*error).code,
+            ErrorCode::SrsTooSmall
+        );
+        assert!(!dir.join("merkle.vk").exists());
+
+        keygen_args.srs = dir.join("missing-srs");
+        assert_eq!(exit_code_of(merkle_keygen(&keygen_args)), IO);
+
+        keygen_args.backend = BackendId::Plonk;
+        keygen_args.srs = dir.join("garbage-srs");
+        assert!(merkle_keygen(&keygen_args).is_err());
+
+        let mut prove_args = MerkleProveArgs {
+            input: dir.join("input.json"),
+            backend: BackendId::Gemini,
+            proving_key: dir.join("missing-pk"),
+            out: None,
+            json: false,
+        };
+        fs::write(&prove_args.input, "not json").unwrap();
+        assert_eq!(exit_code_of(merkle_prove(&prove_args)), USAGE);
+        fs::write(
+            &prove_args.input,
+            r#"{"leaf": "5", "path": [{"sibling": "7", "is_right": true}]}"#,
+        )
+        .unwrap();
+        assert_eq!(exit_code_of(merkle_prove(&prove_args)), IO);
+        fs::write(dir.join("garbage-pk"), b"not a key").unwrap();
+        prove_args.proving_key = dir.join("garbage-pk");
+        assert!(merkle_prove(&prove_args).is_err());
+        assert!(!dir.join("input.proof").exists());
+
+        // Bundles without a backend need `--backend`, and a missing key is an IO error
+        fs::write(
+            dir.join("bundle.proof"),
+            ProofBundle::new(vec![1, 2, 3], Vec::new())
+                .encode(Codec::Bincode)
+                .unwrap(),
+        )
+        .unwrap();
+        let mut verify_args = MerkleVerifyArgs {
+            bundle: dir.join("bundle.proof"),
+            verifying_key: dir.join("missing-vk"),
+            depth: 1,
+            backend: None,
+            json: false,
+        };
+        assert_eq!(exit_code(&merkle_verify(&verify_args)), USAGE);
+        verify_args.backend = Some(BackendId::Gemini);
+        assert_eq!(exit_code(&merkle_verify(&verify_args)), IO);
+        verify_args.bundle = dir.join("missing.proof");
+        assert_eq!(exit_code(&merkle_verify(&verify_args)), IO);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_completions() {
+        let mut script = Vec::new();
+        completions(Shell::Bash, &mut script);
+        let script = String::from_utf8(script).unwrap();
+        assert!(script.contains("fibonacci"));
+        assert!(script.contains("merkle"));
+        assert!(script.contains("--verifying-key"));
+    }
+
+    #[test]
+    fn test_watch_pending() {
+        let dir = std::env::temp_dir().join(format!("fibonacci-watch-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let srs = Path::new(env!("CARGO_MANIFEST_DIR")).join("../gemini/unihyperplonk-srs-4");
+        let (proving_key, _) =
+            fibonacci_mobile::keygen(BackendId::Gemini, &fs::read(&srs).unwrap()).unwrap();
+        fs::write(dir.join("pk"), proving_key).unwrap();
+        let mut prover = Prover::load(&ProveArgs {
+            input: None,
+            watch: Some(dir.clone()),
+            backend: BackendId::Gemini,
+            srs,
+            proving_key: dir.join("pk"),
+            out: None,
+            json: false,
+        })
+        .unwrap();
+
+        fs::write(dir.join("a.json"), r#"{"out": ["55"]}"#).unwrap();
+        fs::write(dir.join("b.json"), "not json").unwrap();
+
+        let results = prover.prove_pending(&dir).unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].1.as_ref().unwrap(), &dir.join("a.proof"));
+        assert!(results[1].1.is_err());
+        assert!(
+            ProofBundle::decode(&fs::read(dir.join("a.proof")).unwrap(), Codec::Bincode)
+                .unwrap()
+                .validate()
+                .is_ok()
+        );
+        let report: ErrorReport =
+            serde_json::from_slice(&fs::read(dir.join("b.error")).unwrap()).unwrap();
+        assert_eq!(report.code, ErrorCode::InvalidInput);
+
+        let args = ProveArgs {
+            input: None,
+            watch: Some(dir.clone()),
+            backend: BackendId::Gemini,
+            srs: Path::new(env!("CARGO_MANIFEST_DIR")).join("../gemini/unihyperplonk-srs-4"),
+            proving_key: dir.join("pk"),
+            out: None,
+            json: true,
+        };
+        let (input, result, elapsed) = &results[1];
+        let report = prove_report(
+            &args,
+            input,
+            &proof_path(input),
+            &prover.artifacts,
+            *elapsed,
+            result,
+        );
+        assert!(!report.ok);
+        assert_eq!(report.error.unwrap().code, ErrorCode::InvalidInput);
+        let (input, result, elapsed) = &results[0];
+        let report = prove_report(
+            &args,
+            input,
+            &proof_path(input),
+            &prover.artifacts,
+            *elapsed,
+            result,
+        );
+        assert!(report.ok);
+        assert_eq!(report.artifacts.len(), 4);
+        // Fingerprints are of the contents, those of the loaded files match those of the files
+        assert_eq!(prover.artifacts, prove_artifacts(&args));
+        assert_eq!(
+            report.artifacts[2].keccak256,
+            Artifact::of("proving_key", &dir.join("pk"))
+                .unwrap()
+                .keccak256
+        );
+
+        let stopped: Result<(), Box<dyn Error>> = Err(FibonacciError::Io("gone".into()).into());
+        let report = watch_report(&args, Duration::ZERO, &stopped);
+        assert!(!report.ok);
+        assert_eq!(report.error.unwrap().code, ErrorCode::Io);
+
+        // Proven and failed inputs aren't picked up again until they change
+        assert!(prover.prove_pending(&dir).unwrap().is_empty());
+
+        // Inputs rewritten within the timestamp resolution of their output are compared by
+        // contents
+        let set_modified_like_output = |input: &str, output: &str| {
+            let output_modified = modified(&dir.join(output)).unwrap();
+            fs::File::options()
+                .write(true)
+                .open(dir.join(input))
+                .unwrap()
+                .set_modified(output_modified)
+                .unwrap();
+        };
+        set_modified_like_output("b.json", "b.error");
+        assert!(prover.prove_pending(&dir).unwrap().is_empty());
+        fs::write(dir.join("a.json"), r#"{"out": ["89"]}"#).unwrap();
+        set_modified_like_output("a.json", "a.proof");
+        let results = prover.prove_pending(&dir).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].0, dir.join("a.json"));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
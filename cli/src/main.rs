@@ -0,0 +1,157 @@
+use std::{
+    error::Error,
+    io,
+    process::ExitCode,
+    time::{Duration, Instant},
+};
+
+use clap::Parser;
+use fibonacci_circuit::VerificationOutcome;
+use fibonacci_cli::{
+    command_exit_code, completions, error_exit_code, exit_code, merkle_keygen,
+    merkle_keygen_report, merkle_prove, merkle_prove_report, merkle_verify, merkle_verify_report,
+    proof_path, prove, prove_artifacts, prove_report, report::CommandReport, verify, verify_report,
+    watch, watch_report, Cli, Command, MerkleCommand,
+};
+
+fn main() -> ExitCode {
+    // Usage errors exit with 2 from clap, like malformed inputs
+    let cli = Cli::parse();
+
+    match cli.command {
+        Command::Prove(args) if args.watch.is_some() => {
+            let dir = args.watch.clone().unwrap();
+            if !args.json {
+                println!("Watching {} for inputs", dir.display());
+            }
+            let started = Instant::now();
+            let result = watch(&args, &dir, |input, result, elapsed, artifacts| {
+                if args.json {
+                    let out = proof_path(input);
+                    let report = prove_report(&args, input, &out, artifacts, elapsed, result);
+                    println!("{}", report.to_json());
+                    return;
+                }
+                match result {
+                    Ok(out) => println!(
+                        "{}: proof bundle written to {}",
+                        input.display(),
+                        out.display()
+                    ),
+                    Err(e) => eprintln!("{}: {}", input.display(), e),
+                }
+            });
+            // Watching only stops on errors
+            if args.json {
+                return print_report(&watch_report(&args, started.elapsed(), &result));
+            }
+            fail(result.unwrap_err().as_ref())
+        }
+        Command::Prove(args) => run(
+            args.json,
+            || prove(&args),
+            |elapsed, result| {
+                let input = args.input.clone().unwrap_or_default();
+                let out = args.out.clone().unwrap_or_else(|| proof_path(&input));
+                let artifacts = prove_artifacts(&args);
+                prove_report(&args, &input, &out, &artifacts, elapsed, result)
+            },
+            |out| format!("Proof bundle written to {}", out.display()),
+        ),
+        Command::Verify(args) => check(
+            args.json || args.stdin,
+            || verify(&args),
+            |elapsed, outcome| verify_report(&args, elapsed, outcome),
+        ),
+        Command::Merkle(MerkleCommand::Keygen(args)) => run(
+            args.json,
+            || merkle_keygen(&args),
+            |elapsed, result| merkle_keygen_report(&args, elapsed, result),
+            |(proving_key, verifying_key)| {
+                format!(
+                    "Keys written to {} and {}",
+                    proving_key.display(),
+                    verifying_key.display()
+                )
+            },
+        ),
+        Command::Merkle(MerkleCommand::Prove(args)) => run(
+            args.json,
+            || merkle_prove(&args),
+            |elapsed, result| merkle_prove_report(&args, elapsed, result),
+            |out| format!("Proof bundle written to {}", out.display()),
+        ),
+        Command::Merkle(MerkleCommand::Verify(args)) => check(
+            args.json,
+            || merkle_verify(&args),
+            |elapsed, outcome| merkle_verify_report(&args, elapsed, outcome),
+        ),
+        Command::Completions { shell } => {
+            completions(shell, &mut io::stdout());
+            ExitCode::SUCCESS
+        }
+    }
+}
+
+/// Run a command other than `verify`, printing its report with `json` and otherwise the message
+/// of its result or the error.
+fn run<T>(
+    json: bool,
+    command: impl FnOnce() -> Result<T, Box<dyn Error>>,
+    report: impl FnOnce(Duration, &Result<T, Box<dyn Error>>) -> CommandReport,
+    message: impl FnOnce(&T) -> String,
+) -> ExitCode {
+    let started = Instant::now();
+    let result = command();
+    if json {
+        return print_report(&report(started.elapsed(), &result));
+    }
+    match result {
+        Ok(value) => {
+            println!("{}", message(&value));
+            ExitCode::SUCCESS
+        }
+        Err(e) => fail(e.as_ref()),
+    }
+}
+
+/// Run `verify` or `merkle verify`, printing its report with `json` and otherwise its outcome.
+fn check(
+    json: bool,
+    verify: impl FnOnce() -> VerificationOutcome,
+    report: impl FnOnce(Duration, &VerificationOutcome) -> CommandReport,
+) -> ExitCode {
+    let started = Instant::now();
+    let outcome = verify();
+    if json {
+        println!("{}", report(started.elapsed(), &outcome).to_json());
+    } else {
+        print_outcome(&outcome);
+    }
+    ExitCode::from(exit_code(&outcome))
+}
+
+/// Print the report of a command other than `verify` and exit with its code.
+fn print_report(report: &CommandReport) -> ExitCode {
+    println!("{}", report.to_json());
+    match &report.error {
+        Some(error) => ExitCode::from(error_exit_code(error.code)),
+        None => ExitCode::SUCCESS,
+    }
+}
+
+fn fail(error: &(dyn Error + 'static)) -> ExitCode {
+    eprintln!("Error: {}", error);
+    ExitCode::from(command_exit_code(error))
+}
+
+fn print_outcome(outcome: &VerificationOutcome) {
+    if outcome.valid {
+        println!("Proof is valid");
+    } else {
+        match &outcome.error {
+            Some(error) if !outcome.is_invalid() => eprintln!("Error: {}", error.message),
+            _ => println!("Proof is invalid"),
+        }
+    }
+}
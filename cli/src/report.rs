@@ -0,0 +1,128 @@
+//! Machine-readable results of the `fibonacci` commands, printed with `--json`.
+//!
+//! Every command prints a single `CommandReport` on standard output: the command, its backend,
+//! how long it ran, the files it read and wrote with their Keccak256 fingerprints, and the error
+//! report if it failed. `verify` keeps the `backend`, `valid` and `error` fields of its
+//! `VerificationOutcome`, so scripts reading the outcome keep working. Watch mode prints one
+//! report per line, for every input it proves.
+use std::{
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use fibonacci_circuit::{
+    hex, keygen::file_fingerprint, BackendId, ErrorReport, FibonacciError, VerificationOutcome,
+};
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Keccak256};
+
+/// File a command read or wrote
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Artifact {
+    /// `input`, `srs`, `proving_key`, `verifying_key` or `bundle`
+    pub role: String,
+    pub path: PathBuf,
+    pub bytes: u64,
+    /// Keccak256 of the contents, hex-encoded
+    pub keccak256: String,
+}
+
+impl Artifact {
+    /// Artifact of the file at `path`, `None` if it can't be read.
+    pub fn of(role: &str, path: &Path) -> Option<Self> {
+        let bytes = fs::metadata(path).ok()?.len();
+        let fingerprint = file_fingerprint(path).ok()?;
+        Some(Self {
+            role: role.to_string(),
+            path: path.to_path_buf(),
+            bytes,
+            keccak256: hex::encode(&fingerprint),
+        })
+    }
+
+    /// Artifact of the file at `path`, from the `contents` already read from it.
+    pub fn from_contents(role: &str, path: &Path, contents: &[u8]) -> Self {
+        Self {
+            role: role.to_string(),
+            path: path.to_path_buf(),
+            bytes: contents.len() as u64,
+            keccak256: hex::encode(&Keccak256::digest(contents)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CommandReport {
+    /// Subcommand, e.g. `prove` or `merkle keygen`
+    pub command: String,
+    /// Whether the command succeeded, i.e. exits with 0
+    pub ok: bool,
+    pub backend: Option<BackendId>,
+    /// Whether the proof is valid, only reported by `verify` and `merkle verify`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub valid: Option<bool>,
+    /// Wall-clock time of the command, in milliseconds
+    pub elapsed_ms: f64,
+    /// Files read and written, those which couldn't be read are left out
+    pub artifacts: Vec<Artifact>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<ErrorReport>,
+}
+
+impl CommandReport {
+    pub fn new(command: &str, backend: Option<BackendId>) -> Self {
+        Self {
+            command: command.to_string(),
+            ok: false,
+            backend,
+            valid: None,
+            elapsed_ms: 0.0,
+            artifacts: Vec::new(),
+            error: None,
+        }
+    }
+
+    /// Record the file at `path`, skipped if it can't be read.
+    pub fn with_artifact(mut self, role: &str, path: &Path) -> Self {
+        self.artifacts.extend(Artifact::of(role, path));
+        self
+    }
+
+    /// Record files recorded before, e.g. those read once for all inputs of watch mode.
+    pub fn with_artifacts(mut self, artifacts: &[Artifact]) -> Self {
+        self.artifacts.extend_from_slice(artifacts);
+        self
+    }
+
+    /// Record the result of a command other than `verify`.
+    pub fn finish<T>(mut self, elapsed: Duration, result: &Result<T, Box<dyn Error>>) -> Self {
+        self.elapsed_ms = elapsed.as_secs_f64() * 1000.0;
+        self.ok = result.is_ok();
+        if let Err(e) = result {
+            self.error = Some(ErrorReport::from_error(e.as_ref()));
+        }
+        self
+    }
+
+    /// Record the outcome of `verify`, which also settles the backend of bundles recording it.
+    pub fn finish_verification(mut self, elapsed: Duration, outcome: &VerificationOutcome) -> Self {
+        self.elapsed_ms = elapsed.as_secs_f64() * 1000.0;
+        self.ok = outcome.valid;
+        self.backend = outcome.backend.or(self.backend);
+        self.valid = Some(outcome.valid);
+        self.error = outcome.error.clone();
+        self
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("Command report should serialize")
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, FibonacciError> {
+        serde_json::from_str(json).map_err(|e| {
+            FibonacciError::Serialization(format!("Invalid command report: {}", e).into())
+        })
+    }
+}
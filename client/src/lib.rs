@@ -0,0 +1,326 @@
+//! Async client of the prover service in `fibonacci-server`.
+//!
+//! Requests and responses use the types of `fibonacci-api`, which the server shares, so a change
+//! of the API fails to compile here rather than at runtime. `ProverClient::prove` submits inputs
+//! and polls the job until its proof bundle is ready or the wait timeout passed, `verify` checks a
+//! bundle with the server's verifying key. Errors the server reports come back as its
+//! `ErrorReport`.
+use std::{collections::HashMap, time::Duration};
+
+pub use fibonacci_api::{CircuitInfo, JobResponse, JobStatus};
+use fibonacci_api::{JobRequest, JobSubmitted, VerifyRequest};
+use fibonacci_circuit::{serialisation::Codec, ErrorCode, FibonacciError};
+pub use fibonacci_circuit::{ErrorReport, ProofBundle, VerificationOutcome};
+use reqwest::{header, RequestBuilder, Response, StatusCode};
+use thiserror::Error;
+
+/// Interval `prove` polls the status of its job in by default
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Time `prove` waits for its job by default before giving up
+pub const DEFAULT_WAIT_TIMEOUT: Duration = Duration::from_secs(600);
+
+#[derive(Debug, Error)]
+pub enum ClientError {
+    /// The server couldn't be reached or its response couldn't be read
+    #[error("Request failed: {0}")]
+    Http(#[from] reqwest::Error),
+    /// The server rejected the request
+    #[error("Server answered {status}: {}", report.message)]
+    Api { status: u16, report: ErrorReport },
+    /// The job was accepted, but proving failed
+    #[error("Job {id} failed: {}", report.message)]
+    JobFailed { id: String, report: ErrorReport },
+    /// The job didn't finish within the wait timeout, it may still be running on the server
+    #[error("Job {id} didn't finish within {timeout:?}")]
+    Timeout { id: String, timeout: Duration },
+    /// The server returned a malformed proof bundle
+    #[error("{0}")]
+    Decode(#[from] FibonacciError),
+}
+
+impl ClientError {
+    /// Whether the same request may succeed later, i.e. the server is loading, shutting down or
+    /// its queue is full, or the job is still running and can be waited for again.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            ClientError::Api { status, .. } => *status == StatusCode::SERVICE_UNAVAILABLE.as_u16(),
+            ClientError::Timeout { .. } => true,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ProverClient {
+    http: reqwest::Client,
+    base_url: String,
+    api_key: Option<String>,
+    poll_interval: Duration,
+    wait_timeout: Duration,
+}
+
+impl ProverClient {
+    /// Client of the server at `base_url`, e.g. `http://localhost:3000`.
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            api_key: None,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            wait_timeout: DEFAULT_WAIT_TIMEOUT,
+        }
+    }
+
+    /// Send `key` with the requests which need one, see `fibonacci_server::auth`.
+    pub fn with_api_key(mut self, key: impl Into<String>) -> Self {
+        self.api_key = Some(key.into());
+        self
+    }
+
+    /// Use `http` for the requests, e.g. one with timeouts or proxies configured.
+    pub fn with_http_client(mut self, http: reqwest::Client) -> Self {
+        self.http = http;
+        self
+    }
+
+    pub fn with_poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    /// Give up waiting for a job after `timeout`, including the time of the polls themselves.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.wait_timeout = timeout;
+        self
+    }
+
+    /// Prove `input` with the only circuit of the server, waiting for the proof.
+    pub async fn prove(
+        &self,
+        input: HashMap<String, Vec<String>>,
+    ) -> Result<ProofBundle, ClientError> {
+        self.prove_circuit(None, input).await
+    }
+
+    /// Prove `input` with the circuit `circuit`, or the only one, waiting for the proof.
+    pub async fn prove_circuit(
+        &self,
+        circuit: Option<&str>,
+        input: HashMap<String, Vec<String>>,
+    ) -> Result<ProofBundle, ClientError> {
+        let id = self.submit(circuit, input).await?;
+        self.wait(&id).await
+    }
+
+    /// Queue a job proving `input`, returning its id. Inputs proven before return the id of the
+    /// existing job.
+    pub async fn submit(
+        &self,
+        circuit: Option<&str>,
+        input: HashMap<String, Vec<String>>,
+    ) -> Result<String, ClientError> {
+        let request = JobRequest {
+            circuit: circuit.map(str::to_string),
+            input,
+        };
+        let request = self.authorized(self.http.post(self.url("/jobs")).json(&request));
+        let submitted: JobSubmitted = check(request.send().await?).await?.json().await?;
+        Ok(submitted.id)
+    }
+
+    /// Status of the job `id`, with its proof bundle once it's done.
+    pub async fn job(&self, id: &str) -> Result<JobResponse, ClientError> {
+        let request = self.http.get(self.url(&format!("/jobs/{}", id)));
+        Ok(check(request.send().await?).await?.json().await?)
+    }
+
+    /// Poll the job `id` until it's finished, returning its proof bundle, or fail with
+    /// `ClientError::Timeout` once the wait timeout passed.
+    pub async fn wait(&self, id: &str) -> Result<ProofBundle, ClientError> {
+        tokio::time::timeout(self.wait_timeout, self.poll(id))
+            .await
+            .map_err(|_| ClientError::Timeout {
+                id: id.to_string(),
+                timeout: self.wait_timeout,
+            })?
+    }
+
+    async fn poll(&self, id: &str) -> Result<ProofBundle, ClientError> {
+        loop {
+            let response = self.job(id).await?;
+            match response.status {
+                JobStatus::Done => {
+                    return match response.bundle {
+                        Some(bundle) => Ok(bundle.decode()?),
+                        // Fall back to the bincode endpoint if the response left it out
+                        None => self.proof(id).await,
+                    };
+                }
+                JobStatus::Failed => {
+                    return Err(ClientError::JobFailed {
+                        id: id.to_string(),
+                        report: response
+                            .error
+                            .unwrap_or_else(|| ErrorReport::new(ErrorCode::Internal, "Job failed")),
+                    })
+                }
+                _ => tokio::time::sleep(self.poll_interval).await,
+            }
+        }
+    }
+
+    /// Proof bundle of the job `id`, which must be done.
+    pub async fn proof(&self, id: &str) -> Result<ProofBundle, ClientError> {
+        let request = self.http.get(self.url(&format!("/proofs/{}", id)));
+        let bytes = check(request.send().await?).await?.bytes().await?;
+        Ok(ProofBundle::decode(&bytes, Codec::Bincode)?)
+    }
+
+    /// Verify `bundle` with the verifying key of the only circuit of the server.
+    pub async fn verify(&self, bundle: &ProofBundle) -> Result<VerificationOutcome, ClientError> {
+        self.verify_circuit(None, bundle).await
+    }
+
+    /// Verify `bundle` with the verifying key of `circuit`, or the only one. A proof which isn't
+    /// valid is an outcome, not an error.
+    pub async fn verify_circuit(
+        &self,
+        circuit: Option<&str>,
+        bundle: &ProofBundle,
+    ) -> Result<VerificationOutcome, ClientError> {
+        let request = VerifyRequest {
+            circuit: circuit.map(str::to_string),
+            bundle: bundle.into(),
+        };
+        let request = self.authorized(self.http.post(self.url("/verify")).json(&request));
+        Ok(check(request.send().await?).await?.json().await?)
+    }
+
+    /// Circuits the server hosts
+    pub async fn circuits(&self) -> Result<Vec<CircuitInfo>, ClientError> {
+        let request = self.http.get(self.url("/circuits"));
+        Ok(check(request.send().await?).await?.json().await?)
+    }
+
+    /// Whether the server has loaded its keys and accepts jobs
+    pub async fn is_ready(&self) -> Result<bool, ClientError> {
+        let response = self.http.get(self.url("/readyz")).send().await?;
+        Ok(response.status().is_success())
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+
+    fn authorized(&self, request: RequestBuilder) -> RequestBuilder {
+        match &self.api_key {
+            Some(key) => request.header(header::AUTHORIZATION, format!("Bearer {}", key)),
+            None => request,
+        }
+    }
+}
+
+/// Turn a response with an error status into `ClientError::Api`, with the server's error report
+/// or one holding the body if it didn't send one.
+async fn check(response: Response) -> Result<Response, ClientError> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let body = response.bytes().await?;
+    let report = serde_json::from_slice(&body)
+        .unwrap_or_else(|_| ErrorReport::new(ErrorCode::Internal, String::from_utf8_lossy(&body)));
+    Err(ClientError::Api {
+        status: status.as_u16(),
+        report,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, future::IntoFuture, path::Path, sync::Arc};
+
+    use fibonacci_circuit::BackendId;
+    use fibonacci_server::{router, AppState, Artifacts, Circuits, JobQueue, JobStore};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_prove_and_verify() {
+        let dir = std::env::temp_dir().join(format!("fibonacci-client-{}", std::process::id()));
+        let srs_key =
+            fs::read(Path::new(env!("CARGO_MANIFEST_DIR")).join("../gemini/unihyperplonk-srs-4"))
+                .unwrap();
+        let (proving_key, verifying_key) =
+            fibonacci_mobile::keygen(BackendId::Gemini, &srs_key).unwrap();
+        let artifacts = Artifacts::new(BackendId::Gemini, srs_key, proving_key, verifying_key);
+        let queue = JobQueue::start(
+            Arc::new(Circuits::single(Arc::new(artifacts))),
+            Arc::new(JobStore::open(&dir).unwrap()),
+            4,
+            1,
+        );
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(axum::serve(listener, router(AppState::with_queue(queue))).into_future());
+
+        let client = ProverClient::new(url).with_poll_interval(Duration::from_millis(100));
+        assert!(client.is_ready().await.unwrap());
+        assert_eq!(
+            client.circuits().await.unwrap()[0].backend,
+            BackendId::Gemini
+        );
+
+        let input = |out: &str| HashMap::from([("out".to_string(), vec![out.to_string()])]);
+        let bundle = client.prove(input("55")).await.unwrap();
+        assert_eq!(bundle.backend, Some(BackendId::Gemini));
+        assert!(client.verify(&bundle).await.unwrap().valid);
+
+        let mut tampered = bundle.clone();
+        let last = tampered.public_inputs.len() - 32;
+        tampered.public_inputs[last] ^= 1;
+        assert!(!client.verify(&tampered).await.unwrap().valid);
+
+        match client.prove(input("x")).await {
+            Err(ClientError::JobFailed { report, .. }) => {
+                assert_eq!(report.code, ErrorCode::InvalidInput)
+            }
+            other => panic!("Expected a failed job, got {:?}", other),
+        }
+        match client.prove_circuit(Some("plonk"), input("55")).await {
+            Err(ClientError::Api { status, report }) => {
+                assert_eq!(status, 400);
+                assert_eq!(report.code, ErrorCode::InvalidInput);
+            }
+            other => panic!("Expected a rejected request, got {:?}", other),
+        }
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_wait_timeout() {
+        // A server whose jobs never finish
+        let running = axum::routing::get(|| async {
+            axum::Json(JobResponse {
+                id: "0".repeat(32),
+                status: JobStatus::Running,
+                bundle: None,
+                error: None,
+            })
+        });
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let router = axum::Router::new().route("/jobs/:id", running);
+        tokio::spawn(axum::serve(listener, router).into_future());
+
+        let client = ProverClient::new(url)
+            .with_poll_interval(Duration::from_millis(10))
+            .with_timeout(Duration::from_millis(100));
+        let error = client.wait(&"0".repeat(32)).await.unwrap_err();
+        assert!(matches!(error, ClientError::Timeout { .. }));
+        assert!(error.is_retryable());
+    }
+}
@@ -0,0 +1,15 @@
+use cosmwasm_std::StdError;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+    #[error("Invalid public inputs: {0}")]
+    PublicInputs(fibonacci_verifier_core::Error),
+    /// Malformed verifying key or proof
+    #[error("Failed to verify proof: {0}")]
+    Verifier(String),
+    #[error("Proof is invalid")]
+    InvalidProof,
+}
@@ -0,0 +1,212 @@
+//! CosmWasm contract verifying HyperPlonk Fibonacci proofs on chain.
+//!
+//! The contract stores a verifying key and transcript label at instantiation and checks proofs
+//! against them, failing an `Execute::Verify` message on an invalid proof or answering whether it's
+//! valid to a `Query::Verify`. Contracts which verify proofs as part of their own messages build
+//! with the `library` feature, which leaves the entry points out, and call `verify_proof`.
+//!
+//! CosmWasm runs contracts on wasm32-unknown-unknown without JS bindings and rejects modules with
+//! floating point instructions. The verifier builds there with `default-features = false`: no
+//! threads, clocks or files, and randomness only from seeded rngs, so the contract registers a
+//! `getrandom` source which refuses instead of the `js` one of the browser builds.
+pub mod error;
+pub mod msg;
+
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+use cosmwasm_std::{
+    to_json_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdError, StdResult,
+};
+use fibonacci_circuit::{FibonacciError, TranscriptBinding};
+use fibonacci_verifier_core::{check_public_inputs, decode_public_inputs};
+
+pub use crate::error::ContractError;
+use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg, VerifyResponse};
+
+const VERIFYING_KEY: &[u8] = b"verifying_key";
+const LABEL: &[u8] = b"label";
+
+// The verifier only draws from seeded rngs, refusing other randomness keeps the contract
+// deterministic
+fn no_randomness(_: &mut [u8]) -> Result<(), getrandom::Error> {
+    Err(getrandom::Error::UNSUPPORTED)
+}
+getrandom::register_custom_getrandom!(no_randomness);
+
+/// Verify a HyperPlonk proof against `verifying_key` and the transcript binding of `label` and
+/// `context`. The public inputs are checked before the proof. A proof which fails verification is
+/// invalid, a key or proof which doesn't decode is an error.
+pub fn verify_proof(
+    verifying_key: &[u8],
+    proof: &[u8],
+    public_inputs: &[u8],
+    label: &[u8],
+    context: &[u8],
+) -> Result<bool, ContractError> {
+    let inputs = decode_public_inputs(public_inputs).map_err(ContractError::PublicInputs)?;
+    check_public_inputs(&inputs).map_err(ContractError::PublicInputs)?;
+
+    match hyperplonk_fibonacci::verify_with_vk(
+        verifying_key,
+        proof,
+        public_inputs,
+        TranscriptBinding { label, context },
+    ) {
+        Ok(valid) => Ok(valid),
+        Err(e)
+            if matches!(
+                e.downcast_ref::<FibonacciError>(),
+                Some(FibonacciError::Verification(_))
+            ) =>
+        {
+            Ok(false)
+        }
+        Err(e) => Err(ContractError::Verifier(e.to_string())),
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    deps.storage
+        .set(VERIFYING_KEY, msg.verifying_key.as_slice());
+    deps.storage
+        .set(LABEL, msg.label.unwrap_or_default().as_slice());
+
+    Ok(Response::new().add_attribute("action", "instantiate"))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    match msg {
+        ExecuteMsg::Verify {
+            proof,
+            public_inputs,
+            context,
+        } => {
+            if !verify_stored(deps.as_ref(), &proof, &public_inputs, &context)? {
+                return Err(ContractError::InvalidProof);
+            }
+            Ok(Response::new().add_attribute("action", "verify"))
+        }
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> Result<Binary, ContractError> {
+    match msg {
+        QueryMsg::Verify {
+            proof,
+            public_inputs,
+            context,
+        } => {
+            let valid = verify_stored(deps, &proof, &public_inputs, &context)?;
+            Ok(to_json_binary(&VerifyResponse { valid })?)
+        }
+    }
+}
+
+// Verify against the key and label the contract was instantiated with
+fn verify_stored(
+    deps: Deps,
+    proof: &[u8],
+    public_inputs: &[u8],
+    context: &[u8],
+) -> Result<bool, ContractError> {
+    let verifying_key = load(deps, VERIFYING_KEY)?;
+    let label = load(deps, LABEL)?;
+    verify_proof(&verifying_key, proof, public_inputs, &label, context)
+}
+
+fn load(deps: Deps, key: &[u8]) -> StdResult<Vec<u8>> {
+    deps.storage
+        .get(key)
+        .ok_or_else(|| StdError::not_found(String::from_utf8_lossy(key)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use cosmwasm_std::{
+        from_json,
+        testing::{message_info, mock_dependencies, mock_env},
+    };
+    use fibonacci_circuit::{artifact_dirs, BackendId};
+    use hyperplonk_fibonacci::{keygen_from_bytes, prove_from_bytes};
+
+    use super::*;
+
+    const APP_LABEL: &[u8] = b"fibonacci-cosmwasm";
+
+    #[test]
+    fn test_verify_messages() {
+        let srs_key =
+            std::fs::read(artifact_dirs::shipped_srs_path(BackendId::HyperPlonk)).unwrap();
+        let (proving_key, verifying_key) = keygen_from_bytes(&srs_key).unwrap();
+        let mut input = HashMap::new();
+        input.insert("out".to_string(), vec!["55".to_string()]);
+        let (proof, public_inputs) = prove_from_bytes(
+            &srs_key,
+            &proving_key,
+            input,
+            TranscriptBinding {
+                label: APP_LABEL,
+                context: b"chain-1",
+            },
+        )
+        .unwrap();
+
+        let mut deps = mock_dependencies();
+        let info = message_info(&deps.api.addr_make("creator"), &[]);
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            InstantiateMsg {
+                verifying_key: verifying_key.into(),
+                label: Some(APP_LABEL.into()),
+            },
+        )
+        .unwrap();
+
+        let verify = |context: &[u8]| ExecuteMsg::Verify {
+            proof: proof.clone().into(),
+            public_inputs: public_inputs.clone().into(),
+            context: context.into(),
+        };
+        execute(deps.as_mut(), mock_env(), info.clone(), verify(b"chain-1")).unwrap();
+        assert!(matches!(
+            execute(deps.as_mut(), mock_env(), info, verify(b"chain-2")),
+            Err(ContractError::InvalidProof)
+        ));
+
+        let query_verify = |public_inputs: &[u8], context: &[u8]| {
+            query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::Verify {
+                    proof: proof.clone().into(),
+                    public_inputs: public_inputs.into(),
+                    context: context.into(),
+                },
+            )
+        };
+        let response: VerifyResponse =
+            from_json(query_verify(&public_inputs, b"chain-2").unwrap()).unwrap();
+        assert!(!response.valid);
+        assert!(matches!(
+            query_verify(&[0; 8], b"chain-1"),
+            Err(ContractError::PublicInputs(_))
+        ));
+    }
+}
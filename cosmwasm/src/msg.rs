@@ -0,0 +1,39 @@
+use cosmwasm_schema::{cw_serde, QueryResponses};
+use cosmwasm_std::Binary;
+
+#[cw_serde]
+pub struct InstantiateMsg {
+    /// HyperPlonk verifying key, as `keygen` writes it
+    pub verifying_key: Binary,
+    /// Transcript label proofs are bound to, none for unlabelled proofs
+    pub label: Option<Binary>,
+}
+
+#[cw_serde]
+pub enum ExecuteMsg {
+    /// Fail unless the proof is valid, for messages which only go through with a valid proof
+    Verify {
+        proof: Binary,
+        /// Canonical encoding of the public inputs, without an artifact header
+        public_inputs: Binary,
+        /// Transcript context the proof was bound to, e.g. the chain id
+        context: Binary,
+    },
+}
+
+#[cw_serde]
+#[derive(QueryResponses)]
+pub enum QueryMsg {
+    /// Whether the proof is valid, without failing
+    #[returns(VerifyResponse)]
+    Verify {
+        proof: Binary,
+        public_inputs: Binary,
+        context: Binary,
+    },
+}
+
+#[cw_serde]
+pub struct VerifyResponse {
+    pub valid: bool,
+}
@@ -0,0 +1,24 @@
+//! Key bundles, key set indexes and verifying keys, as loaded from disk or a server.
+#![no_main]
+
+use fibonacci_circuit::{
+    io::{decode_artifact, KeyBundle, KeySet},
+    PlonkishComponents, TranscriptBinding,
+};
+use gemini_fibonacci::GeminiScheme;
+use hyperplonk_fibonacci::HyperPlonkScheme;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(bundle) = KeyBundle::from_bytes(data) {
+        // Whatever parses re-encodes to a bundle with the same contents
+        let bytes = bundle.to_bytes().unwrap();
+        assert_eq!(KeyBundle::from_bytes(&bytes).unwrap(), bundle);
+    }
+    let _ = serde_json::from_slice::<KeySet>(data);
+
+    let _ = decode_artifact::<<HyperPlonkScheme as PlonkishComponents>::VerifierParam>(data);
+    let _ = decode_artifact::<<GeminiScheme as PlonkishComponents>::VerifierParam>(data);
+    // The verifying key as the verifier decodes it, with its circuit hash checked
+    let _ = hyperplonk_fibonacci::verify_with_vk(data, &[], &[], TranscriptBinding::default());
+});
@@ -0,0 +1,23 @@
+//! Proof bundles and containers, bare and wrapped in an artifact header.
+#![no_main]
+
+use fibonacci_circuit::{
+    container::ProofContainer,
+    io::{decode_artifact, ArtifactHeader},
+    serialisation::Codec,
+    BackendId, ProofBundle,
+};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = ArtifactHeader::parse(data);
+
+    if let Ok(bundle) = ProofBundle::decode(data, Codec::Bincode) {
+        let _ = bundle.validate();
+    }
+    let _ = decode_artifact::<ProofBundle>(data);
+
+    if let Ok(container) = ProofContainer::decode(data, Codec::Bincode) {
+        let _ = container.validate(BackendId::HyperPlonk, data);
+    }
+});
@@ -0,0 +1,22 @@
+//! Public inputs as received from provers: the canonical encoding, artifacts wrapping it and the
+//! JSON form of `InputsSerialisationWrapper`.
+#![no_main]
+
+use fibonacci_circuit::serialisation::{
+    canonical_public_inputs, decode_public_inputs, encode_public_inputs, Codec,
+    InputsSerialisationWrapper, InstanceColumnsSerialisationWrapper,
+};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(inputs) = decode_public_inputs(data) {
+        // Whatever decodes re-encodes canonically and decodes to the same elements
+        let encoded = encode_public_inputs(&inputs);
+        assert_eq!(decode_public_inputs(&encoded).unwrap(), inputs);
+        assert_eq!(canonical_public_inputs(data).unwrap(), encoded);
+    }
+
+    let _ = Codec::Bincode.decode::<InputsSerialisationWrapper>(data);
+    let _ = Codec::Bincode.decode::<InstanceColumnsSerialisationWrapper>(data);
+    let _ = serde_json::from_slice::<InputsSerialisationWrapper>(data);
+});
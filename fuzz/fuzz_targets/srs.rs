@@ -0,0 +1,46 @@
+//! SRS files, parsed with the setup of each backend and passed to the public APIs taking SRS
+//! contents, which return an error rather than panicking on a malformed SRS.
+#![no_main]
+
+use std::collections::HashMap;
+
+use fibonacci_circuit::{
+    io::parse_srs_bytes, keygen_from_bytes, merkle, nonce, prove_from_bytes, step_count,
+    CircuitShape, ProverContext, TranscriptBinding,
+};
+use gemini_fibonacci::GeminiScheme;
+use hyperplonk_fibonacci::HyperPlonkScheme;
+use libfuzzer_sys::fuzz_target;
+
+// The contents double as the proving key, which is only decoded once the SRS parsed
+macro_rules! fuzz_apis {
+    ($scheme:ty, $srs_key:expr) => {{
+        let srs_key: &[u8] = $srs_key;
+        let shape = CircuitShape::default();
+        let binding = TranscriptBinding::default();
+        let nonce = nonce::nonce_from_bytes(b"fuzz");
+
+        let _ = parse_srs_bytes::<$scheme>(srs_key);
+        let _ = keygen_from_bytes::<$scheme>(srs_key);
+        let _ = prove_from_bytes::<$scheme>(srs_key, srs_key, input(), binding);
+        let _ = ProverContext::<$scheme>::from_bytes(srs_key, srs_key, shape);
+        let _ = nonce::keygen_with_nonce::<$scheme>(srs_key);
+        let _ = nonce::prove_with_nonce::<$scheme>(srs_key, srs_key, input(), nonce, binding);
+        let _ = step_count::keygen_with_step_count::<$scheme>(srs_key, shape);
+        let _ =
+            step_count::prove_with_step_count::<$scheme>(srs_key, srs_key, input(), shape, binding);
+        let _ = merkle::keygen_from_bytes::<$scheme>(srs_key, merkle::DEFAULT_MERKLE_DEPTH);
+    }};
+}
+
+fn input() -> HashMap<String, Vec<String>> {
+    HashMap::from([("out".to_string(), vec!["55".to_string()])])
+}
+
+fuzz_target!(|data: &[u8]| {
+    fuzz_apis!(HyperPlonkScheme, data);
+    fuzz_apis!(GeminiScheme, data);
+
+    let _ = plonk_fibonacci::keygen_from_bytes(data);
+    let _ = plonk_fibonacci::prove_from_bytes(data, data, input(), TranscriptBinding::default());
+});
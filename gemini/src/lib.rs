@@ -1,8 +1,4 @@
-use std::{collections::HashMap, error::Error};
-
-use fibonacci_circuit::{
-    prove as _prove, verify as _verify, GenerateProofResult, PlonkishComponents,
-};
+use fibonacci_circuit::{BackendId, PlonkishComponents};
 use halo2_proofs::halo2curves::bn256::{Bn256, Fr};
 use plonkish_backend::{
     backend::hyperplonk::{HyperPlonk, HyperPlonkProverParam, HyperPlonkVerifierParam},
@@ -15,71 +11,328 @@ use plonkish_backend::{
 pub struct GeminiScheme;
 
 impl PlonkishComponents for GeminiScheme {
+    const BACKEND: BackendId = BackendId::Gemini;
     type Param = UnivariateKzgParam<Bn256>;
     type ProverParam = HyperPlonkProverParam<Fr, Self::Pcs>;
     type VerifierParam = HyperPlonkVerifierParam<Fr, Self::Pcs>;
     type Pcs = multilinear::Gemini<UnivariateKzg<Bn256>>;
     type ProvingBackend = HyperPlonk<Self::Pcs>;
-}
-
-#[cfg(not(target_arch = "wasm32"))]
-pub fn prove(
-    srs_key_path: &str,
-    proving_key_path: &str,
-    input: HashMap<String, Vec<String>>,
-) -> Result<GenerateProofResult, Box<dyn Error>> {
-    _prove::<GeminiScheme>(srs_key_path, proving_key_path, input)
-}
-
-#[cfg(target_arch = "wasm32")]
-pub fn prove(
-    srs_key: &[u8],
-    proving_key: &[u8],
-    input: HashMap<String, Vec<String>>,
-) -> Result<GenerateProofResult, Box<dyn Error>> {
-    _prove::<GeminiScheme>(srs_key, proving_key, input)
-}
 
-#[cfg(not(target_arch = "wasm32"))]
-pub fn verify(
-    srs_key_path: &str,
-    verifying_key_path: &str,
-    proof: Vec<u8>,
-    public_inputs: Vec<u8>,
-) -> Result<bool, Box<dyn Error>> {
-    _verify::<GeminiScheme>(srs_key_path, verifying_key_path, proof, public_inputs)
+    fn srs_k(param: &Self::Param) -> usize {
+        // Gemini commits to the 2^k evaluations of a multilinear polynomial as a univariate
+        // polynomial of degree 2^k - 1
+        (param.degree() + 1).ilog2() as usize
+    }
 }
 
-#[cfg(target_arch = "wasm32")]
-pub fn verify(
-    srs_key: &[u8],
-    verifying_key: &[u8],
-    proof: Vec<u8>,
-    public_inputs: Vec<u8>,
-) -> Result<bool, Box<dyn Error>> {
-    _verify::<GeminiScheme>(srs_key, verifying_key, proof, public_inputs)
-}
+fibonacci_circuit::define_scheme!(GeminiScheme);
+fibonacci_circuit::scheme_tests!(GeminiScheme);
 
 #[cfg(test)]
 mod tests {
-    use fibonacci_circuit::circuit::test_utils::{
-        bad_proof_not_verified_test, fibonacci_circuit_test, helper_functions_test,
+    use std::collections::HashMap;
+
+    use fibonacci_circuit::{
+        artifact_dirs::shipped_srs_path, circuit::test_utils::witness_file_test,
+        container::ProofContainer, GenerateProofResult, TranscriptBinding,
     };
 
     use super::*;
 
     #[test]
-    fn test_fibonacci_circuit() {
-        fibonacci_circuit_test::<GeminiScheme>();
+    fn test_witness_file() {
+        witness_file_test::<GeminiScheme>();
+    }
+
+    #[test]
+    fn test_generate_keys() {
+        use fibonacci_circuit::{
+            generate_keys, keygen::file_fingerprint, test_output_dir, CircuitShape, KeygenOptions,
+        };
+
+        let out_dir = test_output_dir("gemini-generate-keys");
+        let srs_path = &shipped_srs_path(BackendId::Gemini);
+
+        let options = KeygenOptions {
+            bundle: true,
+            ..Default::default()
+        };
+        let artifacts =
+            generate_keys::<GeminiScheme>(srs_path, &out_dir, "gemini", &options).unwrap();
+        assert_eq!(artifacts.backend, BackendId::Gemini);
+        assert!(artifacts.manifest.is_none());
+        let bundle_path = artifacts.bundle_path.clone().unwrap();
+        assert_eq!(artifacts.fingerprints.len(), 3);
+        for path in [&artifacts.pk_path, &artifacts.vk_path, &bundle_path] {
+            assert_eq!(
+                artifacts.fingerprints[path],
+                file_fingerprint(path).unwrap()
+            );
+        }
+
+        let options = KeygenOptions {
+            shapes: Some(vec![CircuitShape::default(), CircuitShape::new(8)]),
+            bundle: false,
+        };
+        let artifacts =
+            generate_keys::<GeminiScheme>(srs_path, &out_dir, "gemini", &options).unwrap();
+        assert!(artifacts.manifest.is_some());
+        assert_eq!(artifacts.fingerprints.len(), 5);
+
+        std::fs::remove_dir_all(out_dir).unwrap();
     }
 
     #[test]
-    fn test_bad_proof_not_verified() {
-        bad_proof_not_verified_test::<GeminiScheme>();
+    fn test_plan_keys() {
+        use fibonacci_circuit::{
+            generate_keys, plan_keys, test_output_dir, CircuitShape, KeygenOptions, DEFAULT_STEPS,
+        };
+
+        let out_dir = test_output_dir("gemini-plan-keys");
+        let srs_path = &shipped_srs_path(BackendId::Gemini);
+        let too_large = CircuitShape {
+            steps: DEFAULT_STEPS,
+            k: 8,
+        };
+        let options = KeygenOptions {
+            shapes: Some(vec![CircuitShape::default(), too_large]),
+            bundle: false,
+        };
+        let plan = plan_keys::<GeminiScheme>(srs_path, &out_dir, "gemini", &options).unwrap();
+        assert!(!plan.is_compatible());
+        assert!(plan.keys[0].sizes.is_some());
+        assert!(plan.keys[1].sizes.is_none());
+        assert!(plan.manifest.is_some());
+
+        // Nothing is written
+        assert!(plan.keys.iter().all(|keys| !keys.pk_path.exists()));
+        assert!(!plan.manifest.unwrap().exists());
+
+        // The sizes match the keys generate_keys writes
+        let plan =
+            plan_keys::<GeminiScheme>(srs_path, &out_dir, "gemini", &Default::default()).unwrap();
+        assert!(plan.is_compatible());
+        let artifacts =
+            generate_keys::<GeminiScheme>(srs_path, &out_dir, "gemini", &Default::default())
+                .unwrap();
+        let (pk_size, vk_size) = plan.keys[0].sizes.unwrap();
+        assert_eq!(plan.keys[0].pk_path, artifacts.pk_path);
+        assert_eq!(
+            std::fs::metadata(&artifacts.pk_path).unwrap().len(),
+            pk_size as u64
+        );
+        assert_eq!(
+            std::fs::metadata(&artifacts.vk_path).unwrap().len(),
+            vk_size as u64
+        );
+
+        std::fs::remove_dir_all(out_dir).unwrap();
     }
 
     #[test]
-    fn test_helper_functions() {
-        helper_functions_test::<GeminiScheme>();
+    fn test_prover_context_caches_witness() {
+        use fibonacci_circuit::{CircuitShape, ProverContext};
+
+        let srs_key = std::fs::read(shipped_srs_path(BackendId::Gemini)).unwrap();
+        let (proving_key, verifying_key) = keygen_from_bytes(&srs_key).unwrap();
+        let prover = ProverContext::<GeminiScheme>::from_bytes(
+            &srs_key,
+            &proving_key,
+            CircuitShape::default(),
+        )
+        .unwrap()
+        .with_cache_capacity(1);
+
+        let input = |out: &str| HashMap::from([("out".to_string(), vec![out.to_string()])]);
+        let verify = |(proof, public_inputs): GenerateProofResult| {
+            verify_with_vk(
+                &verifying_key,
+                &proof,
+                &public_inputs,
+                TranscriptBinding::default(),
+            )
+            .unwrap_or(false)
+        };
+
+        // Proofs from a cached witness verify like fresh ones
+        for _ in 0..2 {
+            let result = prover
+                .prove(input("55"), TranscriptBinding::default())
+                .unwrap();
+            assert!(verify(result));
+        }
+        let stats = prover.cache_stats();
+        assert_eq!((stats.hits, stats.misses, stats.entries), (1, 1, 1));
+
+        // Another input evicts the cached one and isn't satisfied by its witness
+        let result = prover
+            .prove(input("56"), TranscriptBinding::default())
+            .unwrap();
+        assert!(!verify(result));
+        prover
+            .prove(input("55"), TranscriptBinding::default())
+            .unwrap();
+        let stats = prover.cache_stats();
+        assert_eq!((stats.hits, stats.misses, stats.entries), (1, 3, 1));
+
+        prover.clear_cache();
+        assert_eq!(prover.cache_stats().entries, 0);
+
+        // Warming up proves without touching the cache
+        assert!(!prover.is_warm());
+        prover.warm_up().unwrap();
+        assert!(prover.is_warm());
+        assert_eq!(prover.cache_stats().entries, 0);
+    }
+
+    #[test]
+    fn test_circuit_config() {
+        use fibonacci_circuit::{prove_with_config, CircuitConfig, CircuitShape, FibonacciError};
+
+        let config: CircuitConfig =
+            serde_json::from_str(r#"{"steps": 9, "k": 4, "a": "2", "b": "0x3"}"#).unwrap();
+        assert_eq!(config.shape, CircuitShape::default());
+        assert_eq!(config.output(), Fr::from(144));
+        let json = serde_json::to_value(config).unwrap();
+        assert_eq!(json["a"], "2");
+        assert_eq!(
+            serde_json::from_value::<CircuitConfig>(json).unwrap(),
+            config
+        );
+        // Starting values default to 1
+        let config: CircuitConfig = serde_json::from_str(r#"{"steps": 9, "k": 4}"#).unwrap();
+        assert_eq!(config, CircuitConfig::default());
+        assert_eq!(config.output(), Fr::from(55));
+
+        let srs_key = std::fs::read(shipped_srs_path(BackendId::Gemini)).unwrap();
+        let (proving_key, verifying_key) = keygen_from_bytes(&srs_key).unwrap();
+        let (proof, public_inputs) = prove_with_config::<GeminiScheme>(
+            &srs_key,
+            &proving_key,
+            &config,
+            TranscriptBinding::default(),
+        )
+        .unwrap();
+        assert!(verify_with_vk(
+            &verifying_key,
+            &proof,
+            &public_inputs,
+            TranscriptBinding::default()
+        )
+        .unwrap());
+
+        // Keys of another shape don't match the config
+        let other = CircuitConfig::new(CircuitShape::new(8));
+        let err = prove_with_config::<GeminiScheme>(
+            &srs_key,
+            &proving_key,
+            &other,
+            TranscriptBinding::default(),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<FibonacciError>(),
+            Some(FibonacciError::CircuitMismatch(_))
+        ));
+    }
+
+    #[test]
+    fn test_prove_prepared() {
+        use fibonacci_circuit::{
+            prove_prepared, CircuitShape, FibonacciError, PreparedCircuit, ProverContext,
+        };
+
+        let srs_key = std::fs::read(shipped_srs_path(BackendId::Gemini)).unwrap();
+        let (proving_key, verifying_key) = keygen_from_bytes(&srs_key).unwrap();
+        let prover = ProverContext::<GeminiScheme>::from_bytes(
+            &srs_key,
+            &proving_key,
+            CircuitShape::default(),
+        )
+        .unwrap();
+
+        let input = HashMap::from([("out".to_string(), vec!["55".to_string()])]);
+        let prepared = prover.prepare(input).unwrap();
+        assert_eq!(prepared.instances()[0][2], Fr::from(55));
+
+        // One witness proven under several transcript bindings
+        for label in [&b"app-a"[..], b"app-b"] {
+            let binding = TranscriptBinding::label(label);
+            let (proof, public_inputs) = prove_prepared(&prover, &prepared, binding).unwrap();
+            assert_eq!(public_inputs, prepared.public_inputs());
+            assert!(verify_with_vk(&verifying_key, &proof, &public_inputs, binding).unwrap());
+        }
+        // Preparing doesn't go through the witness cache
+        assert_eq!(prover.cache_stats().misses, 0);
+
+        // Circuits prepared for another shape don't fit the proving key
+        let shape = CircuitShape::new(8);
+        let circuit = fibonacci_circuit::CircuitConfig::new(shape)
+            .circuit()
+            .unwrap();
+        let other = PreparedCircuit::new::<GeminiScheme>(circuit, shape).unwrap();
+        let err = prover
+            .prove_prepared(&other, TranscriptBinding::default())
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<FibonacciError>(),
+            Some(FibonacciError::CircuitMismatch(_))
+        ));
+    }
+
+    #[test]
+    fn test_verifier_context_key_rotation() {
+        use fibonacci_circuit::{
+            container::{vk_fingerprint, ProofBundle},
+            keygen_from_bytes_with_shape, prove_with_config, CircuitConfig, CircuitShape,
+            VerifierContext,
+        };
+
+        let srs_key = std::fs::read(shipped_srs_path(BackendId::Gemini)).unwrap();
+        let binding = TranscriptBinding::default();
+        // The circuit is upgraded from 8 steps to the default shape
+        let old_config = CircuitConfig::new(CircuitShape::new(8));
+        let new_config = CircuitConfig::default();
+        let contain = |config: &CircuitConfig| {
+            let (proving_key, verifying_key) =
+                keygen_from_bytes_with_shape::<GeminiScheme>(&srs_key, config.shape).unwrap();
+            let (proof, public_inputs) =
+                prove_with_config::<GeminiScheme>(&srs_key, &proving_key, config, binding).unwrap();
+            let mut container = ProofContainer::new(BackendId::Gemini, &verifying_key);
+            container
+                .push(ProofBundle::new(proof, public_inputs))
+                .unwrap();
+            (verifying_key, container)
+        };
+        let (old_key, old_container) = contain(&old_config);
+        let (new_key, new_container) = contain(&new_config);
+
+        let mut verifier = VerifierContext::<GeminiScheme>::new(&new_key)
+            .unwrap()
+            .with_key_for_shape(&old_key, old_config.shape)
+            .unwrap();
+        assert_eq!(
+            verifier.fingerprints(),
+            vec![vk_fingerprint(&new_key), vk_fingerprint(&old_key)]
+        );
+
+        // Containers are verified with the key they record, bare proofs with any trusted key
+        for container in [&old_container, &new_container] {
+            assert!(verifier.verify_container(container, binding).unwrap());
+            let bundle = &container.bundles[0];
+            assert!(verifier
+                .verify(&bundle.proof, &bundle.public_inputs, binding)
+                .unwrap());
+        }
+
+        // Once the old key is retired, its proofs are rejected
+        assert!(verifier.retire(&vk_fingerprint(&old_key)));
+        assert!(!verifier.retire(&vk_fingerprint(&old_key)));
+        assert!(verifier.verify_container(&old_container, binding).is_err());
+        let bundle = &old_container.bundles[0];
+        assert!(!verifier
+            .verify(&bundle.proof, &bundle.public_inputs, binding)
+            .unwrap());
+        assert!(verifier.verify_container(&new_container, binding).unwrap());
     }
 }
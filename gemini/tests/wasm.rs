@@ -0,0 +1,6 @@
+//! Proving and verification from byte slices on the wasm target, see
+//! `fibonacci_circuit::wasm_tests`. Run in node with
+//! `wasm-pack test --node gemini --no-default-features`.
+#![cfg(target_arch = "wasm32")]
+
+fibonacci_circuit::wasm_tests!(gemini_fibonacci, include_bytes!("../unihyperplonk-srs-4"));
@@ -1,8 +1 @@
-use fibonacci_circuit::gen_keys;
-
-use hyperplonk_fibonacci::HyperPlonkScheme;
-
-pub fn main() {
-    // This function read SRS file as argument
-    gen_keys::<HyperPlonkScheme>("hyperplonk")
-}
+fibonacci_circuit::gen_keys_main!(hyperplonk_fibonacci::HyperPlonkScheme, "hyperplonk");
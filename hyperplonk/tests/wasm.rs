@@ -0,0 +1,6 @@
+//! Proving and verification from byte slices on the wasm target, see
+//! `fibonacci_circuit::wasm_tests`. Run in node with
+//! `wasm-pack test --node hyperplonk --no-default-features`.
+#![cfg(target_arch = "wasm32")]
+
+fibonacci_circuit::wasm_tests!(hyperplonk_fibonacci, include_bytes!("../hyperplonk-srs-4"));
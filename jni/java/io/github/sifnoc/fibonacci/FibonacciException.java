@@ -0,0 +1,8 @@
+package io.github.sifnoc.fibonacci;
+
+/** Thrown when loading the artifacts, proving or verifying fails in the native prover. */
+public class FibonacciException extends RuntimeException {
+    public FibonacciException(String message) {
+        super(message);
+    }
+}
@@ -0,0 +1,32 @@
+package io.github.sifnoc.fibonacci;
+
+/**
+ * Entry points of `libfibonacci_jni.so`.
+ *
+ * Backend ids: 0 = plonk, 1 = hyperplonk, 2 = gemini, 3 = unihyperplonk. Invalid arguments throw
+ * {@link IllegalArgumentException}, prover failures throw {@link FibonacciException}.
+ */
+public final class FibonacciNative {
+    public static final int BACKEND_PLONK = 0;
+    public static final int BACKEND_HYPERPLONK = 1;
+    public static final int BACKEND_GEMINI = 2;
+    public static final int BACKEND_UNIHYPERPLONK = 3;
+
+    static {
+        System.loadLibrary("fibonacci_jni");
+    }
+
+    private FibonacciNative() {}
+
+    /**
+     * Generate a proof. `inputJson` maps circuit input names to decimal strings, e.g.
+     * `{"out": ["55"]}`.
+     *
+     * @return the proof at index 0 and the serialized public inputs at index 1
+     */
+    public static native byte[][] prove(
+            int backend, byte[] srsKey, byte[] provingKey, String inputJson);
+
+    public static native boolean verify(
+            int backend, byte[] srsKey, byte[] verifyingKey, byte[] proof, byte[] publicInputs);
+}
@@ -0,0 +1,158 @@
+//! JNI entry points of `io.github.sifnoc.fibonacci.FibonacciNative`, see `java/` for the Java side.
+//!
+//! Errors never cross the boundary as panics: invalid arguments throw
+//! `IllegalArgumentException`, prover failures and panics throw `FibonacciException`.
+use std::{
+    panic::{catch_unwind, AssertUnwindSafe},
+    ptr,
+};
+
+use fibonacci_circuit::BackendId;
+use fibonacci_mobile::{backend_from_id, panic_message, parse_input};
+use jni::{
+    objects::{JByteArray, JClass, JObject, JString},
+    sys::{jboolean, jint, jobjectArray, JNI_FALSE},
+    JNIEnv,
+};
+
+const ILLEGAL_ARGUMENT_EXCEPTION: &str = "java/lang/IllegalArgumentException";
+const FIBONACCI_EXCEPTION: &str = "io/github/sifnoc/fibonacci/FibonacciException";
+
+/// Exception class and message to throw on the Java side
+type JavaException = (&'static str, String);
+
+fn illegal_argument(message: impl Into<String>) -> JavaException {
+    (ILLEGAL_ARGUMENT_EXCEPTION, message.into())
+}
+
+fn failure(message: impl Into<String>) -> JavaException {
+    (FIBONACCI_EXCEPTION, message.into())
+}
+
+fn backend(id: jint) -> Result<BackendId, JavaException> {
+    u8::try_from(id)
+        .map_err(|_| illegal_argument(format!("Unknown backend id {}", id)))
+        .and_then(|id| backend_from_id(id).map_err(|e| illegal_argument(e.to_string())))
+}
+
+fn byte_array(env: &JNIEnv, array: &JByteArray, name: &str) -> Result<Vec<u8>, JavaException> {
+    if array.is_null() {
+        return Err(illegal_argument(format!("`{}` is null", name)));
+    }
+    env.convert_byte_array(array)
+        .map_err(|e| illegal_argument(format!("Failed to read `{}`: {}", name, e)))
+}
+
+/// Run `f`, throwing its error or panic as a Java exception and returning `default` instead.
+fn throwing<'local, T>(
+    env: &mut JNIEnv<'local>,
+    default: T,
+    f: impl FnOnce(&mut JNIEnv<'local>) -> Result<T, JavaException>,
+) -> T {
+    let result = catch_unwind(AssertUnwindSafe(|| f(&mut *env)))
+        .unwrap_or_else(|payload| Err(failure(panic_message(payload))));
+
+    match result {
+        Ok(value) => value,
+        Err((class, message)) => {
+            // Nothing else can be reported if throwing fails, the JVM aborts the call anyway
+            let _ = env.throw_new(class, message);
+            default
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "system" fn Java_io_github_sifnoc_fibonacci_FibonacciNative_prove<'local>(
+    mut env: JNIEnv<'local>,
+    _class: JClass<'local>,
+    backend_id: jint,
+    srs_key: JByteArray<'local>,
+    proving_key: JByteArray<'local>,
+    input_json: JString<'local>,
+) -> jobjectArray {
+    throwing(&mut env, ptr::null_mut(), |env| {
+        let backend = backend(backend_id)?;
+        let srs_key = byte_array(env, &srs_key, "srsKey")?;
+        let proving_key = byte_array(env, &proving_key, "provingKey")?;
+        if input_json.is_null() {
+            return Err(illegal_argument("`inputJson` is null"));
+        }
+        let input_json: String = env
+            .get_string(&input_json)
+            .map_err(|e| illegal_argument(format!("Failed to read `inputJson`: {}", e)))?
+            .into();
+        let input =
+            parse_input(input_json.as_bytes()).map_err(|e| illegal_argument(e.to_string()))?;
+
+        let (proof, public_inputs) =
+            fibonacci_mobile::prove(backend, &srs_key, &proving_key, input)
+                .map_err(|e| failure(e.to_string()))?;
+
+        let java_error = |e: jni::errors::Error| failure(format!("JNI call failed: {}", e));
+        let result = env
+            .new_object_array(2, "[B", JObject::null())
+            .map_err(java_error)?;
+        let proof = env.byte_array_from_slice(&proof).map_err(java_error)?;
+        let public_inputs = env
+            .byte_array_from_slice(&public_inputs)
+            .map_err(java_error)?;
+        env.set_object_array_element(&result, 0, proof)
+            .map_err(java_error)?;
+        env.set_object_array_element(&result, 1, public_inputs)
+            .map_err(java_error)?;
+
+        Ok(result.into_raw())
+    })
+}
+
+#[no_mangle]
+pub extern "system" fn Java_io_github_sifnoc_fibonacci_FibonacciNative_verify<'local>(
+    mut env: JNIEnv<'local>,
+    _class: JClass<'local>,
+    backend_id: jint,
+    srs_key: JByteArray<'local>,
+    verifying_key: JByteArray<'local>,
+    proof: JByteArray<'local>,
+    public_inputs: JByteArray<'local>,
+) -> jboolean {
+    throwing(&mut env, JNI_FALSE, |env| {
+        let backend = backend(backend_id)?;
+        let srs_key = byte_array(env, &srs_key, "srsKey")?;
+        let verifying_key = byte_array(env, &verifying_key, "verifyingKey")?;
+        let proof = byte_array(env, &proof, "proof")?;
+        let public_inputs = byte_array(env, &public_inputs, "publicInputs")?;
+
+        let is_valid =
+            fibonacci_mobile::verify(backend, &srs_key, &verifying_key, &proof, &public_inputs)
+                .map_err(|e| failure(e.to_string()))?;
+
+        Ok(is_valid as jboolean)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backend() {
+        assert_eq!(backend(0).unwrap(), BackendId::Plonk);
+        assert_eq!(backend(3).unwrap(), BackendId::UniHyperPlonk);
+        for id in [-1, 4, 256] {
+            let (class, message) = backend(id).unwrap_err();
+            assert_eq!(class, ILLEGAL_ARGUMENT_EXCEPTION);
+            assert!(message.contains("Unknown backend id"));
+        }
+    }
+
+    #[test]
+    fn test_panic_message() {
+        let payload = catch_unwind(|| panic!("static message")).unwrap_err();
+        assert_eq!(panic_message(payload), "static message");
+        let payload = catch_unwind(|| panic!("formatted {}", 42)).unwrap_err();
+        assert_eq!(panic_message(payload), "formatted 42");
+        let payload = catch_unwind(|| std::panic::panic_any(42)).unwrap_err();
+        assert_eq!(panic_message(payload), "Prover panicked");
+    }
+}
@@ -0,0 +1,26 @@
+#!/usr/bin/env bash
+# Build the iOS static libraries and bundle them with the C header into
+# `target/ios/FibonacciMobile.xcframework`. Add the framework and `swift/Fibonacci.swift`
+# to the Xcode project.
+set -euo pipefail
+
+cd "$(dirname "$0")/.."
+
+# Embed LLVM bitcode for apps which are still built with ENABLE_BITCODE
+export RUSTFLAGS="${RUSTFLAGS:-} -C embed-bitcode=yes"
+
+for target in aarch64-apple-ios aarch64-apple-ios-sim x86_64-apple-ios; do
+    cargo build --release -p fibonacci-mobile --features ios --target "$target"
+done
+
+mkdir -p target/ios
+lipo -create \
+    target/aarch64-apple-ios-sim/release/libfibonacci_mobile.a \
+    target/x86_64-apple-ios/release/libfibonacci_mobile.a \
+    -output target/ios/libfibonacci_mobile-sim.a
+
+rm -rf target/ios/FibonacciMobile.xcframework
+xcodebuild -create-xcframework \
+    -library target/aarch64-apple-ios/release/libfibonacci_mobile.a -headers mobile/include \
+    -library target/ios/libfibonacci_mobile-sim.a -headers mobile/include \
+    -output target/ios/FibonacciMobile.xcframework
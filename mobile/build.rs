@@ -0,0 +1,5 @@
+fn main() {
+    #[cfg(feature = "uniffi")]
+    uniffi::generate_scaffolding("src/fibonacci.udl")
+        .expect("UniFFI scaffolding generation failed");
+}
@@ -0,0 +1,56 @@
+/* C interface of the `fibonacci-mobile` static library, built with the `ios` feature. */
+#ifndef FIBONACCI_MOBILE_H
+#define FIBONACCI_MOBILE_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#define FIBONACCI_BACKEND_PLONK 0
+#define FIBONACCI_BACKEND_HYPERPLONK 1
+#define FIBONACCI_BACKEND_GEMINI 2
+#define FIBONACCI_BACKEND_UNIHYPERPLONK 3
+
+typedef int32_t FibonacciStatus;
+
+#define FIBONACCI_STATUS_OK 0
+#define FIBONACCI_STATUS_INVALID_ARGUMENT 1
+#define FIBONACCI_STATUS_FAILED 2
+#define FIBONACCI_STATUS_PANIC 3
+
+/* Owned by the library, release with `fibonacci_buffer_free`. */
+typedef struct {
+    uint8_t *data;
+    size_t len;
+    size_t capacity;
+} FibonacciBuffer;
+
+typedef struct {
+    FibonacciStatus status;
+    FibonacciBuffer proof;
+    FibonacciBuffer public_inputs;
+    /* UTF-8 error message, empty on success */
+    FibonacciBuffer error;
+} FibonacciProveResult;
+
+typedef struct {
+    FibonacciStatus status;
+    bool valid;
+    /* UTF-8 error message, empty on success */
+    FibonacciBuffer error;
+} FibonacciVerifyResult;
+
+FibonacciProveResult fibonacci_prove(uint8_t backend,
+                                     const uint8_t *srs_key, size_t srs_key_len,
+                                     const uint8_t *proving_key, size_t proving_key_len,
+                                     const uint8_t *input_json, size_t input_json_len);
+
+FibonacciVerifyResult fibonacci_verify(uint8_t backend,
+                                       const uint8_t *srs_key, size_t srs_key_len,
+                                       const uint8_t *verifying_key, size_t verifying_key_len,
+                                       const uint8_t *proof, size_t proof_len,
+                                       const uint8_t *public_inputs, size_t public_inputs_len);
+
+void fibonacci_buffer_free(FibonacciBuffer buffer);
+
+#endif /* FIBONACCI_MOBILE_H */
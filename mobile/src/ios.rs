@@ -0,0 +1,261 @@
+//! C ABI for the Swift wrapper in `swift/Fibonacci.swift`, declared in `include/fibonacci.h`.
+//!
+//! Buffers handed out by this module are owned by Rust and must be released with
+//! `fibonacci_buffer_free`. Panics are caught at the boundary and reported as
+//! `FIBONACCI_STATUS_PANIC` instead of unwinding into Swift.
+use std::{
+    mem::ManuallyDrop,
+    panic::{catch_unwind, AssertUnwindSafe},
+    ptr, slice,
+};
+
+use fibonacci_circuit::GenerateProofResult;
+
+use crate::panic_message;
+
+/// Byte buffer allocated by Rust
+#[repr(C)]
+pub struct FibonacciBuffer {
+    pub data: *mut u8,
+    pub len: usize,
+    pub capacity: usize,
+}
+
+impl FibonacciBuffer {
+    fn empty() -> Self {
+        Self {
+            data: ptr::null_mut(),
+            len: 0,
+            capacity: 0,
+        }
+    }
+
+    fn from_vec(vec: Vec<u8>) -> Self {
+        let mut vec = ManuallyDrop::new(vec);
+        Self {
+            data: vec.as_mut_ptr(),
+            len: vec.len(),
+            capacity: vec.capacity(),
+        }
+    }
+}
+
+#[repr(i32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FibonacciStatus {
+    Ok = 0,
+    /// Unknown backend, null buffer or malformed input JSON
+    InvalidArgument = 1,
+    /// Loading the artifacts, proving or verifying returned an error
+    Failed = 2,
+    Panic = 3,
+}
+
+#[repr(C)]
+pub struct FibonacciProveResult {
+    pub status: FibonacciStatus,
+    pub proof: FibonacciBuffer,
+    pub public_inputs: FibonacciBuffer,
+    /// UTF-8 error message, empty on success
+    pub error: FibonacciBuffer,
+}
+
+#[repr(C)]
+pub struct FibonacciVerifyResult {
+    pub status: FibonacciStatus,
+    pub valid: bool,
+    /// UTF-8 error message, empty on success
+    pub error: FibonacciBuffer,
+}
+
+type FfiError = (FibonacciStatus, String);
+
+fn invalid_argument(message: impl Into<String>) -> FfiError {
+    (FibonacciStatus::InvalidArgument, message.into())
+}
+
+unsafe fn bytes<'a>(data: *const u8, len: usize, name: &str) -> Result<&'a [u8], FfiError> {
+    if len == 0 {
+        Ok(&[])
+    } else if data.is_null() {
+        Err(invalid_argument(format!("`{}` is null", name)))
+    } else {
+        Ok(slice::from_raw_parts(data, len))
+    }
+}
+
+fn catch<T>(f: impl FnOnce() -> Result<T, FfiError>) -> Result<T, FfiError> {
+    catch_unwind(AssertUnwindSafe(f))
+        .unwrap_or_else(|payload| Err((FibonacciStatus::Panic, panic_message(payload))))
+}
+
+/// Generate a proof with the backend `backend` from the SRS, proving key and JSON inputs.
+///
+/// # Safety
+///
+/// Each pointer must be valid for reads of its length, or may be null if the length is zero.
+#[no_mangle]
+pub unsafe extern "C" fn fibonacci_prove(
+    backend: u8,
+    srs_key: *const u8,
+    srs_key_len: usize,
+    proving_key: *const u8,
+    proving_key_len: usize,
+    input_json: *const u8,
+    input_json_len: usize,
+) -> FibonacciProveResult {
+    let result: Result<GenerateProofResult, FfiError> = catch(|| {
+        let backend =
+            crate::backend_from_id(backend).map_err(|e| invalid_argument(e.to_string()))?;
+        let srs_key = bytes(srs_key, srs_key_len, "srs_key")?;
+        let proving_key = bytes(proving_key, proving_key_len, "proving_key")?;
+        let input_json = bytes(input_json, input_json_len, "input_json")?;
+        let input = crate::parse_input(input_json).map_err(|e| invalid_argument(e.to_string()))?;
+
+        crate::prove(backend, srs_key, proving_key, input)
+            .map_err(|e| (FibonacciStatus::Failed, e.to_string()))
+    });
+
+    match result {
+        Ok((proof, public_inputs)) => FibonacciProveResult {
+            status: FibonacciStatus::Ok,
+            proof: FibonacciBuffer::from_vec(proof),
+            public_inputs: FibonacciBuffer::from_vec(public_inputs),
+            error: FibonacciBuffer::empty(),
+        },
+        Err((status, message)) => FibonacciProveResult {
+            status,
+            proof: FibonacciBuffer::empty(),
+            public_inputs: FibonacciBuffer::empty(),
+            error: FibonacciBuffer::from_vec(message.into_bytes()),
+        },
+    }
+}
+
+/// Verify `proof` against the serialized `public_inputs` with the backend `backend`.
+///
+/// # Safety
+///
+/// Each pointer must be valid for reads of its length, or may be null if the length is zero.
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn fibonacci_verify(
+    backend: u8,
+    srs_key: *const u8,
+    srs_key_len: usize,
+    verifying_key: *const u8,
+    verifying_key_len: usize,
+    proof: *const u8,
+    proof_len: usize,
+    public_inputs: *const u8,
+    public_inputs_len: usize,
+) -> FibonacciVerifyResult {
+    let result = catch(|| {
+        let backend =
+            crate::backend_from_id(backend).map_err(|e| invalid_argument(e.to_string()))?;
+        let srs_key = bytes(srs_key, srs_key_len, "srs_key")?;
+        let verifying_key = bytes(verifying_key, verifying_key_len, "verifying_key")?;
+        let proof = bytes(proof, proof_len, "proof")?;
+        let public_inputs = bytes(public_inputs, public_inputs_len, "public_inputs")?;
+
+        crate::verify(backend, srs_key, verifying_key, proof, public_inputs)
+            .map_err(|e| (FibonacciStatus::Failed, e.to_string()))
+    });
+
+    match result {
+        Ok(valid) => FibonacciVerifyResult {
+            status: FibonacciStatus::Ok,
+            valid,
+            error: FibonacciBuffer::empty(),
+        },
+        Err((status, message)) => FibonacciVerifyResult {
+            status,
+            valid: false,
+            error: FibonacciBuffer::from_vec(message.into_bytes()),
+        },
+    }
+}
+
+/// Release a buffer returned by `fibonacci_prove` or `fibonacci_verify`.
+///
+/// # Safety
+///
+/// `buffer` must come from this library and must not be freed twice.
+#[no_mangle]
+pub unsafe extern "C" fn fibonacci_buffer_free(buffer: FibonacciBuffer) {
+    if !buffer.data.is_null() {
+        drop(Vec::from_raw_parts(
+            buffer.data,
+            buffer.len,
+            buffer.capacity,
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn take_string(buffer: FibonacciBuffer) -> String {
+        let message = if buffer.data.is_null() {
+            String::new()
+        } else {
+            let bytes = unsafe { slice::from_raw_parts(buffer.data, buffer.len) };
+            String::from_utf8(bytes.to_vec()).unwrap()
+        };
+        unsafe { fibonacci_buffer_free(buffer) };
+        message
+    }
+
+    fn prove_with(backend: u8, srs_key: &[u8], input_json: &[u8]) -> FibonacciProveResult {
+        unsafe {
+            fibonacci_prove(
+                backend,
+                srs_key.as_ptr(),
+                srs_key.len(),
+                ptr::null(),
+                0,
+                input_json.as_ptr(),
+                input_json.len(),
+            )
+        }
+    }
+
+    #[test]
+    fn test_invalid_arguments() {
+        let result = prove_with(7, &[], br#"{"out": ["55"]}"#);
+        assert_eq!(result.status, FibonacciStatus::InvalidArgument);
+        assert_eq!(take_string(result.error), "Unknown backend id 7");
+
+        let result = prove_with(0, &[], b"not json");
+        assert_eq!(result.status, FibonacciStatus::InvalidArgument);
+        assert!(take_string(result.error).starts_with("Invalid input JSON"));
+
+        let result = unsafe {
+            fibonacci_verify(
+                0,
+                ptr::null(),
+                4,
+                ptr::null(),
+                0,
+                ptr::null(),
+                0,
+                ptr::null(),
+                0,
+            )
+        };
+        assert_eq!(result.status, FibonacciStatus::InvalidArgument);
+        assert!(!result.valid);
+        assert_eq!(take_string(result.error), "`srs_key` is null");
+    }
+
+    #[test]
+    fn test_invalid_artifacts_reported() {
+        for backend in 0..3 {
+            let result = prove_with(backend, &[1, 2, 3], br#"{"out": ["55"]}"#);
+            assert_ne!(result.status, FibonacciStatus::Ok);
+            assert!(result.proof.data.is_null());
+            assert!(!take_string(result.error).is_empty());
+        }
+    }
+}
@@ -0,0 +1,257 @@
+//! Byte-buffer prover API shared by the mobile bindings.
+//!
+//! Artifacts are passed by content rather than by path, and the backend is selected at runtime,
+//! so an app can bundle any of the workspace backends behind a single native library.
+use std::{any::Any, collections::HashMap, error::Error};
+
+use fibonacci_circuit::{
+    container::ProofContainer, BackendId, Capabilities, CircuitShape, FibonacciError,
+    GenerateProofResult, ProverContext, TranscriptBinding,
+};
+use gemini_fibonacci::GeminiScheme;
+use hyperplonk_fibonacci::HyperPlonkScheme;
+use unihyperplonk_fibonacci::UniHyperPlonkScheme;
+
+#[cfg(feature = "ios")]
+pub mod ios;
+pub mod merkle;
+#[cfg(feature = "uniffi")]
+mod uniffi_api;
+#[cfg(feature = "uniffi")]
+pub use uniffi_api::*;
+
+// The scaffolding refers to the exported types and functions from the crate root
+#[cfg(feature = "uniffi")]
+uniffi::include_scaffolding!("fibonacci");
+
+/// Backend ids used across the FFI boundaries: 0 = plonk, 1 = hyperplonk, 2 = gemini,
+/// 3 = unihyperplonk.
+pub fn backend_from_id(id: u8) -> Result<BackendId, FibonacciError> {
+    match id {
+        0 => Ok(BackendId::Plonk),
+        1 => Ok(BackendId::HyperPlonk),
+        2 => Ok(BackendId::Gemini),
+        3 => Ok(BackendId::UniHyperPlonk),
+        _ => Err(FibonacciError::InvalidInput(
+            format!("Unknown backend id {}", id).into(),
+        )),
+    }
+}
+
+/// Backends, codecs and features of this build. Every backend is linked in.
+pub fn capabilities() -> Capabilities {
+    Capabilities::new(BackendId::ALL)
+}
+
+/// Message of a panic caught at an FFI boundary, so bindings report it as an error rather than
+/// unwind into foreign code.
+pub fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "Prover panicked".to_string())
+}
+
+/// Parse circuit inputs from a JSON object of decimal strings, e.g. `{"out": ["55"]}`.
+pub fn parse_input(input_json: &[u8]) -> Result<HashMap<String, Vec<String>>, FibonacciError> {
+    serde_json::from_slice(input_json)
+        .map_err(|e| FibonacciError::InvalidInput(format!("Invalid input JSON: {}", e).into()))
+}
+
+/// Generate the proving and verifying key contents from the SRS contents.
+pub fn keygen(backend: BackendId, srs_key: &[u8]) -> Result<(Vec<u8>, Vec<u8>), Box<dyn Error>> {
+    match backend {
+        BackendId::Plonk => plonk_fibonacci::keygen_from_bytes(srs_key),
+        BackendId::HyperPlonk => hyperplonk_fibonacci::keygen_from_bytes(srs_key),
+        BackendId::Gemini => gemini_fibonacci::keygen_from_bytes(srs_key),
+        BackendId::UniHyperPlonk => unihyperplonk_fibonacci::keygen_from_bytes(srs_key),
+    }
+}
+
+pub fn prove(
+    backend: BackendId,
+    srs_key: &[u8],
+    proving_key: &[u8],
+    input: HashMap<String, Vec<String>>,
+) -> Result<GenerateProofResult, Box<dyn Error>> {
+    let binding = TranscriptBinding::default();
+    match backend {
+        BackendId::Plonk => plonk_fibonacci::prove_from_bytes(srs_key, proving_key, input, binding),
+        BackendId::HyperPlonk => {
+            hyperplonk_fibonacci::prove_from_bytes(srs_key, proving_key, input, binding)
+        }
+        BackendId::Gemini => {
+            gemini_fibonacci::prove_from_bytes(srs_key, proving_key, input, binding)
+        }
+        BackendId::UniHyperPlonk => {
+            unihyperplonk_fibonacci::prove_from_bytes(srs_key, proving_key, input, binding)
+        }
+    }
+}
+
+/// Prover of one backend keeping its proving key decoded across proofs, like `prove` for a
+/// long-running process. The plonkish backends prove with a `ProverContext`, plonk has none and
+/// decodes its keys for every proof.
+pub enum Prover {
+    Plonk {
+        srs_key: Vec<u8>,
+        proving_key: Vec<u8>,
+    },
+    HyperPlonk(ProverContext<HyperPlonkScheme>),
+    Gemini(ProverContext<GeminiScheme>),
+    UniHyperPlonk(ProverContext<UniHyperPlonkScheme>),
+}
+
+impl Prover {
+    /// Prover with the SRS and proving key contents of the default circuit shape.
+    pub fn new(
+        backend: BackendId,
+        srs_key: &[u8],
+        proving_key: &[u8],
+    ) -> Result<Self, FibonacciError> {
+        let shape = CircuitShape::default();
+        Ok(match backend {
+            BackendId::Plonk => Self::Plonk {
+                srs_key: srs_key.to_vec(),
+                proving_key: proving_key.to_vec(),
+            },
+            BackendId::HyperPlonk => {
+                Self::HyperPlonk(ProverContext::from_bytes(srs_key, proving_key, shape)?)
+            }
+            BackendId::Gemini => {
+                Self::Gemini(ProverContext::from_bytes(srs_key, proving_key, shape)?)
+            }
+            BackendId::UniHyperPlonk => {
+                Self::UniHyperPlonk(ProverContext::from_bytes(srs_key, proving_key, shape)?)
+            }
+        })
+    }
+
+    /// See `ProverContext::warm_up`. A no-op for plonk, which keeps nothing between proofs.
+    pub fn warm_up(&self) -> Result<(), FibonacciError> {
+        match self {
+            Self::Plonk { .. } => Ok(()),
+            Self::HyperPlonk(prover) => prover.warm_up(),
+            Self::Gemini(prover) => prover.warm_up(),
+            Self::UniHyperPlonk(prover) => prover.warm_up(),
+        }
+    }
+
+    /// Prove `input` like `prove`.
+    pub fn prove(
+        &self,
+        input: HashMap<String, Vec<String>>,
+    ) -> Result<GenerateProofResult, Box<dyn Error>> {
+        let binding = TranscriptBinding::default();
+        match self {
+            Self::Plonk {
+                srs_key,
+                proving_key,
+            } => plonk_fibonacci::prove_from_bytes(srs_key, proving_key, input, binding),
+            Self::HyperPlonk(prover) => prover.prove(input, binding),
+            Self::Gemini(prover) => prover.prove(input, binding),
+            Self::UniHyperPlonk(prover) => prover.prove(input, binding),
+        }
+    }
+}
+
+pub fn verify(
+    backend: BackendId,
+    srs_key: &[u8],
+    verifying_key: &[u8],
+    proof: &[u8],
+    public_inputs: &[u8],
+) -> Result<bool, Box<dyn Error>> {
+    let binding = TranscriptBinding::default();
+    match backend {
+        BackendId::Plonk => plonk_fibonacci::verify_from_bytes(
+            srs_key,
+            verifying_key,
+            proof,
+            public_inputs,
+            binding,
+        ),
+        BackendId::HyperPlonk => hyperplonk_fibonacci::verify_from_bytes(
+            srs_key,
+            verifying_key,
+            proof,
+            public_inputs,
+            binding,
+        ),
+        BackendId::Gemini => gemini_fibonacci::verify_from_bytes(
+            srs_key,
+            verifying_key,
+            proof,
+            public_inputs,
+            binding,
+        ),
+        BackendId::UniHyperPlonk => unihyperplonk_fibonacci::verify_from_bytes(
+            srs_key,
+            verifying_key,
+            proof,
+            public_inputs,
+            binding,
+        ),
+    }
+}
+
+/// Verify every proof of a container with the backend it records, see
+/// `fibonacci_circuit::container`. The proofs must have been made with the same `binding`.
+pub fn verify_container(
+    srs_key: &[u8],
+    verifying_key: &[u8],
+    container: &ProofContainer,
+    binding: TranscriptBinding,
+) -> Result<bool, Box<dyn Error>> {
+    match container.backend {
+        BackendId::Plonk => {
+            plonk_fibonacci::verify_container(srs_key, verifying_key, container, binding)
+        }
+        BackendId::HyperPlonk => {
+            hyperplonk_fibonacci::verify_container(verifying_key, container, binding)
+        }
+        BackendId::Gemini => gemini_fibonacci::verify_container(verifying_key, container, binding),
+        BackendId::UniHyperPlonk => {
+            unihyperplonk_fibonacci::verify_container(verifying_key, container, binding)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verify_container_binding() {
+        let srs_key = include_bytes!("../../gemini/unihyperplonk-srs-4");
+        let (proving_key, verifying_key) = keygen(BackendId::Gemini, srs_key).unwrap();
+
+        let binding = TranscriptBinding::label(b"mobile");
+        let mut input = HashMap::new();
+        input.insert("out".to_string(), vec!["55".to_string()]);
+        let proof =
+            gemini_fibonacci::prove_from_bytes(srs_key, &proving_key, input, binding).unwrap();
+
+        let mut container = ProofContainer::new(BackendId::Gemini, &verifying_key);
+        container.push(proof.into()).unwrap();
+
+        assert!(verify_container(srs_key, &verifying_key, &container, binding).unwrap());
+        assert!(!verify_container(
+            srs_key,
+            &verifying_key,
+            &container,
+            TranscriptBinding::default()
+        )
+        .unwrap());
+    }
+
+    #[test]
+    fn test_malformed_srs() {
+        let input = HashMap::from([("out".to_string(), vec!["55".to_string()])]);
+        for backend in BackendId::ALL {
+            assert!(keygen(backend, b"not an srs").is_err());
+            assert!(prove(backend, b"not an srs", b"not a key", input.clone()).is_err());
+        }
+    }
+}
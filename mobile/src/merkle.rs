@@ -0,0 +1,100 @@
+//! Byte-buffer API of the Merkle inclusion example, see `fibonacci_circuit::merkle`.
+//!
+//! The example is built on the plonkish frontend, so it's available with every backend but
+//! `plonk`.
+use std::error::Error;
+
+use fibonacci_circuit::{
+    merkle::{self, MerkleInput},
+    BackendId, FibonacciError, GenerateProofResult, TranscriptBinding,
+};
+use gemini_fibonacci::GeminiScheme;
+use hyperplonk_fibonacci::HyperPlonkScheme;
+use unihyperplonk_fibonacci::UniHyperPlonkScheme;
+
+fn unsupported(backend: BackendId) -> Box<dyn Error> {
+    FibonacciError::InvalidInput(
+        format!(
+            "The Merkle example isn't available with the {} backend",
+            backend.name()
+        )
+        .into(),
+    )
+    .into()
+}
+
+/// Parse a Merkle path from JSON, e.g.
+/// `{"leaf": "5", "path": [{"sibling": "7", "is_right": true}]}`.
+pub fn parse_input(input_json: &[u8]) -> Result<MerkleInput, FibonacciError> {
+    serde_json::from_slice(input_json).map_err(|e| {
+        FibonacciError::InvalidInput(format!("Invalid Merkle input JSON: {}", e).into())
+    })
+}
+
+/// Generate the proving and verifying key contents of paths of `depth` levels.
+pub fn keygen(
+    backend: BackendId,
+    srs_key: &[u8],
+    depth: usize,
+) -> Result<(Vec<u8>, Vec<u8>), Box<dyn Error>> {
+    match backend {
+        BackendId::Plonk => Err(unsupported(backend)),
+        BackendId::HyperPlonk => merkle::keygen_from_bytes::<HyperPlonkScheme>(srs_key, depth),
+        BackendId::Gemini => merkle::keygen_from_bytes::<GeminiScheme>(srs_key, depth),
+        BackendId::UniHyperPlonk => {
+            merkle::keygen_from_bytes::<UniHyperPlonkScheme>(srs_key, depth)
+        }
+    }
+}
+
+pub fn prove(
+    backend: BackendId,
+    proving_key: &[u8],
+    input: &MerkleInput,
+) -> Result<GenerateProofResult, Box<dyn Error>> {
+    let binding = TranscriptBinding::default();
+    match backend {
+        BackendId::Plonk => Err(unsupported(backend)),
+        BackendId::HyperPlonk => {
+            merkle::prove_from_bytes::<HyperPlonkScheme>(proving_key, input, binding)
+        }
+        BackendId::Gemini => merkle::prove_from_bytes::<GeminiScheme>(proving_key, input, binding),
+        BackendId::UniHyperPlonk => {
+            merkle::prove_from_bytes::<UniHyperPlonkScheme>(proving_key, input, binding)
+        }
+    }
+}
+
+pub fn verify(
+    backend: BackendId,
+    verifying_key: &[u8],
+    proof: &[u8],
+    public_inputs: &[u8],
+    depth: usize,
+) -> Result<bool, Box<dyn Error>> {
+    let binding = TranscriptBinding::default();
+    match backend {
+        BackendId::Plonk => Err(unsupported(backend)),
+        BackendId::HyperPlonk => merkle::verify_from_bytes::<HyperPlonkScheme>(
+            verifying_key,
+            proof,
+            public_inputs,
+            depth,
+            binding,
+        ),
+        BackendId::Gemini => merkle::verify_from_bytes::<GeminiScheme>(
+            verifying_key,
+            proof,
+            public_inputs,
+            depth,
+            binding,
+        ),
+        BackendId::UniHyperPlonk => merkle::verify_from_bytes::<UniHyperPlonkScheme>(
+            verifying_key,
+            proof,
+            public_inputs,
+            depth,
+            binding,
+        ),
+    }
+}
@@ -0,0 +1,92 @@
+//! Functions and types exported through `fibonacci.udl`.
+use std::collections::HashMap;
+
+pub use fibonacci_circuit::{BackendId, ProofBundle};
+use thiserror::Error;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyPair {
+    pub proving_key: Vec<u8>,
+    pub verifying_key: Vec<u8>,
+}
+
+#[derive(Debug, Error)]
+pub enum ProverError {
+    /// Malformed input or a bundle created by another backend
+    #[error("{0}")]
+    InvalidArgument(String),
+    /// Loading the artifacts, proving or verifying returned an error
+    #[error("{0}")]
+    Failed(String),
+}
+
+pub fn generate_keys(backend: BackendId, srs_key: Vec<u8>) -> Result<KeyPair, ProverError> {
+    let (proving_key, verifying_key) =
+        crate::keygen(backend, &srs_key).map_err(|e| ProverError::Failed(e.to_string()))?;
+
+    Ok(KeyPair {
+        proving_key,
+        verifying_key,
+    })
+}
+
+pub fn generate_proof(
+    backend: BackendId,
+    srs_key: Vec<u8>,
+    proving_key: Vec<u8>,
+    input: HashMap<String, Vec<String>>,
+) -> Result<ProofBundle, ProverError> {
+    let (proof, public_inputs) = crate::prove(backend, &srs_key, &proving_key, input)
+        .map_err(|e| ProverError::Failed(e.to_string()))?;
+
+    Ok(ProofBundle::new(proof, public_inputs).with_backend(backend))
+}
+
+pub fn verify_proof(
+    backend: BackendId,
+    srs_key: Vec<u8>,
+    verifying_key: Vec<u8>,
+    bundle: ProofBundle,
+) -> Result<bool, ProverError> {
+    if let Some(bundle_backend) = bundle.backend {
+        if bundle_backend != backend {
+            return Err(ProverError::InvalidArgument(format!(
+                "Proof was created with the {} backend, not {}",
+                bundle_backend.name(),
+                backend.name()
+            )));
+        }
+    }
+    crate::verify(
+        backend,
+        &srs_key,
+        &verifying_key,
+        &bundle.proof,
+        &bundle.public_inputs,
+    )
+    .map_err(|e| ProverError::Failed(e.to_string()))
+}
+
+/// Encode `bundle` as a string for a QR code or deep link, to pass it to another device.
+pub fn bundle_to_multibase(bundle: ProofBundle) -> Result<String, ProverError> {
+    bundle
+        .to_multibase()
+        .map_err(|e| ProverError::Failed(e.to_string()))
+}
+
+pub fn bundle_from_multibase(encoded: String) -> Result<ProofBundle, ProverError> {
+    ProofBundle::from_multibase(&encoded).map_err(|e| ProverError::InvalidArgument(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verify_rejects_other_backend() {
+        let bundle = ProofBundle::new(vec![1, 2, 3], vec![]).with_backend(BackendId::Gemini);
+
+        let result = verify_proof(BackendId::Plonk, vec![], vec![], bundle);
+        assert!(matches!(result, Err(ProverError::InvalidArgument(_))));
+    }
+}
@@ -0,0 +1,97 @@
+import Foundation
+import FibonacciMobileFFI
+
+public enum FibonacciBackend: UInt8 {
+    case plonk = 0
+    case hyperPlonk = 1
+    case gemini = 2
+    case uniHyperPlonk = 3
+}
+
+public struct FibonacciError: Error, CustomStringConvertible {
+    public enum Code: Int32 {
+        case invalidArgument = 1
+        case failed = 2
+        case panic = 3
+    }
+
+    public let code: Code
+    public let message: String
+
+    public var description: String { message }
+}
+
+public struct FibonacciProof {
+    public let proof: Data
+    public let publicInputs: Data
+}
+
+public enum Fibonacci {
+    /// Generate a proof from the SRS and proving key contents. `input` maps circuit input names
+    /// to decimal strings, e.g. `["out": ["55"]]`.
+    public static func prove(
+        backend: FibonacciBackend,
+        srsKey: Data,
+        provingKey: Data,
+        input: [String: [String]]
+    ) throws -> FibonacciProof {
+        let inputJson = try JSONSerialization.data(withJSONObject: input)
+        let result = srsKey.withUnsafeBytes { srs in
+            provingKey.withUnsafeBytes { pk in
+                inputJson.withUnsafeBytes { json in
+                    fibonacci_prove(
+                        backend.rawValue,
+                        srs.bindMemory(to: UInt8.self).baseAddress, srs.count,
+                        pk.bindMemory(to: UInt8.self).baseAddress, pk.count,
+                        json.bindMemory(to: UInt8.self).baseAddress, json.count)
+                }
+            }
+        }
+        defer {
+            fibonacci_buffer_free(result.proof)
+            fibonacci_buffer_free(result.public_inputs)
+            fibonacci_buffer_free(result.error)
+        }
+
+        try check(status: result.status, error: result.error)
+        return FibonacciProof(
+            proof: data(result.proof), publicInputs: data(result.public_inputs))
+    }
+
+    public static func verify(
+        backend: FibonacciBackend,
+        srsKey: Data,
+        verifyingKey: Data,
+        proof: FibonacciProof
+    ) throws -> Bool {
+        let result = srsKey.withUnsafeBytes { srs in
+            verifyingKey.withUnsafeBytes { vk in
+                proof.proof.withUnsafeBytes { p in
+                    proof.publicInputs.withUnsafeBytes { inputs in
+                        fibonacci_verify(
+                            backend.rawValue,
+                            srs.bindMemory(to: UInt8.self).baseAddress, srs.count,
+                            vk.bindMemory(to: UInt8.self).baseAddress, vk.count,
+                            p.bindMemory(to: UInt8.self).baseAddress, p.count,
+                            inputs.bindMemory(to: UInt8.self).baseAddress, inputs.count)
+                    }
+                }
+            }
+        }
+        defer { fibonacci_buffer_free(result.error) }
+
+        try check(status: result.status, error: result.error)
+        return result.valid
+    }
+
+    private static func check(status: FibonacciStatus, error: FibonacciBuffer) throws {
+        guard status != FIBONACCI_STATUS_OK else { return }
+        let message = String(decoding: data(error), as: UTF8.self)
+        throw FibonacciError(code: FibonacciError.Code(rawValue: status) ?? .failed, message: message)
+    }
+
+    private static func data(_ buffer: FibonacciBuffer) -> Data {
+        guard let bytes = buffer.data, buffer.len > 0 else { return Data() }
+        return Data(bytes: bytes, count: buffer.len)
+    }
+}
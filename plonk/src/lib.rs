@@ -1,27 +1,70 @@
-#[cfg(not(target_arch = "wasm32"))]
-use std::fs::File;
-#[cfg(target_arch = "wasm32")]
-use std::io::BufReader;
-use std::{collections::HashMap, error::Error};
+use std::{
+    collections::HashMap,
+    error::Error,
+    io::BufRead,
+    path::{Path, PathBuf},
+};
 
-use fibonacci_circuit::{serialisation::*, FibonacciCircuit, FibonacciError, GenerateProofResult};
+use fibonacci_circuit::{
+    circuit::initial_value,
+    container::ProofContainer,
+    io,
+    keygen::{KeygenArtifacts, KeygenPlan, PlannedKeys},
+    lock, requested_steps,
+    serialisation::*,
+    telemetry, BackendId, CircuitShape, ErrorMessage, FibonacciCircuit, FibonacciError,
+    GenerateProofResult, Hiding, TranscriptBinding, DEFAULT_STEPS,
+};
+use fibonacci_verifier_core::ProofReader;
 use halo2_proofs::{
     halo2curves::bn256::{Bn256, Fr, G1Affine},
-    plonk::{create_proof, verify_proof, ProvingKey, VerifyingKey},
+    plonk::{create_proof, keygen_pk, keygen_vk, verify_proof, ProvingKey, VerifyingKey},
     poly::{
         commitment::Params,
         kzg::{
             commitment::{KZGCommitmentScheme, ParamsKZG},
             multiopen::{ProverSHPLONK, VerifierSHPLONK},
-            strategy::SingleStrategy,
+            strategy::{AccumulatorStrategy, SingleStrategy},
         },
+        VerificationStrategy,
     },
     transcript::{
-        Blake2bRead, Blake2bWrite, Challenge255, TranscriptReadBuffer, TranscriptWriterBuffer,
+        Blake2bRead, Blake2bWrite, Challenge255, Transcript, TranscriptReadBuffer,
+        TranscriptWriterBuffer,
     },
     SerdeFormat::RawBytes,
 };
-use rand::rngs::OsRng;
+use rand::{
+    rngs::{OsRng, StdRng},
+    RngCore, SeedableRng,
+};
+
+/// The circuit over the Pasta cycle with halo2 IPA, for recursive composition
+pub mod pasta;
+/// Export proofs in the snarkjs `proof.json` / `public.json` layout
+pub mod snarkjs;
+/// SRS files with compressed or uncompressed points
+pub mod srs;
+/// The verifying key as a flat array of field elements for on-chain verifiers
+pub mod vk_elements;
+
+/// Absorb the label and context of `binding` into the transcript before proving or verifying,
+/// so a proof is only accepted under the binding it was created with. An empty binding leaves
+/// the transcript untouched.
+pub fn absorb_binding<T: Transcript<G1Affine, Challenge255<G1Affine>>>(
+    transcript: &mut T,
+    binding: &TranscriptBinding,
+) -> Result<(), FibonacciError> {
+    for fe in binding.to_field_elements() {
+        transcript.common_scalar(fe).map_err(|e| {
+            FibonacciError::Transcript(ErrorMessage::with_source(
+                format!("Failed to absorb transcript binding: {}", e),
+                e,
+            ))
+        })?;
+    }
+    Ok(())
+}
 
 pub fn generate_halo2_proof(
     params: &ParamsKZG<Bn256>,
@@ -29,24 +72,92 @@ pub fn generate_halo2_proof(
     circuit: FibonacciCircuit<Fr>,
     public_inputs: Vec<Fr>,
 ) -> Result<(Vec<u8>, Vec<Fr>), Box<dyn Error>> {
+    generate_halo2_proof_with_binding(
+        params,
+        pk,
+        circuit,
+        public_inputs,
+        TranscriptBinding::default(),
+    )
+}
+
+/// Like `generate_halo2_proof`, binding the proof to the label and context of `binding`.
+pub fn generate_halo2_proof_with_binding(
+    params: &ParamsKZG<Bn256>,
+    pk: &ProvingKey<G1Affine>,
+    circuit: FibonacciCircuit<Fr>,
+    public_inputs: Vec<Fr>,
+    binding: TranscriptBinding,
+) -> Result<(Vec<u8>, Vec<Fr>), Box<dyn Error>> {
+    generate_halo2_proof_with_hiding(params, pk, circuit, public_inputs, binding, Hiding::Off)
+}
+
+/// Like `generate_halo2_proof_with_binding`, blinding the witness if `hiding` is on. The proving
+/// key must have been generated with the same setting, see `keygen_with_hiding`.
+pub fn generate_halo2_proof_with_hiding(
+    params: &ParamsKZG<Bn256>,
+    pk: &ProvingKey<G1Affine>,
+    circuit: FibonacciCircuit<Fr>,
+    public_inputs: Vec<Fr>,
+    binding: TranscriptBinding,
+    hiding: Hiding,
+) -> Result<(Vec<u8>, Vec<Fr>), Box<dyn Error>> {
+    generate_halo2_proof_with_rng(params, pk, circuit, public_inputs, binding, hiding, OsRng)
+}
+
+/// Like `generate_halo2_proof_with_hiding`, drawing the prover's randomness from `rng` instead of
+/// the OS. A seeded `rng` makes the proof reproducible, e.g. for test vectors.
+pub fn generate_halo2_proof_with_rng(
+    params: &ParamsKZG<Bn256>,
+    pk: &ProvingKey<G1Affine>,
+    circuit: FibonacciCircuit<Fr>,
+    public_inputs: Vec<Fr>,
+    binding: TranscriptBinding,
+    hiding: Hiding,
+    rng: impl RngCore,
+) -> Result<(Vec<u8>, Vec<Fr>), Box<dyn Error>> {
+    match hiding {
+        Hiding::Off => {
+            create_halo2_proof::<false>(params, pk, circuit, public_inputs, binding, rng)
+        }
+        Hiding::On => create_halo2_proof::<true>(params, pk, circuit, public_inputs, binding, rng),
+    }
+}
+
+fn create_halo2_proof<const ZK: bool>(
+    params: &ParamsKZG<Bn256>,
+    pk: &ProvingKey<G1Affine>,
+    circuit: FibonacciCircuit<Fr>,
+    public_inputs: Vec<Fr>,
+    binding: TranscriptBinding,
+    rng: impl RngCore,
+) -> Result<(Vec<u8>, Vec<Fr>), Box<dyn Error>> {
+    fibonacci_circuit::profile_scope!("generate_halo2_proof");
+
     let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
-    let _result = create_proof::<
+    absorb_binding(&mut transcript, &binding)?;
+    create_proof::<
         KZGCommitmentScheme<Bn256>,
         ProverSHPLONK<'_, Bn256>,
         Challenge255<G1Affine>,
         _,
         Blake2bWrite<Vec<u8>, G1Affine, Challenge255<G1Affine>>,
         _,
-        false,
+        ZK,
     >(
         &params,
         &pk,
         &[circuit],
         &[&[&public_inputs]],
-        OsRng,
+        rng,
         &mut transcript,
     )
-    .expect("prover should not fail");
+    .map_err(|e| {
+        FibonacciError::Proving(ErrorMessage::with_source(
+            format!("Failed to generate the proof: {:?}", e),
+            e,
+        ))
+    })?;
 
     let proof = transcript.finalize();
 
@@ -56,19 +167,66 @@ pub fn generate_halo2_proof(
 pub fn verify_halo2_proof(
     params: &ParamsKZG<Bn256>,
     vk: &VerifyingKey<G1Affine>,
-    proof: Vec<u8>,
+    proof: &[u8],
+    public_inputs: Vec<Fr>,
+) -> Result<bool, FibonacciError> {
+    verify_halo2_proof_with_binding(
+        params,
+        vk,
+        proof,
+        public_inputs,
+        TranscriptBinding::default(),
+    )
+}
+
+/// Like `verify_halo2_proof`, only accepting proofs bound to the label and context of `binding`.
+pub fn verify_halo2_proof_with_binding(
+    params: &ParamsKZG<Bn256>,
+    vk: &VerifyingKey<G1Affine>,
+    proof: &[u8],
     public_inputs: Vec<Fr>,
+    binding: TranscriptBinding,
 ) -> Result<bool, FibonacciError> {
+    verify_halo2_proof_with_hiding(params, vk, proof, public_inputs, binding, Hiding::Off)
+}
+
+/// Like `verify_halo2_proof_with_binding`, for proofs created with `hiding`. A proof only verifies
+/// under the setting the keys were generated with.
+pub fn verify_halo2_proof_with_hiding(
+    params: &ParamsKZG<Bn256>,
+    vk: &VerifyingKey<G1Affine>,
+    proof: &[u8],
+    public_inputs: Vec<Fr>,
+    binding: TranscriptBinding,
+    hiding: Hiding,
+) -> Result<bool, FibonacciError> {
+    match hiding {
+        Hiding::Off => check_halo2_proof::<false>(params, vk, proof, public_inputs, binding),
+        Hiding::On => check_halo2_proof::<true>(params, vk, proof, public_inputs, binding),
+    }
+}
+
+fn check_halo2_proof<const ZK: bool>(
+    params: &ParamsKZG<Bn256>,
+    vk: &VerifyingKey<G1Affine>,
+    proof: &[u8],
+    public_inputs: Vec<Fr>,
+    binding: TranscriptBinding,
+) -> Result<bool, FibonacciError> {
+    fibonacci_circuit::profile_scope!("verify_halo2_proof");
+
     let strategy = SingleStrategy::new(&params);
-    let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(&proof[..]);
+    let mut reader = ProofReader::new(proof);
+    let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(&mut reader);
+    absorb_binding(&mut transcript, &binding)?;
 
     let result = verify_proof::<
         KZGCommitmentScheme<Bn256>,
         VerifierSHPLONK<'_, Bn256>,
         Challenge255<G1Affine>,
-        Blake2bRead<&[u8], G1Affine, Challenge255<G1Affine>>,
+        Blake2bRead<&mut ProofReader<'_>, G1Affine, Challenge255<G1Affine>>,
         SingleStrategy<'_, Bn256>,
-        false,
+        ZK,
     >(
         &params,
         &vk,
@@ -78,35 +236,530 @@ pub fn verify_halo2_proof(
     )
     .is_ok();
 
-    Ok(result)
+    // Bytes after the end of the transcript are rejected, so each proof has a single encoding
+    Ok(result && reader.finish().is_ok())
+}
+
+/// Verify proofs of the same verifying key and binding with one multi-scalar multiplication.
+///
+/// Each proof is checked up to its final MSM, which halo2's `AccumulatorStrategy` accumulates
+/// across proofs and computes once at the end, much cheaper than verifying the proofs one by one.
+/// The result only tells whether all proofs are valid, verify them individually to find the
+/// invalid ones.
+pub fn verify_batch(
+    params: &ParamsKZG<Bn256>,
+    vk: &VerifyingKey<G1Affine>,
+    proofs: &[(&[u8], Vec<Fr>)],
+    binding: TranscriptBinding,
+) -> Result<bool, FibonacciError> {
+    fibonacci_circuit::profile_scope!("verify_batch");
+
+    let mut strategy = AccumulatorStrategy::new(params);
+    for (proof, public_inputs) in proofs {
+        let mut reader = ProofReader::new(proof);
+        let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(&mut reader);
+        absorb_binding(&mut transcript, &binding)?;
+
+        strategy = match verify_proof::<
+            KZGCommitmentScheme<Bn256>,
+            VerifierSHPLONK<'_, Bn256>,
+            Challenge255<G1Affine>,
+            Blake2bRead<&mut ProofReader<'_>, G1Affine, Challenge255<G1Affine>>,
+            AccumulatorStrategy<'_, Bn256>,
+            false,
+        >(
+            params,
+            vk,
+            strategy,
+            &[&[public_inputs.as_slice()]],
+            &mut transcript,
+        ) {
+            Ok(strategy) if reader.remaining() == 0 => strategy,
+            // Malformed proofs and proofs with trailing bytes already fail before the
+            // accumulated check
+            _ => return Ok(false),
+        };
+    }
+
+    Ok(strategy.finalize())
+}
+
+/// Smallest k of params the circuit fits in, its 8 rows fit the 2^3 rows of
+/// `perpetual-powers-of-tau-raw-3`
+pub const MIN_K: u32 = 3;
+/// Smallest k of params the circuit fits in with hiding on, which reserves blinding rows
+pub const MIN_K_HIDING: u32 = 4;
+
+/// Smallest k of params the circuit fits in with `hiding`, `MIN_K` or `MIN_K_HIDING`.
+pub fn min_k(hiding: Hiding) -> u32 {
+    match hiding {
+        Hiding::Off => MIN_K,
+        Hiding::On => MIN_K_HIDING,
+    }
+}
+
+/// Check the params hold enough points for a circuit of 2^`needed` rows, instead of failing later
+/// with an opaque halo2 error or panic.
+pub fn check_params_size(params: &ParamsKZG<Bn256>, needed: u32) -> Result<(), FibonacciError> {
+    if params.k() < needed {
+        return Err(FibonacciError::SrsTooSmall {
+            needed: needed as usize,
+            available: params.k() as usize,
+        });
+    }
+    Ok(())
+}
+
+/// Generate the proving and verifying keys of the Fibonacci circuit.
+pub fn keygen(
+    params: &ParamsKZG<Bn256>,
+) -> Result<(ProvingKey<G1Affine>, VerifyingKey<G1Affine>), halo2_proofs::plonk::Error> {
+    keygen_with_hiding(params, Hiding::Off)
+}
+
+/// Like `keygen`, for proofs blinded according to `hiding`.
+pub fn keygen_with_hiding(
+    params: &ParamsKZG<Bn256>,
+    hiding: Hiding,
+) -> Result<(ProvingKey<G1Affine>, VerifyingKey<G1Affine>), halo2_proofs::plonk::Error> {
+    match hiding {
+        Hiding::Off => keygen_circuit::<false>(params),
+        Hiding::On => keygen_circuit::<true>(params),
+    }
+}
+
+fn keygen_circuit<const ZK: bool>(
+    params: &ParamsKZG<Bn256>,
+) -> Result<(ProvingKey<G1Affine>, VerifyingKey<G1Affine>), halo2_proofs::plonk::Error> {
+    fibonacci_circuit::profile_scope!("keygen");
+
+    // Use empty value on public input for only for getting proving / verifying keys
+    let circuit = FibonacciCircuit {
+        public_input: vec![vec![]],
+        ..Default::default()
+    };
+
+    let verifying_key = keygen_vk::<_, _, _, ZK>(params, &circuit)?;
+    let proving_key = keygen_pk::<_, _, _, ZK>(params, verifying_key.clone(), &circuit)?;
+
+    Ok((proving_key, verifying_key))
+}
+
+/// Regenerate the proving key matching a published verifying key. Proving key generation is
+/// deterministic, so the result matches the key originally generated with `keygen`.
+pub fn keygen_pk_from_vk(
+    params: &ParamsKZG<Bn256>,
+    verifying_key: VerifyingKey<G1Affine>,
+) -> Result<ProvingKey<G1Affine>, halo2_proofs::plonk::Error> {
+    fibonacci_circuit::profile_scope!("keygen_pk");
+
+    let circuit = FibonacciCircuit {
+        public_input: vec![vec![]],
+        ..Default::default()
+    };
+
+    keygen_pk::<_, _, _, false>(params, verifying_key, &circuit)
+}
+
+/// Like `write_keys`, taking the SRS contents and returning the proving and verifying key
+/// contents instead of writing them.
+pub fn keygen_from_bytes(srs_key: &[u8]) -> Result<(Vec<u8>, Vec<u8>), Box<dyn Error>> {
+    keygen_from_bytes_with_hiding(srs_key, Hiding::Off)
+}
+
+/// Like `keygen_from_bytes`, for proofs blinded according to `hiding`.
+pub fn keygen_from_bytes_with_hiding(
+    srs_key: &[u8],
+    hiding: Hiding,
+) -> Result<(Vec<u8>, Vec<u8>), Box<dyn Error>> {
+    let params = srs::read_params(srs_key)?;
+    check_params_size(&params, min_k(hiding))?;
+
+    let (proving_key, verifying_key) = keygen_with_hiding(&params, hiding).map_err(|e| {
+        FibonacciError::Setup(ErrorMessage::with_source(
+            format!("Key generation failed: {:?}", e),
+            e,
+        ))
+    })?;
+
+    let mut pk_bytes = Vec::new();
+    proving_key.write(&mut pk_bytes, RawBytes)?;
+    let mut vk_bytes = Vec::new();
+    verifying_key.write(&mut vk_bytes, RawBytes)?;
+
+    Ok((pk_bytes, vk_bytes))
+}
+
+/// Like `keygen_pk_from_vk`, taking the SRS and verifying key contents and returning the proving
+/// key contents.
+pub fn keygen_pk_from_vk_bytes(
+    srs_key: &[u8],
+    verifying_key: &[u8],
+) -> Result<Vec<u8>, Box<dyn Error>> {
+    let params = srs::read_params(srs_key)?;
+
+    let verifying_key = read_verifying_key(verifying_key, Hiding::Off)?;
+    check_params_size(&params, verifying_key.get_domain().k())?;
+
+    let proving_key = keygen_pk_from_vk(&params, verifying_key).map_err(|e| {
+        FibonacciError::Setup(ErrorMessage::with_source(
+            format!("Key generation failed: {:?}", e),
+            e,
+        ))
+    })?;
+
+    let mut pk_bytes = Vec::new();
+    proving_key.write(&mut pk_bytes, RawBytes)?;
+    Ok(pk_bytes)
+}
+
+/// Error for key contents which don't fit the column layout of the compiled-in circuit.
+///
+/// Raw halo2 keys carry no circuit hash. They are read according to the layout of
+/// `FibonacciCircuit`, so a key of another layout either runs out of bytes or leaves some unread.
+fn key_mismatch(key: &str, cause: impl std::fmt::Display) -> FibonacciError {
+    FibonacciError::CircuitMismatch(
+        format!(
+        "The {} doesn't match the circuit ({}), regenerate the keys for the current circuit with \
+         gen-plonk-keys",
+        key, cause
+    )
+        .into(),
+    )
+}
+
+/// Read a proving key generated with `hiding`, from its contents or a key file opened with
+/// `io::open_key_file`.
+fn read_proving_key<R: BufRead>(
+    mut reader: R,
+    hiding: Hiding,
+) -> Result<ProvingKey<G1Affine>, FibonacciError> {
+    let proving_key = match hiding {
+        Hiding::Off => ProvingKey::read::<_, FibonacciCircuit<Fr>, false>(&mut reader, RawBytes),
+        Hiding::On => ProvingKey::read::<_, FibonacciCircuit<Fr>, true>(&mut reader, RawBytes),
+    }
+    .map_err(|e| key_mismatch("proving key", e))?;
+    check_key_end(&mut reader, "proving key")?;
+    Ok(proving_key)
+}
+
+/// Like `read_proving_key`, for a verifying key.
+fn read_verifying_key<R: BufRead>(
+    mut reader: R,
+    hiding: Hiding,
+) -> Result<VerifyingKey<G1Affine>, FibonacciError> {
+    let verifying_key = match hiding {
+        Hiding::Off => VerifyingKey::read::<_, FibonacciCircuit<Fr>, false>(&mut reader, RawBytes),
+        Hiding::On => VerifyingKey::read::<_, FibonacciCircuit<Fr>, true>(&mut reader, RawBytes),
+    }
+    .map_err(|e| key_mismatch("verifying key", e))?;
+    check_key_end(&mut reader, "verifying key")?;
+    Ok(verifying_key)
+}
+
+// Keys of another layout may parse, but leave bytes behind
+fn check_key_end<R: BufRead>(reader: &mut R, key: &str) -> Result<(), FibonacciError> {
+    let trailing = reader.fill_buf().map_err(|e| {
+        FibonacciError::Io(ErrorMessage::with_source(
+            format!("Failed to read the {}: {}", key, e),
+            e,
+        ))
+    })?;
+    if !trailing.is_empty() {
+        return Err(key_mismatch(key, "trailing bytes"));
+    }
+    Ok(())
+}
+
+/// Extract the verifying key embedded in a proving key, for hosts which only stored the proving
+/// key.
+pub fn extract_vk(proving_key: &ProvingKey<G1Affine>) -> VerifyingKey<G1Affine> {
+    proving_key.get_vk().clone()
+}
+
+/// Like `extract_vk`, taking and returning key contents as written by `write_keys`.
+pub fn extract_vk_from_bytes(proving_key: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+    let proving_key = read_proving_key(proving_key, Hiding::Off)?;
+
+    let mut vk_bytes = Vec::new();
+    extract_vk(&proving_key).write(&mut vk_bytes, RawBytes)?;
+    Ok(vk_bytes)
+}
+
+/// Read a proving key file written by `write_keys` after checking its detached signature, the
+/// plonk counterpart of `io::load_from_file_verified`. Returns the verified contents for
+/// `prove_from_bytes`, once they parse as a key of the circuit.
+#[cfg(feature = "signatures")]
+pub fn read_proving_key_verified(
+    path: &Path,
+    publisher_key: &[u8; 32],
+    hiding: Hiding,
+) -> Result<Vec<u8>, Box<dyn Error>> {
+    let bytes = fibonacci_circuit::signature::read_verified_file(path, publisher_key)?;
+    read_proving_key(bytes.as_slice(), hiding)?;
+    Ok(bytes)
+}
+
+/// Like `read_proving_key_verified`, for a verifying key.
+#[cfg(feature = "signatures")]
+pub fn read_verifying_key_verified(
+    path: &Path,
+    publisher_key: &[u8; 32],
+    hiding: Hiding,
+) -> Result<Vec<u8>, Box<dyn Error>> {
+    let bytes = fibonacci_circuit::signature::read_verified_file(path, publisher_key)?;
+    read_verifying_key(bytes.as_slice(), hiding)?;
+    Ok(bytes)
+}
+
+/// Generate proving and verifying keys from the SRS file and store them in `out_dir`.
+///
+/// Returns the paths of the proving key and verifying key files. Panics on failure, see
+/// `try_write_keys`.
+pub fn write_keys(srs_path: &Path, out_dir: &Path, filename_prefix: &str) -> (PathBuf, PathBuf) {
+    try_write_keys(srs_path, out_dir, filename_prefix).expect("Writing keys should not fail")
+}
+
+/// Like `write_keys`, returning an error for an unusable SRS or an unwritable `out_dir`.
+pub fn try_write_keys(
+    srs_path: &Path,
+    out_dir: &Path,
+    filename_prefix: &str,
+) -> Result<(PathBuf, PathBuf), Box<dyn Error>> {
+    let params = srs::read_params_path(srs_path)?;
+    check_params_size(&params, MIN_K)?;
+    std::fs::create_dir_all(out_dir)?;
+
+    let (proving_key, verifying_key) = keygen(&params).map_err(|e| {
+        FibonacciError::Setup(ErrorMessage::with_source(
+            format!("Key generation failed: {:?}", e),
+            e,
+        ))
+    })?;
+
+    // Keep other processes from writing or reading keys in `out_dir` meanwhile
+    let _lock = lock::ArtifactLock::exclusive(out_dir)?;
+
+    let pk_path = out_dir.join(io::key_file_name(filename_prefix, "pk"));
+    let mut pk_bytes = Vec::new();
+    ProvingKey::<G1Affine>::write(&proving_key, &mut pk_bytes, RawBytes)?;
+    io::write_atomic(&pk_path, &pk_bytes, true)?;
+
+    let vk_path = out_dir.join(io::key_file_name(filename_prefix, "vk"));
+    let mut vk_bytes = Vec::new();
+    VerifyingKey::<G1Affine>::write(&verifying_key, &mut vk_bytes, RawBytes)?;
+    io::write_atomic(&vk_path, &vk_bytes, true)?;
+
+    Ok((pk_path, vk_path))
+}
+
+/// Generate keys from the SRS file into `out_dir` as `try_write_keys` does, and also pack them
+/// into a key bundle with `bundle`.
+pub fn generate_keys(
+    srs_path: &Path,
+    out_dir: &Path,
+    filename_prefix: &str,
+    bundle: bool,
+) -> Result<KeygenArtifacts, Box<dyn Error>> {
+    let (pk_path, vk_path) = try_write_keys(srs_path, out_dir, filename_prefix)?;
+    let artifacts = KeygenArtifacts::new(BackendId::Plonk, srs_path, pk_path, vk_path)?;
+    if !bundle {
+        return Ok(artifacts);
+    }
+
+    let bundle_path = out_dir.join(io::bundle_file_name(filename_prefix));
+    io::bundle_key_files(
+        BackendId::Plonk,
+        &artifacts.pk_path,
+        &artifacts.vk_path,
+        &bundle_path,
+    )?;
+    Ok(artifacts.with_bundle(bundle_path)?)
+}
+
+/// Report the files `write_keys` would write, and the key bundle with `bundle`, with the size of
+/// each key and whether the SRS is large enough for the circuit, without writing anything.
+pub fn plan_keys(
+    srs_path: &Path,
+    out_dir: &Path,
+    filename_prefix: &str,
+    bundle: bool,
+) -> Result<KeygenPlan, Box<dyn Error>> {
+    let params = srs::read_params_path(srs_path)?;
+
+    // halo2 keys span the whole domain of the params
+    let sizes = match check_params_size(&params, MIN_K) {
+        Ok(()) => {
+            let (proving_key, verifying_key) = keygen(&params).map_err(|e| {
+                FibonacciError::Setup(ErrorMessage::with_source(
+                    format!("Key generation failed: {:?}", e),
+                    e,
+                ))
+            })?;
+            let mut pk_bytes = Vec::new();
+            proving_key.write(&mut pk_bytes, RawBytes)?;
+            let mut vk_bytes = Vec::new();
+            verifying_key.write(&mut vk_bytes, RawBytes)?;
+            Some((pk_bytes.len(), vk_bytes.len()))
+        }
+        Err(_) => None,
+    };
+    let keys = PlannedKeys {
+        shape: CircuitShape {
+            steps: DEFAULT_STEPS,
+            k: params.k().max(MIN_K) as usize,
+        },
+        pk_path: out_dir.join(io::key_file_name(filename_prefix, "pk")),
+        vk_path: out_dir.join(io::key_file_name(filename_prefix, "vk")),
+        sizes,
+    };
+
+    Ok(KeygenPlan {
+        backend: BackendId::Plonk,
+        srs_path: srs_path.to_path_buf(),
+        srs_k: params.k() as usize,
+        keys: vec![keys],
+        manifest: None,
+        bundle_path: bundle.then(|| out_dir.join(io::bundle_file_name(filename_prefix))),
+    })
 }
 
 fn prove_with_params(
     params: ParamsKZG<Bn256>,
     proving_key: ProvingKey<G1Affine>,
     input: HashMap<String, Vec<String>>,
+    binding: TranscriptBinding,
+    hiding: Hiding,
+    rng: impl RngCore,
 ) -> Result<GenerateProofResult, Box<dyn Error>> {
+    check_params_size(&params, proving_key.get_vk().get_domain().k())?;
+
+    let timer = telemetry::Timer::start();
+    let result = prove_circuit(params, proving_key, input, binding, hiding, rng);
+    telemetry::record_proof_result(BackendId::Plonk, timer, &result);
+
+    result
+}
+
+fn prove_circuit(
+    params: ParamsKZG<Bn256>,
+    proving_key: ProvingKey<G1Affine>,
+    input: HashMap<String, Vec<String>>,
+    binding: TranscriptBinding,
+    hiding: Hiding,
+    rng: impl RngCore,
+) -> Result<GenerateProofResult, Box<dyn Error>> {
+    // Keys are only generated for the default sequence length, see `keygen`, and don't record
+    // it, so unlike a plonkish key set they can't serve other lengths
+    let steps = requested_steps(&input)?;
+    if steps != DEFAULT_STEPS {
+        return Err(FibonacciError::InvalidInput(
+            format!(
+                "Plonk keys only prove sequences of length {}, not `steps` {}, use a plonkish \
+                 backend for other lengths",
+                DEFAULT_STEPS, steps
+            )
+            .into(),
+        )
+        .into());
+    }
     let circuit = FibonacciCircuit::<Fr>::default();
 
-    let circuit_inputs = deserialize_circuit_inputs(input)
-        .map_err(|e| FibonacciError(format!("Failed to deserialize circuit inputs: {}", e)))?;
+    let circuit_inputs = deserialize_circuit_inputs(input).map_err(|e| {
+        FibonacciError::InvalidInput(format!("Failed to deserialize circuit inputs: {}", e).into())
+    })?;
 
     let out = circuit_inputs
         .get("out")
-        .ok_or_else(|| FibonacciError("Failed to get `out` value".to_string()))?
+        .ok_or_else(|| FibonacciError::InvalidInput("Failed to get `out` value".into()))?
         .get(0)
-        .ok_or_else(|| FibonacciError("Failed to get `out` value".to_string()))?
+        .ok_or_else(|| FibonacciError::InvalidInput("Failed to get `out` value".into()))?
         .clone();
 
     // The public input followed fibonacci circuit
-    let public_input = vec![Fr::from(1), Fr::from(1), out];
+    let a = initial_value(&circuit_inputs, "a")?;
+    let b = initial_value(&circuit_inputs, "b")?;
+    let public_input = vec![a, b, out];
+
+    let (proof, unserialized_inputs) = generate_halo2_proof_with_rng(
+        &params,
+        &proving_key,
+        circuit,
+        public_input,
+        binding,
+        hiding,
+        rng,
+    )?;
+    Ok((proof, encode_public_inputs(&unserialized_inputs)))
+}
+
+/// Like `prove_with_binding`, taking the SRS and proving key contents instead of paths. Available
+/// on every target for embedders (e.g. mobile apps) which don't keep artifacts on the filesystem.
+pub fn prove_from_bytes(
+    srs_key: &[u8],
+    proving_key: &[u8],
+    input: HashMap<String, Vec<String>>,
+    binding: TranscriptBinding,
+) -> Result<GenerateProofResult, Box<dyn Error>> {
+    prove_from_bytes_with_hiding(srs_key, proving_key, input, binding, Hiding::Off)
+}
 
-    let (proof, unserialized_inputs) =
-        generate_halo2_proof(&params, &proving_key, circuit, public_input).unwrap();
-    let serialized_inputs = bincode::serialize(&InputsSerialisationWrapper(unserialized_inputs))
-        .map_err(|e| FibonacciError(format!("Serialization of Inputs failed: {}", e)))?;
+/// Like `prove_from_bytes`, with a proving key generated by `keygen_from_bytes_with_hiding`.
+pub fn prove_from_bytes_with_hiding(
+    srs_key: &[u8],
+    proving_key: &[u8],
+    input: HashMap<String, Vec<String>>,
+    binding: TranscriptBinding,
+    hiding: Hiding,
+) -> Result<GenerateProofResult, Box<dyn Error>> {
+    let params = srs::read_params(srs_key)?;
+    let proving_key = read_proving_key(proving_key, hiding)?;
 
-    Ok((proof, serialized_inputs))
+    prove_with_params(params, proving_key, input, binding, hiding, OsRng)
+}
+
+/// Median time of `runs` proofs with params of 2^`k` rows, for the samples of a
+/// `fibonacci_circuit::estimate::HardwareProfile`. The params come from a seeded setup, which is
+/// only good for timing.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn measure_prover_time(k: u32, runs: usize) -> Result<std::time::Duration, Box<dyn Error>> {
+    if k < MIN_K || k as usize > fibonacci_circuit::estimate::MAX_K {
+        return Err(FibonacciError::InvalidInput(
+            format!(
+                "Calibration size k = {} is outside {}..={}",
+                k,
+                MIN_K,
+                fibonacci_circuit::estimate::MAX_K
+            )
+            .into(),
+        )
+        .into());
+    }
+    let params = ParamsKZG::<Bn256>::setup(k, StdRng::seed_from_u64(0));
+    let (proving_key, _) = keygen(&params).map_err(|e| {
+        FibonacciError::Setup(ErrorMessage::with_source(
+            format!("Key generation failed: {:?}", e),
+            e,
+        ))
+    })?;
+    let input = HashMap::from([("out".to_string(), vec!["55".to_string()])]);
+
+    let mut times = Vec::with_capacity(runs.max(1));
+    for seed in 0..runs.max(1) as u64 {
+        let (params, proving_key) = (params.clone(), proving_key.clone());
+        let start = std::time::Instant::now();
+        prove_circuit(
+            params,
+            proving_key,
+            input.clone(),
+            TranscriptBinding::default(),
+            Hiding::Off,
+            StdRng::seed_from_u64(seed),
+        )?;
+        times.push(start.elapsed());
+    }
+    times.sort();
+    Ok(times[times.len() / 2])
 }
 
 #[cfg(not(target_arch = "wasm32"))]
@@ -115,16 +768,66 @@ pub fn prove(
     proving_key_path: &str,
     input: HashMap<String, Vec<String>>,
 ) -> Result<GenerateProofResult, Box<dyn Error>> {
-    let mut param_fs =
-        File::open(srs_key_path).expect(&format!("Couldn't load params from '{}'", srs_key_path));
-    let params = ParamsKZG::<Bn256>::read(&mut param_fs)
-        .expect(&format!("Failed to read params from '{}'", srs_key_path));
+    prove_with_label(srs_key_path, proving_key_path, input, &[])
+}
+
+/// Like `prove`, binding the proof to a domain separation label (e.g. the application name)
+/// which the verifier must supply identically.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn prove_with_label(
+    srs_key_path: &str,
+    proving_key_path: &str,
+    input: HashMap<String, Vec<String>>,
+    label: &[u8],
+) -> Result<GenerateProofResult, Box<dyn Error>> {
+    prove_with_binding(
+        srs_key_path,
+        proving_key_path,
+        input,
+        TranscriptBinding::label(label),
+    )
+}
+
+/// Like `prove`, binding the proof to a label and caller-supplied context (e.g. a session id or
+/// chain id), so it can't be replayed where the verifier expects a different context.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn prove_with_binding(
+    srs_key_path: &str,
+    proving_key_path: &str,
+    input: HashMap<String, Vec<String>>,
+    binding: TranscriptBinding,
+) -> Result<GenerateProofResult, Box<dyn Error>> {
+    let params = srs::read_params_path(Path::new(srs_key_path))?;
 
-    let mut pk_fs = File::open(proving_key_path).expect("Couldn't load proving key");
     let proving_key =
-        ProvingKey::read::<_, FibonacciCircuit<Fr>, false>(&mut pk_fs, RawBytes).unwrap();
+        read_proving_key(io::open_key_file(Path::new(proving_key_path))?, Hiding::Off)?;
+
+    prove_with_params(params, proving_key, input, binding, Hiding::Off, OsRng)
+}
 
-    prove_with_params(params, proving_key, input)
+/// Like `prove`, with the prover's randomness drawn from an RNG seeded with `seed`, so the same
+/// keys and input always give the same proof. For test vectors, not for proofs which should hide
+/// their witness.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn prove_seeded(
+    srs_key_path: &str,
+    proving_key_path: &str,
+    input: HashMap<String, Vec<String>>,
+    seed: u64,
+) -> Result<GenerateProofResult, Box<dyn Error>> {
+    let params = srs::read_params_path(Path::new(srs_key_path))?;
+
+    let proving_key =
+        read_proving_key(io::open_key_file(Path::new(proving_key_path))?, Hiding::Off)?;
+
+    prove_with_params(
+        params,
+        proving_key,
+        input,
+        TranscriptBinding::default(),
+        Hiding::Off,
+        StdRng::seed_from_u64(seed),
+    )
 }
 
 #[cfg(target_arch = "wasm32")]
@@ -133,74 +836,235 @@ pub fn prove(
     proving_key: &[u8],
     input: HashMap<String, Vec<String>>,
 ) -> Result<GenerateProofResult, Box<dyn Error>> {
-    let mut params_reader = BufReader::new(srs_key);
-    let params =
-        ParamsKZG::<Bn256>::read(&mut params_reader).expect("Failed to read params from bytes");
+    prove_with_label(srs_key, proving_key, input, &[])
+}
 
-    let mut pk_reader = BufReader::new(proving_key);
-    let proving_key =
-        ProvingKey::read::<_, FibonacciCircuit<Fr>, false>(&mut pk_reader, RawBytes).unwrap();
+/// Like `prove`, binding the proof to a domain separation label (e.g. the application name)
+/// which the verifier must supply identically.
+#[cfg(target_arch = "wasm32")]
+pub fn prove_with_label(
+    srs_key: &[u8],
+    proving_key: &[u8],
+    input: HashMap<String, Vec<String>>,
+    label: &[u8],
+) -> Result<GenerateProofResult, Box<dyn Error>> {
+    prove_with_binding(srs_key, proving_key, input, TranscriptBinding::label(label))
+}
 
-    prove_with_params(params, proving_key, input)
+/// Like `prove`, binding the proof to a label and caller-supplied context (e.g. a session id or
+/// chain id), so it can't be replayed where the verifier expects a different context.
+#[cfg(target_arch = "wasm32")]
+pub fn prove_with_binding(
+    srs_key: &[u8],
+    proving_key: &[u8],
+    input: HashMap<String, Vec<String>>,
+    binding: TranscriptBinding,
+) -> Result<GenerateProofResult, Box<dyn Error>> {
+    prove_from_bytes(srs_key, proving_key, input, binding)
 }
 
 fn verify_with_params(
     params: ParamsKZG<Bn256>,
     verifying_key: VerifyingKey<G1Affine>,
-    proof: Vec<u8>,
-    public_inputs: Vec<u8>,
+    proof: &[u8],
+    public_inputs: &[u8],
+    binding: TranscriptBinding,
+    hiding: Hiding,
 ) -> Result<bool, Box<dyn Error>> {
-    let deserialized_inputs: Vec<Fr> =
-        bincode::deserialize::<InputsSerialisationWrapper>(&public_inputs)
-            .map_err(|e| FibonacciError(e.to_string()))?
-            .0;
+    check_params_size(&params, verifying_key.get_domain().k())?;
+
+    let timer = telemetry::Timer::start();
+    let result = verify_circuit(params, verifying_key, proof, public_inputs, binding, hiding);
+    telemetry::record_verification(
+        BackendId::Plonk,
+        timer.elapsed(),
+        result.as_ref().ok().copied(),
+    );
+
+    result
+}
 
-    let result = verify_halo2_proof(&params, &verifying_key, proof, deserialized_inputs)
-        .map_err(|e| FibonacciError(format!("Verification failed: {}", e)))?;
+fn verify_circuit(
+    params: ParamsKZG<Bn256>,
+    verifying_key: VerifyingKey<G1Affine>,
+    proof: &[u8],
+    public_inputs: &[u8],
+    binding: TranscriptBinding,
+    hiding: Hiding,
+) -> Result<bool, Box<dyn Error>> {
+    let deserialized_inputs = decode_public_inputs(public_inputs)?;
+
+    let result = verify_halo2_proof_with_hiding(
+        &params,
+        &verifying_key,
+        proof,
+        deserialized_inputs,
+        binding,
+        hiding,
+    )
+    .map_err(|e| {
+        FibonacciError::Verification(ErrorMessage::with_source(
+            format!("Verification failed: {}", e),
+            e,
+        ))
+    })?;
 
     Ok(result)
 }
 
+/// Like `verify_with_binding`, taking the SRS and verifying key contents instead of paths.
+pub fn verify_from_bytes(
+    srs_key: &[u8],
+    verifying_key: &[u8],
+    proof: &[u8],
+    public_inputs: &[u8],
+    binding: TranscriptBinding,
+) -> Result<bool, Box<dyn Error>> {
+    verify_from_bytes_with_hiding(
+        srs_key,
+        verifying_key,
+        proof,
+        public_inputs,
+        binding,
+        Hiding::Off,
+    )
+}
+
+/// Like `verify_from_bytes`, with a verifying key generated by `keygen_from_bytes_with_hiding`.
+pub fn verify_from_bytes_with_hiding(
+    srs_key: &[u8],
+    verifying_key: &[u8],
+    proof: &[u8],
+    public_inputs: &[u8],
+    binding: TranscriptBinding,
+    hiding: Hiding,
+) -> Result<bool, Box<dyn Error>> {
+    let verifying_key = read_verifying_key(verifying_key, hiding)?;
+    let params = srs::read_verifier_params(srs_key, verifying_key.get_domain().k())?;
+
+    verify_with_params(params, verifying_key, proof, public_inputs, binding, hiding)
+}
+
+/// Verify every proof of a container made for `verifying_key` with one accumulated check, see
+/// `verify_batch`. Containers of another backend or key and malformed bundles are errors.
+pub fn verify_container(
+    srs_key: &[u8],
+    verifying_key: &[u8],
+    container: &ProofContainer,
+    binding: TranscriptBinding,
+) -> Result<bool, Box<dyn Error>> {
+    container.validate(BackendId::Plonk, verifying_key)?;
+    let verifying_key = read_verifying_key(verifying_key, Hiding::Off)?;
+    let params = srs::read_verifier_params(srs_key, verifying_key.get_domain().k())?;
+
+    let proofs = container
+        .bundles
+        .iter()
+        .map(|bundle| {
+            let inputs = decode_public_inputs(&bundle.public_inputs)?;
+            Ok((bundle.proof.as_slice(), inputs))
+        })
+        .collect::<Result<Vec<_>, FibonacciError>>()?;
+
+    Ok(verify_batch(&params, &verifying_key, &proofs, binding)?)
+}
+
 #[cfg(not(target_arch = "wasm32"))]
 pub fn verify(
     srs_key_path: &str,
     verifying_key_path: &str,
-    proof: Vec<u8>,
-    public_inputs: Vec<u8>,
+    proof: &[u8],
+    public_inputs: &[u8],
 ) -> Result<bool, Box<dyn Error>> {
-    let mut param_fs =
-        File::open(srs_key_path).expect(&format!("Couldn't load params from '{}'", srs_key_path));
-    let params = ParamsKZG::<Bn256>::read(&mut param_fs)
-        .expect(&format!("Failed to read params from '{}'", srs_key_path));
+    verify_with_label(srs_key_path, verifying_key_path, proof, public_inputs, &[])
+}
 
-    let mut vk_fs = File::open(verifying_key_path).expect("Couldn't load verifying key");
-    let verifying_key =
-        VerifyingKey::read::<_, FibonacciCircuit<Fr>, false>(&mut vk_fs, RawBytes).unwrap();
+/// Like `verify`, only accepting proofs created with the same domain separation label.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn verify_with_label(
+    srs_key_path: &str,
+    verifying_key_path: &str,
+    proof: &[u8],
+    public_inputs: &[u8],
+    label: &[u8],
+) -> Result<bool, Box<dyn Error>> {
+    verify_with_binding(
+        srs_key_path,
+        verifying_key_path,
+        proof,
+        public_inputs,
+        TranscriptBinding::label(label),
+    )
+}
 
-    verify_with_params(params, verifying_key, proof, public_inputs)
+/// Like `verify`, only accepting proofs created with the same label and context.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn verify_with_binding(
+    srs_key_path: &str,
+    verifying_key_path: &str,
+    proof: &[u8],
+    public_inputs: &[u8],
+    binding: TranscriptBinding,
+) -> Result<bool, Box<dyn Error>> {
+    let verifying_key = read_verifying_key(
+        io::open_key_file(Path::new(verifying_key_path))?,
+        Hiding::Off,
+    )?;
+    let params =
+        srs::read_verifier_params_path(Path::new(srs_key_path), verifying_key.get_domain().k())?;
+
+    verify_with_params(
+        params,
+        verifying_key,
+        proof,
+        public_inputs,
+        binding,
+        Hiding::Off,
+    )
 }
 
 #[cfg(target_arch = "wasm32")]
 pub fn verify(
     srs_key: &[u8],
     verifying_key: &[u8],
-    proof: Vec<u8>,
-    public_inputs: Vec<u8>,
+    proof: &[u8],
+    public_inputs: &[u8],
 ) -> Result<bool, Box<dyn Error>> {
-    let mut params_reader = BufReader::new(srs_key);
-    let params = ParamsKZG::<Bn256>::read(&mut params_reader).expect("Failed to read params");
+    verify_with_label(srs_key, verifying_key, proof, public_inputs, &[])
+}
 
-    let mut vk_reader = BufReader::new(verifying_key);
-    let verifying_key =
-        VerifyingKey::read::<_, FibonacciCircuit<Fr>, false>(&mut vk_reader, RawBytes).unwrap();
+/// Like `verify`, only accepting proofs created with the same domain separation label.
+#[cfg(target_arch = "wasm32")]
+pub fn verify_with_label(
+    srs_key: &[u8],
+    verifying_key: &[u8],
+    proof: &[u8],
+    public_inputs: &[u8],
+    label: &[u8],
+) -> Result<bool, Box<dyn Error>> {
+    verify_with_binding(
+        srs_key,
+        verifying_key,
+        proof,
+        public_inputs,
+        TranscriptBinding::label(label),
+    )
+}
 
-    verify_with_params(params, verifying_key, proof, public_inputs)
+/// Like `verify`, only accepting proofs created with the same label and context.
+#[cfg(target_arch = "wasm32")]
+pub fn verify_with_binding(
+    srs_key: &[u8],
+    verifying_key: &[u8],
+    proof: &[u8],
+    public_inputs: &[u8],
+    binding: TranscriptBinding,
+) -> Result<bool, Box<dyn Error>> {
+    verify_from_bytes(srs_key, verifying_key, proof, public_inputs, binding)
 }
 
 #[cfg(test)]
 mod tests {
-    use halo2_proofs::plonk::{keygen_pk, keygen_vk};
-
     use super::*;
 
     fn initialize_params_and_circuit() -> (FibonacciCircuit<Fr>, ParamsKZG<Bn256>) {
@@ -211,13 +1075,11 @@ mod tests {
         (circuit, params)
     }
 
-    fn generate_and_verify_proof(public_inputs: Vec<Fr>) -> bool {
+    fn generate_and_verify_proof(public_inputs: Vec<Fr>) -> Result<bool, Box<dyn Error>> {
         let (circuit, params) = initialize_params_and_circuit();
 
-        let verifying_key = keygen_vk::<_, _, _, false>(&params, &circuit)
-            .expect("Verifying Key generation should not fail");
-        let proving_key = keygen_pk::<_, _, _, false>(&params, verifying_key.clone(), &circuit)
-            .expect("Proving Key generation should not fail");
+        let verifying_key = keygen_vk::<_, _, _, false>(&params, &circuit)?;
+        let proving_key = keygen_pk::<_, _, _, false>(&params, verifying_key.clone(), &circuit)?;
 
         let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
 
@@ -236,8 +1098,7 @@ mod tests {
             &[&[&public_inputs]],
             OsRng,
             &mut transcript,
-        )
-        .expect("prover should not fail");
+        )?;
 
         let proof = transcript.finalize();
 
@@ -245,7 +1106,7 @@ mod tests {
         let strategy = SingleStrategy::new(&params);
         let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(&proof[..]);
 
-        verify_proof::<
+        Ok(verify_proof::<
             KZGCommitmentScheme<Bn256>,
             VerifierSHPLONK<'_, Bn256>,
             Challenge255<G1Affine>,
@@ -259,19 +1120,19 @@ mod tests {
             &[&[&public_inputs]],
             &mut transcript,
         )
-        .is_ok()
+        .is_ok())
     }
 
     #[test]
     fn test_fibonacci_circuit() {
         let public_inputs = vec![Fr::from(1), Fr::from(1), Fr::from(55)];
-        let valid_result = generate_and_verify_proof(public_inputs);
+        let valid_result = generate_and_verify_proof(public_inputs).unwrap();
 
         assert_eq!(valid_result, true);
 
         // Ensures verification fails with incorrect public input
         let wrong_public_inputs = vec![Fr::from(1), Fr::from(1), Fr::from(56)];
-        let invalid_result = generate_and_verify_proof(wrong_public_inputs);
+        let invalid_result = generate_and_verify_proof(wrong_public_inputs).unwrap();
 
         assert_eq!(invalid_result, false)
     }
@@ -292,7 +1153,7 @@ mod tests {
             generate_halo2_proof(&params, &proving_key, circuit.clone(), public_input).unwrap();
 
         assert_eq!(
-            verify_halo2_proof(&params, &verifying_key, proof, serialized_inputs).unwrap(),
+            verify_halo2_proof(&params, &verifying_key, &proof, serialized_inputs).unwrap(),
             true
         );
 
@@ -302,8 +1163,447 @@ mod tests {
             generate_halo2_proof(&params, &proving_key, circuit, wrong_public_input).unwrap();
 
         assert_eq!(
-            verify_halo2_proof(&params, &verifying_key, bad_proof, serialized_inputs).unwrap(),
+            verify_halo2_proof(&params, &verifying_key, &bad_proof, serialized_inputs).unwrap(),
             false
         );
     }
+
+    #[test]
+    fn test_prover_error_source() {
+        let (circuit, params) = initialize_params_and_circuit();
+        let (proving_key, _) = keygen(&params).unwrap();
+
+        // More public inputs than rows, which halo2 rejects while proving
+        let public_inputs = vec![Fr::from(1); 1 << params.k()];
+        let error = generate_halo2_proof(&params, &proving_key, circuit, public_inputs)
+            .unwrap_err()
+            .downcast::<FibonacciError>()
+            .unwrap();
+        assert!(matches!(*error, FibonacciError::Proving(_)));
+        assert!(error.source().unwrap().is::<halo2_proofs::plonk::Error>());
+    }
+
+    #[test]
+    fn test_transcript_binding() {
+        let (circuit, params) = initialize_params_and_circuit();
+
+        let verifying_key = keygen_vk::<_, _, _, false>(&params, &circuit)
+            .expect("Verifying Key generation should not fail");
+        let proving_key = keygen_pk::<_, _, _, false>(&params, verifying_key.clone(), &circuit)
+            .expect("Proving Key generation should not fail");
+
+        let binding = TranscriptBinding {
+            label: b"app-a",
+            context: b"session-1",
+        };
+        let public_input = vec![Fr::from(1), Fr::from(1), Fr::from(55)];
+        let (proof, inputs) = generate_halo2_proof_with_binding(
+            &params,
+            &proving_key,
+            circuit,
+            public_input,
+            binding,
+        )
+        .unwrap();
+
+        let verify_with = |binding: TranscriptBinding| {
+            verify_halo2_proof_with_binding(
+                &params,
+                &verifying_key,
+                &proof,
+                inputs.clone(),
+                binding,
+            )
+            .unwrap()
+        };
+
+        assert!(verify_with(binding));
+        assert!(!verify_with(TranscriptBinding {
+            label: b"app-b",
+            ..binding
+        }));
+        assert!(!verify_with(TranscriptBinding {
+            context: b"session-2",
+            ..binding
+        }));
+        assert!(!verify_with(TranscriptBinding::label(b"app-a")));
+        assert!(!verify_with(TranscriptBinding::default()));
+    }
+
+    #[test]
+    fn test_corrupted_proof_rejected() {
+        use fibonacci_circuit::circuit::test_utils::{
+            corrupted_proofs, extend_proof, swap_public_inputs,
+        };
+
+        let (circuit, params) = initialize_params_and_circuit();
+        let (proving_key, verifying_key) = keygen(&params).unwrap();
+
+        let public_input = vec![Fr::from(1), Fr::from(1), Fr::from(55)];
+        let (proof, inputs) =
+            generate_halo2_proof(&params, &proving_key, circuit, public_input).unwrap();
+
+        let verify = |proof: &[u8], inputs: Vec<Fr>| {
+            verify_halo2_proof(&params, &verifying_key, proof, inputs).unwrap_or(false)
+        };
+        assert!(verify(&proof, inputs.clone()));
+
+        for (mutation, corrupted) in corrupted_proofs(&proof) {
+            assert!(
+                !verify(&corrupted, inputs.clone()),
+                "Proof with {} verified",
+                mutation
+            );
+        }
+        assert!(!verify(&proof, swap_public_inputs(&inputs, 0, 2)));
+        assert!(!verify(&extend_proof(&proof, &[0]), inputs.clone()));
+        assert!(!verify(
+            &extend_proof(&proof, &[0; 32]),
+            swap_public_inputs(&inputs, 0, 2)
+        ));
+    }
+
+    #[test]
+    fn test_verify_batch() {
+        let (circuit, params) = initialize_params_and_circuit();
+        let (proving_key, verifying_key) = keygen(&params).unwrap();
+
+        let binding = TranscriptBinding::label(b"batch");
+        let generated: Vec<_> = [(1, 1, 55), (2, 3, 144), (0, 1, 34)]
+            .into_iter()
+            .map(|(a, b, out)| {
+                generate_halo2_proof_with_binding(
+                    &params,
+                    &proving_key,
+                    circuit.clone(),
+                    vec![Fr::from(a), Fr::from(b), Fr::from(out)],
+                    binding,
+                )
+                .unwrap()
+            })
+            .collect();
+        let mut proofs: Vec<_> = generated
+            .iter()
+            .map(|(proof, inputs)| (proof.as_slice(), inputs.clone()))
+            .collect();
+
+        assert!(verify_batch(&params, &verifying_key, &[], binding).unwrap());
+        assert!(verify_batch(&params, &verifying_key, &proofs, binding).unwrap());
+        assert!(!verify_batch(
+            &params,
+            &verifying_key,
+            &proofs,
+            TranscriptBinding::default()
+        )
+        .unwrap());
+
+        // One invalid proof fails the whole batch
+        proofs[1].1[2] += Fr::from(1);
+        assert!(!verify_batch(&params, &verifying_key, &proofs, binding).unwrap());
+    }
+
+    #[test]
+    fn test_verify_container() {
+        let mut srs_key = Vec::new();
+        ParamsKZG::<Bn256>::setup(MIN_K, OsRng)
+            .write(&mut srs_key)
+            .unwrap();
+        let (proving_key, verifying_key) = keygen_from_bytes(&srs_key).unwrap();
+
+        let mut input = HashMap::new();
+        input.insert("out".to_string(), vec!["55".to_string()]);
+        let mut container = ProofContainer::new(BackendId::Plonk, &verifying_key);
+        for _ in 0..3 {
+            let bundle = prove_from_bytes(
+                &srs_key,
+                &proving_key,
+                input.clone(),
+                TranscriptBinding::default(),
+            )
+            .unwrap();
+            container.push(bundle.into()).unwrap();
+        }
+
+        let binding = TranscriptBinding::default();
+        assert!(verify_container(&srs_key, &verifying_key, &container, binding).unwrap());
+        assert!(!verify_container(
+            &srs_key,
+            &verifying_key,
+            &container,
+            TranscriptBinding::label(b"other")
+        )
+        .unwrap());
+
+        let mut other_backend = container.clone();
+        other_backend.backend = BackendId::Gemini;
+        assert!(verify_container(&srs_key, &verifying_key, &other_backend, binding).is_err());
+
+        let mut invalid = container;
+        invalid.bundles[2].proof[40] ^= 1;
+        assert!(!verify_container(&srs_key, &verifying_key, &invalid, binding).unwrap());
+    }
+
+    #[test]
+    fn test_extract_vk() {
+        let (_, params) = initialize_params_and_circuit();
+        let (proving_key, verifying_key) = keygen(&params).unwrap();
+
+        let mut pk_bytes = Vec::new();
+        proving_key.write(&mut pk_bytes, RawBytes).unwrap();
+        let mut vk_bytes = Vec::new();
+        verifying_key.write(&mut vk_bytes, RawBytes).unwrap();
+
+        assert_eq!(extract_vk_from_bytes(&pk_bytes).unwrap(), vk_bytes);
+    }
+
+    #[test]
+    fn test_vk_to_field_elements() {
+        use halo2_proofs::halo2curves::bn256::Fq;
+        use vk_elements::*;
+
+        let (_, params) = initialize_params_and_circuit();
+        let (_, verifying_key) = keygen(&params).unwrap();
+        let mut vk_bytes = Vec::new();
+        verifying_key.write(&mut vk_bytes, RawBytes).unwrap();
+
+        let elements = vk_to_field_elements(&verifying_key);
+        let fixed = verifying_key.fixed_commitments();
+        let permutation = verifying_key.permutation().commitments();
+        assert_eq!(
+            elements.len(),
+            VK_HEADER_LEN + 2 * (fixed.len() + permutation.len())
+        );
+        assert_eq!(elements[0], Fq::from(params.k() as u64));
+        assert_eq!(elements[1], Fq::from(fixed.len() as u64));
+        assert_eq!(elements[VK_HEADER_LEN], fixed[0].x);
+        assert_eq!(elements[elements.len() - 1], permutation.last().unwrap().y);
+        assert_eq!(
+            vk_to_field_elements_from_bytes(&vk_bytes, Hiding::Off).unwrap(),
+            elements
+        );
+
+        let hex = field_elements_to_hex(&elements);
+        assert_eq!(hex[0], format!("0x{:064x}", params.k()));
+        assert!(
+            vk_to_field_elements_from_bytes(&vk_bytes[..vk_bytes.len() - 1], Hiding::Off).is_err()
+        );
+    }
+
+    #[test]
+    fn test_prove_seeded() {
+        let mut srs_key = Vec::new();
+        initialize_params_and_circuit()
+            .1
+            .write(&mut srs_key)
+            .unwrap();
+        let (pk_bytes, vk_bytes) = keygen_from_bytes(&srs_key).unwrap();
+
+        let dir = std::env::temp_dir().join(format!("plonk-seeded-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let srs_path = dir.join("srs.bin");
+        let pk_path = dir.join("pk.bin");
+        std::fs::write(&srs_path, &srs_key).unwrap();
+        std::fs::write(&pk_path, &pk_bytes).unwrap();
+        let (srs_path, pk_path) = (srs_path.to_str().unwrap(), pk_path.to_str().unwrap());
+
+        let mut input = HashMap::new();
+        input.insert("out".to_string(), vec!["55".to_string()]);
+        let (proof, public_inputs) = prove_seeded(srs_path, pk_path, input.clone(), 7).unwrap();
+        assert_eq!(
+            prove_seeded(srs_path, pk_path, input.clone(), 7).unwrap().0,
+            proof
+        );
+        assert_ne!(prove_seeded(srs_path, pk_path, input, 8).unwrap().0, proof);
+        assert!(verify_from_bytes(
+            &srs_key,
+            &vk_bytes,
+            &proof,
+            &public_inputs,
+            TranscriptBinding::default()
+        )
+        .unwrap());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_keygen_pk_from_vk() {
+        let mut srs_key = Vec::new();
+        initialize_params_and_circuit()
+            .1
+            .write(&mut srs_key)
+            .unwrap();
+        let (pk_bytes, vk_bytes) = keygen_from_bytes(&srs_key).unwrap();
+
+        assert_eq!(
+            keygen_pk_from_vk_bytes(&srs_key, &vk_bytes).unwrap(),
+            pk_bytes
+        );
+    }
+
+    #[test]
+    fn test_hiding() {
+        let mut srs_key = Vec::new();
+        ParamsKZG::<Bn256>::setup(MIN_K_HIDING, OsRng)
+            .write(&mut srs_key)
+            .unwrap();
+        let (proving_key, verifying_key) =
+            keygen_from_bytes_with_hiding(&srs_key, Hiding::On).unwrap();
+        assert_ne!(
+            (proving_key.clone(), verifying_key.clone()),
+            keygen_from_bytes(&srs_key).unwrap()
+        );
+        assert!(vk_elements::vk_to_field_elements_from_bytes(&verifying_key, Hiding::On).is_ok());
+
+        let mut input = HashMap::new();
+        input.insert("out".to_string(), vec!["55".to_string()]);
+        let binding = TranscriptBinding::default();
+        let (proof, public_inputs) =
+            prove_from_bytes_with_hiding(&srs_key, &proving_key, input, binding, Hiding::On)
+                .unwrap();
+
+        assert!(verify_from_bytes_with_hiding(
+            &srs_key,
+            &verifying_key,
+            &proof,
+            &public_inputs,
+            binding,
+            Hiding::On
+        )
+        .unwrap());
+        // Keys are specific to the setting, the verifier can't ignore the blinding
+        assert!(
+            !verify_from_bytes(&srs_key, &verifying_key, &proof, &public_inputs, binding)
+                .unwrap_or(false)
+        );
+
+        // The blinding rows don't fit the smallest SRS
+        let mut small_srs_key = Vec::new();
+        ParamsKZG::<Bn256>::setup(MIN_K, OsRng)
+            .write(&mut small_srs_key)
+            .unwrap();
+        assert!(keygen_from_bytes_with_hiding(&small_srs_key, Hiding::On).is_err());
+    }
+
+    #[test]
+    fn test_steps() {
+        let mut srs_key = Vec::new();
+        initialize_params_and_circuit()
+            .1
+            .write(&mut srs_key)
+            .unwrap();
+        let (pk_bytes, _) = keygen_from_bytes(&srs_key).unwrap();
+        let prove_steps = |steps: &str| {
+            let input = HashMap::from([
+                ("out".to_string(), vec!["55".to_string()]),
+                ("steps".to_string(), vec![steps.to_string()]),
+            ]);
+            prove_from_bytes(&srs_key, &pk_bytes, input, TranscriptBinding::default())
+        };
+
+        assert!(prove_steps(&DEFAULT_STEPS.to_string()).is_ok());
+        // Other lengths are rejected rather than proven with the default length
+        for steps in ["20", "nine"] {
+            let error = prove_steps(steps).unwrap_err();
+            assert!(matches!(
+                error.downcast_ref::<FibonacciError>(),
+                Some(FibonacciError::InvalidInput(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_key_mismatch() {
+        let mut srs_key = Vec::new();
+        initialize_params_and_circuit()
+            .1
+            .write(&mut srs_key)
+            .unwrap();
+        let (pk_bytes, vk_bytes) = keygen_from_bytes(&srs_key).unwrap();
+        assert!(read_proving_key(pk_bytes.as_slice(), Hiding::Off).is_ok());
+
+        // Too short for the layout, e.g. a verifying key passed as proving key
+        let error = read_proving_key(vk_bytes.as_slice(), Hiding::Off).unwrap_err();
+        assert!(matches!(error, FibonacciError::CircuitMismatch(_)));
+
+        // Longer than the layout
+        let mut extended = vk_bytes.clone();
+        extended.extend_from_slice(&[0; 32]);
+        let error = read_verifying_key(extended.as_slice(), Hiding::Off).unwrap_err();
+        assert!(matches!(error, FibonacciError::CircuitMismatch(_)));
+
+        let mut input = HashMap::new();
+        input.insert("out".to_string(), vec!["55".to_string()]);
+        let error =
+            prove_from_bytes(&srs_key, &vk_bytes, input, TranscriptBinding::default()).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<FibonacciError>(),
+            Some(FibonacciError::CircuitMismatch(_))
+        ));
+    }
+
+    #[cfg(feature = "signatures")]
+    #[test]
+    fn test_read_keys_verified() {
+        use ed25519_dalek::{Signer, SigningKey};
+        use fibonacci_circuit::signature::signature_path;
+
+        let mut srs_key = Vec::new();
+        initialize_params_and_circuit()
+            .1
+            .write(&mut srs_key)
+            .unwrap();
+        let (pk_bytes, vk_bytes) = keygen_from_bytes(&srs_key).unwrap();
+
+        let dir = std::env::temp_dir().join(format!("plonk-signed-keys-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let signing_key = SigningKey::from_bytes(&[5u8; 32]);
+        let publisher_key = signing_key.verifying_key().to_bytes();
+        let pk_path = dir.join("pk.bin");
+        let vk_path = dir.join("vk.bin");
+        for (path, bytes) in [(&pk_path, &pk_bytes), (&vk_path, &vk_bytes)] {
+            std::fs::write(path, bytes).unwrap();
+            std::fs::write(signature_path(path), signing_key.sign(bytes).to_bytes()).unwrap();
+        }
+
+        assert_eq!(
+            read_proving_key_verified(&pk_path, &publisher_key, Hiding::Off).unwrap(),
+            pk_bytes
+        );
+        assert_eq!(
+            read_verifying_key_verified(&vk_path, &publisher_key, Hiding::Off).unwrap(),
+            vk_bytes
+        );
+
+        // A signed file which isn't a key of the circuit is still rejected
+        std::fs::write(
+            signature_path(&pk_path),
+            signing_key.sign(&vk_bytes).to_bytes(),
+        )
+        .unwrap();
+        std::fs::write(&pk_path, &vk_bytes).unwrap();
+        assert!(read_proving_key_verified(&pk_path, &publisher_key, Hiding::Off).is_err());
+
+        let other_key = SigningKey::from_bytes(&[6u8; 32])
+            .verifying_key()
+            .to_bytes();
+        assert!(read_verifying_key_verified(&vk_path, &other_key, Hiding::Off).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_srs_too_small() {
+        let mut srs_key = Vec::new();
+        ParamsKZG::<Bn256>::setup(MIN_K - 1, OsRng)
+            .write(&mut srs_key)
+            .unwrap();
+
+        let error = keygen_from_bytes(&srs_key).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<FibonacciError>(),
+            Some(FibonacciError::SrsTooSmall {
+                needed: 3,
+                available: 2
+            })
+        ));
+    }
 }
@@ -0,0 +1,139 @@
+//! The Fibonacci circuit proven with halo2 IPA over the Pasta curves.
+//!
+//! Proofs commit with Vesta points and the circuit runs over its scalar field `Fp`, which is the
+//! base field of Pallas. A Pallas circuit can therefore do the Vesta arithmetic of the verifier
+//! natively, the cycle recursive composition of Fibonacci proofs builds on. The IPA setup is
+//! transparent, so params are derived from `k` alone and there is no SRS file to ship.
+use fibonacci_circuit::{ErrorMessage, FibonacciCircuit, FibonacciError};
+use fibonacci_verifier_core::ProofReader;
+use halo2_proofs::{
+    halo2curves::pasta::{EqAffine, Fp},
+    plonk::{create_proof, keygen_pk, keygen_vk, verify_proof, ProvingKey, VerifyingKey},
+    poly::{
+        commitment::ParamsProver,
+        ipa::{
+            commitment::{IPACommitmentScheme, ParamsIPA},
+            multiopen::{ProverIPA, VerifierIPA},
+            strategy::SingleStrategy,
+        },
+    },
+    transcript::{
+        Blake2bRead, Blake2bWrite, Challenge255, TranscriptReadBuffer, TranscriptWriterBuffer,
+    },
+};
+use rand::rngs::OsRng;
+
+use crate::MIN_K;
+
+/// Params of the default circuit, deterministic for a given k.
+pub fn setup() -> ParamsIPA<EqAffine> {
+    ParamsIPA::<EqAffine>::new(MIN_K)
+}
+
+/// Generate the proving and verifying keys of the Fibonacci circuit over `Fp`.
+pub fn keygen(
+    params: &ParamsIPA<EqAffine>,
+) -> Result<(ProvingKey<EqAffine>, VerifyingKey<EqAffine>), halo2_proofs::plonk::Error> {
+    let circuit = FibonacciCircuit::<Fp> {
+        public_input: vec![vec![]],
+        ..Default::default()
+    };
+
+    let verifying_key = keygen_vk::<_, _, _, false>(params, &circuit)?;
+    let proving_key = keygen_pk::<_, _, _, false>(params, verifying_key.clone(), &circuit)?;
+
+    Ok((proving_key, verifying_key))
+}
+
+/// Prove the sequence with public inputs `a`, `b` and `out`.
+pub fn generate_proof(
+    params: &ParamsIPA<EqAffine>,
+    pk: &ProvingKey<EqAffine>,
+    public_inputs: Vec<Fp>,
+) -> Result<Vec<u8>, FibonacciError> {
+    let circuit = FibonacciCircuit::<Fp>::default();
+
+    let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
+    create_proof::<
+        IPACommitmentScheme<EqAffine>,
+        ProverIPA<'_, EqAffine>,
+        Challenge255<EqAffine>,
+        _,
+        Blake2bWrite<Vec<u8>, EqAffine, Challenge255<EqAffine>>,
+        _,
+        false,
+    >(
+        params,
+        pk,
+        &[circuit],
+        &[&[&public_inputs]],
+        OsRng,
+        &mut transcript,
+    )
+    .map_err(|e| {
+        FibonacciError::Proving(ErrorMessage::with_source(
+            format!("Failed to generate the proof: {:?}", e),
+            e,
+        ))
+    })?;
+
+    Ok(transcript.finalize())
+}
+
+pub fn verify(
+    params: &ParamsIPA<EqAffine>,
+    vk: &VerifyingKey<EqAffine>,
+    proof: &[u8],
+    public_inputs: Vec<Fp>,
+) -> bool {
+    let strategy = SingleStrategy::new(params);
+    let mut reader = ProofReader::new(proof);
+    let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(&mut reader);
+
+    let result = verify_proof::<
+        IPACommitmentScheme<EqAffine>,
+        VerifierIPA<'_, EqAffine>,
+        Challenge255<EqAffine>,
+        Blake2bRead<&mut ProofReader<'_>, EqAffine, Challenge255<EqAffine>>,
+        SingleStrategy<'_, EqAffine>,
+        false,
+    >(params, vk, strategy, &[&[&public_inputs]], &mut transcript)
+    .is_ok();
+
+    // Bytes after the end of the transcript are rejected, as by the KZG verifier
+    result && reader.finish().is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use fibonacci_circuit::{circuit::fibonacci, DEFAULT_STEPS};
+
+    use super::*;
+
+    #[test]
+    fn test_pasta_fibonacci_circuit() {
+        let params = setup();
+        let (proving_key, verifying_key) = keygen(&params).unwrap();
+
+        let (a, b) = (Fp::from(2), Fp::from(3));
+        let out = fibonacci(a, b, DEFAULT_STEPS);
+        assert_eq!(out, Fp::from(144));
+
+        let proof = generate_proof(&params, &proving_key, vec![a, b, out]).unwrap();
+        assert!(verify(&params, &verifying_key, &proof, vec![a, b, out]));
+        assert!(!verify(
+            &params,
+            &verifying_key,
+            &proof,
+            vec![a, b, out + Fp::from(1)]
+        ));
+
+        let bad_proof = generate_proof(&params, &proving_key, vec![a, b, Fp::from(145)]).unwrap();
+        assert!(!verify(
+            &params,
+            &verifying_key,
+            &bad_proof,
+            vec![a, b, Fp::from(145)]
+        ));
+    }
+}
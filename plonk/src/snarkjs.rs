@@ -0,0 +1,99 @@
+//! Export of plonk proofs in the `proof.json` / `public.json` layout used by snarkjs.
+//!
+//! `public.json` is fully compatible: an array of the public signals as decimal strings.
+//! A halo2 proof does not share the snarkjs plonk proof structure (`A`, `B`, `C`, `Z`, ...),
+//! so `proof.json` keeps the `protocol` and `curve` fields snarkjs tooling dispatches on and
+//! carries the raw proof as a hex string.
+use std::{error::Error, fs, path::Path};
+
+use fibonacci_circuit::{
+    hex,
+    serialisation::{decode_public_inputs, field_element_to_decimal},
+    FibonacciError,
+};
+use serde::{Deserialize, Serialize};
+
+pub const SNARKJS_PROTOCOL: &str = "halo2_plonk";
+pub const SNARKJS_CURVE: &str = "bn128";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SnarkjsProof {
+    pub protocol: String,
+    pub curve: String,
+    pub transcript: String,
+    pub proof: String,
+}
+
+impl SnarkjsProof {
+    pub fn new(proof: &[u8]) -> Self {
+        Self {
+            protocol: SNARKJS_PROTOCOL.to_string(),
+            curve: SNARKJS_CURVE.to_string(),
+            transcript: "blake2b".to_string(),
+            proof: format!("0x{}", hex::encode(proof)),
+        }
+    }
+
+    pub fn proof_bytes(&self) -> Result<Vec<u8>, FibonacciError> {
+        if self.protocol != SNARKJS_PROTOCOL || self.curve != SNARKJS_CURVE {
+            return Err(FibonacciError::InvalidInput(
+                format!(
+                    "Unsupported snarkjs proof with protocol '{}' on curve '{}'",
+                    self.protocol, self.curve
+                )
+                .into(),
+            ));
+        }
+        hex::decode(self.proof.trim_start_matches("0x"))
+    }
+}
+
+/// Convert the output of `prove` into snarkjs `proof.json` and `public.json` contents.
+pub fn to_snarkjs_json(
+    proof: &[u8],
+    public_inputs: &[u8],
+) -> Result<(String, String), Box<dyn Error>> {
+    let inputs = decode_public_inputs(public_inputs)?;
+    let public_signals: Vec<String> = inputs.iter().map(field_element_to_decimal).collect();
+
+    let proof_json = serde_json::to_string_pretty(&SnarkjsProof::new(proof))?;
+    let public_json = serde_json::to_string_pretty(&public_signals)?;
+
+    Ok((proof_json, public_json))
+}
+
+/// Write `proof.json` and `public.json` into `out_dir`.
+pub fn write_snarkjs_files(
+    out_dir: &Path,
+    proof: &[u8],
+    public_inputs: &[u8],
+) -> Result<(), Box<dyn Error>> {
+    let (proof_json, public_json) = to_snarkjs_json(proof, public_inputs)?;
+    fs::create_dir_all(out_dir)?;
+    fs::write(out_dir.join("proof.json"), proof_json)?;
+    fs::write(out_dir.join("public.json"), public_json)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use fibonacci_circuit::serialisation::encode_public_inputs;
+    use halo2_proofs::halo2curves::bn256::Fr;
+
+    use super::*;
+
+    #[test]
+    fn test_snarkjs_json() {
+        let inputs = encode_public_inputs(&[Fr::from(1), Fr::from(1), Fr::from(55)]);
+        let proof = vec![0u8, 1, 254, 255];
+
+        let (proof_json, public_json) = to_snarkjs_json(&proof, &inputs).unwrap();
+
+        let public_signals: Vec<String> = serde_json::from_str(&public_json).unwrap();
+        assert_eq!(public_signals, vec!["1", "1", "55"]);
+
+        let snarkjs_proof: SnarkjsProof = serde_json::from_str(&proof_json).unwrap();
+        assert_eq!(snarkjs_proof.proof, "0x0001feff");
+        assert_eq!(snarkjs_proof.proof_bytes().unwrap(), proof);
+    }
+}
@@ -0,0 +1,282 @@
+//! Reading KZG params whose curve points are stored compressed or uncompressed.
+//!
+//! Ceremony files converted for halo2 come in both forms, and the file doesn't record which one
+//! it uses. The layout is fixed though: `k` as a little-endian u32, 2^k G1 points in monomial
+//! basis, 2^k G1 points in Lagrange basis and two G2 points. So the encoding follows from `k` and
+//! the file length.
+use std::{
+    fs::File,
+    io::{BufRead, Read, Seek, SeekFrom},
+    path::Path,
+};
+
+use fibonacci_circuit::{io, ErrorMessage, FibonacciError};
+use halo2_proofs::{
+    halo2curves::bn256::Bn256,
+    poly::{commitment::Params, kzg::commitment::ParamsKZG},
+    SerdeFormat,
+};
+
+/// Encoding of the curve points of an SRS file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PointEncoding {
+    /// x coordinate and a sign bit, 32 bytes per G1 point
+    Compressed,
+    /// Both coordinates, 64 bytes per G1 point, as in `perpetual-powers-of-tau-raw-*`
+    Uncompressed,
+}
+
+impl PointEncoding {
+    /// Detect the encoding of an SRS file of `len` bytes whose header holds `k`.
+    pub fn detect(k: u32, len: u64) -> Result<Self, FibonacciError> {
+        let encodings = [PointEncoding::Compressed, PointEncoding::Uncompressed];
+        encodings
+            .into_iter()
+            .find(|encoding| encoding.file_len(k) == Some(len))
+            .ok_or_else(|| {
+                FibonacciError::Setup(
+                    format!(
+                    "SRS file of k = {} has {} bytes, expected {} compressed or {} uncompressed",
+                    k,
+                    len,
+                    describe_len(PointEncoding::Compressed.file_len(k)),
+                    describe_len(PointEncoding::Uncompressed.file_len(k))
+                )
+                    .into(),
+                )
+            })
+    }
+
+    /// Length of an SRS file of `k` in this encoding, `None` if it doesn't fit a u64.
+    pub fn file_len(&self, k: u32) -> Option<u64> {
+        let (g1, g2) = self.point_lens();
+        let (g1, g2) = (g1 as u64, g2 as u64);
+        let points = 1u64.checked_shl(k)?;
+        points.checked_mul(2 * g1)?.checked_add(4 + 2 * g2)
+    }
+
+    /// Bytes of a G1 and a G2 point
+    fn point_lens(&self) -> (usize, usize) {
+        match self {
+            PointEncoding::Compressed => (32, 64),
+            PointEncoding::Uncompressed => (64, 128),
+        }
+    }
+
+    fn serde_format(&self) -> SerdeFormat {
+        match self {
+            PointEncoding::Compressed => SerdeFormat::Processed,
+            PointEncoding::Uncompressed => SerdeFormat::RawBytes,
+        }
+    }
+}
+
+fn describe_len(len: Option<u64>) -> String {
+    len.map_or_else(|| "too many".to_string(), |len| len.to_string())
+}
+
+fn header_k(header: &[u8]) -> Result<u32, FibonacciError> {
+    header
+        .get(..4)
+        .map(|k| u32::from_le_bytes(k.try_into().unwrap()))
+        .ok_or_else(|| FibonacciError::Setup("SRS file is shorter than its header".into()))
+}
+
+/// Read params from SRS contents in either encoding.
+pub fn read_params(srs_key: &[u8]) -> Result<ParamsKZG<Bn256>, FibonacciError> {
+    let encoding = PointEncoding::detect(header_k(srs_key)?, srs_key.len() as u64)?;
+
+    let mut reader = srs_key;
+    ParamsKZG::<Bn256>::read_custom(&mut reader, encoding.serde_format()).map_err(|e| {
+        FibonacciError::Setup(format!("Failed to read params from bytes: {}", e).into())
+    })
+}
+
+/// Like `read_params`, parsing the SRS file while streaming it.
+pub fn read_params_path(path: &Path) -> Result<ParamsKZG<Bn256>, FibonacciError> {
+    let read_error = |e: std::io::Error| {
+        FibonacciError::Io(format!("Couldn't load params from '{}': {}", path.display(), e).into())
+    };
+    let mut reader = io::open_srs_file(path).map_err(read_error)?;
+    let len = reader.get_ref().metadata().map_err(read_error)?.len();
+    // The read buffer holds far more than the header, peeking doesn't consume it
+    let k = header_k(reader.fill_buf().map_err(read_error)?)?;
+    let encoding = PointEncoding::detect(k, len)?;
+
+    ParamsKZG::<Bn256>::read_custom(&mut reader, encoding.serde_format()).map_err(|e| {
+        FibonacciError::Setup(
+            format!("Failed to read params from '{}': {}", path.display(), e).into(),
+        )
+    })
+}
+
+/// Verifier params for a circuit of 2^`k` rows, read from SRS contents in either encoding.
+///
+/// The verifier only needs the first G1 points and the two G2 points, so only the first 2^`k` G1
+/// points and the G2 tail are parsed, and the 2^`srs_k` Lagrange basis points in between are
+/// skipped. The result equals the full params downsized to `k`.
+pub fn read_verifier_params(srs_key: &[u8], k: u32) -> Result<ParamsKZG<Bn256>, FibonacciError> {
+    let srs_k = header_k(srs_key)?;
+    let encoding = PointEncoding::detect(srs_k, srs_key.len() as u64)?;
+    let (prefix_len, tail_len) = verifier_lens(encoding, srs_k, k)?;
+
+    verifier_params(
+        encoding,
+        k,
+        &srs_key[4..4 + prefix_len],
+        &srs_key[srs_key.len() - tail_len..],
+    )
+}
+
+/// Like `read_verifier_params`, reading only the needed parts of the SRS file.
+pub fn read_verifier_params_path(path: &Path, k: u32) -> Result<ParamsKZG<Bn256>, FibonacciError> {
+    let read_error = |e: std::io::Error| {
+        FibonacciError::Io(ErrorMessage::with_source(
+            format!("Couldn't load params from '{}': {}", path.display(), e),
+            e,
+        ))
+    };
+    let mut file = File::open(path).map_err(read_error)?;
+    let len = file.metadata().map_err(read_error)?.len();
+    let mut header = [0u8; 4];
+    file.read_exact(&mut header).map_err(read_error)?;
+    let srs_k = header_k(&header)?;
+    let encoding = PointEncoding::detect(srs_k, len)?;
+    let (prefix_len, tail_len) = verifier_lens(encoding, srs_k, k)?;
+
+    let mut prefix = vec![0u8; prefix_len];
+    file.read_exact(&mut prefix).map_err(read_error)?;
+    let mut tail = vec![0u8; tail_len];
+    file.seek(SeekFrom::End(-(tail_len as i64)))
+        .map_err(read_error)?;
+    file.read_exact(&mut tail).map_err(read_error)?;
+
+    verifier_params(encoding, k, &prefix, &tail)
+}
+
+/// Bytes of the first 2^`k` G1 points and of the G2 points of an SRS of `srs_k`.
+fn verifier_lens(
+    encoding: PointEncoding,
+    srs_k: u32,
+    k: u32,
+) -> Result<(usize, usize), FibonacciError> {
+    if k > srs_k {
+        return Err(FibonacciError::SrsTooSmall {
+            needed: k as usize,
+            available: srs_k as usize,
+        });
+    }
+    let (g1, g2) = encoding.point_lens();
+    Ok(((1usize << k) * g1, 2 * g2))
+}
+
+/// Params of `k` from its G1 points and the G2 points of the SRS.
+fn verifier_params(
+    encoding: PointEncoding,
+    k: u32,
+    g: &[u8],
+    g2: &[u8],
+) -> Result<ParamsKZG<Bn256>, FibonacciError> {
+    // The file layout has a Lagrange basis between them. The G1 points stand in for it, as
+    // `downsize` recomputes the basis from them anyway.
+    let mut params_bytes = Vec::with_capacity(4 + 2 * g.len() + g2.len());
+    params_bytes.extend_from_slice(&k.to_le_bytes());
+    params_bytes.extend_from_slice(g);
+    params_bytes.extend_from_slice(g);
+    params_bytes.extend_from_slice(g2);
+
+    let mut params =
+        ParamsKZG::<Bn256>::read_custom(&mut params_bytes.as_slice(), encoding.serde_format())
+            .map_err(|e| {
+                FibonacciError::Setup(format!("Failed to read the verifier params: {}", e).into())
+            })?;
+    params.downsize(k);
+    Ok(params)
+}
+
+#[cfg(test)]
+mod tests {
+    use fibonacci_circuit::{artifact_dirs, BackendId};
+    use halo2_proofs::poly::commitment::ParamsProver;
+    use rand::rngs::OsRng;
+
+    use super::*;
+
+    #[test]
+    fn test_point_encodings() {
+        let params = ParamsKZG::<Bn256>::setup(3, OsRng);
+
+        for encoding in [PointEncoding::Compressed, PointEncoding::Uncompressed] {
+            let mut srs_key = Vec::new();
+            params
+                .write_custom(&mut srs_key, encoding.serde_format())
+                .unwrap();
+            assert_eq!(Some(srs_key.len() as u64), encoding.file_len(3));
+            assert_eq!(
+                PointEncoding::detect(3, srs_key.len() as u64).unwrap(),
+                encoding
+            );
+
+            let read = read_params(&srs_key).unwrap();
+            assert_eq!(read.k(), 3);
+            assert_eq!(read.get_g(), params.get_g());
+        }
+
+        // The SRS shipped with the crate is uncompressed
+        let shipped_path = artifact_dirs::shipped_srs_path(BackendId::Plonk);
+        let shipped = std::fs::read(&shipped_path).unwrap();
+        assert_eq!(
+            PointEncoding::detect(3, shipped.len() as u64).unwrap(),
+            PointEncoding::Uncompressed
+        );
+        assert!(read_params_path(&shipped_path).is_ok());
+
+        assert!(PointEncoding::detect(3, 1000).is_err());
+        assert!(read_params(&[3, 0]).is_err());
+    }
+
+    #[test]
+    fn test_read_verifier_params() {
+        let params = ParamsKZG::<Bn256>::setup(5, OsRng);
+        let mut downsized = params.clone();
+        downsized.downsize(3);
+        let mut expected = Vec::new();
+        downsized.write(&mut expected).unwrap();
+
+        for encoding in [PointEncoding::Compressed, PointEncoding::Uncompressed] {
+            let mut srs_key = Vec::new();
+            params
+                .write_custom(&mut srs_key, encoding.serde_format())
+                .unwrap();
+
+            let mut trimmed = Vec::new();
+            read_verifier_params(&srs_key, 3)
+                .unwrap()
+                .write(&mut trimmed)
+                .unwrap();
+            assert_eq!(trimmed, expected);
+
+            let path = std::env::temp_dir().join(format!(
+                "plonk-verifier-params-{:?}-{}",
+                encoding,
+                std::process::id()
+            ));
+            std::fs::write(&path, &srs_key).unwrap();
+            let mut trimmed = Vec::new();
+            read_verifier_params_path(&path, 3)
+                .unwrap()
+                .write(&mut trimmed)
+                .unwrap();
+            std::fs::remove_file(&path).unwrap();
+            assert_eq!(trimmed, expected);
+
+            assert!(matches!(
+                read_verifier_params(&srs_key, 6),
+                Err(FibonacciError::SrsTooSmall {
+                    needed: 6,
+                    available: 5
+                })
+            ));
+        }
+    }
+}
@@ -0,0 +1,74 @@
+//! The plonk verifying key as a flat array of bn256 base field elements, for on-chain verifiers
+//! and circuits which take the vk as public parameters rather than parsing the halo2 key format.
+//!
+//! The elements are, in this order:
+//!
+//! 1. `k`, the log2 of the number of rows of the circuit
+//! 2. the number `f` of fixed commitments
+//! 3. the number `p` of permutation commitments
+//! 4. the transcript representation of the vk, the scalar halo2 absorbs into the transcript
+//!    before the proof, which commits to the constraint system and all commitments below
+//! 5. `f` fixed commitments as `x, y` pairs, in the order of the fixed columns
+//! 6. `p` permutation commitments as `x, y` pairs, in the order of the permutation columns
+//!
+//! Points are affine, with the point at infinity encoded as `0, 0`. Scalars are smaller than the
+//! base field modulus, so `k`, the counts and the transcript representation embed unchanged.
+use fibonacci_circuit::{hex, FibonacciError, Hiding};
+use halo2_proofs::{
+    halo2curves::{
+        bn256::{Fq, Fr, G1Affine},
+        ff::PrimeField,
+    },
+    plonk::VerifyingKey,
+};
+
+use crate::read_verifying_key;
+
+/// Elements preceding the commitments: `k`, the two counts and the transcript representation
+pub const VK_HEADER_LEN: usize = 4;
+
+/// Field elements of `vk` in the order documented in this module.
+pub fn vk_to_field_elements(vk: &VerifyingKey<G1Affine>) -> Vec<Fq> {
+    let fixed = vk.fixed_commitments();
+    let permutation = vk.permutation().commitments();
+
+    let mut elements = Vec::with_capacity(VK_HEADER_LEN + 2 * (fixed.len() + permutation.len()));
+    elements.push(Fq::from(vk.get_domain().k() as u64));
+    elements.push(Fq::from(fixed.len() as u64));
+    elements.push(Fq::from(permutation.len() as u64));
+    elements.push(scalar_to_base(&vk.transcript_repr()));
+    for point in fixed.iter().chain(permutation) {
+        elements.push(point.x);
+        elements.push(point.y);
+    }
+    elements
+}
+
+/// Like `vk_to_field_elements`, taking verifying key contents as written by `write_keys`, for a
+/// key generated with `hiding`.
+pub fn vk_to_field_elements_from_bytes(
+    verifying_key: &[u8],
+    hiding: Hiding,
+) -> Result<Vec<Fq>, FibonacciError> {
+    Ok(vk_to_field_elements(&read_verifying_key(
+        verifying_key,
+        hiding,
+    )?))
+}
+
+/// `elements` as 0x-prefixed big-endian hex strings, the `uint256[]` form of Solidity tooling.
+pub fn field_elements_to_hex(elements: &[Fq]) -> Vec<String> {
+    elements
+        .iter()
+        .map(|fe| {
+            let mut be_bytes = fe.to_repr().as_ref().to_vec();
+            be_bytes.reverse();
+            format!("0x{}", hex::encode(&be_bytes))
+        })
+        .collect()
+}
+
+fn scalar_to_base(scalar: &Fr) -> Fq {
+    Fq::from_repr(scalar.to_repr())
+        .expect("the scalar field modulus should be smaller than the base field modulus")
+}
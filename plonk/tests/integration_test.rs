@@ -1,23 +1,26 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, path::Path};
 
-use fibonacci_circuit::setup_keys;
+use fibonacci_circuit::test_output_dir;
 use plonk_fibonacci::*;
 
 #[test]
 pub fn plonk_integration_test() {
-    let genkey_cmd = "gen-plonk-keys";
-    let srs_key_path = "perpetual-powers-of-tau-raw-3";
-    let proving_key_path = "out/plonk_fibonacci_pk.bin";
-    let verifying_key_path = "out/plonk_fibonacci_vk.bin";
+    let srs_key_path = concat!(env!("CARGO_MANIFEST_DIR"), "/perpetual-powers-of-tau-raw-3");
+
+    let out_dir = test_output_dir("plonk");
+    let (proving_key_path, verifying_key_path) =
+        write_keys(Path::new(srs_key_path), &out_dir, "plonk");
+    let proving_key_path = proving_key_path.to_str().unwrap();
+    let verifying_key_path = verifying_key_path.to_str().unwrap();
 
     let mut input = HashMap::new();
     input.insert("out".to_string(), vec!["55".to_string()]);
 
-    setup_keys(genkey_cmd, srs_key_path);
-
     let result = prove(&srs_key_path, &proving_key_path, input).unwrap();
 
-    let verified = verify(&srs_key_path, &verifying_key_path, result.0, result.1).unwrap();
+    let verified = verify(&srs_key_path, &verifying_key_path, &result.0, &result.1).unwrap();
 
     assert!(verified);
+
+    std::fs::remove_dir_all(out_dir).unwrap();
 }
@@ -0,0 +1,71 @@
+#![cfg(feature = "solidity-verifier")]
+
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use fibonacci_circuit::FibonacciCircuit;
+use halo2_proofs::{
+    halo2curves::bn256::{Bn256, Fr, G1Affine},
+    plonk::{create_proof, keygen_pk, keygen_vk, ProvingKey},
+    poly::kzg::{
+        commitment::{KZGCommitmentScheme, ParamsKZG},
+        multiopen::ProverSHPLONK,
+    },
+    transcript::TranscriptWriterBuffer,
+};
+use halo2_solidity_verifier::{
+    compile_solidity, encode_calldata, BatchOpenScheme::Bdfg21, Evm, Keccak256Transcript,
+    SolidityGenerator,
+};
+use rand::rngs::OsRng;
+
+// The EVM verifier only understands Keccak256 transcripts, so the proof is created here
+// instead of using `generate_halo2_proof`, which uses Blake2b.
+fn create_keccak_proof(
+    params: &ParamsKZG<Bn256>,
+    pk: &ProvingKey<G1Affine>,
+    public_inputs: &[Fr],
+) -> Vec<u8> {
+    let mut transcript = Keccak256Transcript::new(Vec::new());
+    create_proof::<KZGCommitmentScheme<Bn256>, ProverSHPLONK<'_, Bn256>, _, _, _, _, false>(
+        params,
+        pk,
+        &[FibonacciCircuit::<Fr>::default()],
+        &[&[public_inputs]],
+        OsRng,
+        &mut transcript,
+    )
+    .expect("prover should not fail");
+    transcript.finalize()
+}
+
+#[test]
+pub fn plonk_solidity_verifier_test() {
+    let params = ParamsKZG::<Bn256>::setup(4, OsRng);
+    let circuit = FibonacciCircuit::<Fr>::default();
+
+    let vk = keygen_vk::<_, _, _, false>(&params, &circuit)
+        .expect("verifying key generation should not fail");
+    let pk = keygen_pk::<_, _, _, false>(&params, vk.clone(), &circuit)
+        .expect("proving key generation should not fail");
+
+    // Render the verifier contract and deploy it
+    let generator = SolidityGenerator::new(&params, &vk, Bdfg21, 3);
+    let verifier_solidity = generator
+        .render()
+        .expect("rendering solidity verifier should not fail");
+    let mut evm = Evm::default();
+    let verifier_address = evm.create(compile_solidity(&verifier_solidity));
+
+    // Valid proof is accepted
+    let public_inputs = vec![Fr::from(1), Fr::from(1), Fr::from(55)];
+    let proof = create_keccak_proof(&params, &pk, &public_inputs);
+    let calldata = encode_calldata(None, &proof, &public_inputs);
+    let (_, output) = evm.call(verifier_address, calldata);
+    assert_eq!(output, [vec![0; 31], vec![1]].concat());
+
+    // Same proof with a different public input is rejected, the verifier reverts
+    let wrong_public_inputs = vec![Fr::from(1), Fr::from(1), Fr::from(56)];
+    let calldata = encode_calldata(None, &proof, &wrong_public_inputs);
+    let rejected = catch_unwind(AssertUnwindSafe(|| evm.call(verifier_address, calldata)));
+    assert!(rejected.is_err());
+}
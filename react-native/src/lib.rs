@@ -0,0 +1,237 @@
+//! N-API module for React Native and Node.js apps.
+//!
+//! Every method returns a promise and runs on the libuv thread pool, so proving doesn't block the
+//! JS thread. Artifacts, proofs and public inputs are passed as base64 strings, which cross the
+//! React Native bridge without copies into typed arrays. An optional callback receives
+//! `{ stage, progress }` events while a call runs.
+use std::{
+    collections::HashMap,
+    panic::{catch_unwind, AssertUnwindSafe},
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use fibonacci_circuit::{BackendId, GenerateProofResult};
+use fibonacci_mobile::panic_message;
+use napi::{
+    bindgen_prelude::AsyncTask,
+    threadsafe_function::{ErrorStrategy, ThreadsafeFunction, ThreadsafeFunctionCallMode},
+    Env, Error, Result, Status, Task,
+};
+use napi_derive::napi;
+
+#[napi(object)]
+pub struct KeyPair {
+    pub proving_key: String,
+    pub verifying_key: String,
+}
+
+#[napi(object)]
+pub struct ProofResult {
+    pub proof: String,
+    pub public_inputs: String,
+}
+
+#[napi(object)]
+pub struct ProgressEvent {
+    /// One of `decoding`, `keygen`, `proving`, `verifying`, `encoding` and `done`
+    pub stage: String,
+    /// Between 0 and 1
+    pub progress: f64,
+}
+
+type ProgressCallback = ThreadsafeFunction<ProgressEvent, ErrorStrategy::Fatal>;
+
+struct Progress(Option<ProgressCallback>);
+
+impl Progress {
+    fn report(&self, stage: &str, progress: f64) {
+        if let Some(callback) = &self.0 {
+            callback.call(
+                ProgressEvent {
+                    stage: stage.to_string(),
+                    progress,
+                },
+                ThreadsafeFunctionCallMode::NonBlocking,
+            );
+        }
+    }
+}
+
+fn invalid_arg(message: impl ToString) -> Error {
+    Error::new(Status::InvalidArg, message)
+}
+
+fn decode(name: &str, value: &str) -> Result<Vec<u8>> {
+    STANDARD
+        .decode(value)
+        .map_err(|e| invalid_arg(format!("`{}` is not valid base64: {}", name, e)))
+}
+
+/// Run the prover call `f`, reporting its errors and panics as rejections.
+fn run<T>(f: impl FnOnce() -> std::result::Result<T, Box<dyn std::error::Error>>) -> Result<T> {
+    match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(result) => result.map_err(|e| Error::from_reason(e.to_string())),
+        Err(payload) => Err(Error::from_reason(panic_message(payload))),
+    }
+}
+
+pub struct KeygenTask {
+    backend: BackendId,
+    srs_key: String,
+    progress: Progress,
+}
+
+impl Task for KeygenTask {
+    type Output = (Vec<u8>, Vec<u8>);
+    type JsValue = KeyPair;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        self.progress.report("decoding", 0.0);
+        let srs_key = decode("srsKey", &self.srs_key)?;
+
+        self.progress.report("keygen", 0.1);
+        run(|| fibonacci_mobile::keygen(self.backend, &srs_key))
+    }
+
+    fn resolve(
+        &mut self,
+        _env: Env,
+        (proving_key, verifying_key): Self::Output,
+    ) -> Result<KeyPair> {
+        self.progress.report("encoding", 0.9);
+        let key_pair = KeyPair {
+            proving_key: STANDARD.encode(proving_key),
+            verifying_key: STANDARD.encode(verifying_key),
+        };
+        self.progress.report("done", 1.0);
+        Ok(key_pair)
+    }
+}
+
+pub struct ProveTask {
+    backend: BackendId,
+    srs_key: String,
+    proving_key: String,
+    input: HashMap<String, Vec<String>>,
+    progress: Progress,
+}
+
+impl Task for ProveTask {
+    type Output = GenerateProofResult;
+    type JsValue = ProofResult;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        self.progress.report("decoding", 0.0);
+        let srs_key = decode("srsKey", &self.srs_key)?;
+        let proving_key = decode("provingKey", &self.proving_key)?;
+        let input = std::mem::take(&mut self.input);
+
+        self.progress.report("proving", 0.1);
+        run(|| fibonacci_mobile::prove(self.backend, &srs_key, &proving_key, input))
+    }
+
+    fn resolve(&mut self, _env: Env, (proof, public_inputs): Self::Output) -> Result<ProofResult> {
+        self.progress.report("encoding", 0.9);
+        let result = ProofResult {
+            proof: STANDARD.encode(proof),
+            public_inputs: STANDARD.encode(public_inputs),
+        };
+        self.progress.report("done", 1.0);
+        Ok(result)
+    }
+}
+
+pub struct VerifyTask {
+    backend: BackendId,
+    srs_key: String,
+    verifying_key: String,
+    proof: String,
+    public_inputs: String,
+    progress: Progress,
+}
+
+impl Task for VerifyTask {
+    type Output = bool;
+    type JsValue = bool;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        self.progress.report("decoding", 0.0);
+        let srs_key = decode("srsKey", &self.srs_key)?;
+        let verifying_key = decode("verifyingKey", &self.verifying_key)?;
+        let proof = decode("proof", &self.proof)?;
+        let public_inputs = decode("publicInputs", &self.public_inputs)?;
+
+        self.progress.report("verifying", 0.1);
+        run(|| {
+            fibonacci_mobile::verify(
+                self.backend,
+                &srs_key,
+                &verifying_key,
+                &proof,
+                &public_inputs,
+            )
+        })
+    }
+
+    fn resolve(&mut self, _env: Env, is_valid: bool) -> Result<bool> {
+        self.progress.report("done", 1.0);
+        Ok(is_valid)
+    }
+}
+
+fn backend(name: &str) -> Result<BackendId> {
+    BackendId::from_name(name).map_err(|e| invalid_arg(e.to_string()))
+}
+
+/// Generate the proving and verifying keys of `backend` (`plonk`, `hyperplonk`, `gemini` or
+/// `unihyperplonk`).
+#[napi(ts_return_type = "Promise<KeyPair>")]
+pub fn keygen(
+    backend_name: String,
+    srs_key: String,
+    on_progress: Option<ProgressCallback>,
+) -> Result<AsyncTask<KeygenTask>> {
+    Ok(AsyncTask::new(KeygenTask {
+        backend: backend(&backend_name)?,
+        srs_key,
+        progress: Progress(on_progress),
+    }))
+}
+
+/// Generate a proof. `input` maps circuit input names to decimal strings, e.g.
+/// `{ out: ["55"] }`.
+#[napi(ts_return_type = "Promise<ProofResult>")]
+pub fn prove(
+    backend_name: String,
+    srs_key: String,
+    proving_key: String,
+    input: HashMap<String, Vec<String>>,
+    on_progress: Option<ProgressCallback>,
+) -> Result<AsyncTask<ProveTask>> {
+    Ok(AsyncTask::new(ProveTask {
+        backend: backend(&backend_name)?,
+        srs_key,
+        proving_key,
+        input,
+        progress: Progress(on_progress),
+    }))
+}
+
+#[napi(ts_return_type = "Promise<boolean>")]
+pub fn verify(
+    backend_name: String,
+    srs_key: String,
+    verifying_key: String,
+    proof: String,
+    public_inputs: String,
+    on_progress: Option<ProgressCallback>,
+) -> Result<AsyncTask<VerifyTask>> {
+    Ok(AsyncTask::new(VerifyTask {
+        backend: backend(&backend_name)?,
+        srs_key,
+        verifying_key,
+        proof,
+        public_inputs,
+        progress: Progress(on_progress),
+    }))
+}
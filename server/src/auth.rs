@@ -0,0 +1,282 @@
+//! API keys and per-key rate limits of the endpoints which queue proofs.
+//!
+//! Keys are read from a file of `<name>=<key>` lines, where the name identifies the client in
+//! logs. Clients send their key as `Authorization: Bearer <key>`. Only hashes of the keys are
+//! kept in memory. Each key may queue `RateLimit::requests` jobs per `RateLimit::period`, with
+//! unused requests adding up to at most that many. Verifications and verifying keys need a key
+//! but aren't counted.
+use std::{
+    collections::HashMap,
+    fs,
+    path::Path,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use axum::{
+    extract::{Request, State},
+    http::{header, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use fibonacci_circuit::{ErrorCode, ErrorMessage, ErrorReport, FibonacciError};
+use sha3::{Digest, Keccak256};
+
+use crate::AppState;
+
+/// Requests allowed per key within `period`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    pub requests: u32,
+    pub period: Duration,
+}
+
+impl RateLimit {
+    pub fn per_minute(requests: u32) -> Self {
+        Self {
+            requests,
+            period: Duration::from_secs(60),
+        }
+    }
+}
+
+// Token bucket of one key
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+#[derive(Default)]
+pub struct ApiKeys {
+    names: HashMap<[u8; 32], String>,
+    rate_limit: Option<RateLimit>,
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+fn key_hash(key: &str) -> [u8; 32] {
+    Keccak256::digest(key.as_bytes()).into()
+}
+
+impl ApiKeys {
+    /// Keys as `(name, key)` pairs
+    pub fn new<'a>(
+        keys: impl IntoIterator<Item = (&'a str, &'a str)>,
+        rate_limit: Option<RateLimit>,
+    ) -> Self {
+        Self {
+            names: keys
+                .into_iter()
+                .map(|(name, key)| (key_hash(key), name.to_string()))
+                .collect(),
+            rate_limit,
+            buckets: Mutex::default(),
+        }
+    }
+
+    /// Parse `<name>=<key>` lines, skipping empty lines and `#` comments.
+    pub fn parse(contents: &str, rate_limit: Option<RateLimit>) -> Result<Self, FibonacciError> {
+        let mut keys = Vec::new();
+        for (number, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            match line.split_once('=') {
+                Some((name, key)) if !name.trim().is_empty() && !key.trim().is_empty() => {
+                    keys.push((name.trim(), key.trim()))
+                }
+                _ => {
+                    return Err(FibonacciError::InvalidInput(
+                        format!(
+                            "Line {} of the API keys isn't of the form <name>=<key>",
+                            number + 1
+                        )
+                        .into(),
+                    ))
+                }
+            }
+        }
+        Ok(Self::new(keys, rate_limit))
+    }
+
+    pub fn from_file(path: &Path, rate_limit: Option<RateLimit>) -> Result<Self, FibonacciError> {
+        let contents = fs::read_to_string(path).map_err(|e| {
+            FibonacciError::Io(ErrorMessage::with_source(
+                format!("Couldn't read '{}': {}", path.display(), e),
+                e,
+            ))
+        })?;
+        Self::parse(&contents, rate_limit)
+    }
+
+    /// Name of the client with `key`, `None` for unknown keys.
+    pub fn authenticate(&self, key: &str) -> Option<&str> {
+        self.names.get(&key_hash(key)).map(String::as_str)
+    }
+
+    /// Count a request of the client `name` against its rate limit, returning how long to wait
+    /// if it's exhausted.
+    pub fn acquire(&self, name: &str) -> Result<(), Duration> {
+        let Some(limit) = self.rate_limit else {
+            return Ok(());
+        };
+        let capacity = f64::from(limit.requests);
+        let per_second = capacity / limit.period.as_secs_f64();
+
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap();
+        let bucket = buckets.entry(name.to_string()).or_insert(Bucket {
+            tokens: capacity,
+            updated: now,
+        });
+        let refilled = (now - bucket.updated).as_secs_f64() * per_second;
+        bucket.tokens = (bucket.tokens + refilled).min(capacity);
+        bucket.updated = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            // A limit of 0 requests never refills, its clients are told to wait a whole period
+            Err(
+                Duration::try_from_secs_f64((1.0 - bucket.tokens) / per_second)
+                    .unwrap_or(limit.period),
+            )
+        }
+    }
+}
+
+fn bearer_token(request: &Request) -> Option<&str> {
+    request
+        .headers()
+        .get(header::AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+}
+
+/// Name of the client whose API key authenticated a request, added to the request's extensions
+/// by `authenticate` and `require_api_key`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApiClient(pub String);
+
+/// Report of a request of `client` over its rate limit, which may be retried after `wait`
+pub fn rate_limited(client: &str, wait: Duration) -> ErrorReport {
+    ErrorReport::new(ErrorCode::InvalidInput, "Rate limit exceeded")
+        .with_context("client", client)
+        .with_context("retry_after", retry_after_secs(wait))
+}
+
+// Rounded up, retrying earlier would fail again
+fn retry_after_secs(wait: Duration) -> u64 {
+    wait.as_secs() + u64::from(wait.subsec_nanos() > 0)
+}
+
+fn unauthorized() -> Response {
+    (
+        StatusCode::UNAUTHORIZED,
+        [(header::WWW_AUTHENTICATE, "Bearer")],
+        Json(ErrorReport::new(
+            ErrorCode::InvalidInput,
+            "Missing or unknown API key",
+        )),
+    )
+        .into_response()
+}
+
+/// Middleware rejecting requests without a known API key with `401 Unauthorized`, for the
+/// endpoints which don't queue proofs or count them per call, like `/rpc`. Passes all requests if
+/// the server has no keys configured.
+pub async fn authenticate(
+    State(state): State<AppState>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let Some(keys) = state.api_keys() else {
+        return next.run(request).await;
+    };
+
+    let Some(name) = bearer_token(&request).and_then(|key| keys.authenticate(key)) else {
+        return unauthorized();
+    };
+    let client = ApiClient(name.to_string());
+    request.extensions_mut().insert(client);
+    next.run(request).await
+}
+
+/// Like `authenticate`, also rejecting requests over the key's rate limit with
+/// `429 Too Many Requests`, for the endpoints which queue a proof per request.
+pub async fn require_api_key(
+    State(state): State<AppState>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let Some(keys) = state.api_keys() else {
+        return next.run(request).await;
+    };
+
+    let Some(name) = bearer_token(&request).and_then(|key| keys.authenticate(key)) else {
+        return unauthorized();
+    };
+    if let Err(wait) = keys.acquire(name) {
+        return (
+            StatusCode::TOO_MANY_REQUESTS,
+            [(header::RETRY_AFTER, retry_after_secs(wait).to_string())],
+            Json(rate_limited(name, wait)),
+        )
+            .into_response();
+    }
+    let client = ApiClient(name.to_string());
+    request.extensions_mut().insert(client);
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_api_keys() {
+        let keys = ApiKeys::parse(
+            "# Clients\nalice = secret-a\n\nbob=secret-b\n",
+            Some(RateLimit {
+                requests: 2,
+                period: Duration::from_secs(3600),
+            }),
+        )
+        .unwrap();
+        assert_eq!(keys.authenticate("secret-a"), Some("alice"));
+        assert_eq!(keys.authenticate("secret-b"), Some("bob"));
+        assert_eq!(keys.authenticate("secret"), None);
+
+        assert!(keys.acquire("alice").is_ok());
+        assert!(keys.acquire("alice").is_ok());
+        let wait = keys.acquire("alice").unwrap_err();
+        assert!(wait > Duration::from_secs(1700) && wait <= Duration::from_secs(1800));
+        // Limits are per key
+        assert!(keys.acquire("bob").is_ok());
+
+        assert!(ApiKeys::parse("alice", None).is_err());
+        assert!(ApiKeys::parse("=secret", None).is_err());
+        assert!(ApiKeys::new([("alice", "secret-a")], None)
+            .acquire("alice")
+            .is_ok());
+
+        let closed = ApiKeys::new([("alice", "secret-a")], Some(RateLimit::per_minute(0)));
+        assert_eq!(
+            closed.acquire("alice").unwrap_err(),
+            Duration::from_secs(60)
+        );
+
+        // The IO error stays available as the source
+        let Err(missing) = ApiKeys::from_file(Path::new("missing-api-keys"), None) else {
+            panic!("a missing key file should be an error");
+        };
+        let source = std::error::Error::source(&missing).unwrap();
+        assert_eq!(
+            source.downcast_ref::<std::io::Error>().unwrap().kind(),
+            std::io::ErrorKind::NotFound
+        );
+    }
+}
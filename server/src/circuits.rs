@@ -0,0 +1,188 @@
+//! Circuits a server proves, each a backend with its SRS and keys, addressed by a circuit id.
+//!
+//! A `KeyStore` is a directory with a `keys.json` manifest as written by `gen-all-keys` of
+//! `fibonacci-test-vectors`, see `fibonacci_circuit::keygen::KeysManifest`. Entries may name their circuit with an `id`, which defaults to the
+//! backend name, so a store generated by `gen-all-keys` serves every backend under its name.
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+pub use fibonacci_circuit::keygen::{BackendKeys, KeysManifest, KEYS_MANIFEST};
+use fibonacci_circuit::{BackendId, ErrorMessage, FibonacciError};
+
+use crate::Artifacts;
+
+/// Artifacts by circuit id
+#[derive(Default)]
+pub struct Circuits(BTreeMap<String, Arc<Artifacts>>);
+
+impl Circuits {
+    /// The circuit of `artifacts` alone, with its backend name as id
+    pub fn single(artifacts: Arc<Artifacts>) -> Self {
+        let mut circuits = Self::default();
+        circuits.insert(artifacts.backend.name(), artifacts);
+        circuits
+    }
+
+    pub fn insert(&mut self, id: impl Into<String>, artifacts: Arc<Artifacts>) {
+        self.0.insert(id.into(), artifacts);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Arc<Artifacts>)> {
+        self.0
+            .iter()
+            .map(|(id, artifacts)| (id.as_str(), artifacts))
+    }
+
+    /// The circuit `id`, or the only circuit if no id is given.
+    pub fn get(&self, id: Option<&str>) -> Result<(&str, &Arc<Artifacts>), FibonacciError> {
+        let found = match id {
+            Some(id) => self.0.get_key_value(id),
+            None if self.0.len() == 1 => self.0.iter().next(),
+            None => None,
+        };
+        found
+            .map(|(id, artifacts)| (id.as_str(), artifacts))
+            .ok_or_else(|| {
+                let ids: Vec<_> = self.0.keys().map(String::as_str).collect();
+                FibonacciError::InvalidInput(
+                    match id {
+                        Some(id) => {
+                            format!("Unknown circuit '{}', available: {}", id, ids.join(", "))
+                        }
+                        None => format!("Choose a circuit, one of: {}", ids.join(", ")),
+                    }
+                    .into(),
+                )
+            })
+    }
+
+    /// Warm up the prover of every circuit, see `Artifacts::warm_up`.
+    pub fn warm_up(&self) -> Result<(), FibonacciError> {
+        for (_, artifacts) in self.iter() {
+            artifacts.warm_up()?;
+        }
+        Ok(())
+    }
+
+    /// Check the artifacts of every circuit, see `Artifacts::check`.
+    pub fn check(&self) -> Result<(), Box<dyn Error>> {
+        for (id, artifacts) in self.iter() {
+            artifacts.check().map_err(|e| {
+                FibonacciError::Setup(
+                    format!("Artifacts of circuit '{}' are unusable: {}", id, e).into(),
+                )
+            })?;
+        }
+        Ok(())
+    }
+}
+
+/// Directory of keys described by a `keys.json` manifest
+pub struct KeyStore {
+    dir: PathBuf,
+}
+
+impl KeyStore {
+    pub fn open(dir: &Path) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+        }
+    }
+
+    pub fn manifest(&self) -> Result<KeysManifest, FibonacciError> {
+        let path = self.dir.join(KEYS_MANIFEST);
+        let contents = fs::read(&path).map_err(|e| {
+            FibonacciError::Io(ErrorMessage::with_source(
+                format!("Couldn't read '{}': {}", path.display(), e),
+                e,
+            ))
+        })?;
+        serde_json::from_slice(&contents).map_err(|e| {
+            FibonacciError::Serialization(ErrorMessage::with_source(
+                format!("Invalid key store manifest: {}", e),
+                e,
+            ))
+        })
+    }
+
+    /// Load the artifacts of every circuit of the manifest.
+    pub fn load(&self) -> Result<Circuits, FibonacciError> {
+        let mut circuits = Circuits::default();
+        for entry in self.manifest()?.backends {
+            let backend = BackendId::from_name(&entry.backend)?;
+            let id = entry.id.unwrap_or_else(|| backend.name().to_string());
+            if circuits.0.contains_key(&id) {
+                return Err(FibonacciError::InvalidInput(
+                    format!("Circuit '{}' appears twice in the key store manifest", id).into(),
+                ));
+            }
+            let artifacts = Artifacts::load(
+                backend,
+                &self.dir.join(&entry.srs),
+                &self.dir.join(&entry.proving_key),
+                &self.dir.join(&entry.verifying_key),
+            )?;
+            circuits.insert(id, Arc::new(artifacts));
+        }
+        if circuits.0.is_empty() {
+            return Err(FibonacciError::InvalidInput(
+                "The key store manifest lists no circuits".into(),
+            ));
+        }
+        Ok(circuits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_store() {
+        let dir = std::env::temp_dir().join(format!("fibonacci-key-store-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let srs = Path::new(env!("CARGO_MANIFEST_DIR")).join("../gemini/unihyperplonk-srs-4");
+        let (proving_key, verifying_key) =
+            fibonacci_mobile::keygen(BackendId::Gemini, &fs::read(&srs).unwrap()).unwrap();
+        fs::write(dir.join("gemini_pk.bin"), proving_key).unwrap();
+        fs::write(dir.join("gemini_vk.bin"), verifying_key).unwrap();
+
+        let entry = |id: Option<&str>| BackendKeys {
+            id: id.map(str::to_string),
+            backend: "gemini".to_string(),
+            srs: srs.to_str().unwrap().to_string(),
+            proving_key: "gemini_pk.bin".to_string(),
+            verifying_key: "gemini_vk.bin".to_string(),
+        };
+        let write_manifest = |backends: Vec<BackendKeys>| {
+            let manifest = KeysManifest { backends };
+            fs::write(
+                dir.join(KEYS_MANIFEST),
+                serde_json::to_vec(&manifest).unwrap(),
+            )
+            .unwrap();
+        };
+
+        write_manifest(vec![entry(None), entry(Some("fibonacci-v2"))]);
+        let circuits = KeyStore::open(&dir).load().unwrap();
+        let ids: Vec<_> = circuits.iter().map(|(id, _)| id).collect();
+        assert_eq!(ids, ["fibonacci-v2", "gemini"]);
+        assert_eq!(circuits.get(Some("gemini")).unwrap().0, "gemini");
+        assert!(circuits.get(Some("plonk")).is_err());
+        // Without an id the choice is ambiguous
+        assert!(circuits.get(None).is_err());
+        assert!(circuits.check().is_ok());
+
+        write_manifest(vec![entry(None), entry(None)]);
+        assert!(KeyStore::open(&dir).load().is_err());
+        write_manifest(Vec::new());
+        assert!(KeyStore::open(&dir).load().is_err());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
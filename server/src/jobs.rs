@@ -0,0 +1,637 @@
+//! Bounded queue of proving jobs and the store their results are persisted to.
+//!
+//! Jobs are proven one after the other by a fixed number of worker threads, proving already uses
+//! all cores. Results are written to the store directory as soon as a job finishes, so clients
+//! can poll for them after a server restart. Only the state of unfinished jobs is kept in memory.
+//!
+//! Job ids are derived from the circuit, its verifying key and the inputs, so submitting the same
+//! inputs again returns the job which proves them rather than proving twice.
+//!
+//! Unfinished jobs are checkpointed to the store too. `JobQueue::shutdown` stops accepting jobs
+//! and waits for the running ones, whatever is left is queued again by `JobQueue::recover` after
+//! the restart.
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    fs::{self, File},
+    io::Write,
+    panic::{catch_unwind, AssertUnwindSafe},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, SyncSender, TrySendError},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use fibonacci_api::JobResponse;
+pub use fibonacci_api::JobStatus;
+use fibonacci_circuit::{
+    container::{vk_fingerprint, VkFingerprint},
+    hex,
+    serialisation::Codec,
+    ErrorCode, ErrorMessage, ErrorReport, FibonacciError, ProofBundle,
+};
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Keccak256};
+use tokio::sync::{broadcast, watch};
+
+use crate::{telemetry, Artifacts, Circuits};
+
+/// Id of a job, 32 lowercase hex digits
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct JobId(String);
+
+impl JobId {
+    /// Id of the job proving `input` for the circuit `circuit` with the verifying key of
+    /// `vk_fingerprint`: the start of the Keccak256 hash of all three, with the inputs sorted by
+    /// name. A circuit id hosted with new keys gets new job ids, so proofs stored for the old
+    /// keys aren't returned for it.
+    pub fn for_input(
+        circuit: &str,
+        vk_fingerprint: &VkFingerprint,
+        input: &HashMap<String, Vec<String>>,
+    ) -> Self {
+        let sorted: BTreeMap<_, _> = input.iter().collect();
+        let mut hasher = Keccak256::new();
+        hasher.update(circuit);
+        hasher.update([0]);
+        hasher.update(vk_fingerprint);
+        hasher.update(serde_json::to_vec(&sorted).unwrap());
+        let hash = hasher.finalize();
+        Self(hex::encode(&hash[..16]))
+    }
+
+    /// Parse an id sent by a client. Ids name files of the store, anything but hex is rejected.
+    pub fn parse(id: &str) -> Result<Self, FibonacciError> {
+        if id.len() == 32
+            && id
+                .bytes()
+                .all(|byte| matches!(byte, b'0'..=b'9' | b'a'..=b'f'))
+        {
+            Ok(Self(id.to_string()))
+        } else {
+            Err(FibonacciError::InvalidInput(
+                format!("Invalid job id '{}'", id).into(),
+            ))
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for JobId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Outcome of a finished job
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JobResult {
+    Done(ProofBundle),
+    Failed(ErrorReport),
+}
+
+/// State of a job, with its result once finished
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JobState {
+    Queued,
+    Running,
+    Finished(JobResult),
+}
+
+impl JobState {
+    pub fn status(&self) -> JobStatus {
+        match self {
+            JobState::Queued => JobStatus::Queued,
+            JobState::Running => JobStatus::Running,
+            JobState::Finished(JobResult::Done(_)) => JobStatus::Done,
+            JobState::Finished(JobResult::Failed(_)) => JobStatus::Failed,
+        }
+    }
+
+    /// Response of `GET /jobs/{id}` for the job `id` in this state
+    pub fn response(&self, id: &JobId) -> JobResponse {
+        let (bundle, error) = match self {
+            JobState::Finished(JobResult::Done(bundle)) => (Some(bundle.into()), None),
+            JobState::Finished(JobResult::Failed(report)) => (None, Some(report.clone())),
+            _ => (None, None),
+        };
+        JobResponse {
+            id: id.to_string(),
+            status: self.status(),
+            bundle,
+            error,
+        }
+    }
+}
+
+/// Circuit and inputs of a job which hasn't finished
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QueuedJob {
+    pub circuit: String,
+    pub input: HashMap<String, Vec<String>>,
+}
+
+/// Directory of jobs: `<id>.proof` holds the bincode proof bundle of a job which succeeded and
+/// `<id>.error` the error report of one which failed. Jobs which haven't finished are kept as
+/// `<id>.job`, so they can be queued again after a restart.
+#[derive(Debug)]
+pub struct JobStore {
+    dir: PathBuf,
+}
+
+impl JobStore {
+    /// Store in `dir`, created if missing.
+    pub fn open(dir: &Path) -> Result<Self, FibonacciError> {
+        fs::create_dir_all(dir).map_err(|e| {
+            FibonacciError::Io(ErrorMessage::with_source(
+                format!("Couldn't create '{}': {}", dir.display(), e),
+                e,
+            ))
+        })?;
+        Ok(Self {
+            dir: dir.to_path_buf(),
+        })
+    }
+
+    fn path(&self, id: &JobId, extension: &str) -> PathBuf {
+        self.dir.join(id.as_str()).with_extension(extension)
+    }
+
+    // Synced and renamed into place, so readers never see a partially written file and it
+    // survives a crash once this returns
+    fn write(&self, id: &JobId, path: &Path, contents: &[u8]) -> Result<(), FibonacciError> {
+        let partial = self.path(id, "partial");
+        File::create(&partial)
+            .and_then(|mut file| {
+                file.write_all(contents)?;
+                file.sync_all()
+            })
+            .and_then(|_| fs::rename(&partial, path))
+            .map_err(|e| {
+                FibonacciError::Io(ErrorMessage::with_source(
+                    format!("Couldn't write '{}': {}", path.display(), e),
+                    e,
+                ))
+            })
+    }
+
+    fn remove(&self, path: &Path) -> Result<(), FibonacciError> {
+        match fs::remove_file(path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                Err(FibonacciError::Io(ErrorMessage::with_source(
+                    format!("Couldn't remove '{}': {}", path.display(), e),
+                    e,
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Store the result of the job `id`, which is no longer queued.
+    pub fn save(&self, id: &JobId, result: &JobResult) -> Result<(), FibonacciError> {
+        let (path, contents) = match result {
+            JobResult::Done(bundle) => (self.path(id, "proof"), bundle.encode(Codec::Bincode)?),
+            JobResult::Failed(report) => (self.path(id, "error"), report.to_json().into_bytes()),
+        };
+        self.write(id, &path, &contents)?;
+        self.remove(&self.path(id, "job"))
+    }
+
+    /// Forget the error of the job `id`, so it can be retried.
+    fn remove_error(&self, id: &JobId) -> Result<(), FibonacciError> {
+        self.remove(&self.path(id, "error"))
+    }
+
+    fn save_queued(&self, id: &JobId, job: &QueuedJob) -> Result<(), FibonacciError> {
+        let contents = serde_json::to_vec(job).map_err(|e| {
+            FibonacciError::Serialization(ErrorMessage::with_source(
+                format!("Failed to serialize job {}: {}", id, e),
+                e,
+            ))
+        })?;
+        self.write(id, &self.path(id, "job"), &contents)
+    }
+
+    fn remove_queued(&self, id: &JobId) -> Result<(), FibonacciError> {
+        self.remove(&self.path(id, "job"))
+    }
+
+    /// Jobs which were queued or running when the server stopped.
+    pub fn queued(&self) -> Result<Vec<(JobId, QueuedJob)>, FibonacciError> {
+        let io_error = |e: std::io::Error| {
+            FibonacciError::Io(ErrorMessage::with_source(
+                format!("Couldn't read '{}': {}", self.dir.display(), e),
+                e,
+            ))
+        };
+        let mut jobs = Vec::new();
+        for entry in fs::read_dir(&self.dir).map_err(io_error)? {
+            let path = entry.map_err(io_error)?.path();
+            if path
+                .extension()
+                .map_or(true, |extension| extension != "job")
+            {
+                continue;
+            }
+            let Some(id) = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(|stem| JobId::parse(stem).ok())
+            else {
+                continue;
+            };
+            let contents = fs::read(&path).map_err(io_error)?;
+            let job = serde_json::from_slice(&contents).map_err(|e| {
+                FibonacciError::Serialization(ErrorMessage::with_source(
+                    format!("Invalid queued job {}: {}", id, e),
+                    e,
+                ))
+            })?;
+            jobs.push((id, job));
+        }
+        jobs.sort_by(|a, b| a.0.as_str().cmp(b.0.as_str()));
+        Ok(jobs)
+    }
+
+    /// Result of the job `id`, `None` while it hasn't finished or if it doesn't exist.
+    pub fn load(&self, id: &JobId) -> Result<Option<JobResult>, FibonacciError> {
+        let read = |path: PathBuf| match fs::read(&path) {
+            Ok(contents) => Ok(Some(contents)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(FibonacciError::Io(ErrorMessage::with_source(
+                format!("Couldn't read '{}': {}", path.display(), e),
+                e,
+            ))),
+        };
+
+        if let Some(contents) = read(self.path(id, "proof"))? {
+            return Ok(Some(JobResult::Done(ProofBundle::decode(
+                &contents,
+                Codec::Bincode,
+            )?)));
+        }
+        match read(self.path(id, "error"))? {
+            Some(contents) => serde_json::from_slice(&contents)
+                .map(|report| Some(JobResult::Failed(report)))
+                .map_err(|e| {
+                    FibonacciError::Serialization(ErrorMessage::with_source(
+                        format!("Invalid error report of job {}: {}", id, e),
+                        e,
+                    ))
+                }),
+            None => Ok(None),
+        }
+    }
+}
+
+struct Job {
+    id: JobId,
+    artifacts: Arc<Artifacts>,
+    input: HashMap<String, Vec<String>>,
+}
+
+/// Change of a job's state, broadcast to the subscribers of `JobQueue::subscribe`
+#[derive(Debug, Clone)]
+pub struct JobEvent {
+    pub id: JobId,
+    pub state: JobState,
+}
+
+// Events buffered per subscriber, slow subscribers miss older ones
+const EVENT_CAPACITY: usize = 64;
+
+const SHUTDOWN_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+// States of queued and running jobs, and the channel their changes are announced on
+struct States {
+    pending: Mutex<HashMap<JobId, JobState>>,
+    events: broadcast::Sender<JobEvent>,
+    // Set once shutting down, workers leave the jobs they haven't started to the next run
+    closed: AtomicBool,
+    // Set once the running jobs finished or the grace period is over
+    stopped: watch::Sender<bool>,
+}
+
+impl States {
+    fn update(&self, id: &JobId, state: JobState) {
+        self.pending
+            .lock()
+            .unwrap()
+            .insert(id.clone(), state.clone());
+        // Fails only without subscribers
+        let _ = self.events.send(JobEvent {
+            id: id.clone(),
+            state,
+        });
+    }
+
+    fn finish(&self, id: JobId, result: JobResult, store: &JobStore) {
+        // Stored before the lock is taken, so submissions don't wait for the disk, and removed
+        // only once stored, so the job never seems to be missing
+        let stored = store.save(&id, &result);
+        let mut pending = self.pending.lock().unwrap();
+        match stored {
+            Ok(()) => pending.remove(&id),
+            Err(e) => {
+                eprintln!("Failed to store the result of job {}: {}", id, e);
+                pending.insert(id.clone(), JobState::Finished(result.clone()))
+            }
+        };
+        drop(pending);
+        let _ = self.events.send(JobEvent {
+            id,
+            state: JobState::Finished(result),
+        });
+    }
+}
+
+/// Outcome of `JobQueue::submit`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Submission {
+    /// The inputs were queued for proving
+    Queued(JobId),
+    /// A job for the inputs is queued, running or done already
+    Existing(JobId),
+    /// The queue is full
+    Full,
+    /// The server is shutting down
+    Closed,
+}
+
+/// Jobs waiting for a worker, at most `capacity` of them
+pub struct JobQueue {
+    circuits: Arc<Circuits>,
+    sender: SyncSender<Job>,
+    store: Arc<JobStore>,
+    states: Arc<States>,
+}
+
+impl JobQueue {
+    /// Start `workers` threads proving queued jobs of `circuits`. Both `capacity` and `workers`
+    /// should be at least 1, `fibonacci-server` rejects 0 for either.
+    pub fn start(
+        circuits: Arc<Circuits>,
+        store: Arc<JobStore>,
+        capacity: usize,
+        workers: usize,
+    ) -> Self {
+        let (sender, receiver) = mpsc::sync_channel(capacity);
+        let receiver = Arc::new(Mutex::new(receiver));
+        let states = Arc::new(States {
+            pending: Mutex::default(),
+            events: broadcast::channel(EVENT_CAPACITY).0,
+            closed: AtomicBool::new(false),
+            stopped: watch::channel(false).0,
+        });
+        for _ in 0..workers.max(1) {
+            let store = store.clone();
+            let receiver = receiver.clone();
+            let states = states.clone();
+            thread::spawn(move || work(&store, &receiver, &states));
+        }
+        Self {
+            circuits,
+            sender,
+            store,
+            states,
+        }
+    }
+
+    /// Queue proving `input` for `circuit`, or the only circuit if `None`, unless a job for it is
+    /// queued, running or done already. Jobs which failed are queued again.
+    pub fn submit(
+        &self,
+        circuit: Option<&str>,
+        input: HashMap<String, Vec<String>>,
+    ) -> Result<Submission, FibonacciError> {
+        let (circuit, artifacts) = self.circuits.get(circuit)?;
+        let id = JobId::for_input(circuit, &vk_fingerprint(artifacts.verifying_key()), &input);
+        // Reserved under the lock, so concurrent submissions of the same inputs queue it once.
+        // The store is only touched after releasing it, so other submissions and the workers
+        // don't wait for the disk.
+        {
+            let mut pending = self.states.pending.lock().unwrap();
+            if self.is_closed() {
+                return Ok(Submission::Closed);
+            }
+            if pending.contains_key(&id) {
+                return Ok(Submission::Existing(id));
+            }
+            pending.insert(id.clone(), JobState::Queued);
+        }
+
+        let submission = self.queue(&id, circuit, artifacts, input);
+        if !matches!(submission, Ok(Submission::Queued(_))) {
+            self.states.pending.lock().unwrap().remove(&id);
+        }
+        submission
+    }
+
+    // Queue the job `id` reserved by `submit`, unless the store has its proof already
+    fn queue(
+        &self,
+        id: &JobId,
+        circuit: &str,
+        artifacts: &Arc<Artifacts>,
+        input: HashMap<String, Vec<String>>,
+    ) -> Result<Submission, FibonacciError> {
+        match self.store.load(id)? {
+            Some(JobResult::Done(_)) => return Ok(Submission::Existing(id.clone())),
+            Some(JobResult::Failed(_)) => self.store.remove_error(id)?,
+            None => {}
+        }
+
+        // Checkpointed before it's queued, so a worker never finishes it before
+        self.store.save_queued(
+            id,
+            &QueuedJob {
+                circuit: circuit.to_string(),
+                input: input.clone(),
+            },
+        )?;
+        let backend = artifacts.backend;
+        match self.sender.try_send(Job {
+            id: id.clone(),
+            artifacts: artifacts.clone(),
+            input,
+        }) {
+            Ok(()) => {
+                telemetry::record_queued(backend);
+                Ok(Submission::Queued(id.clone()))
+            }
+            Err(TrySendError::Full(_) | TrySendError::Disconnected(_)) => {
+                self.store.remove_queued(id)?;
+                Ok(Submission::Full)
+            }
+        }
+    }
+
+    /// Queue the jobs which hadn't finished when the server stopped, returns how many. Jobs of
+    /// circuits which are no longer hosted fail.
+    pub fn recover(&self) -> Result<usize, FibonacciError> {
+        let mut jobs = Vec::new();
+        for (id, queued) in self.store.queued()? {
+            match self.circuits.get(Some(&queued.circuit)) {
+                Ok((_, artifacts)) => jobs.push(Job {
+                    id,
+                    artifacts: artifacts.clone(),
+                    input: queued.input,
+                }),
+                Err(e) => self
+                    .store
+                    .save(&id, &JobResult::Failed(ErrorReport::from_error(&e)))?,
+            }
+        }
+        {
+            let mut pending = self.states.pending.lock().unwrap();
+            for job in &jobs {
+                telemetry::record_queued(job.artifacts.backend);
+                pending.insert(job.id.clone(), JobState::Queued);
+            }
+        }
+
+        let count = jobs.len();
+        // There may be more than the queue holds, they're sent as workers free up
+        let sender = self.sender.clone();
+        thread::spawn(move || {
+            for job in jobs {
+                if sender.send(job).is_err() {
+                    return;
+                }
+            }
+        });
+        Ok(count)
+    }
+
+    /// Stop accepting jobs and wait up to `grace` for the running ones to finish. Returns how
+    /// many jobs are left, they're kept in the store for `recover`.
+    pub fn shutdown(&self, grace: Duration) -> usize {
+        self.states.closed.store(true, Ordering::SeqCst);
+        let deadline = Instant::now() + grace;
+        let left = loop {
+            let pending = self.states.pending.lock().unwrap();
+            let running = pending
+                .values()
+                .any(|state| matches!(state, JobState::Running));
+            if !running || Instant::now() >= deadline {
+                break pending
+                    .values()
+                    .filter(|state| !matches!(state, JobState::Finished(_)))
+                    .count();
+            }
+            drop(pending);
+            thread::sleep(SHUTDOWN_POLL_INTERVAL);
+        };
+        self.states.stopped.send_replace(true);
+        left
+    }
+
+    /// Whether `shutdown` was called, no jobs are accepted anymore.
+    pub fn is_closed(&self) -> bool {
+        self.states.closed.load(Ordering::SeqCst)
+    }
+
+    /// Receive `true` once `shutdown` is done waiting for the running jobs.
+    pub fn stopped(&self) -> watch::Receiver<bool> {
+        self.states.stopped.subscribe()
+    }
+
+    /// State of the job `id`, `None` if there's no such job.
+    pub fn state(&self, id: &JobId) -> Result<Option<JobState>, FibonacciError> {
+        if let Some(state) = self.states.pending.lock().unwrap().get(id) {
+            return Ok(Some(state.clone()));
+        }
+        Ok(self.store.load(id)?.map(JobState::Finished))
+    }
+
+    /// Receive the state changes of all jobs from now on. Subscribe before reading a job's
+    /// `state` to not miss changes in between.
+    pub fn subscribe(&self) -> broadcast::Receiver<JobEvent> {
+        self.states.events.subscribe()
+    }
+
+    pub fn circuits(&self) -> &Circuits {
+        &self.circuits
+    }
+
+    pub fn store(&self) -> &JobStore {
+        &self.store
+    }
+}
+
+fn work(store: &JobStore, receiver: &Mutex<Receiver<Job>>, states: &States) {
+    loop {
+        // The lock is released before proving, so idle workers pick up the next job
+        let job = match receiver.lock().unwrap().recv() {
+            Ok(job) => job,
+            Err(_) => return,
+        };
+        let artifacts = &job.artifacts;
+        telemetry::record_dequeued(artifacts.backend);
+        // Left in the store as queued, for the next run
+        if states.closed.load(Ordering::SeqCst) {
+            continue;
+        }
+
+        states.update(&job.id, JobState::Running);
+
+        let prove = || artifacts.prove(job.input);
+        let result = match catch_unwind(AssertUnwindSafe(prove)) {
+            Ok(Ok(bundle)) => JobResult::Done(bundle),
+            Ok(Err(e)) => JobResult::Failed(ErrorReport::from_error(e.as_ref())),
+            Err(_) => JobResult::Failed(ErrorReport::new(ErrorCode::Internal, "Prover panicked")),
+        };
+        telemetry::record_finished(artifacts.backend, &result);
+        states.finish(job.id, result, store);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_job_id() {
+        let input = |pairs: &[(&str, &str)]| -> HashMap<String, Vec<String>> {
+            pairs
+                .iter()
+                .map(|(name, value)| (name.to_string(), vec![value.to_string()]))
+                .collect()
+        };
+        let vk = vk_fingerprint(b"verifying key");
+        let id = JobId::for_input("gemini", &vk, &input(&[("a", "1"), ("out", "55")]));
+        assert_eq!(JobId::parse(id.as_str()).unwrap(), id);
+        assert_eq!(
+            JobId::for_input("gemini", &vk, &input(&[("out", "55"), ("a", "1")])),
+            id
+        );
+        assert_ne!(
+            JobId::for_input("plonk", &vk, &input(&[("a", "1"), ("out", "55")])),
+            id
+        );
+        assert_ne!(
+            JobId::for_input("gemini", &vk, &input(&[("a", "1"), ("out", "56")])),
+            id
+        );
+        // Rotated keys of the same circuit id
+        assert_ne!(
+            JobId::for_input(
+                "gemini",
+                &vk_fingerprint(b"new verifying key"),
+                &input(&[("a", "1"), ("out", "55")])
+            ),
+            id
+        );
+
+        assert!(JobId::parse("../../etc/passwd").is_err());
+        assert!(JobId::parse(&id.as_str()[1..]).is_err());
+        assert!(JobId::parse("0123456789abcdef0123456789ABCDEF").is_err());
+    }
+}
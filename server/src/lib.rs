@@ -0,0 +1,1013 @@
+//! HTTP prover service.
+//!
+//! Clients submit circuit inputs with `POST /jobs` and get a job id back immediately, proofs take
+//! far longer than an HTTP request should be held open. `GET /jobs/{id}` reports the status of a
+//! job and the proof bundle once it's done, `GET /jobs/{id}/events` streams the same over a
+//! WebSocket as the job progresses.
+//!
+//! Ids follow from the circuit, its keys and the inputs, submitting them again returns the same
+//! job, and `GET /proofs/{id}` the proof of a job which is done. `POST /verify` checks a proof
+//! bundle against the verifying key of a circuit.
+//!
+//! A server hosts one or more circuits, each a backend with its SRS and keys, see `circuits`.
+//! They're loaded and checked with a test proof after the server started listening, `/healthz`
+//! answers as soon as it runs and `/readyz` once it's ready to prove all of them.
+//!
+//! The same operations are available over JSON-RPC 2.0 at `POST /rpc`, see `rpc`.
+//!
+//! Submitting jobs can require API keys, see `auth`, and `/metrics` serves Prometheus metrics, see
+//! `telemetry`. Errors are returned as JSON `ErrorReport`s with an HTTP status following their
+//! code, see `status_code`.
+use std::{
+    collections::HashMap,
+    error::Error,
+    fs,
+    path::Path,
+    sync::{Arc, OnceLock},
+};
+
+use axum::{
+    body::Bytes,
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Path as UrlPath, State,
+    },
+    http::{header, StatusCode},
+    middleware,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use fibonacci_circuit::{
+    serialisation::Codec, BackendId, Capabilities, ErrorCode, ErrorMessage, ErrorReport,
+    FibonacciError, ProofBundle, VerificationOutcome,
+};
+use fibonacci_mobile::Prover;
+use metrics_exporter_prometheus::PrometheusHandle;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::{self, error::RecvError};
+
+pub mod auth;
+pub mod circuits;
+pub mod jobs;
+pub mod rpc;
+pub mod telemetry;
+
+pub use auth::{authenticate, require_api_key, ApiClient, ApiKeys, RateLimit};
+pub use circuits::{Circuits, KeyStore};
+pub use fibonacci_api::{
+    CircuitInfo, EncodedBundle, JobRequest, JobResponse, JobSubmitted, VerifyRequest,
+};
+pub use jobs::{JobEvent, JobId, JobQueue, JobResult, JobState, JobStatus, JobStore, Submission};
+
+/// Backend, SRS and keys the server proves with
+pub struct Artifacts {
+    pub backend: BackendId,
+    srs_key: Vec<u8>,
+    proving_key: Vec<u8>,
+    verifying_key: Vec<u8>,
+    /// Decoded from the proving key on first use, then shared by all proofs
+    prover: OnceLock<Prover>,
+}
+
+impl Artifacts {
+    pub fn new(
+        backend: BackendId,
+        srs_key: Vec<u8>,
+        proving_key: Vec<u8>,
+        verifying_key: Vec<u8>,
+    ) -> Self {
+        Self {
+            backend,
+            srs_key,
+            proving_key,
+            verifying_key,
+            prover: OnceLock::new(),
+        }
+    }
+
+    pub fn load(
+        backend: BackendId,
+        srs: &Path,
+        proving_key: &Path,
+        verifying_key: &Path,
+    ) -> Result<Self, FibonacciError> {
+        let read = |path: &Path| {
+            fs::read(path).map_err(|e| {
+                FibonacciError::Io(ErrorMessage::with_source(
+                    format!("Couldn't read '{}': {}", path.display(), e),
+                    e,
+                ))
+            })
+        };
+        Ok(Self::new(
+            backend,
+            read(srs)?,
+            read(proving_key)?,
+            read(verifying_key)?,
+        ))
+    }
+
+    /// Prove and verify a test input, so keys of another circuit, backend or SRS are found before
+    /// any job is accepted.
+    pub fn check(&self) -> Result<(), Box<dyn Error>> {
+        let input = HashMap::from([("out".to_string(), vec!["55".to_string()])]);
+        let bundle = self.prove(input)?;
+        if !self.verify(&bundle)? {
+            return Err(FibonacciError::Setup(
+                format!(
+                    "The {} proving and verifying keys don't match",
+                    self.backend.name()
+                )
+                .into(),
+            )
+            .into());
+        }
+        Ok(())
+    }
+
+    /// Run a throwaway proof now, see `ProverContext::warm_up`, so the first job isn't the slower
+    /// first proof of the process.
+    pub fn warm_up(&self) -> Result<(), FibonacciError> {
+        self.prover()?.warm_up()
+    }
+
+    /// Prove `input`. The backends don't report progress within a proof, so neither do jobs.
+    pub fn prove(
+        &self,
+        input: HashMap<String, Vec<String>>,
+    ) -> Result<ProofBundle, Box<dyn Error>> {
+        let result = self.prover()?.prove(input)?;
+        Ok(ProofBundle::from(result).with_backend(self.backend))
+    }
+
+    fn prover(&self) -> Result<&Prover, FibonacciError> {
+        if let Some(prover) = self.prover.get() {
+            return Ok(prover);
+        }
+        // Workers racing to decode the key keep whichever prover was stored first
+        let prover = Prover::new(self.backend, &self.srs_key, &self.proving_key)?;
+        Ok(self.prover.get_or_init(|| prover))
+    }
+
+    pub fn verifying_key(&self) -> &[u8] {
+        &self.verifying_key
+    }
+
+    /// Verify `bundle` with the verifying key, after checking it's a well-formed bundle of this
+    /// backend.
+    pub fn verify(&self, bundle: &ProofBundle) -> Result<bool, Box<dyn Error>> {
+        if bundle
+            .backend
+            .is_some_and(|backend| backend != self.backend)
+        {
+            return Err(FibonacciError::InvalidInput(
+                format!(
+                    "The bundle holds a {} proof, the circuit uses {}",
+                    bundle.backend.unwrap().name(),
+                    self.backend.name()
+                )
+                .into(),
+            )
+            .into());
+        }
+        bundle.validate()?;
+        fibonacci_mobile::verify(
+            self.backend,
+            &self.srs_key,
+            &self.verifying_key,
+            &bundle.proof,
+            &bundle.public_inputs,
+        )
+    }
+}
+
+/// HTTP status of an error of `code`
+pub fn status_code(code: ErrorCode) -> StatusCode {
+    match code {
+        ErrorCode::InvalidInput | ErrorCode::Serialization | ErrorCode::Transcript => {
+            StatusCode::BAD_REQUEST
+        }
+        ErrorCode::Verification => StatusCode::UNPROCESSABLE_ENTITY,
+        ErrorCode::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+/// Error response carrying an `ErrorReport`
+#[derive(Debug)]
+pub struct ApiError {
+    pub status: StatusCode,
+    pub report: ErrorReport,
+}
+
+impl From<FibonacciError> for ApiError {
+    fn from(error: FibonacciError) -> Self {
+        let report = ErrorReport::from(&error);
+        Self {
+            status: status_code(report.code),
+            report,
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.status, Json(self.report)).into_response()
+    }
+}
+
+/// Shared state of the handlers, the job queue is started once the artifacts are loaded
+#[derive(Clone, Default)]
+pub struct AppState {
+    queue: Arc<OnceLock<Arc<JobQueue>>>,
+    api_keys: Option<Arc<ApiKeys>>,
+    metrics: Option<PrometheusHandle>,
+}
+
+impl AppState {
+    /// State of a server which is ready right away
+    pub fn with_queue(queue: JobQueue) -> Self {
+        let state = Self::default();
+        state.set_ready(queue);
+        state
+    }
+
+    /// Require one of `keys` for submitting jobs.
+    pub fn with_api_keys(mut self, keys: ApiKeys) -> Self {
+        self.api_keys = Some(Arc::new(keys));
+        self
+    }
+
+    /// Serve the metrics rendered by `handle` at `/metrics`, see `telemetry::install`.
+    pub fn with_metrics(mut self, handle: PrometheusHandle) -> Self {
+        self.metrics = Some(handle);
+        self
+    }
+
+    pub fn api_keys(&self) -> Option<&ApiKeys> {
+        self.api_keys.as_deref()
+    }
+
+    /// Accept jobs into `queue` from now on. Only the first queue is used.
+    pub fn set_ready(&self, queue: JobQueue) {
+        let _ = self.queue.set(Arc::new(queue));
+    }
+
+    /// Whether jobs are accepted: the keys are loaded and the queue isn't shutting down.
+    pub fn is_ready(&self) -> bool {
+        self.queue.get().is_some_and(|queue| !queue.is_closed())
+    }
+
+    /// The queue jobs are accepted into, `None` while loading.
+    pub fn job_queue(&self) -> Option<Arc<JobQueue>> {
+        self.queue.get().cloned()
+    }
+
+    pub(crate) fn queue(&self) -> Result<Arc<JobQueue>, ApiError> {
+        self.queue.get().cloned().ok_or_else(|| ApiError {
+            status: StatusCode::SERVICE_UNAVAILABLE,
+            report: ErrorReport::new(ErrorCode::Setup, "The server is still loading its keys"),
+        })
+    }
+}
+
+/// Seconds a client should wait before resubmitting to a full queue
+const RETRY_AFTER_SECS: &str = "5";
+
+async fn submit_job(State(state): State<AppState>, body: Bytes) -> Result<Response, ApiError> {
+    let queue = state.queue()?;
+    // Parsed here rather than by the `Json` extractor, so malformed requests get error reports too
+    let request: JobRequest = serde_json::from_slice(&body).map_err(|e| {
+        FibonacciError::InvalidInput(ErrorMessage::with_source(
+            format!("Invalid job request: {}", e),
+            e,
+        ))
+    })?;
+
+    match queue.submit(request.circuit.as_deref(), request.input)? {
+        Submission::Queued(id) => Ok((
+            StatusCode::ACCEPTED,
+            Json(JobSubmitted { id: id.to_string() }),
+        )
+            .into_response()),
+        Submission::Existing(id) => {
+            Ok((StatusCode::OK, Json(JobSubmitted { id: id.to_string() })).into_response())
+        }
+        Submission::Full => Ok((
+            StatusCode::SERVICE_UNAVAILABLE,
+            [(header::RETRY_AFTER, RETRY_AFTER_SECS)],
+            Json(ErrorReport::new(
+                ErrorCode::Unavailable,
+                "The job queue is full, retry later",
+            )),
+        )
+            .into_response()),
+        // Another instance may take it, or this one once restarted
+        Submission::Closed => Ok((
+            StatusCode::SERVICE_UNAVAILABLE,
+            [(header::RETRY_AFTER, RETRY_AFTER_SECS)],
+            Json(ErrorReport::new(
+                ErrorCode::Unavailable,
+                "The server is shutting down, retry later",
+            )),
+        )
+            .into_response()),
+    }
+}
+
+fn unknown_job(id: &JobId) -> ApiError {
+    ApiError {
+        status: StatusCode::NOT_FOUND,
+        report: ErrorReport::new(ErrorCode::InvalidInput, format!("No job {}", id)),
+    }
+}
+
+async fn get_job(
+    State(state): State<AppState>,
+    UrlPath(id): UrlPath<String>,
+) -> Result<Json<JobResponse>, ApiError> {
+    let id = JobId::parse(&id)?;
+    let state = state.queue()?.state(&id)?.ok_or_else(|| unknown_job(&id))?;
+    Ok(Json(state.response(&id)))
+}
+
+async fn list_circuits(State(state): State<AppState>) -> Result<Json<Vec<CircuitInfo>>, ApiError> {
+    let queue = state.queue()?;
+    let circuits = queue
+        .circuits()
+        .iter()
+        .map(|(id, artifacts)| CircuitInfo {
+            id: id.to_string(),
+            backend: artifacts.backend,
+        })
+        .collect();
+    Ok(Json(circuits))
+}
+
+/// The proof bundle of a job which is done, bincode-encoded like the bundles of `fibonacci prove`
+async fn get_proof(
+    State(state): State<AppState>,
+    UrlPath(id): UrlPath<String>,
+) -> Result<Response, ApiError> {
+    let id = JobId::parse(&id)?;
+    match state.queue()?.store().load(&id)? {
+        Some(JobResult::Done(bundle)) => Ok((
+            [(header::CONTENT_TYPE, "application/octet-stream")],
+            bundle.encode(Codec::Bincode)?,
+        )
+            .into_response()),
+        _ => Err(ApiError {
+            status: StatusCode::NOT_FOUND,
+            report: ErrorReport::new(ErrorCode::InvalidInput, format!("No proof {}", id)),
+        }),
+    }
+}
+
+/// Verify a bundle with the key of its circuit. Whether the proof is valid, or why it couldn't be
+/// checked, is reported as a `VerificationOutcome`, only malformed requests are errors.
+async fn verify_bundle(
+    State(state): State<AppState>,
+    body: Bytes,
+) -> Result<Json<VerificationOutcome>, ApiError> {
+    let queue = state.queue()?;
+    let request: VerifyRequest = serde_json::from_slice(&body).map_err(|e| {
+        FibonacciError::InvalidInput(ErrorMessage::with_source(
+            format!("Invalid verify request: {}", e),
+            e,
+        ))
+    })?;
+    Ok(Json(verify_request(&queue, request).await?))
+}
+
+/// Verify the bundle of `request` with the key of its circuit, see `POST /verify`.
+pub(crate) async fn verify_request(
+    queue: &JobQueue,
+    request: VerifyRequest,
+) -> Result<VerificationOutcome, ApiError> {
+    let artifacts = queue.circuits().get(request.circuit.as_deref())?.1.clone();
+    let bundle = request.bundle.decode()?;
+
+    // Verifying takes milliseconds of CPU, keep it off the async workers
+    let backend = artifacts.backend;
+    tokio::task::spawn_blocking(move || {
+        VerificationOutcome::from_result(Some(backend), artifacts.verify(&bundle))
+    })
+    .await
+    .map_err(|_| ApiError {
+        status: StatusCode::INTERNAL_SERVER_ERROR,
+        report: ErrorReport::new(ErrorCode::Internal, "Verifier panicked"),
+    })
+}
+
+async fn job_events(
+    State(state): State<AppState>,
+    UrlPath(id): UrlPath<String>,
+    ws: WebSocketUpgrade,
+) -> Result<Response, ApiError> {
+    let id = JobId::parse(&id)?;
+    let queue = state.queue()?;
+    let events = queue.subscribe();
+    let state = queue.state(&id)?.ok_or_else(|| unknown_job(&id))?;
+    Ok(ws.on_upgrade(move |socket| stream_job(socket, queue, id, state, events)))
+}
+
+/// Send a `JobResponse` text message for the current state of the job and every change after,
+/// closing the socket once the job finished or the server stopped.
+async fn stream_job(
+    mut socket: WebSocket,
+    queue: Arc<JobQueue>,
+    id: JobId,
+    mut state: JobState,
+    mut events: broadcast::Receiver<JobEvent>,
+) {
+    let mut stopped = queue.stopped();
+    'stream: loop {
+        let message = serde_json::to_string(&state.response(&id)).unwrap();
+        if socket.send(Message::Text(message)).await.is_err() {
+            return;
+        }
+        if matches!(state, JobState::Finished(_)) {
+            break;
+        }
+
+        state = loop {
+            let event = tokio::select! {
+                event = events.recv() => event,
+                _ = stopped.wait_for(|stopped| *stopped) => break 'stream,
+            };
+            match event {
+                Ok(event) if event.id == id => break event.state,
+                Ok(_) => continue,
+                // Only the latest state matters to clients, skip the missed events
+                Err(RecvError::Lagged(_)) => match queue.state(&id) {
+                    Ok(Some(state)) => break state,
+                    _ => return,
+                },
+                Err(RecvError::Closed) => return,
+            }
+        };
+    }
+    let _ = socket.close().await;
+}
+
+/// Response of `/healthz` and `/readyz`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Health {
+    /// `ok`, `ready`, `loading` or `stopping`
+    pub status: String,
+}
+
+fn health(status: &str) -> Json<Health> {
+    Json(Health {
+        status: status.to_string(),
+    })
+}
+
+/// Backends and features of the build, whichever circuits are hosted
+async fn capabilities() -> Json<Capabilities> {
+    Json(fibonacci_mobile::capabilities())
+}
+
+async fn healthz() -> Json<Health> {
+    health("ok")
+}
+
+async fn readyz(State(state): State<AppState>) -> (StatusCode, Json<Health>) {
+    match state.job_queue() {
+        Some(queue) if queue.is_closed() => (StatusCode::SERVICE_UNAVAILABLE, health("stopping")),
+        Some(_) => (StatusCode::OK, health("ready")),
+        None => (StatusCode::SERVICE_UNAVAILABLE, health("loading")),
+    }
+}
+
+async fn metrics(State(state): State<AppState>) -> Result<Response, ApiError> {
+    let handle = state.metrics.as_ref().ok_or_else(|| ApiError {
+        status: StatusCode::NOT_FOUND,
+        report: ErrorReport::new(ErrorCode::InvalidInput, "Metrics are disabled"),
+    })?;
+    Ok((
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        handle.render(),
+    )
+        .into_response())
+}
+
+pub fn router(state: AppState) -> Router {
+    Router::new()
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+        .route("/metrics", get(metrics))
+        .route("/circuits", get(list_circuits))
+        .route("/capabilities", get(capabilities))
+        .route(
+            "/jobs",
+            post(submit_job).route_layer(middleware::from_fn_with_state(
+                state.clone(),
+                require_api_key,
+            )),
+        )
+        .route("/jobs/:id", get(get_job))
+        .route("/jobs/:id/events", get(job_events))
+        .route("/proofs/:id", get(get_proof))
+        .route(
+            "/verify",
+            post(verify_bundle)
+                .route_layer(middleware::from_fn_with_state(state.clone(), authenticate)),
+        )
+        .route(
+            "/rpc",
+            post(rpc::handle)
+                .route_layer(middleware::from_fn_with_state(state.clone(), authenticate)),
+        )
+        .with_state(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        future::IntoFuture,
+        time::{Duration, Instant},
+    };
+
+    use axum::{body::Body, http::Request};
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    use futures_util::StreamExt;
+    use tokio_tungstenite::tungstenite;
+    use tower::ServiceExt;
+
+    use super::*;
+
+    async fn send(router: &Router, request: Request<Body>) -> (StatusCode, serde_json::Value) {
+        let response = router.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    async fn post_job(router: &Router, body: &str) -> (StatusCode, serde_json::Value) {
+        let request = Request::post("/jobs")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        send(router, request).await
+    }
+
+    async fn get(router: &Router, uri: &str) -> (StatusCode, serde_json::Value) {
+        send(router, Request::get(uri).body(Body::empty()).unwrap()).await
+    }
+
+    async fn get_job(router: &Router, id: &str) -> (StatusCode, serde_json::Value) {
+        get(router, &format!("/jobs/{}", id)).await
+    }
+
+    // Poll the job `id` until it finished
+    async fn wait_for_job(router: &Router, id: &str) -> JobResponse {
+        let started = Instant::now();
+        loop {
+            let (status, body) = get_job(router, id).await;
+            assert_eq!(status, StatusCode::OK);
+            let response: JobResponse = serde_json::from_value(body).unwrap();
+            if matches!(response.status, JobStatus::Done | JobStatus::Failed) {
+                return response;
+            }
+            assert!(started.elapsed() < Duration::from_secs(120));
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    }
+
+    fn gemini_artifacts() -> Arc<Artifacts> {
+        let srs_key =
+            fs::read(Path::new(env!("CARGO_MANIFEST_DIR")).join("../gemini/unihyperplonk-srs-4"))
+                .unwrap();
+        let (proving_key, verifying_key) =
+            fibonacci_mobile::keygen(BackendId::Gemini, &srs_key).unwrap();
+        Arc::new(Artifacts::new(
+            BackendId::Gemini,
+            srs_key,
+            proving_key,
+            verifying_key,
+        ))
+    }
+
+    #[tokio::test]
+    async fn test_job_events() {
+        let dir =
+            std::env::temp_dir().join(format!("fibonacci-server-events-{}", std::process::id()));
+        let store = Arc::new(JobStore::open(&dir).unwrap());
+        let router = router(AppState::with_queue(JobQueue::start(
+            Arc::new(Circuits::single(gemini_artifacts())),
+            store,
+            4,
+            1,
+        )));
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(axum::serve(listener, router.clone()).into_future());
+
+        let (_, body) = post_job(&router, r#"{"input": {"out": ["55"]}}"#).await;
+        let url = format!("ws://{}/jobs/{}/events", addr, body["id"].as_str().unwrap());
+        let (mut socket, _) = tokio_tungstenite::connect_async(url).await.unwrap();
+
+        let mut responses = Vec::new();
+        while let Some(message) = socket.next().await {
+            match message.unwrap() {
+                tungstenite::Message::Text(text) => {
+                    responses.push(serde_json::from_str::<JobResponse>(&text).unwrap())
+                }
+                tungstenite::Message::Close(_) => break,
+                _ => {}
+            }
+        }
+
+        // The status only advances, and the last message carries the proof
+        assert!(responses
+            .windows(2)
+            .all(|pair| pair[0].status <= pair[1].status));
+        let last = responses.last().unwrap();
+        assert_eq!(last.status, JobStatus::Done);
+        assert!(last
+            .bundle
+            .as_ref()
+            .unwrap()
+            .decode()
+            .unwrap()
+            .validate()
+            .is_ok());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_jobs() {
+        let dir = std::env::temp_dir().join(format!("fibonacci-server-{}", std::process::id()));
+        let store = Arc::new(JobStore::open(&dir).unwrap());
+        let circuits = Arc::new(Circuits::single(gemini_artifacts()));
+        let queue = JobQueue::start(circuits.clone(), store.clone(), 4, 1);
+        let router = router(AppState::with_queue(queue));
+
+        let (status, body) = post_job(&router, r#"{"input": {"out": ["55"]}}"#).await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+        let id = body["id"].as_str().unwrap().to_string();
+
+        let (status, body) = post_job(&router, r#"{"out": ["55"]}"#).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], "invalid_input");
+        let (status, _) =
+            post_job(&router, r#"{"circuit": "plonk", "input": {"out": ["55"]}}"#).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        // The only circuit is named after its backend
+        let (_, body) = get(&router, "/circuits").await;
+        assert_eq!(
+            body,
+            serde_json::json!([{"id": "gemini", "backend": "gemini"}])
+        );
+        let (status, body) = get(&router, "/capabilities").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            body["backends"].as_array().unwrap().len(),
+            BackendId::ALL.len()
+        );
+        assert_eq!(body["backends"][2]["pcs"], "Gemini over univariate KZG");
+        let (status, body) = post_job(
+            &router,
+            r#"{"circuit": "gemini", "input": {"out": ["55"]}}"#,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["id"], id.as_str());
+
+        let response = wait_for_job(&router, &id).await;
+        assert_eq!(response.status, JobStatus::Done);
+        let bundle = response.bundle.unwrap().decode().unwrap();
+        assert_eq!(bundle.backend, Some(BackendId::Gemini));
+        assert!(bundle.validate().is_ok());
+
+        // Jobs which fail report their error
+        let (_, body) = post_job(&router, r#"{"input": {"out": ["x"]}}"#).await;
+        let response = wait_for_job(&router, body["id"].as_str().unwrap()).await;
+        assert_eq!(response.status, JobStatus::Failed);
+        assert_eq!(response.error.unwrap().code, ErrorCode::InvalidInput);
+
+        // Results are read back from the store after a restart
+        let restarted = super::router(AppState::with_queue(JobQueue::start(circuits, store, 4, 1)));
+        let (status, body) = get_job(&restarted, &id).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["status"], "done");
+
+        // Submitting the inputs again returns the job which proved them
+        let (status, body) = post_job(&restarted, r#"{"input": {"out": ["55"]}}"#).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["id"], id.as_str());
+
+        let response = restarted
+            .clone()
+            .oneshot(
+                Request::get(format!("/proofs/{}", id))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(ProofBundle::decode(&body, Codec::Bincode).unwrap(), bundle);
+
+        let verify = |bundle: &ProofBundle| {
+            let request = VerifyRequest {
+                circuit: None,
+                bundle: bundle.into(),
+            };
+            Request::post("/verify")
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(serde_json::to_vec(&request).unwrap()))
+                .unwrap()
+        };
+        let (status, body) = send(&restarted, verify(&bundle)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            body,
+            serde_json::json!({"backend": "Gemini", "valid": true})
+        );
+        let mut tampered = bundle.clone();
+        let last = tampered.public_inputs.len() - 32;
+        tampered.public_inputs[last] ^= 1;
+        let (status, body) = send(&restarted, verify(&tampered)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["valid"], false);
+
+        let unknown = JobId::for_input(
+            "gemini",
+            &[0; 32],
+            &HashMap::from([("out".to_string(), vec!["89".to_string()])]),
+        );
+        let (status, _) = get_job(&router, unknown.as_str()).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = get(&router, &format!("/proofs/{}", unknown)).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = get_job(&router, "not-an-id").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_json_rpc() {
+        let dir = std::env::temp_dir().join(format!("fibonacci-server-rpc-{}", std::process::id()));
+        let artifacts = gemini_artifacts();
+        let router = router(AppState::with_queue(JobQueue::start(
+            Arc::new(Circuits::single(artifacts.clone())),
+            Arc::new(JobStore::open(&dir).unwrap()),
+            4,
+            1,
+        )));
+        let rpc = |body: serde_json::Value| {
+            Request::post("/rpc")
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+
+        let (status, body) = send(
+            &router,
+            rpc(serde_json::json!({
+                "jsonrpc": "2.0",
+                "method": "fib_prove",
+                "params": {"input": {"out": ["55"]}},
+                "id": 1
+            })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["id"], 1);
+        let proved: rpc::ProveResult = serde_json::from_value(body["result"].clone()).unwrap();
+        let bundle = proved.bundle;
+
+        // A batch of a verification, the verifying key and an unknown method
+        let (_, body) = send(
+            &router,
+            rpc(serde_json::json!([
+                {"jsonrpc": "2.0", "method": "fib_verify", "params": {"bundle": bundle}, "id": "a"},
+                {"jsonrpc": "2.0", "method": "fib_getVk", "id": "b"},
+                {"jsonrpc": "2.0", "method": "fib_unknown", "id": "c"},
+                {"jsonrpc": "2.0", "method": "fib_getVk"}
+            ])),
+        )
+        .await;
+        let responses: Vec<rpc::RpcResponse> = serde_json::from_value(body).unwrap();
+        assert_eq!(responses.len(), 3);
+        assert_eq!(responses[0].result.as_ref().unwrap()["valid"], true);
+        let vk = &responses[1].result.as_ref().unwrap()["verifying_key"];
+        assert_eq!(
+            STANDARD.decode(vk.as_str().unwrap()).unwrap(),
+            artifacts.verifying_key()
+        );
+        assert_eq!(
+            responses[2].error.as_ref().unwrap().code,
+            rpc::METHOD_NOT_FOUND
+        );
+
+        let (_, body) = send(
+            &router,
+            rpc(serde_json::json!({
+                "jsonrpc": "2.0",
+                "method": "fib_prove",
+                "params": {"input": {"out": ["x"]}},
+                "id": 2
+            })),
+        )
+        .await;
+        assert_eq!(body["error"]["code"], rpc::PROVING_FAILED);
+        assert_eq!(body["error"]["data"]["code"], "invalid_input");
+
+        let (_, body) = send(
+            &router,
+            rpc(
+                serde_json::json!({"jsonrpc": "2.0", "method": "fib_prove", "params": [], "id": 3}),
+            ),
+        )
+        .await;
+        assert_eq!(body["error"]["code"], rpc::INVALID_PARAMS);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_readiness() {
+        let dir =
+            std::env::temp_dir().join(format!("fibonacci-server-ready-{}", std::process::id()));
+        let state = AppState::default();
+        let router = router(state.clone());
+
+        let (status, body) = get(&router, "/healthz").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["status"], "ok");
+        let (status, body) = get(&router, "/readyz").await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body["status"], "loading");
+        let (status, _) = get(&router, "/metrics").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, body) = post_job(&router, r#"{"input": {"out": ["55"]}}"#).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body["code"], "setup");
+
+        let artifacts = gemini_artifacts();
+        artifacts.warm_up().unwrap();
+        artifacts.check().unwrap();
+        // A proving key of another circuit is found while warming up
+        let wrong_key = Artifacts::new(
+            BackendId::Gemini,
+            artifacts.srs_key.clone(),
+            vec![0; 8],
+            artifacts.verifying_key.clone(),
+        );
+        assert!(wrong_key.warm_up().is_err());
+        let mismatched = Artifacts::new(
+            BackendId::Gemini,
+            artifacts.srs_key.clone(),
+            artifacts.proving_key.clone(),
+            vec![0; 8],
+        );
+        assert!(mismatched.check().is_err());
+
+        let store = Arc::new(JobStore::open(&dir).unwrap());
+        state.set_ready(JobQueue::start(
+            Arc::new(Circuits::single(artifacts)),
+            store,
+            4,
+            1,
+        ));
+        let (status, body) = get(&router, "/readyz").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["status"], "ready");
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_shutdown() {
+        let dir =
+            std::env::temp_dir().join(format!("fibonacci-server-shutdown-{}", std::process::id()));
+        let store = Arc::new(JobStore::open(&dir).unwrap());
+
+        // Jobs left unfinished by the previous run
+        let input = HashMap::from([("out".to_string(), vec!["55".to_string()])]);
+        let left = |circuit: &str| {
+            // Recovered jobs keep the id of their file
+            let id = JobId::for_input(circuit, &[0; 32], &input);
+            let job = jobs::QueuedJob {
+                circuit: circuit.to_string(),
+                input: input.clone(),
+            };
+            fs::write(
+                dir.join(format!("{}.job", id)),
+                serde_json::to_vec(&job).unwrap(),
+            )
+            .unwrap();
+            id
+        };
+        let id = left("gemini");
+        let unhosted = left("plonk");
+        assert_eq!(store.queued().unwrap().len(), 2);
+
+        let queue = JobQueue::start(Arc::new(Circuits::single(gemini_artifacts())), store, 4, 1);
+        assert_eq!(queue.recover().unwrap(), 1);
+        let state = AppState::with_queue(queue);
+        let router = router(state.clone());
+        let response = wait_for_job(&router, id.as_str()).await;
+        assert_eq!(response.status, JobStatus::Done);
+        let response = wait_for_job(&router, unhosted.as_str()).await;
+        assert_eq!(response.error.unwrap().code, ErrorCode::InvalidInput);
+
+        let queue = state.job_queue().unwrap();
+        assert!(queue.store().queued().unwrap().is_empty());
+        assert_eq!(
+            tokio::task::spawn_blocking(move || queue.shutdown(Duration::from_secs(1)))
+                .await
+                .unwrap(),
+            0
+        );
+        let (status, body) = get(&router, "/readyz").await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body["status"], "stopping");
+        let (status, body) = post_job(&router, r#"{"input": {"out": ["89"]}}"#).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body["code"], "unavailable");
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_api_keys() {
+        let keys = ApiKeys::new([("alice", "secret")], Some(RateLimit::per_minute(1)));
+        let router = &router(AppState::default().with_api_keys(keys));
+        let post = move |key: Option<&str>| {
+            let mut request = Request::post("/jobs");
+            if let Some(key) = key {
+                request = request.header(header::AUTHORIZATION, format!("Bearer {}", key));
+            }
+            let request = request
+                .body(Body::from(r#"{"input": {"out": ["55"]}}"#))
+                .unwrap();
+            send(router, request)
+        };
+
+        let (status, body) = post(None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(body["code"], "invalid_input");
+        let (status, _) = post(Some("guess")).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        // Passes authentication, the server has no queue yet
+        let (status, _) = post(Some("secret")).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        let (status, body) = post(Some("secret")).await;
+        assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(body["context"]["client"], "alice");
+
+        // Verifications need a key, but aren't counted
+        let verify = |key: Option<&str>, uri: &str, body: serde_json::Value| {
+            let mut request = Request::post(uri);
+            if let Some(key) = key {
+                request = request.header(header::AUTHORIZATION, format!("Bearer {}", key));
+            }
+            send(router, request.body(Body::from(body.to_string())).unwrap())
+        };
+        let bundle =
+            serde_json::json!({"bundle": {"backend": null, "proof": "", "public_inputs": ""}});
+        let (status, _) = verify(None, "/verify", bundle.clone()).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, _) = verify(Some("secret"), "/verify", bundle).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+
+        // Over JSON-RPC, only proofs are counted
+        let (status, body) = verify(
+            Some("secret"),
+            "/rpc",
+            serde_json::json!([
+                {"jsonrpc": "2.0", "method": "fib_getVk", "id": 1},
+                {
+                    "jsonrpc": "2.0",
+                    "method": "fib_prove",
+                    "params": {"input": {"out": ["55"]}},
+                    "id": 2
+                }
+            ]),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body[0]["error"]["code"], rpc::SERVER_BUSY);
+        assert_eq!(body[1]["error"]["code"], rpc::RATE_LIMITED);
+        assert_eq!(body[1]["error"]["data"]["context"]["client"], "alice");
+
+        // Polling needs no key
+        let (status, _) = get(router, "/readyz").await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    }
+}
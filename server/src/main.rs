@@ -0,0 +1,164 @@
+use std::{error::Error, net::SocketAddr, path::PathBuf, process, sync::Arc, time::Duration};
+
+use clap::Parser;
+use fibonacci_circuit::{BackendId, FibonacciError};
+use fibonacci_server::{
+    router, telemetry, ApiKeys, AppState, Artifacts, Circuits, JobQueue, JobStore, KeyStore,
+    RateLimit,
+};
+
+fn parse_backend(name: &str) -> Result<BackendId, FibonacciError> {
+    BackendId::from_name(name)
+}
+
+// A queue without capacity rejects every job while the workers are busy, and without workers
+// no job is ever proven
+fn parse_positive(value: &str) -> Result<usize, String> {
+    match value.parse() {
+        Ok(0) => Err("must be at least 1".to_string()),
+        Ok(value) => Ok(value),
+        Err(e) => Err(format!("{}", e)),
+    }
+}
+
+/// Prover service accepting proving jobs over HTTP
+#[derive(Debug, Parser)]
+#[command(name = "fibonacci-server", version)]
+struct Args {
+    /// Key store directory with a `keys.json` manifest of the circuits to host, as written by
+    /// `gen-all-keys`
+    #[arg(long, conflicts_with = "backend")]
+    keys: Option<PathBuf>,
+    /// Backend of the only circuit to host, without a key store: plonk, hyperplonk, gemini or
+    /// unihyperplonk
+    #[arg(
+        long,
+        value_parser = parse_backend,
+        required_unless_present = "keys",
+        requires_all = ["srs", "proving_key", "verifying_key"]
+    )]
+    backend: Option<BackendId>,
+    #[arg(long, requires = "backend")]
+    srs: Option<PathBuf>,
+    #[arg(long, requires = "backend")]
+    proving_key: Option<PathBuf>,
+    /// Checked against the proving key before the server reports ready
+    #[arg(long, requires = "backend")]
+    verifying_key: Option<PathBuf>,
+    /// Directory job results are persisted to
+    #[arg(long, default_value = "jobs")]
+    store: PathBuf,
+    #[arg(long, default_value = "127.0.0.1:3000")]
+    addr: SocketAddr,
+    /// Jobs waiting for a worker before submissions are rejected
+    #[arg(long, default_value_t = 16, value_parser = parse_positive)]
+    queue_capacity: usize,
+    /// Jobs proven at the same time, each proof already uses all cores
+    #[arg(long, default_value_t = 1, value_parser = parse_positive)]
+    workers: usize,
+    /// File of `<name>=<key>` lines, submitting jobs requires one of the keys
+    #[arg(long)]
+    api_keys: Option<PathBuf>,
+    /// Jobs each API key may submit per minute, at least 1
+    #[arg(long, requires = "api_keys", value_parser = clap::value_parser!(u32).range(1..))]
+    rate_limit: Option<u32>,
+    /// Seconds to wait for running proofs on SIGTERM, unfinished jobs are queued again on the
+    /// next start
+    #[arg(long, default_value_t = 300)]
+    shutdown_grace: u64,
+}
+
+// Load and check the artifacts of all circuits, then start accepting jobs
+fn load(args: &Args, state: &AppState) -> Result<(), Box<dyn Error>> {
+    let circuits = match (&args.keys, args.backend) {
+        (Some(dir), _) => KeyStore::open(dir).load()?,
+        // clap requires the paths with the backend
+        (None, Some(backend)) => Circuits::single(Arc::new(Artifacts::load(
+            backend,
+            args.srs.as_ref().unwrap(),
+            args.proving_key.as_ref().unwrap(),
+            args.verifying_key.as_ref().unwrap(),
+        )?)),
+        (None, None) => unreachable!("clap requires a key store or a backend"),
+    };
+    // The test proofs of the check then already run on warm provers
+    circuits.warm_up()?;
+    circuits.check()?;
+    for (id, artifacts) in circuits.iter() {
+        println!("Hosting circuit '{}' ({})", id, artifacts.backend.name());
+    }
+
+    let store = Arc::new(JobStore::open(&args.store)?);
+    let queue = JobQueue::start(Arc::new(circuits), store, args.queue_capacity, args.workers);
+    let recovered = queue.recover()?;
+    if recovered > 0 {
+        println!("Queued {} unfinished jobs again", recovered);
+    }
+    state.set_ready(queue);
+    Ok(())
+}
+
+// Resolves on SIGTERM or Ctrl-C
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        let _ = tokio::signal::ctrl_c().await;
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(_) => std::future::pending().await,
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    let args = Args::parse();
+    let addr = args.addr;
+    let shutdown_grace = Duration::from_secs(args.shutdown_grace);
+    let mut state = AppState::default().with_metrics(telemetry::install()?);
+    if let Some(path) = &args.api_keys {
+        let rate_limit = args.rate_limit.map(RateLimit::per_minute);
+        state = state.with_api_keys(ApiKeys::from_file(path, rate_limit)?);
+    }
+
+    // Listening right away, so orchestrators see the instance alive while it loads
+    let loading_state = state.clone();
+    tokio::task::spawn_blocking(move || {
+        if let Err(e) = load(&args, &loading_state) {
+            eprintln!("Error: couldn't load the artifacts: {}", e);
+            process::exit(1);
+        }
+        println!("Ready to prove");
+    });
+
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    println!("Listening on {}", addr);
+    let stopping_state = state.clone();
+    axum::serve(listener, router(state))
+        .with_graceful_shutdown(async move {
+            shutdown_signal().await;
+            println!("Shutting down, waiting for running jobs");
+            // Rejects new jobs and reports not ready while the running ones finish
+            if let Some(queue) = stopping_state.job_queue() {
+                let left = tokio::task::spawn_blocking(move || queue.shutdown(shutdown_grace))
+                    .await
+                    .unwrap_or_default();
+                if left > 0 {
+                    println!("Left {} unfinished jobs for the next start", left);
+                }
+            }
+        })
+        .await?;
+    Ok(())
+}
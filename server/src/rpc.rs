@@ -0,0 +1,343 @@
+//! JSON-RPC 2.0 interface at `POST /rpc`, for clients which speak JSON-RPC rather than REST.
+//!
+//! Params are passed by name and mirror the REST bodies:
+//!
+//! - `fib_prove` with `{"circuit"?, "input"}` queues a job like `POST /jobs` and answers once it's
+//!   done, with the job id and the base64 proof bundle.
+//! - `fib_verify` with `{"circuit"?, "bundle"}` answers the `VerificationOutcome` of
+//!   `POST /verify`.
+//! - `fib_getVk` with `{"circuit"?}` answers the backend and base64 verifying key of the circuit.
+//!
+//! Batches and notifications are supported. The calls of a batch run concurrently, and its
+//! response is sent once all of them are answered. Each `fib_prove` call counts against the rate
+//! limit of the client's API key like a `POST /jobs`, the other methods aren't counted. Errors
+//! carry the server's `ErrorReport` as `data`.
+use axum::{
+    body::Bytes,
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use fibonacci_circuit::{BackendId, ErrorCode, ErrorReport};
+use futures_util::future::join_all;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::{
+    auth::rate_limited, verify_request, ApiClient, ApiError, AppState, EncodedBundle, JobId,
+    JobQueue, JobRequest, JobResult, JobState, Submission, VerifyRequest,
+};
+
+/// The request isn't valid JSON
+pub const PARSE_ERROR: i64 = -32700;
+/// The request isn't a JSON-RPC 2.0 request object
+pub const INVALID_REQUEST: i64 = -32600;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+/// Malformed params, inputs or bundles
+pub const INVALID_PARAMS: i64 = -32602;
+pub const INTERNAL_ERROR: i64 = -32603;
+/// The server is loading, shutting down or its queue is full, retry later
+pub const SERVER_BUSY: i64 = -32001;
+/// The job was accepted, but proving failed
+pub const PROVING_FAILED: i64 = -32002;
+/// The client's API key is over its rate limit, retry after the `retry_after` seconds of the
+/// report's context
+pub const RATE_LIMITED: i64 = -32003;
+
+/// Response to a request, with either a `result` or an `error`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RpcResponse {
+    pub jsonrpc: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<RpcError>,
+    /// Id of the request, `null` if it couldn't be read
+    pub id: Value,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<ErrorReport>,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            data: None,
+        }
+    }
+
+    fn with_report(code: i64, report: ErrorReport) -> Self {
+        Self {
+            code,
+            message: report.message.clone(),
+            data: Some(report),
+        }
+    }
+}
+
+impl From<ApiError> for RpcError {
+    fn from(error: ApiError) -> Self {
+        let code = if error.status == StatusCode::SERVICE_UNAVAILABLE {
+            SERVER_BUSY
+        } else if error.status.is_client_error() {
+            INVALID_PARAMS
+        } else {
+            INTERNAL_ERROR
+        };
+        Self::with_report(code, error.report)
+    }
+}
+
+/// Result of `fib_prove`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProveResult {
+    /// Id of the job, for `GET /jobs/{id}` and `GET /proofs/{id}`
+    pub id: String,
+    pub bundle: EncodedBundle,
+}
+
+/// Params of `fib_getVk`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VerifyingKeyRequest {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub circuit: Option<String>,
+}
+
+/// Result of `fib_getVk`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VerifyingKeyResult {
+    pub circuit: String,
+    pub backend: BackendId,
+    /// Verifying key as written by the key generators, base64-encoded
+    pub verifying_key: String,
+}
+
+/// Answer a request or batch of requests. Only notifications get `204 No Content`.
+pub async fn handle(
+    State(state): State<AppState>,
+    client: Option<Extension<ApiClient>>,
+    body: Bytes,
+) -> Response {
+    let client = client.map(|Extension(ApiClient(name))| name);
+    let request: Value = match serde_json::from_slice(&body) {
+        Ok(request) => request,
+        Err(e) => {
+            let error = RpcError::new(PARSE_ERROR, format!("Invalid JSON: {}", e));
+            return Json(error_response(Value::Null, error)).into_response();
+        }
+    };
+
+    match request {
+        Value::Array(requests) if requests.is_empty() => Json(error_response(
+            Value::Null,
+            RpcError::new(INVALID_REQUEST, "Empty batch"),
+        ))
+        .into_response(),
+        Value::Array(requests) => {
+            let calls = requests
+                .into_iter()
+                .map(|request| call(&state, client.as_deref(), request));
+            let responses: Vec<_> = join_all(calls).await.into_iter().flatten().collect();
+            if responses.is_empty() {
+                StatusCode::NO_CONTENT.into_response()
+            } else {
+                Json(responses).into_response()
+            }
+        }
+        request => match call(&state, client.as_deref(), request).await {
+            Some(response) => Json(response).into_response(),
+            None => StatusCode::NO_CONTENT.into_response(),
+        },
+    }
+}
+
+/// Run a single request of `client`, `None` for notifications.
+async fn call(state: &AppState, client: Option<&str>, request: Value) -> Option<RpcResponse> {
+    let Value::Object(mut request) = request else {
+        return Some(error_response(
+            Value::Null,
+            RpcError::new(INVALID_REQUEST, "Requests must be objects"),
+        ));
+    };
+    let id = request.remove("id");
+
+    if request.get("jsonrpc") != Some(&Value::from("2.0")) {
+        // Malformed requests are answered even without an id
+        return Some(error_response(
+            id.unwrap_or(Value::Null),
+            RpcError::new(INVALID_REQUEST, "`jsonrpc` must be \"2.0\""),
+        ));
+    }
+    let Some(Value::String(method)) = request.remove("method") else {
+        return Some(error_response(
+            id.unwrap_or(Value::Null),
+            RpcError::new(INVALID_REQUEST, "`method` must be a string"),
+        ));
+    };
+    let params = request.remove("params").unwrap_or(Value::Null);
+
+    let result = dispatch(state, client, &method, params).await;
+    // Requests without an id are notifications, which get no response
+    Some(match result {
+        Ok(result) => RpcResponse {
+            jsonrpc: "2.0".to_string(),
+            result: Some(result),
+            error: None,
+            id: id?,
+        },
+        Err(error) => error_response(id?, error),
+    })
+}
+
+async fn dispatch(
+    state: &AppState,
+    client: Option<&str>,
+    method: &str,
+    params: Value,
+) -> Result<Value, RpcError> {
+    match method {
+        "fib_prove" => {
+            let request = parse_params(params)?;
+            charge(state, client)?;
+            to_value(prove(state, request).await?)
+        }
+        "fib_verify" => {
+            let queue = state.queue()?;
+            to_value(verify_request(&queue, parse_params(params)?).await?)
+        }
+        "fib_getVk" => {
+            let request: VerifyingKeyRequest = match params {
+                Value::Null => VerifyingKeyRequest::default(),
+                params => parse_params(params)?,
+            };
+            let queue = state.queue()?;
+            let (id, artifacts) = queue
+                .circuits()
+                .get(request.circuit.as_deref())
+                .map_err(ApiError::from)?;
+            to_value(VerifyingKeyResult {
+                circuit: id.to_string(),
+                backend: artifacts.backend,
+                verifying_key: STANDARD.encode(artifacts.verifying_key()),
+            })
+        }
+        _ => Err(RpcError::new(
+            METHOD_NOT_FOUND,
+            format!("Unknown method '{}'", method),
+        )),
+    }
+}
+
+/// Count a proof against the rate limit of `client`, like `require_api_key` does for
+/// `POST /jobs`. Requests only get here without a client if the server has no API keys.
+fn charge(state: &AppState, client: Option<&str>) -> Result<(), RpcError> {
+    let (Some(keys), Some(client)) = (state.api_keys(), client) else {
+        return Ok(());
+    };
+    keys.acquire(client)
+        .map_err(|wait| RpcError::with_report(RATE_LIMITED, rate_limited(client, wait)))
+}
+
+/// Queue the job of `request`, or find the existing one, and wait until it finished.
+async fn prove(state: &AppState, request: JobRequest) -> Result<ProveResult, RpcError> {
+    let queue = state.queue()?;
+    let id = match queue
+        .submit(request.circuit.as_deref(), request.input)
+        .map_err(ApiError::from)?
+    {
+        Submission::Queued(id) | Submission::Existing(id) => id,
+        Submission::Full => return Err(busy("The job queue is full, retry later")),
+        Submission::Closed => return Err(busy("The server is shutting down, retry later")),
+    };
+
+    match wait_for_job(&queue, &id).await? {
+        JobResult::Done(bundle) => Ok(ProveResult {
+            id: id.to_string(),
+            bundle: (&bundle).into(),
+        }),
+        JobResult::Failed(report) => Err(RpcError::with_report(PROVING_FAILED, report)),
+    }
+}
+
+async fn wait_for_job(queue: &JobQueue, id: &JobId) -> Result<JobResult, RpcError> {
+    // Subscribe before reading the state, so the event of the job finishing can't be missed
+    let mut events = queue.subscribe();
+    let mut stopped = queue.stopped();
+    loop {
+        match queue.state(id).map_err(ApiError::from)? {
+            Some(JobState::Finished(result)) => return Ok(result),
+            Some(_) => {}
+            None => {
+                return Err(RpcError::new(
+                    INTERNAL_ERROR,
+                    format!("Job {} vanished", id),
+                ))
+            }
+        }
+
+        // Wait for an event of the job, or for missed ones to read the state again
+        loop {
+            let event = tokio::select! {
+                event = events.recv() => event,
+                _ = stopped.wait_for(|stopped| *stopped) => {
+                    return Err(busy("The server is shutting down, retry later"));
+                }
+            };
+            match event {
+                Ok(event) if event.id == *id => match event.state {
+                    JobState::Finished(result) => return Ok(result),
+                    _ => continue,
+                },
+                Ok(_) => continue,
+                Err(RecvError::Lagged(_)) => break,
+                Err(RecvError::Closed) => {
+                    return Err(busy("The server is shutting down, retry later"))
+                }
+            }
+        }
+    }
+}
+
+fn busy(message: &str) -> RpcError {
+    RpcError::with_report(
+        SERVER_BUSY,
+        ErrorReport::new(ErrorCode::Unavailable, message),
+    )
+}
+
+fn parse_params<T: DeserializeOwned>(params: Value) -> Result<T, RpcError> {
+    serde_json::from_value(params).map_err(|e| {
+        RpcError::with_report(
+            INVALID_PARAMS,
+            ErrorReport::new(ErrorCode::InvalidInput, format!("Invalid params: {}", e)),
+        )
+    })
+}
+
+fn to_value<T: Serialize>(result: T) -> Result<Value, RpcError> {
+    serde_json::to_value(result).map_err(|e| {
+        RpcError::new(
+            INTERNAL_ERROR,
+            format!("Failed to serialize the result: {}", e),
+        )
+    })
+}
+
+fn error_response(id: Value, error: RpcError) -> RpcResponse {
+    RpcResponse {
+        jsonrpc: "2.0".to_string(),
+        result: None,
+        error: Some(error),
+        id,
+    }
+}
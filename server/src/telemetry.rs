@@ -0,0 +1,81 @@
+//! Prometheus metrics of the server, served at `/metrics`.
+//!
+//! Besides the proving metrics of `fibonacci_circuit::telemetry`, the server records the jobs
+//! waiting in its queue and the jobs it finished, labelled with the backend.
+use fibonacci_circuit::{telemetry, BackendId, ErrorMessage, FibonacciError};
+use metrics::{counter, describe_counter, describe_gauge, gauge, Unit};
+use metrics_exporter_prometheus::{PrometheusBuilder, PrometheusHandle};
+
+use crate::JobResult;
+
+/// Gauge of jobs waiting for a worker
+pub const QUEUE_DEPTH: &str = "fibonacci_server_queue_depth";
+/// Counter of finished jobs, labelled with `status` (`done` or `failed`)
+pub const JOBS_FINISHED: &str = "fibonacci_server_jobs_finished_total";
+
+/// Install the Prometheus recorder for the whole process, returning the handle rendering the
+/// metrics.
+pub fn install() -> Result<PrometheusHandle, FibonacciError> {
+    let handle = PrometheusBuilder::new().install_recorder().map_err(|e| {
+        FibonacciError::Setup(ErrorMessage::with_source(
+            format!("Couldn't install the metrics recorder: {}", e),
+            e,
+        ))
+    })?;
+    describe();
+    Ok(handle)
+}
+
+/// Register descriptions of the server and proving metrics.
+pub fn describe() {
+    telemetry::describe();
+    describe_gauge!(QUEUE_DEPTH, Unit::Count, "Jobs waiting for a worker");
+    describe_counter!(JOBS_FINISHED, Unit::Count, "Finished jobs");
+}
+
+pub(crate) fn record_queued(backend: BackendId) {
+    gauge!(QUEUE_DEPTH, "backend" => backend.name()).increment(1.0);
+}
+
+/// A worker took the job off the queue, to prove it or to leave it to the next run
+pub(crate) fn record_dequeued(backend: BackendId) {
+    gauge!(QUEUE_DEPTH, "backend" => backend.name()).decrement(1.0);
+}
+
+pub(crate) fn record_finished(backend: BackendId, result: &JobResult) {
+    let status = match result {
+        JobResult::Done(_) => "done",
+        JobResult::Failed(_) => "failed",
+    };
+    counter!(JOBS_FINISHED, "backend" => backend.name(), "status" => status).increment(1);
+}
+
+#[cfg(test)]
+mod tests {
+    use fibonacci_circuit::{ErrorCode, ErrorReport};
+
+    use super::*;
+
+    #[test]
+    fn test_metrics() {
+        let recorder = PrometheusBuilder::new().build_recorder();
+        let handle = recorder.handle();
+        metrics::with_local_recorder(&recorder, || {
+            describe();
+            record_queued(BackendId::Gemini);
+            record_queued(BackendId::Gemini);
+            record_dequeued(BackendId::Gemini);
+            let failed = JobResult::Failed(ErrorReport::new(ErrorCode::Proving, "failed"));
+            record_finished(BackendId::Gemini, &failed);
+            telemetry::record_proof_size(BackendId::Gemini, 1024);
+        });
+
+        let rendered = handle.render();
+        assert!(rendered.contains(r#"fibonacci_server_queue_depth{backend="gemini"} 1"#));
+        assert!(rendered.contains(
+            r#"fibonacci_server_jobs_finished_total{backend="gemini",status="failed"} 1"#
+        ));
+        assert!(rendered.contains("# HELP fibonacci_server_queue_depth Jobs waiting for a worker"));
+        assert!(rendered.contains("fibonacci_proof_size_bytes"));
+    }
+}
@@ -0,0 +1,30 @@
+use std::{env, path::Path};
+
+use fibonacci_circuit::{artifact_dirs, exit_with_error};
+use fibonacci_test_vectors::{backends, generate_all_keys, KEYS_MANIFEST};
+
+pub fn main() {
+    // The SRS files shipped in the backend crates of the workspace these binaries were built in
+    let workspace_root = artifact_dirs::workspace_dir();
+
+    // Output directory can be given as argument, defaults to the standard keys directory
+    let out_dir = env::args()
+        .nth(1)
+        .map(|dir| Path::new(&dir).to_path_buf())
+        .unwrap_or_else(|| artifact_dirs::keys_dir().unwrap_or_else(exit_with_error));
+
+    let manifest = generate_all_keys(&backends(&workspace_root), &out_dir)
+        .unwrap_or_else(|e| exit_with_error(format!("Failed to generate keys: {}", e)));
+
+    println!("Preparation finished successfully.");
+    for keys in manifest.backends {
+        println!(
+            "{} keys stored as {} and {} from {}",
+            keys.backend, keys.proving_key, keys.verifying_key, keys.srs
+        );
+    }
+    println!(
+        "Manifest stored in {}",
+        out_dir.join(KEYS_MANIFEST).display()
+    );
+}
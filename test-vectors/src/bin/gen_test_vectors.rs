@@ -0,0 +1,31 @@
+use std::{env, path::Path};
+
+use fibonacci_circuit::artifact_dirs;
+use fibonacci_test_vectors::{backends, generate_test_vector};
+
+pub fn main() {
+    // The SRS files shipped in the backend crates of the workspace these binaries were built in
+    let workspace_root = artifact_dirs::workspace_dir();
+
+    // Output directory can be given as argument, defaults to `test-vectors` under the artifact
+    // directory, see `artifact_dirs`
+    let out_dir = env::args()
+        .nth(1)
+        .map(|dir| Path::new(&dir).to_path_buf())
+        .unwrap_or_else(|| {
+            artifact_dirs::artifact_dir()
+                .unwrap_or_else(|e| panic!("{}", e))
+                .join("test-vectors")
+        });
+
+    for backend in backends(&workspace_root) {
+        let backend_dir = out_dir.join(backend.name);
+        generate_test_vector(&backend, &backend_dir)
+            .unwrap_or_else(|e| panic!("Failed to generate {} test vectors: {}", backend.name, e));
+        println!(
+            "{} test vectors stored in {}",
+            backend.name,
+            backend_dir.display()
+        );
+    }
+}
@@ -0,0 +1,303 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use fibonacci_circuit::{
+    artifact_dirs, serialisation::encode_public_inputs, BackendId, GenerateProofResult,
+};
+use gemini_fibonacci::GeminiScheme;
+use halo2_proofs::halo2curves::bn256::Fr;
+use hyperplonk_fibonacci::HyperPlonkScheme;
+use serde::{Deserialize, Serialize};
+use unihyperplonk_fibonacci::UniHyperPlonkScheme;
+
+pub type WriteKeysFn = fn(&Path, &Path, &str) -> (PathBuf, PathBuf);
+pub type ProveFn =
+    fn(&str, &str, HashMap<String, Vec<String>>) -> Result<GenerateProofResult, Box<dyn Error>>;
+pub type VerifyFn = fn(&str, &str, &[u8], &[u8]) -> Result<bool, Box<dyn Error>>;
+
+/// Entry points of a backend crate used to produce its test vectors
+pub struct Backend {
+    pub name: &'static str,
+    pub srs_path: PathBuf,
+    pub write_keys: WriteKeysFn,
+    pub prove: ProveFn,
+    pub verify: VerifyFn,
+}
+
+/// Manifest stored as `expected.json` in each backend directory.
+/// All paths are relative to that directory.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TestVector {
+    pub backend: String,
+    pub srs: String,
+    pub proving_key: String,
+    pub verifying_key: String,
+    pub input: String,
+    pub cases: Vec<TestCase>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TestCase {
+    pub name: String,
+    pub proof: String,
+    pub public_inputs: String,
+    pub expected: bool,
+}
+
+/// Seed of the prover randomness of backends whose proofs are randomized, so regenerating the
+/// vectors gives byte-identical proofs
+pub const PROOF_SEED: u64 = 0x5eed;
+
+/// All backends of the workspace, `BackendId::ALL`, with the SRS files shipped in their crate
+/// directories.
+pub fn backends(workspace_root: &Path) -> Vec<Backend> {
+    BackendId::ALL
+        .into_iter()
+        .map(|backend| Backend::new(workspace_root, backend))
+        .collect()
+}
+
+impl Backend {
+    /// Entry points of the crate of `backend`, with the SRS shipped for it, see
+    /// `artifact_dirs::shipped_srs`.
+    pub fn new(workspace_root: &Path, backend: BackendId) -> Self {
+        let (write_keys, prove, verify): (WriteKeysFn, ProveFn, VerifyFn) = match backend {
+            BackendId::Plonk => (
+                plonk_fibonacci::write_keys,
+                // halo2 proofs are randomized, seed them so the vectors are reproducible
+                |srs, pk, input| plonk_fibonacci::prove_seeded(srs, pk, input, PROOF_SEED),
+                plonk_fibonacci::verify,
+            ),
+            BackendId::HyperPlonk => (
+                fibonacci_circuit::write_keys::<HyperPlonkScheme>,
+                hyperplonk_fibonacci::prove,
+                hyperplonk_fibonacci::verify,
+            ),
+            BackendId::Gemini => (
+                fibonacci_circuit::write_keys::<GeminiScheme>,
+                gemini_fibonacci::prove,
+                gemini_fibonacci::verify,
+            ),
+            BackendId::UniHyperPlonk => (
+                fibonacci_circuit::write_keys::<UniHyperPlonkScheme>,
+                unihyperplonk_fibonacci::prove,
+                unihyperplonk_fibonacci::verify,
+            ),
+        };
+        Self {
+            name: backend.name(),
+            srs_path: workspace_root.join(artifact_dirs::shipped_srs(backend)),
+            write_keys,
+            prove,
+            verify,
+        }
+    }
+}
+
+/// Generate keys, proofs and expected verification results for `backend` into `out_dir`.
+pub fn generate_test_vector(
+    backend: &Backend,
+    out_dir: &Path,
+) -> Result<TestVector, Box<dyn Error>> {
+    fs::create_dir_all(out_dir)?;
+
+    // Ship the SRS next to the keys so the directory is self-contained
+    let srs = "srs.bin";
+    fs::copy(&backend.srs_path, out_dir.join(srs))?;
+    let srs_path = path_str(&out_dir.join(srs))?;
+
+    let (pk_path, vk_path) = (backend.write_keys)(&out_dir.join(srs), out_dir, backend.name);
+
+    let mut input = HashMap::new();
+    input.insert("out".to_string(), vec!["55".to_string()]);
+    let input_file = "input.json";
+    fs::write(
+        out_dir.join(input_file),
+        serde_json::to_string_pretty(&input)?,
+    )?;
+
+    let (proof, public_inputs) = (backend.prove)(&srs_path, &path_str(&pk_path)?, input)?;
+    let wrong_public_inputs = encode_public_inputs(&[Fr::from(1), Fr::from(1), Fr::from(56)]);
+
+    fs::write(out_dir.join("proof.bin"), &proof)?;
+    fs::write(out_dir.join("public_inputs.bin"), &public_inputs)?;
+    fs::write(
+        out_dir.join("wrong_public_inputs.bin"),
+        &wrong_public_inputs,
+    )?;
+
+    let cases = vec![
+        TestCase {
+            name: "valid".to_string(),
+            proof: "proof.bin".to_string(),
+            public_inputs: "public_inputs.bin".to_string(),
+            expected: true,
+        },
+        TestCase {
+            name: "wrong_public_input".to_string(),
+            proof: "proof.bin".to_string(),
+            public_inputs: "wrong_public_inputs.bin".to_string(),
+            expected: false,
+        },
+    ];
+
+    // Make sure the recorded results are what this crate actually produces
+    for case in &cases {
+        let verified = (backend.verify)(
+            &srs_path,
+            &path_str(&vk_path)?,
+            &fs::read(out_dir.join(&case.proof))?,
+            &fs::read(out_dir.join(&case.public_inputs))?,
+        )
+        .unwrap_or(false);
+        assert_eq!(
+            verified, case.expected,
+            "unexpected result for case '{}' of backend '{}'",
+            case.name, backend.name
+        );
+    }
+
+    let vector = TestVector {
+        backend: backend.name.to_string(),
+        srs: srs.to_string(),
+        proving_key: file_name(&pk_path)?,
+        verifying_key: file_name(&vk_path)?,
+        input: input_file.to_string(),
+        cases,
+    };
+    fs::write(
+        out_dir.join("expected.json"),
+        serde_json::to_string_pretty(&vector)?,
+    )?;
+
+    Ok(vector)
+}
+
+pub use fibonacci_circuit::keygen::{BackendKeys, KeysManifest, KEYS_MANIFEST};
+
+/// Generate the keys of all `backends` into `out_dir`, one thread per backend, and write a
+/// `keys.json` manifest listing them.
+pub fn generate_all_keys(
+    backends: &[Backend],
+    out_dir: &Path,
+) -> Result<KeysManifest, Box<dyn Error>> {
+    fs::create_dir_all(out_dir)?;
+
+    let results: Vec<_> = std::thread::scope(|scope| {
+        let handles: Vec<_> = backends
+            .iter()
+            .map(|backend| {
+                scope.spawn(move || (backend.write_keys)(&backend.srs_path, out_dir, backend.name))
+            })
+            .collect();
+        handles.into_iter().map(|handle| handle.join()).collect()
+    });
+
+    let mut keys = Vec::with_capacity(backends.len());
+    for (backend, result) in backends.iter().zip(results) {
+        // `write_keys` panics on failure, the panic message is already printed by the thread
+        let (pk_path, vk_path) =
+            result.map_err(|_| format!("Generating the {} keys failed", backend.name))?;
+        keys.push(BackendKeys {
+            id: None,
+            backend: backend.name.to_string(),
+            srs: path_str(&relative_path(out_dir, &backend.srs_path)?)?,
+            proving_key: file_name(&pk_path)?,
+            verifying_key: file_name(&vk_path)?,
+        });
+    }
+
+    let manifest = KeysManifest { backends: keys };
+    fs::write(
+        out_dir.join(KEYS_MANIFEST),
+        serde_json::to_string_pretty(&manifest)?,
+    )?;
+
+    Ok(manifest)
+}
+
+/// Path of `path` relative to the directory `dir`, e.g. `../gemini/unihyperplonk-srs-4`.
+fn relative_path(dir: &Path, path: &Path) -> Result<PathBuf, Box<dyn Error>> {
+    let dir = dir.canonicalize()?;
+    let path = path.canonicalize()?;
+    let common = dir
+        .components()
+        .zip(path.components())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let mut relative: PathBuf = dir.components().skip(common).map(|_| "..").collect();
+    relative.extend(path.components().skip(common));
+    Ok(relative)
+}
+
+fn path_str(path: &Path) -> Result<String, Box<dyn Error>> {
+    path.to_str()
+        .map(|s| s.to_string())
+        .ok_or_else(|| format!("Path '{}' is not valid UTF-8", path.display()).into())
+}
+
+fn file_name(path: &Path) -> Result<String, Box<dyn Error>> {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .map(|name| name.to_string())
+        .ok_or_else(|| format!("Path '{}' has no file name", path.display()).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backends() {
+        let workspace_root = artifact_dirs::workspace_dir();
+        let backends = backends(&workspace_root);
+        let names: Vec<_> = backends.iter().map(|backend| backend.name).collect();
+        assert_eq!(names, BackendId::ALL.map(|backend| backend.name()));
+        assert!(backends.iter().all(|backend| backend.srs_path.is_file()));
+    }
+
+    #[test]
+    fn test_generate_all_keys() {
+        let out_dir =
+            std::env::temp_dir().join(format!("fibonacci-all-keys-{}", std::process::id()));
+        let backends = backends(&artifact_dirs::workspace_dir());
+
+        let manifest = generate_all_keys(&backends, &out_dir).unwrap();
+        let written: KeysManifest =
+            serde_json::from_slice(&fs::read(out_dir.join(KEYS_MANIFEST)).unwrap()).unwrap();
+        assert_eq!(written, manifest);
+
+        assert_eq!(manifest.backends.len(), backends.len());
+        for (keys, backend) in manifest.backends.iter().zip(&backends) {
+            assert_eq!(keys.backend, backend.name);
+            assert!(out_dir.join(&keys.proving_key).is_file());
+            assert!(out_dir.join(&keys.verifying_key).is_file());
+            // The SRS path resolves from the manifest's directory
+            assert!(Path::new(&keys.srs).is_relative());
+            assert_eq!(
+                out_dir.join(&keys.srs).canonicalize().unwrap(),
+                backend.srs_path.canonicalize().unwrap()
+            );
+        }
+
+        fs::remove_dir_all(out_dir).unwrap();
+    }
+
+    #[test]
+    fn test_relative_path() {
+        let root = artifact_dirs::workspace_dir();
+        assert_eq!(
+            relative_path(
+                &root.join("circuit"),
+                &root.join("gemini/unihyperplonk-srs-4")
+            )
+            .unwrap(),
+            Path::new("../gemini/unihyperplonk-srs-4")
+        );
+    }
+}
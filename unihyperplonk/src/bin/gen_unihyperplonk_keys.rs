@@ -0,0 +1,4 @@
+fibonacci_circuit::gen_keys_main!(
+    unihyperplonk_fibonacci::UniHyperPlonkScheme,
+    "unihyperplonk"
+);
@@ -0,0 +1,28 @@
+use fibonacci_circuit::{BackendId, PlonkishComponents};
+use halo2_proofs::halo2curves::bn256::{Bn256, Fr};
+use plonkish_backend::{
+    backend::unihyperplonk::{UniHyperPlonk, UniHyperPlonkProverParam, UniHyperPlonkVerifierParam},
+    pcs::univariate::{UnivariateKzg, UnivariateKzgParam},
+};
+
+/// HyperPlonk with the multilinear polynomials committed to as univariate polynomials, i.e. with
+/// plain univariate KZG and no multilinear PCS on top
+pub struct UniHyperPlonkScheme;
+
+impl PlonkishComponents for UniHyperPlonkScheme {
+    const BACKEND: BackendId = BackendId::UniHyperPlonk;
+    type Param = UnivariateKzgParam<Bn256>;
+    type ProverParam = UniHyperPlonkProverParam<Fr, Self::Pcs>;
+    type VerifierParam = UniHyperPlonkVerifierParam<Fr, Self::Pcs>;
+    type Pcs = UnivariateKzg<Bn256>;
+    // KZG commitments are additive, which lets the backend batch openings by linear combination
+    type ProvingBackend = UniHyperPlonk<Self::Pcs, true>;
+
+    fn srs_k(param: &Self::Param) -> usize {
+        // Like Gemini, the 2^k evaluations are committed to as a polynomial of degree 2^k - 1
+        (param.degree() + 1).ilog2() as usize
+    }
+}
+
+fibonacci_circuit::define_scheme!(UniHyperPlonkScheme);
+fibonacci_circuit::fibonacci_circuit::scheme_tests!(UniHyperPlonkScheme);
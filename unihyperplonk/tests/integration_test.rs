@@ -0,0 +1,12 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+use fibonacci_circuit::test_prove_verify_end_to_end;
+use unihyperplonk_fibonacci::UniHyperPlonkScheme;
+
+#[test]
+pub fn unihyperplonk_integration_test() {
+    test_prove_verify_end_to_end::<UniHyperPlonkScheme>(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../gemini/unihyperplonk-srs-4"
+    ))
+}
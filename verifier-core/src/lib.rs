@@ -0,0 +1,297 @@
+//! The parts of proof verification owned by this repository, without `std`.
+//!
+//! Reading a proof as the transcripts do, decoding and checking the public inputs of a proof and
+//! deriving the field elements a `TranscriptBinding` absorbs into the transcript only need
+//! `alloc`, so embedded verifiers and runtime modules without a filesystem or threads can share
+//! them with `fibonacci-circuit`. Field elements are handled as their canonical 32 byte
+//! little-endian representation of the BN254 scalar field, convert them with `Fr::from_bytes`
+//! where the curve library is available.
+//!
+//! This crate doesn't verify proofs: hashing the transcript into challenges and checking the
+//! polynomial commitments is left to the backend verifiers, which still need `std`.
+#![cfg_attr(not(any(test, feature = "std")), no_std)]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use core::fmt;
+
+/// Little-endian representation of a BN254 scalar field element
+pub type FieldBytes = [u8; 32];
+
+/// Row of the instance column holding the starting value F(0)
+pub const INSTANCE_A_ROW: usize = 0;
+/// Row of the instance column holding the starting value F(1)
+pub const INSTANCE_B_ROW: usize = 1;
+/// Row of the instance column holding the output F(n)
+pub const INSTANCE_OUT_ROW: usize = 2;
+/// Number of public inputs of a proof, the rows of the instance column
+pub const NUM_PUBLIC_INPUTS: usize = 3;
+
+/// Separates the context from the label of a transcript binding, larger than any length prefix
+pub const CONTEXT_TAG: [u8; 16] = *b"fibonacci-ctx\0\0\0";
+
+// The BN254 scalar field modulus, little-endian
+const MODULUS: FieldBytes = [
+    0x01, 0x00, 0x00, 0xf0, 0x93, 0xf5, 0xe1, 0x43, 0x91, 0x70, 0xb9, 0x79, 0x48, 0xe8, 0x33, 0x28,
+    0x5d, 0x58, 0x81, 0x81, 0xb6, 0x45, 0x50, 0xb8, 0x29, 0xa0, 0x31, 0xe1, 0x72, 0x4e, 0x64, 0x30,
+];
+
+/// Errors of decoding and checking public inputs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// The encoding ends before the announced number of elements
+    Truncated,
+    /// Bytes follow the last element
+    TrailingBytes,
+    /// The element at `index` is not smaller than the field modulus
+    NonCanonical { index: usize },
+    /// A proof of this circuit has `expected` public inputs
+    WrongLength { expected: usize, found: usize },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Truncated => f.write_str("public inputs are truncated"),
+            Error::TrailingBytes => f.write_str("trailing bytes after the public inputs"),
+            Error::NonCanonical { index } => {
+                write!(f, "public input {} is not a canonical field element", index)
+            }
+            Error::WrongLength { expected, found } => {
+                write!(f, "expected {} public inputs, got {}", expected, found)
+            }
+        }
+    }
+}
+
+/// Whether `bytes` represents a field element, i.e. is smaller than the modulus.
+pub fn is_canonical(bytes: &FieldBytes) -> bool {
+    for (byte, modulus) in bytes.iter().rev().zip(MODULUS.iter().rev()) {
+        if byte != modulus {
+            return byte < modulus;
+        }
+    }
+    false
+}
+
+/// Field element of a small integer
+pub fn from_u128(value: u128) -> FieldBytes {
+    let mut bytes = [0u8; 32];
+    bytes[..16].copy_from_slice(&value.to_le_bytes());
+    bytes
+}
+
+/// Reads a proof or encoding front to back, the way the transcripts read proofs, and tells
+/// whether all of it was read. Verifiers reject proofs with bytes left after the transcript, so
+/// each proof has a single encoding.
+#[derive(Debug, Clone)]
+pub struct ProofReader<'a> {
+    bytes: &'a [u8],
+    elements: usize,
+}
+
+impl<'a> ProofReader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, elements: 0 }
+    }
+
+    /// Number of bytes not read yet
+    pub fn remaining(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// The next `len` bytes.
+    pub fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        if len > self.bytes.len() {
+            return Err(Error::Truncated);
+        }
+        let (read, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(read)
+    }
+
+    /// The next little-endian u64, e.g. a length prefix.
+    pub fn read_u64(&mut self) -> Result<u64, Error> {
+        let bytes = self.read_bytes(8)?;
+        Ok(u64::from_le_bytes(bytes.try_into().unwrap()))
+    }
+
+    /// The next field element, which must be canonical. `Error::NonCanonical` counts the
+    /// elements read before it.
+    pub fn read_field_element(&mut self) -> Result<FieldBytes, Error> {
+        let element: FieldBytes = self.read_bytes(32)?.try_into().unwrap();
+        if !is_canonical(&element) {
+            return Err(Error::NonCanonical {
+                index: self.elements,
+            });
+        }
+        self.elements += 1;
+        Ok(element)
+    }
+
+    /// Finish reading, failing with `Error::TrailingBytes` unless everything was read.
+    pub fn finish(self) -> Result<(), Error> {
+        if self.bytes.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::TrailingBytes)
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::io::Read for ProofReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let len = buf.len().min(self.bytes.len());
+        buf[..len].copy_from_slice(self.read_bytes(len).unwrap());
+        Ok(len)
+    }
+}
+
+/// Decode public inputs in their canonical encoding, the layout proofs' public inputs are written
+/// in: the number of elements as a little-endian u64, followed by the 32 byte elements.
+pub fn decode_public_inputs(bytes: &[u8]) -> Result<Vec<FieldBytes>, Error> {
+    let mut reader = ProofReader::new(bytes);
+    let len = reader.read_u64()?;
+    // Checked before allocating, the length comes from untrusted input
+    if len > (reader.remaining() / 32) as u64 {
+        return Err(Error::Truncated);
+    }
+
+    let inputs = (0..len)
+        .map(|_| reader.read_field_element())
+        .collect::<Result<Vec<_>, _>>()?;
+    reader.finish()?;
+    Ok(inputs)
+}
+
+/// Encode public inputs in the layout `decode_public_inputs` reads.
+pub fn encode_public_inputs(inputs: &[FieldBytes]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(8 + 32 * inputs.len());
+    bytes.extend_from_slice(&(inputs.len() as u64).to_le_bytes());
+    for input in inputs {
+        bytes.extend_from_slice(input);
+    }
+    bytes
+}
+
+/// Check the public inputs of a Fibonacci proof: `a`, `b` and `out` as canonical field elements.
+pub fn check_public_inputs(inputs: &[FieldBytes]) -> Result<(), Error> {
+    if inputs.len() != NUM_PUBLIC_INPUTS {
+        return Err(Error::WrongLength {
+            expected: NUM_PUBLIC_INPUTS,
+            found: inputs.len(),
+        });
+    }
+    match inputs.iter().position(|input| !is_canonical(input)) {
+        Some(index) => Err(Error::NonCanonical { index }),
+        None => Ok(()),
+    }
+}
+
+/// Encode arbitrary bytes as field elements: the byte length followed by the bytes in 16-byte
+/// little-endian chunks, so distinct byte strings always map to distinct sequences.
+pub fn bytes_to_field_elements(bytes: &[u8]) -> Vec<FieldBytes> {
+    core::iter::once(from_u128(bytes.len() as u128))
+        .chain(bytes.chunks(16).map(|chunk| {
+            let mut limb = [0u8; 16];
+            limb[..chunk.len()].copy_from_slice(chunk);
+            from_u128(u128::from_le_bytes(limb))
+        }))
+        .collect()
+}
+
+/// Field elements a transcript binding of `label` and `context` absorbs, empty when both are.
+pub fn binding_elements(label: &[u8], context: &[u8]) -> Vec<FieldBytes> {
+    let mut elements = Vec::new();
+    if !label.is_empty() {
+        elements.extend(bytes_to_field_elements(label));
+    }
+    if !context.is_empty() {
+        elements.push(from_u128(u128::from_le_bytes(CONTEXT_TAG)));
+        elements.extend(bytes_to_field_elements(context));
+    }
+    elements
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_public_inputs() {
+        let inputs = [from_u128(1), from_u128(1), from_u128(55)];
+        let encoded = encode_public_inputs(&inputs);
+        assert_eq!(decode_public_inputs(&encoded).unwrap(), inputs);
+        assert!(check_public_inputs(&inputs).is_ok());
+        assert_eq!(
+            check_public_inputs(&inputs[..2]),
+            Err(Error::WrongLength {
+                expected: 3,
+                found: 2
+            })
+        );
+
+        assert_eq!(
+            decode_public_inputs(&encoded[..encoded.len() - 1]),
+            Err(Error::Truncated)
+        );
+        let mut trailing = encoded.clone();
+        trailing.push(0);
+        assert_eq!(decode_public_inputs(&trailing), Err(Error::TrailingBytes));
+        // A huge announced length fails without allocating
+        assert_eq!(
+            decode_public_inputs(&u64::MAX.to_le_bytes()),
+            Err(Error::Truncated)
+        );
+
+        let mut modulus = encoded;
+        modulus[8 + 64..].copy_from_slice(&MODULUS);
+        assert_eq!(
+            decode_public_inputs(&modulus),
+            Err(Error::NonCanonical { index: 2 })
+        );
+    }
+
+    #[test]
+    fn test_proof_reader() {
+        let mut proof = from_u128(7).to_vec();
+        proof.extend_from_slice(&[1, 2, 3]);
+
+        let mut reader = ProofReader::new(&proof);
+        assert_eq!(reader.read_field_element(), Ok(from_u128(7)));
+        assert_eq!(reader.remaining(), 3);
+        assert_eq!(reader.clone().read_field_element(), Err(Error::Truncated));
+        assert_eq!(reader.clone().finish(), Err(Error::TrailingBytes));
+        assert_eq!(reader.read_bytes(3), Ok(&[1u8, 2, 3][..]));
+        assert_eq!(reader.finish(), Ok(()));
+
+        let mut reader = ProofReader::new(&MODULUS);
+        assert_eq!(
+            reader.read_field_element(),
+            Err(Error::NonCanonical { index: 0 })
+        );
+    }
+
+    #[test]
+    fn test_is_canonical() {
+        let mut below = MODULUS;
+        below[0] -= 1;
+        assert!(is_canonical(&below));
+        assert!(!is_canonical(&MODULUS));
+        assert!(!is_canonical(&[0xff; 32]));
+        assert!(is_canonical(&[0; 32]));
+    }
+
+    #[test]
+    fn test_binding_elements() {
+        assert!(binding_elements(b"", b"").is_empty());
+        assert_eq!(
+            binding_elements(b"app", b""),
+            [from_u128(3), from_u128(0x707061)]
+        );
+        assert_eq!(binding_elements(b"", b"s").len(), 3);
+        assert_ne!(binding_elements(b"a", b""), binding_elements(b"", b"a"));
+    }
+}
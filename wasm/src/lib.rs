@@ -0,0 +1,158 @@
+//! wasm-bindgen module for browsers, built with `wasm-pack build wasm`.
+//!
+//! Artifacts, proofs and public inputs are passed as `Uint8Array`s and backends by name (see
+//! `BackendId::name`). Only the plonkish backends are exported, the `plonk` crate isn't built for
+//! wasm. With the `verify-only` feature the module exports `verify` alone, which keeps the
+//! prover and key generation out of the binary for dapps that only check proofs.
+use fibonacci_circuit::{BackendId, Capabilities, TranscriptBinding};
+use wasm_bindgen::prelude::*;
+
+const BACKENDS: [BackendId; 3] = [
+    BackendId::HyperPlonk,
+    BackendId::Gemini,
+    BackendId::UniHyperPlonk,
+];
+
+/// Backends, codecs and features of the module as JSON, see `Capabilities`.
+#[wasm_bindgen]
+pub fn capabilities() -> String {
+    serde_json::to_string(&Capabilities::new(BACKENDS)).expect("capabilities should serialize")
+}
+
+fn backend(name: &str) -> Result<BackendId, JsError> {
+    let backend = BackendId::from_name(name).map_err(|e| JsError::new(&e.to_string()))?;
+    Capabilities::new(BACKENDS)
+        .require(backend)
+        .map_err(|e| JsError::new(&e.to_string()))?;
+    Ok(backend)
+}
+
+fn js_error(e: Box<dyn std::error::Error>) -> JsError {
+    JsError::new(&e.to_string())
+}
+
+/// Verify `proof` of `public_inputs` with the verifying key contents of `backend`. The SRS isn't
+/// needed, verifying keys carry the verifier parameters.
+#[wasm_bindgen]
+pub fn verify(
+    backend: &str,
+    verifying_key: &[u8],
+    proof: &[u8],
+    public_inputs: &[u8],
+) -> Result<bool, JsError> {
+    let binding = TranscriptBinding::default();
+    match self::backend(backend)? {
+        BackendId::HyperPlonk => {
+            hyperplonk_fibonacci::verify_with_vk(verifying_key, proof, public_inputs, binding)
+        }
+        BackendId::Gemini => {
+            gemini_fibonacci::verify_with_vk(verifying_key, proof, public_inputs, binding)
+        }
+        BackendId::UniHyperPlonk => {
+            unihyperplonk_fibonacci::verify_with_vk(verifying_key, proof, public_inputs, binding)
+        }
+        BackendId::Plonk => unreachable!("rejected by `backend`"),
+    }
+    .map_err(js_error)
+}
+
+#[cfg(not(feature = "verify-only"))]
+pub use prover::{keygen, prove, KeyPair, ProofResult};
+
+#[cfg(not(feature = "verify-only"))]
+mod prover {
+    use std::collections::HashMap;
+
+    use fibonacci_circuit::{BackendId, GenerateProofResult, TranscriptBinding};
+    use wasm_bindgen::prelude::*;
+
+    use super::{backend, js_error};
+
+    #[wasm_bindgen]
+    pub struct KeyPair {
+        proving_key: Vec<u8>,
+        verifying_key: Vec<u8>,
+    }
+
+    #[wasm_bindgen]
+    impl KeyPair {
+        #[wasm_bindgen(getter, js_name = provingKey)]
+        pub fn proving_key(&self) -> Vec<u8> {
+            self.proving_key.clone()
+        }
+
+        #[wasm_bindgen(getter, js_name = verifyingKey)]
+        pub fn verifying_key(&self) -> Vec<u8> {
+            self.verifying_key.clone()
+        }
+    }
+
+    #[wasm_bindgen]
+    pub struct ProofResult {
+        proof: Vec<u8>,
+        public_inputs: Vec<u8>,
+    }
+
+    #[wasm_bindgen]
+    impl ProofResult {
+        #[wasm_bindgen(getter)]
+        pub fn proof(&self) -> Vec<u8> {
+            self.proof.clone()
+        }
+
+        #[wasm_bindgen(getter, js_name = publicInputs)]
+        pub fn public_inputs(&self) -> Vec<u8> {
+            self.public_inputs.clone()
+        }
+    }
+
+    /// Generate the proving and verifying keys of `backend` from the SRS contents.
+    #[wasm_bindgen]
+    pub fn keygen(backend: &str, srs_key: &[u8]) -> Result<KeyPair, JsError> {
+        let (proving_key, verifying_key) = match self::backend(backend)? {
+            BackendId::HyperPlonk => hyperplonk_fibonacci::keygen_from_bytes(srs_key),
+            BackendId::Gemini => gemini_fibonacci::keygen_from_bytes(srs_key),
+            BackendId::UniHyperPlonk => unihyperplonk_fibonacci::keygen_from_bytes(srs_key),
+            BackendId::Plonk => unreachable!("rejected by `backend`"),
+        }
+        .map_err(js_error)?;
+
+        Ok(KeyPair {
+            proving_key,
+            verifying_key,
+        })
+    }
+
+    /// Prove the input given as JSON, e.g. `{"out": ["55"]}`, with the SRS and proving key
+    /// contents of `backend`.
+    #[wasm_bindgen]
+    pub fn prove(
+        backend: &str,
+        srs_key: &[u8],
+        proving_key: &[u8],
+        input: &str,
+    ) -> Result<ProofResult, JsError> {
+        let input: HashMap<String, Vec<String>> = serde_json::from_str(input)
+            .map_err(|e| JsError::new(&format!("Invalid input JSON: {}", e)))?;
+        let binding = TranscriptBinding::default();
+
+        let (proof, public_inputs): GenerateProofResult = match self::backend(backend)? {
+            BackendId::HyperPlonk => {
+                hyperplonk_fibonacci::prove_from_bytes(srs_key, proving_key, input, binding)
+            }
+            BackendId::Gemini => {
+                gemini_fibonacci::prove_from_bytes(srs_key, proving_key, input, binding)
+            }
+            BackendId::UniHyperPlonk => {
+                unihyperplonk_fibonacci::prove_from_bytes(srs_key, proving_key, input, binding)
+            }
+            BackendId::Plonk => unreachable!("rejected by `backend`"),
+        }
+        .map_err(js_error)?;
+
+        Ok(ProofResult {
+            proof,
+            public_inputs,
+        })
+    }
+}
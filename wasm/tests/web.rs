@@ -0,0 +1,48 @@
+//! Exports of the wasm module. Run with `wasm-pack test --node wasm`, adding
+//! `--features verify-only` for the verifier-only module.
+#![cfg(target_arch = "wasm32")]
+
+use std::collections::HashMap;
+
+use fibonacci_circuit::TranscriptBinding;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+const SRS_KEY: &[u8] = include_bytes!("../../gemini/unihyperplonk-srs-4");
+
+#[wasm_bindgen_test]
+fn test_verify() {
+    let (proving_key, verifying_key) = gemini_fibonacci::keygen_from_bytes(SRS_KEY).unwrap();
+    let input = HashMap::from([("out".to_string(), vec!["55".to_string()])]);
+    let (proof, public_inputs) = gemini_fibonacci::prove_from_bytes(
+        SRS_KEY,
+        &proving_key,
+        input,
+        TranscriptBinding::default(),
+    )
+    .unwrap();
+
+    assert!(fibonacci_wasm::verify("gemini", &verifying_key, &proof, &public_inputs).unwrap());
+    assert!(!matches!(
+        fibonacci_wasm::verify("hyperplonk", &verifying_key, &proof, &public_inputs),
+        Ok(true)
+    ));
+    assert!(fibonacci_wasm::verify("plonk", &verifying_key, &proof, &public_inputs).is_err());
+}
+
+#[cfg(not(feature = "verify-only"))]
+#[wasm_bindgen_test]
+fn test_prove_verify() {
+    let keys = fibonacci_wasm::keygen("gemini", SRS_KEY).unwrap();
+    let result =
+        fibonacci_wasm::prove("gemini", SRS_KEY, &keys.proving_key(), r#"{"out": ["55"]}"#)
+            .unwrap();
+
+    assert!(fibonacci_wasm::verify(
+        "gemini",
+        &keys.verifying_key(),
+        &result.proof(),
+        &result.public_inputs()
+    )
+    .unwrap());
+    assert!(fibonacci_wasm::prove("gemini", SRS_KEY, &keys.proving_key(), "55").is_err());
+}
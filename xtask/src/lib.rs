@@ -0,0 +1,204 @@
+//! Tasks covering the artifact lifecycle of every backend, run with `cargo xtask <command>`.
+//!
+//! The shipped SRS files the backends, tests and benchmarks read are listed in `srs_files`, and
+//! `check_srs` tells which of them are missing or too small before anything fails on them. Key
+//! generation, end-to-end proofs and the benchmark report reuse the entry points of the
+//! per-crate binaries, so both produce the same artifacts. The binaries stay for a single
+//! backend or non-default options.
+use std::{
+    error::Error,
+    fs,
+    io::BufWriter,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use fibonacci_benchmark::{
+    compare_backends, default_k, default_srs_path, ComparisonReport, Scheme,
+};
+use fibonacci_circuit::{
+    artifact_dirs, io::parse_srs_bytes, BackendId, ErrorMessage, FibonacciError, PlonkishComponents,
+};
+use fibonacci_test_vectors::{
+    backends, generate_all_keys, generate_test_vector, KeysManifest, TestVector,
+};
+use gemini_fibonacci::GeminiScheme;
+use halo2_proofs::{poly::commitment::Params, SerdeFormat};
+use hyperplonk_fibonacci::HyperPlonkScheme;
+use unihyperplonk_fibonacci::UniHyperPlonkScheme;
+
+/// Converted perpetual powers of tau ceremony files for halo2, see
+/// <https://github.com/han0110/halo2-kzg-srs>. The file of 2^k points is `<url>-<k>`.
+pub const PLONK_SRS_URL: &str =
+    "https://trusted-setup-halo2kzg.s3.eu-central-1.amazonaws.com/perpetual-powers-of-tau-raw";
+
+/// Root of the workspace this crate is built in
+pub fn workspace_root() -> PathBuf {
+    artifact_dirs::workspace_dir()
+}
+
+/// SRS file the backends, tests and benchmarks read for `backend`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SrsFile {
+    pub backend: BackendId,
+    pub path: PathBuf,
+    /// Domain size of the default circuit, the SRS must support at least 2^k rows
+    pub needed_k: usize,
+}
+
+/// SRS files shipped in the crate directories of the backends
+pub fn srs_files(workspace_root: &Path) -> Vec<SrsFile> {
+    BackendId::ALL
+        .into_iter()
+        .map(|backend| SrsFile {
+            backend,
+            path: default_srs_path(workspace_root, backend),
+            needed_k: default_k(backend),
+        })
+        .collect()
+}
+
+/// Largest k of the circuits the SRS contents `srs_key` of `backend` can commit to.
+pub fn srs_k(backend: BackendId, srs_key: &[u8]) -> Result<usize, FibonacciError> {
+    match backend {
+        BackendId::Plonk => Ok(plonk_fibonacci::srs::read_params(srs_key)?.k() as usize),
+        BackendId::HyperPlonk => Ok(HyperPlonkScheme::srs_k(
+            &parse_srs_bytes::<HyperPlonkScheme>(srs_key)?,
+        )),
+        BackendId::Gemini => Ok(GeminiScheme::srs_k(&parse_srs_bytes::<GeminiScheme>(
+            srs_key,
+        )?)),
+        BackendId::UniHyperPlonk => Ok(UniHyperPlonkScheme::srs_k(&parse_srs_bytes::<
+            UniHyperPlonkScheme,
+        >(srs_key)?)),
+    }
+}
+
+/// Read `file` and check it's an SRS of its backend supporting the default circuit.
+pub fn check_srs(file: &SrsFile) -> Result<usize, FibonacciError> {
+    let srs_key = fs::read(&file.path).map_err(|e| {
+        FibonacciError::Io(ErrorMessage::with_source(
+            format!("Couldn't read '{}': {}", file.path.display(), e),
+            e,
+        ))
+    })?;
+    let available = srs_k(file.backend, &srs_key)?;
+    if available < file.needed_k {
+        return Err(FibonacciError::SrsTooSmall {
+            needed: file.needed_k,
+            available,
+        });
+    }
+    Ok(available)
+}
+
+/// Download the plonk SRS of 2^`k` points into `out_dir` with `curl`, returning its path. The
+/// file is checked to hold an SRS of that size before it's kept.
+pub fn download_plonk_srs(k: u32, out_dir: &Path) -> Result<PathBuf, Box<dyn Error>> {
+    fs::create_dir_all(out_dir)?;
+    let name = format!("perpetual-powers-of-tau-raw-{}", k);
+    let path = out_dir.join(&name);
+    let partial = out_dir.join(format!("{}.part", name));
+
+    let status = Command::new("curl")
+        .args([
+            "--fail",
+            "--location",
+            "--silent",
+            "--show-error",
+            "--output",
+        ])
+        .arg(&partial)
+        .arg(format!("{}-{}", PLONK_SRS_URL, k))
+        .status()
+        .map_err(|e| format!("Couldn't run curl: {}", e))?;
+    if !status.success() {
+        let _ = fs::remove_file(&partial);
+        return Err(format!("Downloading {} failed with {}", name, status).into());
+    }
+
+    let available = srs_k(BackendId::Plonk, &fs::read(&partial)?);
+    if !matches!(available, Ok(available) if available == k as usize) {
+        let _ = fs::remove_file(&partial);
+        return Err(format!("The downloaded {} isn't an SRS of 2^{} points", name, k).into());
+    }
+    fs::rename(&partial, &path)?;
+    Ok(path)
+}
+
+/// Write the first 2^`k` points of the plonk SRS at `src` to `out`, e.g. to ship a smaller file
+/// with an app. The other backends' SRS files can't be trimmed, generate them at the needed size
+/// with the plonkish generators instead.
+pub fn trim_plonk_srs(src: &Path, k: u32, out: &Path) -> Result<(), Box<dyn Error>> {
+    let mut params = plonk_fibonacci::srs::read_params_path(src)?;
+    if k > params.k() {
+        return Err(FibonacciError::SrsTooSmall {
+            needed: k as usize,
+            available: params.k() as usize,
+        }
+        .into());
+    }
+    params.downsize(k);
+
+    let mut writer = BufWriter::new(fs::File::create(out)?);
+    params.write_custom(&mut writer, SerdeFormat::RawBytes)?;
+    Ok(())
+}
+
+/// Generate the keys of every backend from its SRS into `out_dir`, with a `keys.json` manifest.
+pub fn generate_keys(
+    workspace_root: &Path,
+    out_dir: &Path,
+) -> Result<KeysManifest, Box<dyn Error>> {
+    generate_all_keys(&backends(workspace_root), out_dir)
+}
+
+/// Generate keys, prove and verify the default input with every backend, writing the test
+/// vectors of each into its own directory under `out_dir`. Fails on the first backend whose
+/// valid proof doesn't verify or whose invalid one does.
+pub fn run_e2e(workspace_root: &Path, out_dir: &Path) -> Result<Vec<TestVector>, Box<dyn Error>> {
+    backends(workspace_root)
+        .iter()
+        .map(|backend| generate_test_vector(backend, &out_dir.join(backend.name)))
+        .collect()
+}
+
+/// Compare every backend on the default input with the SRS shipped for it.
+pub fn bench(workspace_root: &Path) -> Result<ComparisonReport, Box<dyn Error>> {
+    let schemes = BackendId::ALL
+        .into_iter()
+        .map(|backend| Scheme::from_workspace(workspace_root, backend))
+        .collect::<Result<Vec<_>, _>>()?;
+    let input = [("out".to_string(), vec!["55".to_string()])].into();
+    compare_backends(&input, &schemes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shipped_srs_files() {
+        for file in srs_files(&workspace_root()) {
+            let available =
+                check_srs(&file).unwrap_or_else(|e| panic!("{}: {}", file.path.display(), e));
+            assert!(available >= file.needed_k);
+        }
+    }
+
+    #[test]
+    fn test_trim_plonk_srs() {
+        let src = default_srs_path(&workspace_root(), BackendId::Plonk);
+        let out = std::env::temp_dir().join(format!("xtask-srs-{}", std::process::id()));
+
+        trim_plonk_srs(&src, 2, &out).unwrap();
+        assert_eq!(
+            srs_k(BackendId::Plonk, &fs::read(&out).unwrap()).unwrap(),
+            2
+        );
+        assert!(fs::metadata(&out).unwrap().len() < fs::metadata(&src).unwrap().len());
+        assert!(trim_plonk_srs(&src, 4, &out).is_err());
+
+        fs::remove_file(out).unwrap();
+    }
+}
@@ -0,0 +1,174 @@
+use std::{
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use clap::{Parser, Subcommand};
+use fibonacci_circuit::artifact_dirs;
+use fibonacci_test_vectors::KEYS_MANIFEST;
+use xtask::{
+    bench, check_srs, download_plonk_srs, generate_keys, run_e2e, srs_files, trim_plonk_srs,
+    workspace_root,
+};
+
+/// Artifact lifecycle of every backend: SRS files, keys, end-to-end proofs and benchmarks
+#[derive(Debug, Parser)]
+#[command(name = "cargo xtask")]
+struct Cli {
+    #[command(subcommand)]
+    command: Task,
+}
+
+#[derive(Debug, Subcommand)]
+enum Task {
+    /// Fetch, trim and check SRS files
+    #[command(subcommand)]
+    Srs(SrsTask),
+    /// Generate the keys of every backend with a `keys.json` manifest
+    Keys {
+        /// The standard keys directory by default, see `artifact_dirs`
+        #[arg(long)]
+        out_dir: Option<PathBuf>,
+    },
+    /// Generate keys, prove and verify with every backend, writing the test vectors
+    E2e {
+        #[arg(long, default_value = "target/xtask/e2e")]
+        out_dir: PathBuf,
+    },
+    /// Compare the sizes and timings of every backend
+    Bench {
+        /// Print the report as JSON instead of a markdown table
+        #[arg(long)]
+        json: bool,
+        /// Also write the report to this file
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+    /// Check the SRS files, then run `e2e` and `bench`
+    All,
+}
+
+#[derive(Debug, Subcommand)]
+enum SrsTask {
+    /// Check the SRS file of every backend exists and supports the default circuit
+    Check,
+    /// Download the plonk SRS of 2^k points
+    Download {
+        #[arg(long)]
+        k: u32,
+        #[arg(long, default_value = "plonk")]
+        out_dir: PathBuf,
+    },
+    /// Write the first 2^k points of a plonk SRS to another file
+    Trim {
+        src: PathBuf,
+        #[arg(long)]
+        k: u32,
+        #[arg(long)]
+        out: PathBuf,
+    },
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match run(cli.command) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(task: Task) -> Result<(), Box<dyn Error>> {
+    // Relative paths are resolved against the workspace root, wherever cargo is run from
+    let root = workspace_root();
+
+    match task {
+        Task::Srs(SrsTask::Check) => srs_check(&root),
+        Task::Srs(SrsTask::Download { k, out_dir }) => {
+            let path = download_plonk_srs(k, &root.join(out_dir))?;
+            println!("SRS stored in {}", path.display());
+            Ok(())
+        }
+        Task::Srs(SrsTask::Trim { src, k, out }) => {
+            trim_plonk_srs(&root.join(src), k, &root.join(&out))?;
+            println!("SRS of 2^{} points stored in {}", k, out.display());
+            Ok(())
+        }
+        Task::Keys { out_dir } => {
+            let out_dir = match out_dir {
+                Some(dir) => root.join(dir),
+                None => artifact_dirs::keys_dir()?,
+            };
+            let manifest = generate_keys(&root, &out_dir)?;
+            for keys in manifest.backends {
+                println!(
+                    "{} keys stored as {} and {}",
+                    keys.backend, keys.proving_key, keys.verifying_key
+                );
+            }
+            println!(
+                "Manifest stored in {}",
+                out_dir.join(KEYS_MANIFEST).display()
+            );
+            Ok(())
+        }
+        Task::E2e { out_dir } => e2e(&root, &root.join(out_dir)),
+        Task::Bench { json, out } => {
+            let report = bench(&root)?;
+            let rendered = if json {
+                serde_json::to_string_pretty(&report)?
+            } else {
+                report.to_table()
+            };
+            println!("{}", rendered);
+            if let Some(out) = out {
+                fs::write(root.join(out), rendered)?;
+            }
+            Ok(())
+        }
+        Task::All => {
+            srs_check(&root)?;
+            e2e(&root, &root.join("target/xtask/e2e"))?;
+            print!("{}", bench(&root)?.to_table());
+            Ok(())
+        }
+    }
+}
+
+fn srs_check(root: &Path) -> Result<(), Box<dyn Error>> {
+    let mut failed = 0;
+    for file in srs_files(root) {
+        match check_srs(&file) {
+            Ok(k) => println!(
+                "{}: {} (k = {})",
+                file.backend.name(),
+                file.path.display(),
+                k
+            ),
+            Err(e) => {
+                failed += 1;
+                eprintln!("{}: {}: {}", file.backend.name(), file.path.display(), e);
+            }
+        }
+    }
+    if failed > 0 {
+        return Err(format!("{} SRS files can't be used", failed).into());
+    }
+    Ok(())
+}
+
+fn e2e(root: &Path, out_dir: &Path) -> Result<(), Box<dyn Error>> {
+    for vector in run_e2e(root, out_dir)? {
+        println!(
+            "{}: {} cases passed, test vectors stored in {}",
+            vector.backend,
+            vector.cases.len(),
+            out_dir.join(&vector.backend).display()
+        );
+    }
+    Ok(())
+}
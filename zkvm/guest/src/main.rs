@@ -0,0 +1,43 @@
+//! RISC Zero guest verifying a HyperPlonk Fibonacci proof, for proof composition experiments.
+//!
+//! Reads the verifying key, proof, public inputs, label and context as five byte vectors, the
+//! public inputs in their canonical encoding as `prove` returns them, without an artifact
+//! header. It commits whether the proof is valid together with the public inputs and binding it
+//! was checked against. Malformed inputs commit `false` instead of panicking, so the host gets a
+//! receipt for every outcome.
+#![no_main]
+
+use fibonacci_circuit::TranscriptBinding;
+use fibonacci_verifier_core::{check_public_inputs, decode_public_inputs, FieldBytes};
+use risc0_zkvm::guest::env;
+
+risc0_zkvm::guest::entry!(main);
+
+// The verifier only draws from seeded rngs, refusing OS randomness keeps the guest deterministic
+fn no_randomness(_: &mut [u8]) -> Result<(), getrandom::Error> {
+    Err(getrandom::Error::UNSUPPORTED)
+}
+getrandom::register_custom_getrandom!(no_randomness);
+
+fn main() {
+    let verifying_key: Vec<u8> = env::read();
+    let proof: Vec<u8> = env::read();
+    let public_inputs: Vec<u8> = env::read();
+    let label: Vec<u8> = env::read();
+    let context: Vec<u8> = env::read();
+
+    let inputs: Vec<FieldBytes> = decode_public_inputs(&public_inputs).unwrap_or_default();
+    let valid = check_public_inputs(&inputs).is_ok()
+        && hyperplonk_fibonacci::verify_with_vk(
+            &verifying_key,
+            &proof,
+            &public_inputs,
+            TranscriptBinding {
+                label: &label,
+                context: &context,
+            },
+        )
+        .unwrap_or(false);
+
+    env::commit(&(valid, inputs, label, context));
+}